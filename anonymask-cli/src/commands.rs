@@ -0,0 +1,222 @@
+//! `anonymask scan`, `redact`, `restore` and `migrate`: batch commands
+//! over files or stdin.
+
+use crate::files::Input;
+use anonymask_core::conll::ConllWriter;
+use anonymask_core::migrate::PlaceholderMigration;
+use anonymask_core::sarif::SarifReport;
+use anonymask_core::{AnonymaskError, Anonymizer, Entity, MappingIndex};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// The entities found in one input, as printed by `scan --json`.
+#[derive(Serialize)]
+struct Findings<'a> {
+    path: String,
+    entities: &'a [Entity],
+}
+
+/// How `scan` prints its findings.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ScanFormat {
+    /// One `path:line:column: type` line per entity
+    Lines,
+    /// A single array of `{ "path", "entities" }` objects
+    Json,
+    /// Every input as a CoNLL document of IOB-labeled tokens
+    Conll,
+    /// A SARIF 2.1.0 log for code scanning dashboards
+    Sarif,
+}
+
+/// Report the PII in each input to `out`.
+///
+/// Values are only included in JSON and CoNLL output. Returns whether
+/// anything was found.
+pub fn scan(
+    anonymizer: &Anonymizer,
+    inputs: &[Input],
+    format: ScanFormat,
+    out: &mut impl Write,
+) -> Result<bool, AnonymaskError> {
+    let mut found = false;
+    let mut reports = Vec::new();
+    let mut sarif = SarifReport::new();
+    for input in inputs {
+        let text = input.read()?;
+        let entities = anonymizer.detect(&text)?;
+        found |= !entities.is_empty();
+        match format {
+            ScanFormat::Lines => {
+                for entity in &entities {
+                    let (line, column) = position(&text, entity.start);
+                    writeln!(out, "{}:{}:{}: {}", input.name(), line, column, entity.entity_type)?;
+                }
+            }
+            ScanFormat::Json => reports.push((input.name(), entities)),
+            ScanFormat::Conll => ConllWriter::new(&mut *out).write_document(&text, &entities)?,
+            ScanFormat::Sarif => {
+                sarif.add_file(input.name(), &text, &entities);
+            }
+        }
+    }
+    if format == ScanFormat::Sarif {
+        writeln!(out, "{}", sarif.to_json())?;
+    }
+    if format == ScanFormat::Json {
+        let reports: Vec<Findings<'_>> = reports
+            .iter()
+            .map(|(path, entities)| Findings { path: path.clone(), entities })
+            .collect();
+        serde_json::to_writer_pretty(&mut *out, &reports).map_err(io::Error::from)?;
+        writeln!(out)?;
+    }
+    Ok(found)
+}
+
+/// Anonymize each input, to `out` or with `in_place` back into its file.
+///
+/// Inputs share one session, so a value gets the same placeholder in
+/// every file. Returns the combined mapping.
+pub fn redact(
+    anonymizer: &Anonymizer,
+    inputs: &[Input],
+    in_place: bool,
+    out: &mut impl Write,
+) -> Result<HashMap<String, String>, AnonymaskError> {
+    let mut session = anonymizer.session();
+    for input in inputs {
+        let text = input.read()?;
+        let result = session.anonymize(&text)?;
+        emit(input, &result.anonymized_text, in_place, out)?;
+    }
+    Ok(session.mapping())
+}
+
+/// Restore the original values of each input from `mapping`.
+pub fn restore(
+    inputs: &[Input],
+    mapping: &HashMap<String, String>,
+    in_place: bool,
+    out: &mut impl Write,
+) -> Result<(), AnonymaskError> {
+    let index = MappingIndex::new(mapping)?;
+    for input in inputs {
+        let text = input.read()?;
+        emit(input, &index.deanonymize(&text), in_place, out)?;
+    }
+    Ok(())
+}
+
+/// Replace the placeholders of each input with those of a new format.
+pub fn migrate(
+    inputs: &[Input],
+    migration: &PlaceholderMigration,
+    in_place: bool,
+    out: &mut impl Write,
+) -> Result<(), AnonymaskError> {
+    for input in inputs {
+        let text = input.read()?;
+        emit(input, &migration.rewrite(&text), in_place, out)?;
+    }
+    Ok(())
+}
+
+/// Read a mapping written by `redact --mapping`.
+pub fn read_mapping(path: &Path) -> Result<HashMap<String, String>, AnonymaskError> {
+    let file = fs::read(path)?;
+    serde_json::from_slice(&file).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)).into()
+    })
+}
+
+pub fn write_mapping(path: &Path, mapping: &HashMap<String, String>) -> Result<(), AnonymaskError> {
+    let json = serde_json::to_vec_pretty(mapping).map_err(io::Error::from)?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+fn emit(input: &Input, text: &str, in_place: bool, out: &mut impl Write) -> Result<(), AnonymaskError> {
+    match input {
+        Input::File(path) if in_place => fs::write(path, text)?,
+        Input::Stdin if in_place => {
+            let message = "--in-place needs file arguments, not stdin";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message).into());
+        }
+        _ => out.write_all(text.as_bytes())?,
+    }
+    Ok(())
+}
+
+/// 1-based line and column (in characters) of byte offset `at`.
+fn position(text: &str, at: usize) -> (usize, usize) {
+    let before = &text[..at];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anonymask_core::{AnonymizerConfig, EntityType, PlaceholderFormat};
+
+    #[test]
+    fn test_position() {
+        assert_eq!(position("ab\ncé x", 0), (1, 1));
+        assert_eq!(position("ab\ncé x", 3), (2, 1));
+        assert_eq!(position("ab\ncé x", 7), (2, 4));
+    }
+
+    #[test]
+    fn test_scan_redact_restore() {
+        let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.log"), dir.path().join("b.log"));
+        fs::write(&a, "ok\nmail jo@x.io\n").unwrap();
+        fs::write(&b, "again jo@x.io\n").unwrap();
+        let inputs = [Input::File(a.clone()), Input::File(b.clone())];
+
+        let mut out = Vec::new();
+        assert!(scan(&anonymizer, &inputs, ScanFormat::Lines, &mut out).unwrap());
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{}:2:6: email\n{}:1:7: email\n", a.display(), b.display()));
+
+        let mapping = redact(&anonymizer, &inputs, true, &mut Vec::new()).unwrap();
+        assert_eq!(mapping.len(), 1);
+        let (a_text, b_text) = (fs::read_to_string(&a).unwrap(), fs::read_to_string(&b).unwrap());
+        assert!(!a_text.contains("jo@x.io"));
+        assert_eq!(a_text.lines().nth(1).unwrap()[5..], b_text.trim_end()[6..]);
+        assert!(!scan(&anonymizer, &inputs, ScanFormat::Json, &mut Vec::new()).unwrap());
+
+        let mut out = Vec::new();
+        scan(&anonymizer, &inputs[1..], ScanFormat::Conll, &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().starts_with("-DOCSTART- O\n\nagain O\nEMAIL_"));
+
+        let (c, d) = (dir.path().join("c.log"), dir.path().join("d.log"));
+        fs::write(&c, "ok\nmail jo@x.io\n").unwrap();
+        fs::write(&d, "nothing here\n").unwrap();
+        let mut out = Vec::new();
+        assert!(scan(&anonymizer, &[Input::File(c.clone()), Input::File(d)], ScanFormat::Sarif, &mut out).unwrap());
+        let log: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["ruleId"], "email");
+        assert_eq!(results[0]["locations"][0]["physicalLocation"]["region"]["startLine"], 2);
+        assert!(!String::from_utf8(out).unwrap().contains("jo@x.io"));
+
+        let mut out = Vec::new();
+        restore(&inputs, &mapping, false, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "ok\nmail jo@x.io\nagain jo@x.io\n");
+
+        let config = AnonymizerConfig::builder().with_placeholder_format(PlaceholderFormat::Short).build();
+        let short = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+        let migration = short.migrate_placeholders(&mapping, &AnonymizerConfig::default()).unwrap();
+        migrate(&inputs, &migration, true, &mut Vec::new()).unwrap();
+        assert_eq!(fs::read_to_string(&b).unwrap(), "again EMAIL_1\n");
+        let mut out = Vec::new();
+        restore(&inputs, migration.mapping(), false, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "ok\nmail jo@x.io\nagain jo@x.io\n");
+    }
+}
@@ -0,0 +1,192 @@
+//! Input files for `scan`, `redact` and `restore`.
+//!
+//! Arguments are paths or glob patterns. Shells usually expand globs
+//! themselves; patterns are expanded here too so they also work when
+//! quoted or on shells that don't. `*` and `?` match within one path
+//! component, `**` matches any number of directories. No arguments, or
+//! `-`, means stdin.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// One text to process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Input {
+    /// Name for messages and reports.
+    pub fn name(&self) -> String {
+        match self {
+            Input::Stdin => "<stdin>".to_string(),
+            Input::File(path) => path.display().to_string(),
+        }
+    }
+
+    pub fn read(&self) -> io::Result<String> {
+        match self {
+            Input::Stdin => {
+                let mut text = String::new();
+                io::stdin().read_to_string(&mut text)?;
+                Ok(text)
+            }
+            Input::File(path) => fs::read_to_string(path)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e))),
+        }
+    }
+}
+
+/// The inputs named by command-line arguments, in order.
+///
+/// # Errors
+///
+/// Fails if a glob pattern matches no file.
+pub fn inputs(args: &[String]) -> io::Result<Vec<Input>> {
+    if args.is_empty() {
+        return Ok(vec![Input::Stdin]);
+    }
+    let mut inputs = Vec::new();
+    for arg in args {
+        if arg == "-" {
+            inputs.push(Input::Stdin);
+        } else if !is_pattern(arg) {
+            inputs.push(Input::File(PathBuf::from(arg)));
+        } else {
+            let matches = expand(arg)?;
+            if matches.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::NotFound, format!("{}: no matching files", arg)));
+            }
+            inputs.extend(matches.into_iter().map(Input::File));
+        }
+    }
+    Ok(inputs)
+}
+
+fn is_pattern(arg: &str) -> bool {
+    arg.contains(['*', '?'])
+}
+
+/// Files matching a glob pattern, sorted.
+fn expand(pattern: &str) -> io::Result<Vec<PathBuf>> {
+    let (root, rest) = match pattern.strip_prefix('/') {
+        Some(rest) => (PathBuf::from("/"), rest),
+        None => (PathBuf::new(), pattern),
+    };
+    let components: Vec<&str> = rest.split('/').filter(|c| !c.is_empty()).collect();
+    let mut found = Vec::new();
+    walk(&root, &components, &mut found)?;
+    found.sort();
+    found.dedup();
+    Ok(found)
+}
+
+fn walk(dir: &Path, components: &[&str], found: &mut Vec<PathBuf>) -> io::Result<()> {
+    let Some((&component, rest)) = components.split_first() else {
+        if dir.is_file() {
+            found.push(dir.to_path_buf());
+        }
+        return Ok(());
+    };
+    if !is_pattern(component) {
+        return walk(&dir.join(component), rest, found);
+    }
+
+    let listed = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let Ok(entries) = fs::read_dir(listed) else {
+        return Ok(());
+    };
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        // Hidden files are only matched by patterns starting with a dot
+        if name.starts_with('.') && !component.starts_with('.') {
+            continue;
+        }
+        let path = dir.join(name);
+        if component == "**" {
+            if entry.file_type()?.is_dir() {
+                walk(&path, components, found)?;
+            }
+        } else if matches(component, name) {
+            walk(&path, rest, found)?;
+        }
+    }
+    if component == "**" {
+        walk(dir, rest, found)?;
+    }
+    Ok(())
+}
+
+/// Whether `name` matches a pattern of `*` and `?` wildcards.
+fn matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it resumed from
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((after, from)) => {
+                    p = after;
+                    n = from + 1;
+                    star = Some((after, from + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcards() {
+        assert!(matches("*.log", "app.log"));
+        assert!(matches("app-?.log", "app-1.log"));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(!matches("*.log", "app.log.gz"));
+        assert!(!matches("app-?.log", "app-10.log"));
+    }
+
+    #[test]
+    fn test_expand_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in ["a.log", "b.txt", "logs/c.log", "logs/old/d.log", "logs/.e.log"] {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let root = dir.path().display().to_string();
+        let names = |pattern: &str| -> Vec<String> {
+            expand(&format!("{}/{}", root, pattern))
+                .unwrap()
+                .iter()
+                .map(|path| path.strip_prefix(dir.path()).unwrap().display().to_string())
+                .collect()
+        };
+
+        assert_eq!(names("*.log"), ["a.log"]);
+        assert_eq!(names("logs/*"), ["logs/c.log"]);
+        assert_eq!(names("**/*.log"), ["a.log", "logs/c.log", "logs/old/d.log"]);
+        assert!(inputs(&[format!("{}/*.csv", root)]).is_err());
+        assert_eq!(inputs(&[]).unwrap(), [Input::Stdin]);
+    }
+}
@@ -0,0 +1,369 @@
+//! `anonymask` command-line tool.
+
+mod commands;
+mod files;
+mod mcp;
+#[cfg(feature = "policy-bundle")]
+mod policy;
+mod rpc;
+
+use anonymask_core::{AnonymaskError, Anonymizer, AnonymizerConfig, EntityType};
+use clap::{Parser, Subcommand};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    name = "anonymask",
+    version,
+    about = "Anonymize PII in text",
+    after_help = "Exit status: 0 on success, 1 if `scan` found PII or `config check` found problems, 2 on errors."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Report the PII in files without changing them
+    ///
+    /// Prints `path:line:column: type` for each entity found. Exits with
+    /// status 1 if anything was found, so it can gate commits and CI.
+    Scan {
+        #[command(flatten)]
+        inputs: InputArgs,
+
+        /// Print entities, with their values, as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Print the inputs as CoNLL training data, one IOB-labeled token
+        /// per line
+        #[arg(long, conflicts_with = "json")]
+        conll: bool,
+
+        /// Print a SARIF 2.1.0 log for code scanning dashboards, without
+        /// values
+        #[arg(long, conflicts_with_all = ["json", "conll"])]
+        sarif: bool,
+
+        #[command(flatten)]
+        anonymizer: AnonymizerArgs,
+    },
+    /// Replace PII in files with placeholders
+    ///
+    /// Writes to stdout unless `--in-place` is given. All inputs share one
+    /// mapping, so a value gets the same placeholder everywhere.
+    Redact {
+        #[command(flatten)]
+        inputs: InputArgs,
+
+        /// Rewrite the input files instead of printing them
+        #[arg(long)]
+        in_place: bool,
+
+        /// Write the placeholder mapping here, for `restore`
+        #[arg(long)]
+        mapping: Option<PathBuf>,
+
+        #[command(flatten)]
+        anonymizer: AnonymizerArgs,
+    },
+    /// Put original values back into redacted files
+    Restore {
+        #[command(flatten)]
+        inputs: InputArgs,
+
+        /// Mapping written by `redact --mapping`
+        #[arg(long)]
+        mapping: PathBuf,
+
+        /// Rewrite the input files instead of printing them
+        #[arg(long)]
+        in_place: bool,
+    },
+    /// Move redacted files and their mapping to another placeholder format
+    ///
+    /// The new format is the one of `--config`. Writes the migrated mapping
+    /// to `--new-mapping` first, then the rewritten inputs to stdout unless
+    /// `--in-place` is given.
+    Migrate {
+        #[command(flatten)]
+        inputs: InputArgs,
+
+        /// Mapping written by `redact --mapping`
+        #[arg(long)]
+        mapping: PathBuf,
+
+        /// Configuration the files were redacted with; the default one if omitted
+        #[arg(long)]
+        from_config: Option<PathBuf>,
+
+        /// Where to write the migrated mapping
+        #[arg(long)]
+        new_mapping: PathBuf,
+
+        /// Rewrite the input files instead of printing them
+        #[arg(long)]
+        in_place: bool,
+
+        #[command(flatten)]
+        anonymizer: AnonymizerArgs,
+    },
+    /// Serve JSON-RPC 2.0 requests, one per line, or HTTP requests
+    ///
+    /// Methods: `anonymize`, `deanonymize` and `detect`. With `--stdio`,
+    /// runs until the input is closed.
+    #[command(group = clap::ArgGroup::new("transport").required(true))]
+    Serve {
+        /// Read requests from stdin and write responses to stdout
+        #[arg(long, group = "transport")]
+        stdio: bool,
+
+        /// Listen for HTTP requests on this address, e.g. 127.0.0.1:8080
+        /// (requires the `http` feature)
+        #[arg(long, group = "transport", value_name = "ADDR")]
+        http: Option<String>,
+
+        #[command(flatten)]
+        anonymizer: AnonymizerArgs,
+    },
+    /// Serve the Model Context Protocol on stdin/stdout
+    ///
+    /// Exposes `anonymize`, `deanonymize` and `detect` as tools for LLM
+    /// agents.
+    Mcp {
+        #[command(flatten)]
+        anonymizer: AnonymizerArgs,
+    },
+    /// Work with configuration files
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Check a configuration without anonymizing anything
+    ///
+    /// Reports errors that would stop the anonymizer from starting and
+    /// settings that would be accepted but not work as intended. Exits with
+    /// status 1 if anything is found.
+    Check {
+        #[command(flatten)]
+        anonymizer: AnonymizerArgs,
+    },
+    /// Create a key pair for signing policy bundles
+    ///
+    /// Writes the private key to a new file and prints the public key,
+    /// which services pass to `--trusted-key`.
+    #[cfg(feature = "policy-bundle")]
+    Keygen {
+        /// Where to write the private key; must not exist yet
+        key: PathBuf,
+    },
+    /// Sign a policy bundle and print the signed bundle
+    ///
+    /// The bundle is a JSON file with `format`, `name`, `version`,
+    /// `entity_types`, `config` and optional `profiles`.
+    #[cfg(feature = "policy-bundle")]
+    Sign {
+        /// The unsigned bundle
+        bundle: PathBuf,
+
+        /// Private key written by `config keygen`
+        #[arg(long)]
+        key: PathBuf,
+    },
+}
+
+/// Files to read, for commands that process files.
+#[derive(clap::Args)]
+struct InputArgs {
+    /// Files or glob patterns; stdin if none are given or for `-`
+    files: Vec<String>,
+}
+
+/// Options shared by every command that builds an anonymizer.
+#[derive(clap::Args)]
+struct AnonymizerArgs {
+    /// Entity types to detect, comma-separated
+    #[arg(
+        long,
+        visible_alias = "types",
+        value_delimiter = ',',
+        default_value = "email,phone,ssn,credit_card,ip_address,url"
+    )]
+    entities: Vec<String>,
+
+    /// Anonymizer configuration file (JSON, see the `config` schema)
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Signed policy bundle to load instead of `--entities` and `--config`
+    #[cfg(feature = "policy-bundle")]
+    #[arg(long, conflicts_with_all = ["entities", "config"], requires = "trusted_key")]
+    policy_bundle: Option<PathBuf>,
+
+    /// Public key (hex) trusted to sign the policy bundle; may be repeated
+    #[cfg(feature = "policy-bundle")]
+    #[arg(long, value_name = "KEY", requires = "policy_bundle")]
+    trusted_key: Vec<String>,
+
+    /// Profile of the policy bundle to apply
+    #[cfg(feature = "policy-bundle")]
+    #[arg(long, requires = "policy_bundle")]
+    profile: Option<String>,
+}
+
+impl AnonymizerArgs {
+    fn build(&self) -> Result<Anonymizer, AnonymaskError> {
+        let (entity_types, config) = self.policy()?;
+        Anonymizer::with_config(entity_types, config)
+    }
+
+    /// The entity types and configuration to use.
+    fn policy(&self) -> Result<(Vec<EntityType>, AnonymizerConfig), AnonymaskError> {
+        #[cfg(feature = "policy-bundle")]
+        if let Some(path) = &self.policy_bundle {
+            return policy::load(path, &self.trusted_key, self.profile.as_deref());
+        }
+        let entity_types = self
+            .entities
+            .iter()
+            .map(|name| EntityType::from_str_strict(name.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((entity_types, self.config()?))
+    }
+
+    fn config(&self) -> Result<AnonymizerConfig, AnonymaskError> {
+        match &self.config {
+            Some(path) => read_config(path),
+            None => Ok(AnonymizerConfig::default()),
+        }
+    }
+}
+
+/// Read a configuration file.
+fn read_config(path: &Path) -> Result<AnonymizerConfig, AnonymaskError> {
+    let file = std::fs::read(path)?;
+    serde_json::from_slice(&file).map_err(|e| AnonymaskError::InvalidConfig(format!("{}: {}", path.display(), e)))
+}
+
+/// Exit status for commands that found PII or problems.
+const FOUND: u8 = 1;
+/// Exit status for errors.
+const ERROR: u8 = 2;
+
+/// Print the problems of a configuration; fails if there are any.
+fn check_config(args: &AnonymizerArgs) -> Result<ExitCode, AnonymaskError> {
+    let warnings = args.policy()?.1.validate();
+    for warning in &warnings {
+        println!("warning: {}", warning);
+    }
+    args.build()?;
+    if !warnings.is_empty() {
+        eprintln!("anonymask: {} warning(s)", warnings.len());
+        return Ok(ExitCode::from(FOUND));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Serve the HTTP endpoints of `anonymask_core::http` until killed.
+#[cfg(feature = "http")]
+fn serve_http(addr: &str, anonymizer: anonymask_core::Anonymizer) -> io::Result<()> {
+    let app = anonymask_core::http::router(std::sync::Arc::new(anonymizer));
+    tokio::runtime::Runtime::new()?.block_on(async {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        eprintln!("anonymask: listening on http://{}", listener.local_addr()?);
+        #[cfg(feature = "dashboard")]
+        eprintln!("anonymask: dashboard at http://{}/dashboard", listener.local_addr()?);
+        axum::serve(listener, app).await
+    })
+}
+
+#[cfg(not(feature = "http"))]
+fn serve_http(_addr: &str, _anonymizer: anonymask_core::Anonymizer) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "built without the `http` feature"))
+}
+
+fn run(command: Command) -> Result<ExitCode, AnonymaskError> {
+    match command {
+        Command::Scan { inputs, json, conll, sarif, anonymizer } => {
+            let inputs = files::inputs(&inputs.files)?;
+            let format = match (json, conll, sarif) {
+                (true, _, _) => commands::ScanFormat::Json,
+                (_, true, _) => commands::ScanFormat::Conll,
+                (_, _, true) => commands::ScanFormat::Sarif,
+                _ => commands::ScanFormat::Lines,
+            };
+            let found = commands::scan(&anonymizer.build()?, &inputs, format, &mut io::stdout().lock())?;
+            Ok(if found { ExitCode::from(FOUND) } else { ExitCode::SUCCESS })
+        }
+        Command::Redact { inputs, in_place, mapping, anonymizer } => {
+            let inputs = files::inputs(&inputs.files)?;
+            let map = commands::redact(&anonymizer.build()?, &inputs, in_place, &mut io::stdout().lock())?;
+            if let Some(path) = mapping {
+                commands::write_mapping(&path, &map)?;
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Restore { inputs, mapping, in_place } => {
+            let inputs = files::inputs(&inputs.files)?;
+            let mapping = commands::read_mapping(&mapping)?;
+            commands::restore(&inputs, &mapping, in_place, &mut io::stdout().lock())?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Migrate { inputs, mapping, from_config, new_mapping, in_place, anonymizer } => {
+            let inputs = files::inputs(&inputs.files)?;
+            let mapping = commands::read_mapping(&mapping)?;
+            let from = match from_config {
+                Some(path) => read_config(&path)?,
+                None => AnonymizerConfig::default(),
+            };
+            let migration = anonymizer.build()?.migrate_placeholders(&mapping, &from)?;
+            commands::write_mapping(&new_mapping, migration.mapping())?;
+            commands::migrate(&inputs, &migration, in_place, &mut io::stdout().lock())?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Serve { http: Some(addr), anonymizer, .. } => {
+            serve_http(&addr, anonymizer.build()?)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Serve { anonymizer, .. } => {
+            let methods = rpc::Methods::new(anonymizer.build()?);
+            rpc::serve(&methods, io::stdin().lock(), io::stdout().lock())?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Mcp { anonymizer } => {
+            let server = mcp::McpServer::new(rpc::Methods::new(anonymizer.build()?));
+            rpc::serve(&server, io::stdin().lock(), io::stdout().lock())?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Config { command: ConfigCommand::Check { anonymizer } } => check_config(&anonymizer),
+        #[cfg(feature = "policy-bundle")]
+        Command::Config { command: ConfigCommand::Keygen { key } } => {
+            println!("{}", policy::keygen(&key)?);
+            Ok(ExitCode::SUCCESS)
+        }
+        #[cfg(feature = "policy-bundle")]
+        Command::Config { command: ConfigCommand::Sign { bundle, key } } => {
+            println!("{}", policy::sign(&bundle, &key)?);
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("anonymask: {}", e);
+            ExitCode::from(ERROR)
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Model Context Protocol server, for `anonymask mcp`.
+//!
+//! Exposes the JSON-RPC [`Methods`] as MCP tools over the stdio transport,
+//! so agent frameworks can scrub content before passing it between tools.
+//! Tool results carry the method's result both as JSON text and as
+//! structured content; anonymization failures are reported as tool errors
+//! for the model to see rather than as protocol errors.
+
+use crate::rpc::{Handler, Methods, RpcError, INVALID_PARAMS, METHOD_NOT_FOUND};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/// Protocol revisions this server speaks, newest first.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+#[derive(Deserialize)]
+struct InitializeParams {
+    #[serde(rename = "protocolVersion")]
+    protocol_version: String,
+}
+
+#[derive(Deserialize)]
+struct CallToolParams {
+    name: String,
+    #[serde(default)]
+    arguments: Map<String, Value>,
+}
+
+/// Serves MCP requests with the tools of [`Methods`].
+pub struct McpServer {
+    methods: Methods,
+}
+
+impl McpServer {
+    pub fn new(methods: Methods) -> Self {
+        Self { methods }
+    }
+
+    fn call_tool(&self, params: CallToolParams) -> Result<Value, RpcError> {
+        if !tools().iter().any(|tool| tool["name"] == params.name) {
+            return Err(RpcError::new(INVALID_PARAMS, format!("unknown tool '{}'", params.name)));
+        }
+        Ok(match self.methods.call(&params.name, Value::Object(params.arguments)) {
+            Ok(result) => json!({
+                "content": [{ "type": "text", "text": result.to_string() }],
+                "structuredContent": structured(result),
+                "isError": false,
+            }),
+            Err(e) => json!({
+                "content": [{ "type": "text", "text": e.message }],
+                "isError": true,
+            }),
+        })
+    }
+}
+
+impl Handler for McpServer {
+    fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => {
+                let params: InitializeParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+                let version = PROTOCOL_VERSIONS
+                    .iter()
+                    .find(|&&version| version == params.protocol_version)
+                    .unwrap_or(&PROTOCOL_VERSIONS[0]);
+                Ok(json!({
+                    "protocolVersion": version,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "anonymask", "version": env!("CARGO_PKG_VERSION") },
+                }))
+            }
+            "notifications/initialized" | "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools() })),
+            "tools/call" => {
+                let params = serde_json::from_value(params)
+                    .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+                self.call_tool(params)
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method '{}'", method))),
+        }
+    }
+}
+
+/// Structured content must be an object; arrays are wrapped.
+fn structured(result: Value) -> Value {
+    match result {
+        Value::Object(_) => result,
+        other => json!({ "entities": other }),
+    }
+}
+
+/// Tool descriptions with their argument schemas.
+fn tools() -> Vec<Value> {
+    let text = json!({ "type": "string", "description": "The text to process" });
+    let realm = json!({
+        "type": "string",
+        "description": "Placeholder scope, such as a conversation id: values keep their placeholders \
+            across calls with the same realm, and the server keeps the mapping",
+    });
+    vec![
+        json!({
+            "name": "anonymize",
+            "description": "Replace PII in a text with placeholders. Returns the anonymized text, \
+                the detected entities and the mapping needed to restore the originals.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": text,
+                    "custom_entities": {
+                        "type": "object",
+                        "description": "Additional values to replace, by entity type name",
+                        "additionalProperties": { "type": "array", "items": { "type": "string" } },
+                    },
+                    "realm": realm,
+                },
+                "required": ["text"],
+            },
+        }),
+        json!({
+            "name": "deanonymize",
+            "description": "Restore the original values in a text anonymized by the anonymize tool.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": text,
+                    "mapping": {
+                        "type": "object",
+                        "description": "The mapping returned by anonymize; not needed with a realm",
+                        "additionalProperties": { "type": "string" },
+                    },
+                    "realm": realm,
+                },
+                "required": ["text"],
+            },
+        }),
+        json!({
+            "name": "detect",
+            "description": "Find PII in a text without changing it.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "text": text },
+                "required": ["text"],
+            },
+        }),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::tests::exchange;
+    use anonymask_core::{Anonymizer, EntityType};
+
+    fn server() -> McpServer {
+        McpServer::new(Methods::new(Anonymizer::new(vec![EntityType::Email]).unwrap()))
+    }
+
+    #[test]
+    fn test_session() {
+        let responses = exchange(&server(), concat!(
+            r#"{"jsonrpc":"2.0","id":0,"method":"initialize","params":{"protocolVersion":"2024-11-05","capabilities":{},"clientInfo":{"name":"t","version":"1"}}}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"detect","arguments":{"text":"jo@x.io"}}}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"anonymize","arguments":{}}}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"rm","arguments":{}}}"#,
+            "\n",
+        ));
+        assert_eq!(responses.len(), 5);
+        assert_eq!(responses[0]["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(responses[1]["result"]["tools"].as_array().unwrap().len(), 3);
+
+        let detected = &responses[2]["result"];
+        assert_eq!(detected["isError"], false);
+        assert_eq!(detected["structuredContent"]["entities"][0]["value"], "jo@x.io");
+
+        assert_eq!(responses[3]["result"]["isError"], true);
+        assert_eq!(responses[4]["error"]["code"], INVALID_PARAMS);
+    }
+}
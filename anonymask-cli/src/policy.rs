@@ -0,0 +1,80 @@
+//! `anonymask config keygen` and `config sign`, and loading signed policy
+//! bundles with `--policy-bundle`.
+
+use anonymask_core::bundle::{PolicyBundle, SigningKey, VerifyingKey};
+use anonymask_core::{AnonymaskError, AnonymizerConfig, EntityType};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Create a signing key, write it to `key_path` and return its public key.
+///
+/// The file must not exist yet; on Unix only its owner can read it.
+pub fn keygen(key_path: &Path) -> Result<VerifyingKey, AnonymaskError> {
+    let key = SigningKey::generate()?;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(key_path)?;
+    writeln!(file, "{}", key.to_hex())?;
+    Ok(key.verifying_key())
+}
+
+/// Sign the unsigned bundle at `bundle_path` with the key at `key_path`.
+pub fn sign(bundle_path: &Path, key_path: &Path) -> Result<String, AnonymaskError> {
+    let bundle: PolicyBundle = serde_json::from_slice(&fs::read(bundle_path)?).map_err(|e| {
+        AnonymaskError::PolicyBundleError(format!("{}: {}", bundle_path.display(), e))
+    })?;
+    let key: SigningKey = fs::read_to_string(key_path)?.parse()?;
+    bundle.sign(&key)
+}
+
+/// The entity types and configuration of a profile of a signed bundle.
+pub fn load(
+    bundle_path: &Path,
+    trusted_keys: &[String],
+    profile: Option<&str>,
+) -> Result<(Vec<EntityType>, AnonymizerConfig), AnonymaskError> {
+    let trusted = trusted_keys
+        .iter()
+        .map(|key| key.parse())
+        .collect::<Result<Vec<VerifyingKey>, _>>()?;
+    let signed = fs::read(bundle_path)?;
+    let bundle = PolicyBundle::verify(&signed, &trusted).map_err(|e| match e {
+        AnonymaskError::PolicyBundleError(reason) => {
+            AnonymaskError::PolicyBundleError(format!("{}: {}", bundle_path.display(), reason))
+        }
+        e => e,
+    })?;
+    bundle.resolve(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anonymask_core::bundle::Profile;
+
+    #[test]
+    fn test_keygen_sign_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let (key_path, bundle_path, signed_path) =
+            (dir.path().join("key"), dir.path().join("bundle.json"), dir.path().join("signed.json"));
+        let public = keygen(&key_path).unwrap();
+        assert!(keygen(&key_path).is_err(), "keys are never overwritten");
+
+        let mut bundle = PolicyBundle::new("acme", 2, vec![EntityType::Email], AnonymizerConfig::default());
+        bundle
+            .profiles
+            .insert("support".to_string(), Profile::default().with_entity_types(vec![EntityType::Phone]));
+        fs::write(&bundle_path, serde_json::to_vec(&bundle).unwrap()).unwrap();
+        fs::write(&signed_path, sign(&bundle_path, &key_path).unwrap()).unwrap();
+
+        let (entity_types, _) = load(&signed_path, &[public.to_string()], Some("support")).unwrap();
+        assert_eq!(entity_types, vec![EntityType::Phone]);
+
+        let other = keygen(&dir.path().join("other")).unwrap();
+        let err = load(&signed_path, &[other.to_string()], None).unwrap_err();
+        assert!(err.to_string().contains("trusted key"), "{}", err);
+    }
+}
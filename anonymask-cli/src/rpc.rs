@@ -0,0 +1,335 @@
+//! JSON-RPC 2.0 over newline-delimited streams, for `anonymask serve --stdio`
+//! and `anonymask mcp`.
+//!
+//! Each line of input is one request (or a batch, as a JSON array) and
+//! each response is written on one line, flushed immediately, so editors
+//! and agents can run the binary as a subprocess. Notifications (requests
+//! without an `id`) are executed but get no response.
+//!
+//! Methods of [`Methods`], the `serve` handler:
+//!
+//! | Method | Params | Result |
+//! |--------|--------|--------|
+//! | `anonymize` | `text`, optional `custom_entities` (type name -> values) and `realm` | `AnonymizationResult` |
+//! | `deanonymize` | `text`, `mapping` or `realm` | `{ "text": ... }` |
+//! | `detect` | `text` | array of `Entity` |
+//! | `realm.mapping` | `realm` | `{ "mapping": ... }` |
+//! | `realm.delete` | `realm` | `{ "deleted": bool }` |
+//!
+//! A realm is a client-chosen name for a placeholder scope, such as a
+//! conversation id. Within a realm a value gets the same placeholder in
+//! every request, and the realm's accumulated mapping is kept by the server
+//! until `realm.delete`, so `deanonymize` can restore text by realm instead
+//! of being sent the mapping. Realms live in memory for the life of the
+//! process.
+
+use anonymask_core::{AnonymaskError, Anonymizer, EntityType};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::sync::Mutex;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// Anonymization failed; the message holds the error
+pub const ANONYMIZATION_ERROR: i64 = -32000;
+
+/// A JSON-RPC error object.
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<AnonymaskError> for RpcError {
+    fn from(e: AnonymaskError) -> Self {
+        Self::new(ANONYMIZATION_ERROR, e.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    id: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct AnonymizeParams {
+    text: String,
+    #[serde(default)]
+    custom_entities: HashMap<String, Vec<String>>,
+    realm: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DeanonymizeParams {
+    text: String,
+    mapping: Option<HashMap<String, String>>,
+    realm: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RealmParams {
+    realm: String,
+}
+
+#[derive(Deserialize)]
+struct DetectParams {
+    text: String,
+}
+
+/// Answers the method calls of one protocol.
+pub trait Handler {
+    /// Run `method`, returning its result.
+    fn call(&self, method: &str, params: Value) -> Result<Value, RpcError>;
+}
+
+/// Answer requests from `input` with `handler` until the input is closed.
+pub fn serve<H: Handler, R: BufRead, W: Write>(handler: &H, input: R, mut output: W) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(handler, &line) {
+            serde_json::to_writer(&mut output, &response)?;
+            output.write_all(b"\n")?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// The response to one line, if any.
+fn handle_line<H: Handler>(handler: &H, line: &str) -> Option<Value> {
+    match serde_json::from_str::<Value>(line) {
+        Ok(Value::Array(requests)) if !requests.is_empty() => {
+            let responses: Vec<Value> = requests
+                .into_iter()
+                .filter_map(|request| handle(handler, request))
+                .collect();
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        Ok(Value::Array(_)) => Some(error_response(
+            Value::Null,
+            RpcError::new(INVALID_REQUEST, "empty batch"),
+        )),
+        Ok(request) => handle(handler, request),
+        Err(e) => Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string()))),
+    }
+}
+
+/// The response to one request, or `None` for a notification.
+fn handle<H: Handler>(handler: &H, request: Value) -> Option<Value> {
+    let request: Request = match serde_json::from_value(request) {
+        Ok(request) => request,
+        Err(e) => return Some(error_response(Value::Null, RpcError::new(INVALID_REQUEST, e.to_string()))),
+    };
+    let result = if request.jsonrpc == "2.0" {
+        handler.call(&request.method, request.params)
+    } else {
+        Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""))
+    };
+
+    let id = request.id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+        Err(e) => error_response(id, e),
+    })
+}
+
+/// The `anonymize`, `deanonymize` and `detect` methods over one anonymizer.
+pub struct Methods {
+    anonymizer: Anonymizer,
+    /// Realm name -> accumulated placeholder mapping
+    realms: Mutex<HashMap<String, HashMap<String, String>>>,
+}
+
+impl Methods {
+    pub fn new(anonymizer: Anonymizer) -> Self {
+        Self {
+            anonymizer,
+            realms: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The mapping of `realm`, which must exist.
+    fn realm_mapping(&self, realm: &str) -> Result<HashMap<String, String>, RpcError> {
+        self.realms
+            .lock()
+            .unwrap()
+            .get(realm)
+            .cloned()
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("unknown realm '{}'", realm)))
+    }
+}
+
+impl Handler for Methods {
+    fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "anonymize" => {
+                let params: AnonymizeParams = parse_params(params)?;
+                let custom_entities = params
+                    .custom_entities
+                    .into_iter()
+                    .map(|(name, values)| EntityType::from_str(&name).map(|entity_type| (entity_type, values)))
+                    .collect::<Result<HashMap<_, _>, _>>()
+                    .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+                let custom_entities = (!custom_entities.is_empty()).then_some(&custom_entities);
+                let result = match params.realm {
+                    Some(realm) => {
+                        // Held for the whole call so concurrent requests of a
+                        // realm can't hand out different placeholders
+                        let mut realms = self.realms.lock().unwrap();
+                        let mapping = realms.entry(realm).or_default();
+                        let mut session = self.anonymizer.resume_session(mapping);
+                        let result = session.anonymize_with_custom(&params.text, custom_entities)?;
+                        *mapping = session.mapping();
+                        result
+                    }
+                    None => self.anonymizer.anonymize_with_custom(&params.text, custom_entities)?,
+                };
+                Ok(json!(result))
+            }
+            "deanonymize" => {
+                let params: DeanonymizeParams = parse_params(params)?;
+                let mapping = match (params.mapping, params.realm) {
+                    (Some(mapping), None) => mapping,
+                    (None, Some(realm)) => self.realm_mapping(&realm)?,
+                    _ => return Err(RpcError::new(INVALID_PARAMS, "expected one of `mapping` and `realm`")),
+                };
+                let text = self.anonymizer.deanonymize(&params.text, &mapping);
+                Ok(json!({ "text": text }))
+            }
+            "realm.mapping" => {
+                let params: RealmParams = parse_params(params)?;
+                Ok(json!({ "mapping": self.realm_mapping(&params.realm)? }))
+            }
+            "realm.delete" => {
+                let params: RealmParams = parse_params(params)?;
+                let deleted = self.realms.lock().unwrap().remove(&params.realm).is_some();
+                Ok(json!({ "deleted": deleted }))
+            }
+            "detect" => {
+                let params: DetectParams = parse_params(params)?;
+                Ok(json!(self.anonymizer.detect(&params.text)?))
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method '{}'", method))),
+        }
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "error": { "code": error.code, "message": error.message },
+        "id": id,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn exchange<H: Handler>(handler: &H, input: &str) -> Vec<Value> {
+        let mut output = Vec::new();
+        serve(handler, input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn methods() -> Methods {
+        Methods::new(Anonymizer::new(vec![EntityType::Email]).unwrap())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let responses = exchange(&methods(), concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"anonymize","params":{"text":"Mail jo@x.io, Acme","custom_entities":{"company":["Acme"]}}}"#,
+            "\n\n",
+            r#"{"jsonrpc":"2.0","id":"d","method":"detect","params":{"text":"jo@x.io"}}"#,
+            "\n",
+        ));
+        assert_eq!(responses.len(), 2);
+        let result = &responses[0]["result"];
+        assert_eq!(result["entities"].as_array().unwrap().len(), 2);
+        assert_eq!(responses[1]["id"], "d");
+        assert_eq!(responses[1]["result"][0]["value"], "jo@x.io");
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "deanonymize",
+            "params": { "text": result["anonymized_text"], "mapping": result["mapping"] },
+        });
+        let responses = exchange(&methods(), &request.to_string());
+        assert_eq!(responses[0]["result"]["text"], "Mail jo@x.io, Acme");
+    }
+
+    #[test]
+    fn test_realms() {
+        let methods = methods();
+        let call = |method: &str, params: Value| {
+            let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+            exchange(&methods, &request.to_string()).remove(0)
+        };
+
+        let first = call("anonymize", json!({ "text": "jo@x.io", "realm": "chat-1" }));
+        let second = call("anonymize", json!({ "text": "Again jo@x.io", "realm": "chat-1" }));
+        let other = call("anonymize", json!({ "text": "jo@x.io", "realm": "chat-2" }));
+        let placeholder = first["result"]["anonymized_text"].as_str().unwrap();
+        assert_eq!(second["result"]["anonymized_text"], format!("Again {}", placeholder));
+        assert_ne!(other["result"]["anonymized_text"], placeholder);
+
+        let restored = call("deanonymize", json!({ "text": second["result"]["anonymized_text"], "realm": "chat-1" }));
+        assert_eq!(restored["result"]["text"], "Again jo@x.io");
+        let mapping = call("realm.mapping", json!({ "realm": "chat-1" }));
+        assert_eq!(mapping["result"]["mapping"][placeholder], "jo@x.io");
+
+        assert_eq!(call("realm.delete", json!({ "realm": "chat-1" }))["result"]["deleted"], true);
+        assert_eq!(call("realm.delete", json!({ "realm": "chat-1" }))["result"]["deleted"], false);
+        let gone = call("deanonymize", json!({ "text": placeholder, "realm": "chat-1" }));
+        assert_eq!(gone["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_errors_and_notifications() {
+        let responses = exchange(&methods(), concat!(
+            "not json\n",
+            r#"{"jsonrpc":"2.0","method":"detect","params":{"text":"jo@x.io"}}"#,
+            "\n",
+            r#"[{"jsonrpc":"2.0","id":1,"method":"nope"},{"jsonrpc":"2.0","id":2,"method":"detect"}]"#,
+            "\n",
+            r#"{"jsonrpc":"1.0","id":3,"method":"detect","params":{"text":""}}"#,
+            "\n",
+        ));
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["error"]["code"], PARSE_ERROR);
+        assert_eq!(responses[1][0]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(responses[1][1]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(responses[2]["error"]["code"], INVALID_REQUEST);
+        assert_eq!(responses[2]["id"], 3);
+    }
+}
@@ -1,4 +1,4 @@
-use anonymask_core::{Anonymizer, EntityType};
+use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType, Locale};
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
 fn benchmark_anonymize_email(c: &mut Criterion) {
@@ -45,12 +45,52 @@ fn benchmark_large_text(c: &mut Criterion) {
     });
 }
 
+fn benchmark_long_log(c: &mut Criterion) {
+    // Every pattern of several locales over a log where most lines hold no
+    // PII, so most patterns never match
+    let entity_types = EntityType::all_builtin()
+        .filter(|entity_type| !entity_type.requires_ner())
+        .collect();
+    let config = AnonymizerConfig::builder()
+        .with_locales(vec![Locale::Us, Locale::De, Locale::Fr])
+        .build();
+    let anonymizer = Anonymizer::with_config(entity_types, config).unwrap();
+    let mut log = String::new();
+    for i in 0..5000 {
+        log.push_str(&format!("2024-05-01 12:00:{:02} INFO worker-{} handled job {} in {} ms\n", i % 60, i % 8, i, i % 997));
+        if i % 250 == 0 {
+            log.push_str("2024-05-01 12:00:00 WARN login failed for ops@example.com from 10.0.0.7\n");
+        }
+    }
+
+    c.bench_function("detect_long_log", |b| {
+        b.iter(|| anonymizer.detect(black_box(&log)))
+    });
+}
+
+fn benchmark_construct(c: &mut Criterion) {
+    // Compiled patterns are shared, so only the first anonymizer of a
+    // configuration pays for regex compilation
+    let entity_types: Vec<EntityType> = EntityType::all_builtin()
+        .filter(|entity_type| !entity_type.requires_ner())
+        .collect();
+    let config = AnonymizerConfig::builder()
+        .with_locales(vec![Locale::Us, Locale::De, Locale::Fr])
+        .build();
+
+    c.bench_function("construct_all_types", |b| {
+        b.iter(|| Anonymizer::with_config(black_box(entity_types.clone()), config.clone()))
+    });
+}
+
 criterion_group!(
     benches,
     benchmark_anonymize_email,
     benchmark_anonymize_multiple_entities,
     benchmark_deanonymize,
-    benchmark_large_text
+    benchmark_large_text,
+    benchmark_long_log,
+    benchmark_construct
 );
 criterion_main!(benches);
 
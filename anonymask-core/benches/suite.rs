@@ -0,0 +1,81 @@
+//! Criterion suite over the fixtures of `anonymask_core::bench`.
+//!
+//! Run with `cargo bench --features bench --bench suite`. Every group
+//! reports throughput in bytes per second next to the time per call.
+
+use anonymask_core::{bench, Anonymizer, EntityType};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// One sample sentence per built-in type, with only that type requested.
+fn benchmark_entity_types(c: &mut Criterion) {
+    let mut group = c.benchmark_group("entity_type");
+    for entity_type in bench::regex_types() {
+        let text = bench::sample(&entity_type).unwrap();
+        let anonymizer = Anonymizer::with_config(vec![entity_type.clone()], bench::config()).unwrap();
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(&entity_type), text, |b, text| {
+            b.iter(|| anonymizer.anonymize(black_box(text)))
+        });
+    }
+    group.finish();
+}
+
+/// Every built-in type over documents from a chat message to a long report.
+fn benchmark_documents(c: &mut Criterion) {
+    let anonymizer = Anonymizer::with_config(bench::regex_types(), bench::config()).unwrap();
+    let mut group = c.benchmark_group("document");
+    // 3 KB is about a 500-word message
+    for (name, len) in [("message_3kb", 3_000), ("page_64kb", 64_000), ("report_1mb", 1_000_000)] {
+        let text = bench::document(len);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        if len >= 1_000_000 {
+            group.sample_size(10);
+        }
+        group.bench_with_input(BenchmarkId::from_parameter(name), &text, |b, text| {
+            b.iter(|| anonymizer.anonymize(black_box(text)))
+        });
+    }
+    group.finish();
+}
+
+/// Dictionaries of custom values passed with each call, a few of which
+/// appear in the text.
+fn benchmark_custom_entities(c: &mut Criterion) {
+    let anonymizer = Anonymizer::with_config(vec![EntityType::Email], bench::config()).unwrap();
+    let mut group = c.benchmark_group("custom_entities");
+    for count in [10, 1_000, 10_000] {
+        let custom = bench::custom_entities(count);
+        let mut text = bench::document(16_000);
+        for i in (0..count).step_by((count / 5).max(1)) {
+            text.push_str(&format!(" Customer {:05} called again.", i));
+        }
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &text, |b, text| {
+            b.iter(|| anonymizer.anonymize_with_custom(black_box(text), Some(&custom)))
+        });
+    }
+    group.finish();
+}
+
+/// Restoring the placeholders of anonymized documents.
+fn benchmark_deanonymize(c: &mut Criterion) {
+    let anonymizer = Anonymizer::with_config(bench::regex_types(), bench::config()).unwrap();
+    let mut group = c.benchmark_group("deanonymize");
+    for (name, len) in [("message_3kb", 3_000), ("page_64kb", 64_000)] {
+        let result = anonymizer.anonymize(&bench::document(len)).unwrap();
+        group.throughput(Throughput::Bytes(result.anonymized_text.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &result, |b, result| {
+            b.iter(|| anonymizer.deanonymize(black_box(&result.anonymized_text), black_box(&result.mapping)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_entity_types,
+    benchmark_documents,
+    benchmark_custom_entities,
+    benchmark_deanonymize
+);
+criterion_main!(benches);
@@ -0,0 +1,239 @@
+//! Inline annotation of detected entities.
+//!
+//! Instead of replacing entities, [`annotate`] wraps each one in a marker
+//! naming its type, `<pii type="email">john@x.com</pii>`, for labeling
+//! pipelines and annotation tools. [`parse`] reads such text back into the
+//! plain text and its entities, e.g. after reviewers corrected the labels.
+//!
+//! Annotated text is an XML fragment: `&`, `<`, `>` and `"` in the text
+//! are written as `&amp;`, `&lt;`, `&gt;` and `&quot;`, so text that
+//! happens to contain a marker can't be mistaken for one.
+
+use crate::entity::{Entity, EntityType};
+use crate::error::AnonymaskError;
+use serde::Serialize;
+
+/// Text read back from its annotated form by [`parse`].
+#[derive(Clone, Serialize)]
+pub struct Annotated {
+    /// The text without markers
+    pub text: String,
+    /// The marked entities, with byte positions in `text`, in order
+    pub entities: Vec<Entity>,
+}
+
+/// Wrap each entity of `text` in a `<pii type="...">` marker.
+///
+/// `entities` must be sorted and non-overlapping, as returned by
+/// [`Anonymizer::detect`](crate::Anonymizer::detect); an entity
+/// overlapping the one before it is left unmarked.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::annotate;
+/// use anonymask_core::{Anonymizer, EntityType};
+///
+/// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+/// let text = "Mail <john@x.com> & co";
+/// let annotated = annotate::annotate(text, &anonymizer.detect(text).unwrap());
+///
+/// assert_eq!(annotated, r#"Mail &lt;<pii type="email">john@x.com</pii>&gt; &amp; co"#);
+/// assert_eq!(annotate::parse(&annotated).unwrap().text, text);
+/// ```
+pub fn annotate(text: &str, entities: &[Entity]) -> String {
+    let mut annotated = String::with_capacity(text.len() + entities.len() * 24);
+    let mut last = 0;
+    for entity in entities {
+        if entity.start < last {
+            continue;
+        }
+        escape_into(&mut annotated, &text[last..entity.start]);
+        annotated.push_str("<pii type=\"");
+        escape_into(&mut annotated, entity.entity_type.as_str());
+        annotated.push_str("\">");
+        escape_into(&mut annotated, &text[entity.start..entity.end]);
+        annotated.push_str("</pii>");
+        last = entity.end;
+    }
+    escape_into(&mut annotated, &text[last..]);
+    annotated
+}
+
+/// Read annotated text back into plain text and entities.
+///
+/// Accepts the output of [`annotate`], including markers added or edited
+/// by hand: attributes other than `type` are ignored, and unknown types
+/// become [`EntityType::Custom`]. Parsed entities have confidence 1.0.
+///
+/// # Errors
+///
+/// Returns `AnonymaskError::InvalidAnnotation` for markers without a type,
+/// empty, nested or unclosed markers, stray `</pii>` or `<`, and unknown
+/// character references.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::annotate;
+/// use anonymask_core::EntityType;
+///
+/// let parsed = annotate::parse(r#"Call <pii type="phone" id="7">555-0100</pii> now"#).unwrap();
+///
+/// assert_eq!(parsed.text, "Call 555-0100 now");
+/// assert_eq!(parsed.entities[0].entity_type, EntityType::Phone);
+/// assert_eq!(&parsed.text[parsed.entities[0].start..parsed.entities[0].end], "555-0100");
+/// ```
+pub fn parse(annotated: &str) -> Result<Annotated, AnonymaskError> {
+    let mut text = String::with_capacity(annotated.len());
+    let mut entities = Vec::new();
+    // Type and start in `text` of the marker being read
+    let mut open: Option<(EntityType, usize)> = None;
+    let mut rest = annotated;
+
+    while let Some(i) = rest.find(['<', '&']) {
+        let position = annotated.len() - rest.len() + i;
+        text.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if let Some(reference) = rest.strip_prefix('&') {
+            let end = reference.find(';').ok_or_else(|| invalid(position, "'&' outside a character reference"))?;
+            text.push(unescape(&reference[..end]).ok_or_else(|| invalid(position, "unknown character reference"))?);
+            rest = &reference[end + 1..];
+        } else if let Some(after) = rest.strip_prefix("</pii>") {
+            let (entity_type, start) = open.take().ok_or_else(|| invalid(position, "'</pii>' without a marker to close"))?;
+            if start == text.len() {
+                return Err(invalid(position, "empty marker"));
+            }
+            entities.push(Entity {
+                entity_type,
+                value: text[start..].to_string(),
+                start,
+                end: text.len(),
+                confidence: 1.0,
+            });
+            rest = after;
+        } else if rest.starts_with("<pii") && rest[4..].starts_with([' ', '>']) {
+            if open.is_some() {
+                return Err(invalid(position, "nested marker"));
+            }
+            let end = rest.find('>').ok_or_else(|| invalid(position, "unterminated marker"))?;
+            let entity_type = marker_type(&rest[4..end]).ok_or_else(|| invalid(position, "marker without a type"))?;
+            open = Some((EntityType::from_str(&entity_type)?, text.len()));
+            rest = &rest[end + 1..];
+        } else {
+            return Err(invalid(position, "'<' outside a marker"));
+        }
+    }
+    if open.is_some() {
+        return Err(invalid(annotated.len(), "unclosed marker"));
+    }
+    text.push_str(rest);
+    Ok(Annotated { text, entities })
+}
+
+/// The unescaped `type` attribute of a marker's attribute list.
+fn marker_type(attributes: &str) -> Option<String> {
+    let mut rest = attributes.trim_start();
+    while !rest.is_empty() {
+        let (name, after) = rest.split_once("=\"")?;
+        let (value, after) = after.split_once('"')?;
+        if name.trim() == "type" {
+            let mut unescaped = String::new();
+            let mut parts = value.split('&');
+            unescaped.push_str(parts.next()?);
+            for part in parts {
+                let (reference, tail) = part.split_once(';')?;
+                unescaped.push(unescape(reference)?);
+                unescaped.push_str(tail);
+            }
+            return Some(unescaped);
+        }
+        rest = after.trim_start();
+    }
+    None
+}
+
+/// Append `text` with `&`, `<`, `>` and `"` escaped.
+fn escape_into(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// The character of a named reference, without `&` and `;`.
+fn unescape(reference: &str) -> Option<char> {
+    match reference {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => None,
+    }
+}
+
+fn invalid(position: usize, reason: &str) -> AnonymaskError {
+    AnonymaskError::InvalidAnnotation {
+        position,
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(entity_type: EntityType, text: &str, value: &str) -> Entity {
+        let start = text.find(value).unwrap();
+        Entity {
+            entity_type,
+            value: value.to_string(),
+            start,
+            end: start + value.len(),
+            confidence: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = "Für \"Jo\" <jo@x.io> & a@b.io, not <pii type=\"ssn\">x</pii>";
+        let entities = vec![
+            entity(EntityType::Email, text, "jo@x.io"),
+            entity(EntityType::Custom("my \"tag\"".to_string()), text, "a@b.io"),
+        ];
+        let annotated = annotate(text, &entities);
+        assert!(annotated.starts_with("Für &quot;Jo&quot; &lt;<pii type=\"email\">jo@x.io</pii>&gt;"));
+
+        let parsed = parse(&annotated).unwrap();
+        assert_eq!(parsed.text, text);
+        let spans = |entities: &[Entity]| {
+            entities
+                .iter()
+                .map(|e| (e.entity_type.clone(), e.value.clone(), e.start, e.end))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(spans(&parsed.entities), spans(&entities));
+    }
+
+    #[test]
+    fn test_invalid_annotations() {
+        let position = |annotated: &str| match parse(annotated) {
+            Err(AnonymaskError::InvalidAnnotation { position, .. }) => position,
+            other => panic!("expected InvalidAnnotation, got {:?}", other),
+        };
+        assert_eq!(position("a <pii type=\"email\">b"), 21);
+        assert_eq!(position("a <pii>b</pii>"), 2);
+        assert_eq!(position("a <pii type=\"email\"></pii>"), 20);
+        assert_eq!(position("<pii type=\"x\"><pii type=\"y\">b</pii></pii>"), 14);
+        assert_eq!(position("a </pii>"), 2);
+        assert_eq!(position("a < b"), 2);
+        assert_eq!(position("a &nbsp; b"), 2);
+        assert_eq!(parse("<pii  type=\"phone\" >5</pii>").unwrap().entities[0].entity_type, EntityType::Phone);
+    }
+}
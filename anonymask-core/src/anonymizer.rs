@@ -1,10 +1,21 @@
-use crate::config::{AnonymizerConfig, PlaceholderFormat};
-use crate::detection::EntityDetector;
-use crate::entity::{AnonymizationResult, EntityType};
+use crate::annotate;
+use crate::audit::{self, Leak};
+use crate::cancel::{self, CancellationToken};
+use crate::config::{AnonymizerConfig, FieldAction};
+use crate::detection::{Budget, Decision, EntityDetector, Outcome, Trace};
+use crate::entity::{
+    AnonymizationResult, BatchResult, CowAnonymization, CustomValue, Entity, EntityContext, EntityType, OutputSpan,
+    Status,
+};
 use crate::error::AnonymaskError;
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use uuid::Uuid;
+use crate::gazetteer::Gazetteer;
+use crate::iter::AnonymizeIter;
+use crate::progress::Progress;
+use crate::replacer::{Counters, Replacer};
+use crate::selector::Selector;
+use crate::session::{AnonymizationSession, SessionState};
+use crate::subjects;
+use std::collections::{HashMap, HashSet};
 
 /// Main anonymization engine for protecting PII in text.
 ///
@@ -75,10 +86,16 @@ use uuid::Uuid;
 /// This type is `Send + Sync` and can be safely shared across threads.
 pub struct Anonymizer {
     detector: EntityDetector,
+    replacer: Replacer,
     config: AnonymizerConfig,
-    counter: AtomicUsize,
+    /// Compiled `config.field_policies`
+    field_policies: Vec<(Selector, FieldAction)>,
+    result_hooks: Vec<ResultHook>,
 }
 
+/// Callback invoked with every result before it is returned.
+type ResultHook = Box<dyn Fn(&mut AnonymizationResult) + Send + Sync>;
+
 impl Anonymizer {
     /// Create a new anonymizer for the specified entity types.
     ///
@@ -145,15 +162,190 @@ impl Anonymizer {
     /// ).unwrap();
     /// ```
     pub fn with_config(entity_types: Vec<EntityType>, config: AnonymizerConfig) -> Result<Self, AnonymaskError> {
-        let detector = EntityDetector::new(&entity_types)?;
+        let detector = EntityDetector::with_config(&entity_types, &config)?;
+        Self::from_detector(detector, config)
+    }
+
+    /// Create an anonymizer whose placeholders are the same on every run.
+    ///
+    /// A test mode, for snapshot tests of code that anonymizes text: the
+    /// placeholders are generated by [`Replacer::deterministic`], so they
+    /// only depend on the order of the values replaced since the anonymizer
+    /// was created. Create one anonymizer per test to keep snapshots
+    /// independent of test order. Placeholders are predictable, so never
+    /// use this mode on real data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType};
+    ///
+    /// let anonymizer = Anonymizer::deterministic(vec![EntityType::Email], AnonymizerConfig::default()).unwrap();
+    /// let result = anonymizer.anonymize("Mail ada@example.com").unwrap();
+    /// assert_eq!(result.anonymized_text, "Mail EMAIL_00000000000000000000000000000001");
+    /// ```
+    pub fn deterministic(entity_types: Vec<EntityType>, config: AnonymizerConfig) -> Result<Self, AnonymaskError> {
+        let mut anonymizer = Self::with_config(entity_types, config)?;
+        // Hashed tokens are reproducible already
+        if anonymizer.config.hashed_tokens.is_none() {
+            anonymizer.replacer = Replacer::deterministic(anonymizer.config.placeholder_format.clone())?
+                .with_overrides(&anonymizer.config.placeholder_overrides)?
+                .with_counter_reset(anonymizer.config.reset_counters)
+                .with_tolerant_deanonymization(anonymizer.config.tolerant_deanonymization);
+        }
+        Ok(anonymizer)
+    }
 
+    /// Create a new anonymizer that detects names with an NER backend.
+    ///
+    /// `EntityType::Person`, `EntityType::Org` and `EntityType::Location`
+    /// are detected by the backend and merged with regex-based detection of
+    /// the other requested types. NER detections below the configured
+    /// `min_confidence` are discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_types` - Vector of entity types to detect
+    /// * `config` - Configuration for anonymizer behavior
+    /// * `ner` - The backend, e.g. an ONNX `NerModel` or an `HttpNer`
+    ///   client; pass an `Arc` to share it between anonymizers
+    ///
+    /// See [`NerBackend`](crate::ner::NerBackend) for an example.
+    pub fn with_ner(
+        entity_types: Vec<EntityType>,
+        config: AnonymizerConfig,
+        ner: impl crate::ner::NerBackend + 'static,
+    ) -> Result<Self, AnonymaskError> {
+        let detector = EntityDetector::with_ner(&entity_types, &config, std::sync::Arc::new(ner))?;
+        Self::from_detector(detector, config)
+    }
+
+    fn from_detector(mut detector: EntityDetector, config: AnonymizerConfig) -> Result<Self, AnonymaskError> {
+        let replacer = match &config.hashed_tokens {
+            Some(tokens) => Replacer::hashed(config.placeholder_format.clone(), tokens)?,
+            None => Replacer::new(config.placeholder_format.clone())?,
+        }
+        .with_overrides(&config.placeholder_overrides)?
+        .with_counter_reset(config.reset_counters)
+        .with_tolerant_deanonymization(config.tolerant_deanonymization);
+        detector.skip_placeholders(replacer.placeholder_pattern().clone());
+        let field_policies = config
+            .field_policies
+            .iter()
+            .map(|policy| Ok((Selector::parse(&policy.selector)?, policy.action.clone())))
+            .collect::<Result<_, AnonymaskError>>()?;
         Ok(Anonymizer {
             detector,
+            replacer,
+            field_policies,
             config,
-            counter: AtomicUsize::new(0),
+            result_hooks: Vec::new(),
         })
     }
 
+    /// The detection engine, for finding entities without replacing them.
+    pub fn detector(&self) -> &EntityDetector {
+        &self.detector
+    }
+
+    /// The configuration the anonymizer was created with.
+    pub fn config(&self) -> &AnonymizerConfig {
+        &self.config
+    }
+
+    /// The replacement engine, for placeholder generation and restoring
+    /// text without detection.
+    pub fn replacer(&self) -> &Replacer {
+        &self.replacer
+    }
+
+    /// Also detect the terms of a dictionary on every call.
+    ///
+    /// Use this for large, stable term lists (employee names, project
+    /// codenames): the dictionary is compiled once, whereas values passed to
+    /// `anonymize_with_custom()` are compiled on each call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::Anonymizer;
+    /// use anonymask_core::entity::EntityType;
+    /// use anonymask_core::gazetteer::Gazetteer;
+    ///
+    /// let gazetteer = Gazetteer::builder()
+    ///     .add_terms(EntityType::Custom("codename".to_string()), ["Bluebird", "Nightjar"])
+    ///     .build()
+    ///     .unwrap();
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email])
+    ///     .unwrap()
+    ///     .with_gazetteer(gazetteer);
+    ///
+    /// let result = anonymizer.anonymize("Nightjar ships Friday").unwrap();
+    /// assert!(result.anonymized_text.starts_with("CODENAME_"));
+    /// ```
+    pub fn with_gazetteer(mut self, gazetteer: Gazetteer) -> Self {
+        self.detector.add_gazetteer(gazetteer);
+        self
+    }
+
+    /// Register a hook invoked with each result before it is returned.
+    ///
+    /// Hooks run in registration order on every result produced by
+    /// `anonymize()`, `anonymize_with_custom()`, `anonymize_with_custom_values()`
+    /// and session calls, which makes them a single place to push metrics,
+    /// persist mappings or post-process output. Batch results are not passed
+    /// to hooks.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - Closure receiving the final result; it may modify it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::Anonymizer;
+    /// use anonymask_core::entity::EntityType;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let redactions = Arc::new(AtomicUsize::new(0));
+    /// let counter = Arc::clone(&redactions);
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email])
+    ///     .unwrap()
+    ///     .on_result(move |result| {
+    ///         counter.fetch_add(result.entities.len(), Ordering::Relaxed);
+    ///     });
+    ///
+    /// anonymizer.anonymize("Contact a@b.io or c@d.io").unwrap();
+    /// assert_eq!(redactions.load(Ordering::Relaxed), 2);
+    /// ```
+    pub fn on_result<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut AnonymizationResult) + Send + Sync + 'static,
+    {
+        self.result_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Link subjects and rename their placeholders if configured, then pass
+    /// a result through the registered hooks.
+    pub(crate) fn finish(&self, mut result: AnonymizationResult) -> AnonymizationResult {
+        if self.config.link_subjects || self.config.subject_placeholders {
+            result.subject_ids = subjects::link(&result);
+        }
+        if self.config.subject_placeholders {
+            subjects::rename(&mut result);
+        }
+        if self.config.context_window > 0 {
+            result.contexts = context_windows(&result.anonymized_text, &result.output_spans, self.config.context_window);
+        }
+        for hook in &self.result_hooks {
+            hook(&mut result);
+        }
+        result
+    }
+
     /// Anonymize text by replacing detected PII with placeholders.
     ///
     /// Scans the text for entities matching the configured types and replaces
@@ -193,6 +385,45 @@ impl Anonymizer {
         self.anonymize_with_custom(text, None)
     }
 
+    /// Anonymize text, borrowing it back when there is nothing to replace.
+    ///
+    /// For streams where most texts hold no PII, such as log lines: a text
+    /// without entities is returned as [`CowAnonymization::Borrowed`],
+    /// without copying it or building a mapping and result. Texts with
+    /// entities, and results that carry other information (an exceeded
+    /// detection budget or a trace), are returned as
+    /// [`CowAnonymization::Owned`] like [`anonymize`](Self::anonymize)
+    /// would. With result hooks registered, every result is owned, so the
+    /// hooks see each call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, CowAnonymization, EntityType};
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    ///
+    /// let line = "GET /health 200";
+    /// assert!(matches!(anonymizer.anonymize_cow(line).unwrap(), CowAnonymization::Borrowed(text) if text == line));
+    ///
+    /// let line = "login by jo@x.io";
+    /// let result = anonymizer.anonymize_cow(line).unwrap();
+    /// assert!(!result.text().contains("jo@x.io"));
+    /// assert_eq!(result.into_owned().mapping.len(), 1);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`anonymize`](Self::anonymize).
+    pub fn anonymize_cow<'a>(&self, text: &'a str) -> Result<CowAnonymization<'a>, AnonymaskError> {
+        let detected = self.detect_shared(text, None, self.entity_limit())?;
+        let unchanged = detected.entities.is_empty() && !detected.budget_exceeded && detected.trace.is_empty();
+        if unchanged && self.result_hooks.is_empty() {
+            return Ok(CowAnonymization::Borrowed(text));
+        }
+        Ok(CowAnonymization::Owned(self.result_of(text, detected, None)?))
+    }
+
     /// Anonymize text with both built-in and custom entity types.
     ///
     /// Extends the standard anonymization to include user-defined custom entities.
@@ -232,52 +463,795 @@ impl Anonymizer {
     ///
     /// # Note
     ///
-    /// Custom entity matching is case-sensitive and uses exact substring matching.
-    /// For case-insensitive matching, provide lowercase values and lowercase the text.
-    pub fn anonymize_with_custom(&self, text: &str, custom_entities: Option<&std::collections::HashMap<EntityType, Vec<String>>>) -> Result<AnonymizationResult, AnonymaskError> {
+    /// Custom entity matching uses exact substring matching, case-sensitive
+    /// unless `case_sensitive` is turned off in the configuration. Entity
+    /// values and the mapping keep the casing found in the text.
+    pub fn anonymize_with_custom(&self, text: &str, custom_entities: Option<&HashMap<EntityType, Vec<String>>>) -> Result<AnonymizationResult, AnonymaskError> {
+        self.anonymize_with_replacements(text, custom_entities, None)
+    }
+
+    /// Anonymize text with custom entities, honoring curated replacements.
+    ///
+    /// Like [`anonymize_with_custom`](Self::anonymize_with_custom), but each
+    /// custom value may carry a preferred replacement that is used instead
+    /// of a generated placeholder. The replacement becomes the mapping key,
+    /// so deanonymization works the same way.
+    ///
+    /// With case-insensitive matching, the mapping keeps each casing found
+    /// in the text, so deanonymization restores "ACME CORP" and "Acme Corp"
+    /// exactly as written. The replacement goes to the value as listed, or
+    /// to the first other casing found if it doesn't appear; the remaining
+    /// casings get generated placeholders.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to anonymize
+    /// * `custom_entities` - Map of custom entity types to values
+    ///
+    /// # Returns
+    ///
+    /// An `AnonymizationResult` with all detected entities anonymized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::Anonymizer;
+    /// use anonymask_core::entity::{CustomValue, EntityType};
+    /// use std::collections::HashMap;
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    ///
+    /// let mut custom_entities = HashMap::new();
+    /// custom_entities.insert(
+    ///     EntityType::Custom("company".to_string()),
+    ///     vec![
+    ///         CustomValue::with_replacement("Acme Corp", "Company A"),
+    ///         CustomValue::from("Tech Inc"),
+    ///     ],
+    /// );
+    ///
+    /// let result = anonymizer
+    ///     .anonymize_with_custom_values("Acme Corp bought Tech Inc", &custom_entities)
+    ///     .unwrap();
+    ///
+    /// assert!(result.anonymized_text.starts_with("Company A bought "));
+    /// assert_eq!(result.mapping["Company A"], "Acme Corp");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::AnonymizationError` if two different values
+    /// share the same replacement, since the mapping could not restore both.
+    pub fn anonymize_with_custom_values(
+        &self,
+        text: &str,
+        custom_entities: &HashMap<EntityType, Vec<CustomValue>>,
+    ) -> Result<AnonymizationResult, AnonymaskError> {
+        let (values, replacements) = split_custom_values(custom_entities)?;
+        self.anonymize_with_replacements(text, Some(&values), Some(&replacements))
+    }
+
+    fn anonymize_with_replacements(
+        &self,
+        text: &str,
+        custom_entities: Option<&HashMap<EntityType, Vec<String>>>,
+        replacements: Option<&HashMap<String, String>>,
+    ) -> Result<AnonymizationResult, AnonymaskError> {
         if text.is_empty() {
-            return Ok(AnonymizationResult {
+            return Ok(self.finish(AnonymizationResult {
                 anonymized_text: String::new(),
                 mapping: HashMap::new(),
                 entities: Vec::new(),
-            });
+                output_spans: Vec::new(),
+                subject_ids: Vec::new(),
+                contexts: Vec::new(),
+                status: Status::Complete,
+                truncated: false,
+                budget_exceeded: false,
+                trace: Vec::new(),
+            }));
         }
 
-        let entities = self.detector.detect(text, custom_entities);
+        let detected = self.detect_shared(text, custom_entities, self.entity_limit())?;
+        self.result_of(text, detected, replacements)
+    }
+
+    /// Replace detected entities and build the result of one call.
+    fn result_of(
+        &self,
+        text: &str,
+        detected: Detected,
+        replacements: Option<&HashMap<String, String>>,
+    ) -> Result<AnonymizationResult, AnonymaskError> {
+        let mut unique_values = HashMap::new();
+        let mut counters = self.operation_counters();
+        let anonymized = self.replace_detected(text, detected, replacements, &mut unique_values, counters.as_mut())?;
 
-        let mut placeholder_to_original = HashMap::new();
-        let mut anonymized_text = text.to_string();
+        // Build placeholder to original mapping
+        let placeholder_to_original = unique_values
+            .into_iter()
+            .map(|(original, placeholder)| (placeholder, original))
+            .collect();
+
+        Ok(self.finish(AnonymizationResult {
+            anonymized_text: anonymized.text,
+            mapping: placeholder_to_original,
+            entities: anonymized.entities,
+            output_spans: anonymized.output_spans,
+            subject_ids: Vec::new(),
+            contexts: Vec::new(),
+            status: Status::Complete,
+            truncated: anonymized.truncated,
+            budget_exceeded: anonymized.budget_exceeded,
+            trace: anonymized.trace,
+        }))
+    }
 
-        // Collect unique values and generate placeholders
+    /// Anonymize several documents with one shared mapping.
+    ///
+    /// The same value receives the same placeholder in every document, so
+    /// references stay consistent across the whole batch without gluing the
+    /// documents together.
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - The documents to anonymize
+    ///
+    /// # Returns
+    ///
+    /// A `BatchResult` with one anonymized text and entity list per input
+    /// document (in input order) and a single mapping covering all of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::Anonymizer;
+    /// use anonymask_core::entity::EntityType;
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let batch = anonymizer.anonymize_batch(&[
+    ///     "From john@email.com",
+    ///     "Reply to john@email.com",
+    /// ]).unwrap();
+    ///
+    /// assert_eq!(batch.mapping.len(), 1);
+    /// let placeholder = batch.mapping.keys().next().unwrap();
+    /// assert!(batch.anonymized_texts.iter().all(|t| t.contains(placeholder.as_str())));
+    /// ```
+    pub fn anonymize_batch(&self, texts: &[&str]) -> Result<BatchResult, AnonymaskError> {
         let mut unique_values = HashMap::new();
-        for entity in &entities {
-            if !unique_values.contains_key(&entity.value) {
-                let placeholder = self.generate_placeholder(&entity.entity_type, &entity.value);
-                unique_values.insert(entity.value.clone(), placeholder);
+        let mut counters = self.operation_counters();
+        let mut anonymized_texts = Vec::with_capacity(texts.len());
+        let mut entities = Vec::with_capacity(texts.len());
+        let mut output_spans = Vec::with_capacity(texts.len());
+        let mut truncated = false;
+        let mut budget_exceeded = false;
+
+        for text in texts {
+            let anonymized =
+                self.anonymize_shared(text, None, None, &mut unique_values, counters.as_mut(), self.entity_limit())?;
+            truncated |= anonymized.truncated;
+            budget_exceeded |= anonymized.budget_exceeded;
+            anonymized_texts.push(anonymized.text);
+            entities.push(anonymized.entities);
+            output_spans.push(anonymized.output_spans);
+        }
+
+        Ok(BatchResult {
+            anonymized_texts,
+            mapping: unique_values
+                .into_iter()
+                .map(|(original, placeholder)| (placeholder, original))
+                .collect(),
+            entities,
+            output_spans,
+            status: Status::Complete,
+            truncated,
+            budget_exceeded,
+        })
+    }
+
+    /// Anonymize a large text, stopping early when `token` is cancelled.
+    ///
+    /// The text is processed in segments of about 64 KiB that end at line
+    /// breaks, and the token (including its deadline) is checked before each
+    /// segment. Each segment is searched together with the text following
+    /// it, and ends before any entity crossing its end, so entities are
+    /// found as in `anonymize_with_custom()` wherever the segments fall.
+    /// Placeholders are shared across segments. Context keywords only count
+    /// within the segment of an entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to anonymize
+    /// * `custom_entities` - Optional map of custom entity types to values
+    /// * `token` - Cancellation token, optionally with a deadline
+    ///
+    /// # Returns
+    ///
+    /// An `AnonymizationResult`. If processing was aborted, `status` is
+    /// `Status::Aborted` and `anonymized_text` holds only the processed
+    /// prefix; the unprocessed remainder is left out rather than returned
+    /// unscrubbed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, CancellationToken, Deadline, EntityType, Status};
+    /// use std::time::Duration;
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let token = CancellationToken::new().with_deadline(Deadline::after(Duration::from_secs(5)));
+    ///
+    /// let result = anonymizer
+    ///     .anonymize_cancellable("Contact a@b.io", None, &token)
+    ///     .unwrap();
+    /// assert_eq!(result.status, Status::Complete);
+    /// ```
+    pub fn anonymize_cancellable(
+        &self,
+        text: &str,
+        custom_entities: Option<&HashMap<EntityType, Vec<String>>>,
+        token: &CancellationToken,
+    ) -> Result<AnonymizationResult, AnonymaskError> {
+        self.anonymize_with_progress(text, custom_entities, token, |_| {})
+    }
+
+    /// Like [`anonymize_cancellable`](Self::anonymize_cancellable), calling
+    /// `on_progress` after each segment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, CancellationToken, EntityType};
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let mut last = None;
+    /// anonymizer
+    ///     .anonymize_with_progress("a@b.io", None, &CancellationToken::new(), |p| last = Some(*p))
+    ///     .unwrap();
+    ///
+    /// let last = last.unwrap();
+    /// assert_eq!(last.fraction(), Some(1.0));
+    /// assert_eq!(last.entities_found, 1);
+    /// ```
+    pub fn anonymize_with_progress<F>(
+        &self,
+        text: &str,
+        custom_entities: Option<&HashMap<EntityType, Vec<String>>>,
+        token: &CancellationToken,
+        mut on_progress: F,
+    ) -> Result<AnonymizationResult, AnonymaskError>
+    where
+        F: FnMut(&Progress),
+    {
+        let mut unique_values = HashMap::new();
+        let mut counters = self.operation_counters();
+        let mut anonymized_text = String::new();
+        let mut entities = Vec::new();
+        let mut output_spans = Vec::new();
+        let mut trace = Vec::new();
+        let mut truncated = false;
+        let mut budget_exceeded = false;
+        let mut progress = Progress {
+            total_bytes: Some(text.len()),
+            ..Progress::default()
+        };
+
+        let status = for_each_segment(
+            text,
+            token,
+            |searched| self.detect_shared(searched, custom_entities, None),
+            |offset, segment, mut detected| {
+                // The entity limit applies to the whole text, not each segment
+                if let Some(limit) = self.entity_limit() {
+                    let limit = limit.saturating_sub(entities.len());
+                    for entity in detected.entities.split_off(limit.min(detected.entities.len())) {
+                        detected.truncated = true;
+                        if let Some(decision) = detected.trace.iter_mut().find(|d| {
+                            d.outcome == Outcome::Kept && (d.entity.start, d.entity.end) == (entity.start, entity.end)
+                        }) {
+                            decision.outcome = Outcome::Truncated;
+                        }
+                    }
+                }
+                let anonymized = self.replace_detected(segment, detected, None, &mut unique_values, counters.as_mut())?;
+                truncated |= anonymized.truncated;
+                budget_exceeded |= anonymized.budget_exceeded;
+
+                progress.advance(segment.len(), anonymized.entities.len());
+                if progress.bytes_processed == text.len() {
+                    progress.documents_processed = 1;
+                }
+                on_progress(&progress);
+
+                let output_offset = anonymized_text.len();
+                entities.extend(anonymized.entities.into_iter().map(|mut entity| {
+                    entity.start += offset;
+                    entity.end += offset;
+                    entity
+                }));
+                output_spans.extend(anonymized.output_spans.into_iter().map(|mut span| {
+                    span.start += output_offset;
+                    span.end += output_offset;
+                    span
+                }));
+                trace.extend(anonymized.trace.into_iter().map(|mut decision| {
+                    decision.entity.start += offset;
+                    decision.entity.end += offset;
+                    decision
+                }));
+                anonymized_text.push_str(&anonymized.text);
+                Ok(())
+            },
+        )?;
+
+        Ok(self.finish(AnonymizationResult {
+            anonymized_text,
+            mapping: unique_values
+                .into_iter()
+                .map(|(original, placeholder)| (placeholder, original))
+                .collect(),
+            entities,
+            output_spans,
+            subject_ids: Vec::new(),
+            contexts: Vec::new(),
+            status,
+            truncated,
+            budget_exceeded,
+            trace,
+        }))
+    }
+
+    /// Detect entities like [`detect`](Self::detect), segment by segment as
+    /// in [`anonymize_cancellable`](Self::anonymize_cancellable), failing
+    /// once `token` is cancelled.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn detect_cancellable(
+        &self,
+        text: &str,
+        token: &CancellationToken,
+    ) -> Result<Vec<Entity>, AnonymaskError> {
+        let mut entities = Vec::new();
+        let status = for_each_segment(
+            text,
+            token,
+            |searched| {
+                Ok(Detected {
+                    entities: self.detector.detect_all(searched, None)?,
+                    ..Detected::default()
+                })
+            },
+            |offset, _, detected| {
+                entities.extend(detected.entities.into_iter().map(|mut entity| {
+                    entity.start += offset;
+                    entity.end += offset;
+                    entity
+                }));
+                Ok(())
+            },
+        )?;
+        if let Status::Aborted(reason) = status {
+            return Err(AnonymaskError::AnonymizationError(format!("detection aborted: {:?}", reason)));
+        }
+        if let Some(limit) = self.entity_limit() {
+            entities.truncate(limit);
+        }
+        Ok(entities)
+    }
+
+    /// Anonymize several documents with one shared mapping, stopping early
+    /// when `token` is cancelled.
+    ///
+    /// The token is checked before each document. If processing was
+    /// aborted, the result holds the documents completed so far (a prefix of
+    /// `texts`) and `status` is `Status::Aborted`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, CancellationToken, EntityType, Status};
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let token = CancellationToken::new();
+    /// token.cancel();
+    ///
+    /// let batch = anonymizer.anonymize_batch_cancellable(&["a@b.io"], &token).unwrap();
+    /// assert!(batch.anonymized_texts.is_empty());
+    /// assert!(!batch.status.is_complete());
+    /// ```
+    pub fn anonymize_batch_cancellable(
+        &self,
+        texts: &[&str],
+        token: &CancellationToken,
+    ) -> Result<BatchResult, AnonymaskError> {
+        self.anonymize_batch_with_progress(texts, token, |_| {})
+    }
+
+    /// Like [`anonymize_batch_cancellable`](Self::anonymize_batch_cancellable),
+    /// calling `on_progress` after each document.
+    pub fn anonymize_batch_with_progress<F>(
+        &self,
+        texts: &[&str],
+        token: &CancellationToken,
+        mut on_progress: F,
+    ) -> Result<BatchResult, AnonymaskError>
+    where
+        F: FnMut(&Progress),
+    {
+        let mut progress = Progress {
+            total_bytes: Some(texts.iter().map(|text| text.len()).sum()),
+            ..Progress::default()
+        };
+        let mut unique_values = HashMap::new();
+        let mut counters = self.operation_counters();
+        let mut anonymized_texts = Vec::with_capacity(texts.len());
+        let mut entities = Vec::with_capacity(texts.len());
+        let mut output_spans = Vec::with_capacity(texts.len());
+        let mut truncated = false;
+        let mut budget_exceeded = false;
+        let mut status = Status::Complete;
+
+        for text in texts {
+            if let Some(reason) = token.abort_reason() {
+                status = Status::Aborted(reason);
+                break;
             }
+            let anonymized =
+                self.anonymize_shared(text, None, None, &mut unique_values, counters.as_mut(), self.entity_limit())?;
+            truncated |= anonymized.truncated;
+            budget_exceeded |= anonymized.budget_exceeded;
+            progress.advance(text.len(), anonymized.entities.len());
+            progress.documents_processed += 1;
+            on_progress(&progress);
+
+            anonymized_texts.push(anonymized.text);
+            entities.push(anonymized.entities);
+            output_spans.push(anonymized.output_spans);
         }
 
-        // Build placeholder to original mapping
-        for (original, placeholder) in &unique_values {
-            placeholder_to_original.insert(placeholder.clone(), original.clone());
+        Ok(BatchResult {
+            anonymized_texts,
+            mapping: unique_values
+                .into_iter()
+                .map(|(original, placeholder)| (placeholder, original))
+                .collect(),
+            entities,
+            output_spans,
+            status,
+            truncated,
+            budget_exceeded,
+        })
+    }
+
+    /// Detect entities without anonymizing.
+    ///
+    /// Runs the same detection as `anonymize()` (regex, validators and, if
+    /// configured, the NER model) and returns the entities found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::Anonymizer;
+    /// use anonymask_core::entity::EntityType;
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let entities = anonymizer.detect("Contact a@b.io").unwrap();
+    /// assert_eq!(entities[0].value, "a@b.io");
+    /// ```
+    pub fn detect(&self, text: &str) -> Result<Vec<Entity>, AnonymaskError> {
+        let mut entities = self.detector.detect_all(text, None)?;
+        if let Some(limit) = self.entity_limit() {
+            entities.truncate(limit);
         }
+        Ok(entities)
+    }
+
+    /// Detect PII and wrap it in inline markers instead of replacing it.
+    ///
+    /// Returns e.g. `Mail <pii type="email">john@x.com</pii>`; see the
+    /// [`annotate`](crate::annotate) module for the format and for reading
+    /// it back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, EntityType};
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let annotated = anonymizer.annotate("Mail john@x.com").unwrap();
+    /// assert_eq!(annotated, r#"Mail <pii type="email">john@x.com</pii>"#);
+    /// ```
+    pub fn annotate(&self, text: &str) -> Result<String, AnonymaskError> {
+        Ok(annotate::annotate(text, &self.detect(text)?))
+    }
+
+    /// Anonymize caller-supplied entities, skipping detection.
+    ///
+    /// For pipelines with their own detector (e.g. an ML classifier) that
+    /// want anonymask's placeholder generation, mapping and restoration.
+    /// Spans are validated against the text; see
+    /// [`Replacer::anonymize_entities`] for the rules. Result hooks run as
+    /// for `anonymize()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::InvalidSpan` if any span is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, Entity, EntityType};
+    ///
+    /// let anonymizer = Anonymizer::new(vec![]).unwrap();
+    /// let text = "Patient: Jane Roe";
+    /// let entities = vec![Entity {
+    ///     entity_type: EntityType::Person,
+    ///     value: "Jane Roe".to_string(),
+    ///     start: 9,
+    ///     end: 17,
+    ///     confidence: 0.97,
+    /// }];
+    ///
+    /// let result = anonymizer.anonymize_entities(text, entities).unwrap();
+    /// assert!(result.anonymized_text.starts_with("Patient: PERSON_"));
+    /// assert_eq!(anonymizer.deanonymize(&result.anonymized_text, &result.mapping), text);
+    /// ```
+    pub fn anonymize_entities(
+        &self,
+        text: &str,
+        entities: Vec<Entity>,
+    ) -> Result<AnonymizationResult, AnonymaskError> {
+        let result = self.replacer.anonymize_entities(text, entities)?;
+        Ok(self.finish(result))
+    }
+
+    /// Lazily anonymize a stream of texts with bounded parallelism.
+    ///
+    /// Texts are pulled from `texts` in chunks of one per available CPU,
+    /// anonymized in parallel and yielded in input order, so the iterator
+    /// composes with existing pipelines without buffering the whole input.
+    /// Each text gets its own mapping, as with `anonymize()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - Source of texts to anonymize
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding one `Result<AnonymizationResult>` per input text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::Anonymizer;
+    /// use anonymask_core::entity::EntityType;
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let lines = vec!["a@b.io".to_string(), "no pii".to_string()];
+    ///
+    /// let counts: Vec<usize> = anonymizer
+    ///     .anonymize_iter(lines)
+    ///     .map(|result| result.unwrap().entities.len())
+    ///     .collect();
+    /// assert_eq!(counts, vec![1, 0]);
+    /// ```
+    pub fn anonymize_iter<I>(&self, texts: I) -> AnonymizeIter<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let parallelism = std::thread::available_parallelism().map_or(1, |n| n.get());
+        self.anonymize_iter_with_parallelism(texts, parallelism)
+    }
+
+    /// Like [`anonymize_iter`](Self::anonymize_iter), with an explicit
+    /// number of texts processed concurrently (at least 1). Texts are
+    /// pulled from the source in chunks of that size; no more threads than
+    /// the available parallelism of the machine are started.
+    pub fn anonymize_iter_with_parallelism<I>(
+        &self,
+        texts: I,
+        parallelism: usize,
+    ) -> AnonymizeIter<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        AnonymizeIter::new(self, texts.into_iter(), parallelism)
+    }
+
+    /// Start a stateful session that accumulates the mapping across calls.
+    ///
+    /// See [`AnonymizationSession`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::Anonymizer;
+    /// use anonymask_core::entity::EntityType;
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let mut session = anonymizer.session();
+    /// session.anonymize("Contact john@email.com").unwrap();
+    /// assert_eq!(session.len(), 1);
+    /// ```
+    pub fn session(&self) -> AnonymizationSession<'_> {
+        AnonymizationSession::new(self)
+    }
+
+    /// Continue a session from the accumulated mapping of an earlier one.
+    ///
+    /// For services that keep session state between requests: store
+    /// [`AnonymizationSession::mapping`] after each call and resume from it
+    /// on the next, and values seen before keep their placeholders.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::Anonymizer;
+    /// use anonymask_core::entity::EntityType;
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let mut session = anonymizer.session();
+    /// let first = session.anonymize("Contact john@email.com").unwrap();
+    ///
+    /// let mut resumed = anonymizer.resume_session(&session.mapping());
+    /// let second = resumed.anonymize("Contact john@email.com").unwrap();
+    /// assert_eq!(first.anonymized_text, second.anonymized_text);
+    /// ```
+    pub fn resume_session(&self, mapping: &HashMap<String, String>) -> AnonymizationSession<'_> {
+        AnonymizationSession::resume(self, mapping)
+    }
+
+    /// Continue a session exported with
+    /// [`AnonymizationSession::export_state`], possibly by another node.
+    ///
+    /// See [`SessionState`].
+    pub fn import_session(&self, state: &SessionState) -> AnonymizationSession<'_> {
+        AnonymizationSession::import(self, state)
+    }
+
+    /// Start a [`Batch`](crate::store::Batch) that anonymizes several
+    /// documents and commits their mappings to `store` in one transaction.
+    pub fn batch<'a>(&'a self, store: &'a dyn crate::store::MappingStore) -> crate::store::Batch<'a> {
+        crate::store::Batch::new(self, store)
+    }
+
+    /// The configured `max_entities`, or `None` if unlimited.
+    pub(crate) fn entity_limit(&self) -> Option<usize> {
+        (self.config.max_entities > 0).then_some(self.config.max_entities)
+    }
+
+    /// Fresh counters for one operation, if `reset_counters` is set.
+    pub(crate) fn operation_counters(&self) -> Option<Counters> {
+        self.replacer.operation_counters()
+    }
 
-        // Replace in text
-        for (original, placeholder) in &unique_values {
-            anonymized_text = anonymized_text.replace(original, placeholder);
+    /// Anonymize one text, reusing and extending a value -> placeholder table.
+    ///
+    /// New values take their placeholder from `replacements` when present,
+    /// and are numbered with the operation's `counters` if given.
+    /// Each detected span is replaced in place, and the placeholder's
+    /// position in the output is recorded alongside the entity. With a
+    /// `limit`, only the first entities by position are replaced.
+    pub(crate) fn anonymize_shared(
+        &self,
+        text: &str,
+        custom_entities: Option<&HashMap<EntityType, Vec<String>>>,
+        replacements: Option<&HashMap<String, String>>,
+        unique_values: &mut HashMap<String, String>,
+        counters: Option<&mut Counters>,
+        limit: Option<usize>,
+    ) -> Result<Anonymized, AnonymaskError> {
+        if text.is_empty() {
+            return Ok(Anonymized::default());
         }
+        let detected = self.detect_shared(text, custom_entities, limit)?;
+        self.replace_detected(text, detected, replacements, unique_values, counters)
+    }
 
-        Ok(AnonymizationResult {
-            anonymized_text,
-            mapping: placeholder_to_original,
+    /// The detection half of [`anonymize_shared`](Self::anonymize_shared):
+    /// the entities to replace, without touching the text.
+    fn detect_shared(
+        &self,
+        text: &str,
+        custom_entities: Option<&HashMap<EntityType, Vec<String>>>,
+        limit: Option<usize>,
+    ) -> Result<Detected, AnonymaskError> {
+        let mut budget = Budget::start(&self.config.detection_budget);
+        let mut trace = Trace::new(self.config.trace);
+        let mut entities = self.detector.detect_within(text, custom_entities, &mut budget, &mut trace)?;
+        let truncated = limit.is_some_and(|limit| entities.len() > limit);
+        if let Some(limit) = limit {
+            for entity in entities.split_off(limit.min(entities.len())) {
+                trace.record(&entity, Outcome::Truncated);
+            }
+        }
+        for entity in &entities {
+            trace.record(entity, Outcome::Kept);
+        }
+        Ok(Detected {
+            entities,
+            truncated,
+            budget_exceeded: budget.exceeded(),
+            trace: trace.into_decisions(),
+        })
+    }
+
+    /// The replacement half of [`anonymize_shared`](Self::anonymize_shared).
+    fn replace_detected(
+        &self,
+        text: &str,
+        detected: Detected,
+        replacements: Option<&HashMap<String, String>>,
+        unique_values: &mut HashMap<String, String>,
+        counters: Option<&mut Counters>,
+    ) -> Result<Anonymized, AnonymaskError> {
+        let Detected {
             entities,
+            truncated,
+            budget_exceeded,
+            trace,
+        } = detected;
+
+        // Case-insensitive matches may differ from the listed value, and
+        // each casing needs its own placeholder to be restored exactly
+        let folded;
+        let replacements = match replacements {
+            Some(table) if !self.config.case_sensitive && !table.is_empty() => {
+                folded = fold_replacements(table, &entities, unique_values);
+                Some(&folded)
+            }
+            other => other,
+        };
+
+        let (anonymized_text, output_spans) =
+            self.replacer.replace(text, &entities, unique_values, replacements, counters)?;
+
+        Ok(Anonymized {
+            text: anonymized_text,
+            entities,
+            output_spans,
+            truncated,
+            budget_exceeded,
+            trace,
         })
     }
 
+    /// The type of values under `key`, if a configured key rule matches it.
+    pub(crate) fn key_type(&self, key: &str) -> Option<&EntityType> {
+        self.detector.key_type(key)
+    }
+
+    /// The action of the first field policy selecting the field at `path`.
+    pub(crate) fn field_action(&self, path: &[String]) -> Option<&FieldAction> {
+        self.field_policies
+            .iter()
+            .find(|(selector, _)| selector.matches(path))
+            .map(|(_, action)| action)
+    }
+
+    /// The placeholder of an entity's value, reusing and extending a
+    /// value -> placeholder table like [`anonymize_shared`](Self::anonymize_shared).
+    pub(crate) fn placeholder_for(
+        &self,
+        entity: &Entity,
+        unique_values: &mut HashMap<String, String>,
+        counters: Option<&mut Counters>,
+    ) -> String {
+        if let Some(placeholder) = unique_values.get(&entity.value) {
+            return placeholder.clone();
+        }
+        let placeholder = self.replacer.placeholder(&entity.entity_type, &entity.value, counters);
+        unique_values.insert(entity.value.clone(), placeholder.clone());
+        placeholder
+    }
+
     /// Restore original PII values using the anonymization mapping.
     ///
     /// Replaces all placeholders in the text with their original values
     /// from the mapping. This is the inverse operation of `anonymize()`.
+    /// Placeholders must match exactly unless `tolerant_deanonymization`
+    /// is configured, which also restores e.g. `Email 1` for `EMAIL_1`.
     ///
     /// # Arguments
     ///
@@ -321,67 +1295,245 @@ impl Anonymizer {
     /// If the mapping is incomplete (missing placeholders), those
     /// placeholders will remain in the output text unchanged.
     pub fn deanonymize(&self, text: &str, mapping: &HashMap<String, String>) -> String {
-        let mut deanonymized_text = text.to_string();
-
-        // Sort placeholders by length descending to avoid partial replacements
-        let mut placeholders: Vec<_> = mapping.keys().collect();
-        placeholders.sort_by_key(|p| std::cmp::Reverse(p.len()));
-
-        for placeholder in placeholders {
-            if let Some(original) = mapping.get(placeholder) {
-                deanonymized_text = deanonymized_text.replace(placeholder, original);
-            }
-        }
+        self.replacer.deanonymize(text, mapping)
+    }
 
-        deanonymized_text
+    /// Restore original PII values, failing on placeholders missing from the mapping.
+    ///
+    /// Like [`deanonymize`](Self::deanonymize), but first scans the text for
+    /// tokens shaped like placeholders of the configured format. If any of
+    /// them has no mapping entry (e.g. an LLM mangled or invented one), an
+    /// error is returned instead of silently leaving it in the output.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Anonymized text containing placeholders
+    /// * `mapping` - HashMap mapping placeholders to original values
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - Text with all placeholders replaced by original values
+    /// * `Err(AnonymaskError::MappingNotFound)` - The first unknown placeholder and its byte position
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, AnonymaskError, PlaceholderFormat};
+    /// use anonymask_core::entity::EntityType;
+    ///
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_placeholder_format(PlaceholderFormat::Short)
+    ///     .build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+    /// let result = anonymizer.anonymize("Email: user@example.com").unwrap();
+    ///
+    /// let restored = anonymizer.deanonymize_strict(&result.anonymized_text, &result.mapping);
+    /// assert_eq!(restored.unwrap(), "Email: user@example.com");
+    ///
+    /// let err = anonymizer.deanonymize_strict("Ask EMAIL_7", &result.mapping).unwrap_err();
+    /// assert!(matches!(err, AnonymaskError::MappingNotFound { position: 4, .. }));
+    /// ```
+    ///
+    /// # Note
+    ///
+    /// Detection is purely by shape, so text that happens to look like a
+    /// placeholder (e.g. `COVID_19` with the short format) is reported too.
+    pub fn deanonymize_strict(
+        &self,
+        text: &str,
+        mapping: &HashMap<String, String>,
+    ) -> Result<String, AnonymaskError> {
+        self.replacer.deanonymize_strict(text, mapping)
     }
 
-    /// Generate a unique placeholder for an entity.
+    /// Scan anonymized text for original values that survived replacement.
     ///
-    /// Creates a placeholder based on the configured format.
-    /// Supports Standard (TYPE_UUID), Short (TYPE_COUNTER), and Custom formats.
+    /// Final safety net before text leaves your boundary. See
+    /// [`audit::audit_leakage`] for details on which variants are detected.
     ///
     /// # Arguments
     ///
-    /// * `entity_type` - The type of entity being replaced
-    /// * `_value` - The actual PII value (currently unused but reserved for future deterministic generation)
+    /// * `anonymized_text` - Text produced by `anonymize()`
+    /// * `mapping` - HashMap mapping placeholders to original values
     ///
     /// # Returns
     ///
-    /// A unique placeholder string based on the configuration.
+    /// All leaks found, sorted by position. Empty if the text is clean.
     ///
     /// # Examples
     ///
-    /// - Standard: "EMAIL_a1b2c3d4e5f6..."
-    /// - Short: "EMAIL_1", "EMAIL_2", etc.
-    /// - Custom: "[EMAIL:1]" (with template "[{type}:{counter}]")
-    fn generate_placeholder(&self, entity_type: &EntityType, _value: &str) -> String {
-        let type_prefix = match entity_type {
-            EntityType::Email => "EMAIL",
-            EntityType::Phone => "PHONE",
-            EntityType::Ssn => "SSN",
-            EntityType::CreditCard => "CREDIT_CARD",
-            EntityType::IpAddress => "IP_ADDRESS",
-            EntityType::Url => "URL",
-            EntityType::Custom(name) => name,
-        };
+    /// ```
+    /// use anonymask_core::Anonymizer;
+    /// use anonymask_core::entity::EntityType;
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let result = anonymizer.anonymize("Email: user@example.com").unwrap();
+    ///
+    /// let leaks = anonymizer.audit_leakage(&result.anonymized_text, &result.mapping);
+    /// assert!(leaks.is_empty());
+    /// ```
+    pub fn audit_leakage(&self, anonymized_text: &str, mapping: &HashMap<String, String>) -> Vec<Leak> {
+        audit::audit_leakage(anonymized_text, mapping)
+    }
+}
+
+/// The entities of one text found by [`Anonymizer::detect_shared`].
+#[derive(Default)]
+struct Detected {
+    entities: Vec<Entity>,
+    truncated: bool,
+    budget_exceeded: bool,
+    trace: Vec<Decision>,
+}
 
-        match &self.config.placeholder_format {
-            PlaceholderFormat::Standard => {
-                format!("{}_{}", type_prefix.to_uppercase(), Uuid::new_v4().simple())
+/// Run `detect` over `text` in segments of about 64 KiB, checking `token`
+/// before each, and pass every segment to `on_segment` with its offset and
+/// the entities found in it.
+///
+/// Each segment is searched together with the text following it and ends
+/// before any entity crossing its end, so entities are found wherever the
+/// segments fall.
+fn for_each_segment(
+    text: &str,
+    token: &CancellationToken,
+    mut detect: impl FnMut(&str) -> Result<Detected, AnonymaskError>,
+    mut on_segment: impl FnMut(usize, &str, Detected) -> Result<(), AnonymaskError>,
+) -> Result<Status, AnonymaskError> {
+    const SEGMENT_SIZE: usize = 64 * 1024;
+    // Text past the end of a segment searched for entities crossing it
+    const LOOKAHEAD: usize = 4 * 1024;
+
+    let mut offset = 0;
+    while offset < text.len() {
+        if let Some(reason) = token.abort_reason() {
+            return Ok(Status::Aborted(reason));
+        }
+
+        let rest = &text[offset..];
+        let mut end = cancel::segment_end(rest, SEGMENT_SIZE);
+        let searched = &rest[..cancel::ceil_char_boundary(rest, (end + LOOKAHEAD).min(rest.len()))];
+        let mut detected = detect(searched)?;
+        if searched.len() > end {
+            // End the segment before an entity crossing its end, at the
+            // start of its line if there is one, so the next segment sees
+            // the entity whole along with its context
+            let crosses = |e: &Entity, at: usize| e.start < at && e.end > at;
+            if let Some(crossing) = detected.entities.iter().find(|e| crosses(e, end)) {
+                // Entities don't overlap, so none crosses its start
+                end = crossing.start;
+                if let Some(line_start) = rest[..end].rfind('\n').map(|i| i + 1) {
+                    if !detected.entities.iter().any(|e| crosses(e, line_start)) {
+                        end = line_start;
+                    }
+                }
+                if end == 0 {
+                    // Longer than the segment; take it as it is
+                    end = crossing.end;
+                }
             }
-            PlaceholderFormat::Short => {
-                let count = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
-                format!("{}_{}", type_prefix.to_uppercase(), count)
+            detected.entities.retain(|e| e.end <= end);
+            detected.trace.retain(|d| d.entity.end <= end);
+        }
+
+        on_segment(offset, &rest[..end], detected)?;
+        offset += end;
+    }
+    Ok(Status::Complete)
+}
+
+/// One text anonymized by [`Anonymizer::anonymize_shared`].
+#[derive(Debug, Default)]
+pub(crate) struct Anonymized {
+    pub(crate) text: String,
+    pub(crate) entities: Vec<Entity>,
+    pub(crate) output_spans: Vec<OutputSpan>,
+    /// Whether entities past the limit were left in the text
+    pub(crate) truncated: bool,
+    /// Whether detection stopped at the detection budget
+    pub(crate) budget_exceeded: bool,
+    /// Candidate decisions, with tracing on
+    pub(crate) trace: Vec<Decision>,
+}
+
+/// Up to `chars` characters of `text` on either side of each span.
+fn context_windows(text: &str, spans: &[OutputSpan], chars: usize) -> Vec<EntityContext> {
+    spans
+        .iter()
+        .map(|span| {
+            let before = text[..span.start].char_indices().rev().nth(chars - 1).map_or(0, |(i, _)| i);
+            let after = text[span.end..].char_indices().nth(chars).map_or(text.len(), |(i, _)| span.end + i);
+            EntityContext {
+                before: text[before..span.start].to_string(),
+                after: text[span.end..after].to_string(),
             }
-            PlaceholderFormat::Custom(template) => {
-                let count = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
-                let uuid = Uuid::new_v4().simple().to_string();
-                template
-                    .replace("{type}", &type_prefix.to_uppercase())
-                    .replace("{uuid}", &uuid)
-                    .replace("{counter}", &count.to_string())
+        })
+        .collect()
+}
+
+/// Extend curated replacements to other casings of their values.
+///
+/// A replacement is given to at most one surface form: the listed value if
+/// it's among `entities`, otherwise the first other casing found. Forms left
+/// without a replacement get generated placeholders.
+fn fold_replacements(
+    replacements: &HashMap<String, String>,
+    entities: &[Entity],
+    unique_values: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut claimed: HashSet<&str> = unique_values.values().map(String::as_str).collect();
+    claimed.extend(
+        entities
+            .iter()
+            .filter_map(|entity| replacements.get(&entity.value))
+            .map(String::as_str),
+    );
+
+    let mut folded = replacements.clone();
+    for entity in entities {
+        if folded.contains_key(&entity.value) {
+            continue;
+        }
+        let curated = replacements
+            .iter()
+            .find(|(value, _)| value.eq_ignore_ascii_case(&entity.value));
+        if let Some((_, replacement)) = curated {
+            if claimed.insert(replacement.as_str()) {
+                folded.insert(entity.value.clone(), replacement.clone());
             }
         }
     }
-}
\ No newline at end of file
+    folded
+}
+
+type SplitCustomValues = (HashMap<EntityType, Vec<String>>, HashMap<String, String>);
+
+/// Split custom values into plain values for detection and a
+/// value -> replacement table.
+fn split_custom_values(
+    custom_entities: &HashMap<EntityType, Vec<CustomValue>>,
+) -> Result<SplitCustomValues, AnonymaskError> {
+    let mut values = HashMap::new();
+    let mut replacements: HashMap<String, String> = HashMap::new();
+    let mut claimed: HashMap<&str, &str> = HashMap::new();
+
+    for (entity_type, custom_values) in custom_entities {
+        let mut type_values = Vec::with_capacity(custom_values.len());
+        for custom in custom_values {
+            type_values.push(custom.value.clone());
+            if let Some(replacement) = &custom.replacement {
+                if let Some(previous) = claimed.insert(replacement, &custom.value) {
+                    if previous != custom.value {
+                        return Err(AnonymaskError::AnonymizationError(format!(
+                            "Replacement '{}' is assigned to more than one value",
+                            replacement
+                        )));
+                    }
+                }
+                replacements.insert(custom.value.clone(), replacement.clone());
+            }
+        }
+        values.insert(entity_type.clone(), type_values);
+    }
+
+    Ok((values, replacements))
+}
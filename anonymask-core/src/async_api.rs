@@ -0,0 +1,173 @@
+//! Async API for tokio-based services (feature `tokio`).
+//!
+//! Detection is CPU-bound, so running it directly on an async executor
+//! stalls other tasks. These methods move the work to tokio's blocking
+//! pool and await the result.
+//!
+//! Dropping the returned future cancels the call: work that hasn't started
+//! yet is skipped, and work already running stops at the next segment of
+//! about 64 KiB, as with [`CancellationToken`]. This makes the methods
+//! compose with `tokio::time::timeout` and `tokio::select!`.
+
+use crate::anonymizer::Anonymizer;
+use crate::cancel::CancellationToken;
+use crate::entity::{AnonymizationResult, Entity};
+use crate::error::AnonymaskError;
+use std::sync::Arc;
+
+impl Anonymizer {
+    /// Anonymize text on tokio's blocking pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to anonymize
+    ///
+    /// # Returns
+    ///
+    /// The same `AnonymizationResult` as [`anonymize`](Self::anonymize),
+    /// with the text processed in segments as by
+    /// [`anonymize_cancellable`](Self::anonymize_cancellable).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, EntityType};
+    /// use std::sync::Arc;
+    ///
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let anonymizer = Arc::new(Anonymizer::new(vec![EntityType::Email]).unwrap());
+    /// let result = anonymizer.anonymize_async("Contact a@b.io").await.unwrap();
+    /// assert_eq!(result.entities.len(), 1);
+    /// # });
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `anonymize()`, or `AnonymaskError::AnonymizationError`
+    /// if the blocking task panicked.
+    pub async fn anonymize_async(
+        self: &Arc<Self>,
+        text: impl Into<String>,
+    ) -> Result<AnonymizationResult, AnonymaskError> {
+        let text = text.into();
+        self.run_blocking(move |anonymizer, token| {
+            anonymizer.anonymize_cancellable(&text, None, token)
+        })
+        .await
+    }
+
+    /// Detect entities on tokio's blocking pool without anonymizing.
+    ///
+    /// See [`detect`](Self::detect).
+    pub async fn detect_async(
+        self: &Arc<Self>,
+        text: impl Into<String>,
+    ) -> Result<Vec<Entity>, AnonymaskError> {
+        let text = text.into();
+        self.run_blocking(move |anonymizer, token| anonymizer.detect_cancellable(&text, token))
+            .await
+    }
+
+    async fn run_blocking<T, F>(self: &Arc<Self>, work: F) -> Result<T, AnonymaskError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Anonymizer, &CancellationToken) -> Result<T, AnonymaskError> + Send + 'static,
+    {
+        let anonymizer = Arc::clone(self);
+        let guard = CancelOnDrop::default();
+        let token = guard.0.clone();
+
+        let handle = tokio::task::spawn_blocking(move || {
+            if token.is_cancelled() {
+                return Err(AnonymaskError::AnonymizationError(
+                    "cancelled before start".to_string(),
+                ));
+            }
+            work(&anonymizer, &token)
+        });
+
+        let result = handle.await.map_err(|e| {
+            AnonymaskError::AnonymizationError(format!("blocking task failed: {}", e))
+        })?;
+        drop(guard);
+        result
+    }
+}
+
+/// Cancels the blocking task if the awaiting future is dropped.
+#[derive(Default)]
+struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cancel::AbortReason;
+    use crate::ner::NerBackend;
+    use crate::{AnonymaskError, Anonymizer, AnonymizerConfig, Entity, EntityType, Status};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{mpsc, Arc, Mutex};
+
+    /// Holds its first call until released, counting calls.
+    struct GatedNer {
+        calls: AtomicUsize,
+        started: Mutex<mpsc::Sender<()>>,
+        release: Mutex<mpsc::Receiver<()>>,
+    }
+
+    impl NerBackend for GatedNer {
+        fn detect(&self, _: &str, _: &[EntityType]) -> Result<Vec<Entity>, AnonymaskError> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                self.started.lock().unwrap().send(()).unwrap();
+                self.release.lock().unwrap().recv().unwrap();
+            }
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_async_calls() {
+        let anonymizer = Arc::new(Anonymizer::new(vec![EntityType::Email]).unwrap());
+
+        let (first, second) = tokio::join!(
+            anonymizer.anonymize_async("one a@b.io"),
+            anonymizer.detect_async("two c@d.io and e@f.io"),
+        );
+
+        assert_eq!(first.unwrap().entities.len(), 1);
+        assert_eq!(second.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dropping_the_future_stops_running_work() {
+        let (started_tx, started_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel();
+        let ner = Arc::new(GatedNer {
+            calls: AtomicUsize::new(0),
+            started: Mutex::new(started_tx),
+            release: Mutex::new(release_rx),
+        });
+        let (done_tx, done_rx) = mpsc::channel();
+        let anonymizer = Anonymizer::with_ner(vec![EntityType::Person], AnonymizerConfig::default(), Arc::clone(&ner))
+            .unwrap()
+            .on_result(move |result| done_tx.send(result.status).unwrap());
+        let anonymizer = Arc::new(anonymizer);
+
+        // About 1 MiB, many segments
+        let text = "nobody is named here\n".repeat(50_000);
+        let started = tokio::task::spawn_blocking(move || started_rx.recv());
+        tokio::select! {
+            _ = anonymizer.anonymize_async(text) => panic!("the call should still be running"),
+            _ = started => {}
+        }
+        release_tx.send(()).unwrap();
+
+        let status = done_rx.recv().unwrap();
+        assert_eq!(status, Status::Aborted(AbortReason::Cancelled));
+        assert_eq!(ner.calls.load(Ordering::SeqCst), 1);
+    }
+}
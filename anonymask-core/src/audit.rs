@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How a leaked value was found in the anonymized text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LeakKind {
+    /// The original value appears verbatim
+    Exact,
+    /// The original value appears with different ASCII casing
+    CaseInsensitive,
+    /// The original value appears percent-encoded (e.g. `john%40email.com`)
+    PercentEncoded,
+}
+
+/// A raw mapping value that survived anonymization.
+///
+/// # Fields
+///
+/// * `placeholder` - The placeholder that should have replaced the value
+/// * `kind` - How the value was found
+/// * `start` - Starting byte index in the anonymized text
+/// * `end` - Ending byte index in the anonymized text
+///
+/// The leaked value itself is deliberately not stored; use the placeholder
+/// to look it up in the mapping if needed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Leak {
+    /// The placeholder whose original value was found
+    pub placeholder: String,
+    /// How the value was found
+    pub kind: LeakKind,
+    /// Starting position in the anonymized text (byte index)
+    pub start: usize,
+    /// Ending position in the anonymized text (byte index)
+    pub end: usize,
+}
+
+/// Scan anonymized text for original values from the mapping that survived.
+///
+/// Acts as a final safety net before text leaves a trust boundary: any
+/// mapping value found in the output (verbatim, with different casing, or
+/// percent-encoded) is reported. Occurrences that lie entirely inside a
+/// placeholder are ignored, so short values such as `"1"` don't trigger on
+/// `EMAIL_1`.
+///
+/// # Arguments
+///
+/// * `anonymized_text` - Text produced by anonymization
+/// * `mapping` - HashMap mapping placeholders to original values
+///
+/// # Returns
+///
+/// All leaks found, sorted by position. An empty vector means the text is clean.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::audit::audit_leakage;
+/// use std::collections::HashMap;
+///
+/// let mut mapping = HashMap::new();
+/// mapping.insert("EMAIL_1".to_string(), "john@email.com".to_string());
+///
+/// assert!(audit_leakage("Contact EMAIL_1", &mapping).is_empty());
+///
+/// let leaks = audit_leakage("Contact EMAIL_1 or JOHN@EMAIL.COM", &mapping);
+/// assert_eq!(leaks.len(), 1);
+/// assert_eq!(leaks[0].placeholder, "EMAIL_1");
+/// ```
+pub fn audit_leakage(anonymized_text: &str, mapping: &HashMap<String, String>) -> Vec<Leak> {
+    let placeholder_spans = find_placeholder_spans(anonymized_text, mapping);
+    let lowered_text = anonymized_text.to_ascii_lowercase();
+
+    let mut leaks = Vec::new();
+    for (placeholder, value) in mapping {
+        if value.is_empty() {
+            continue;
+        }
+
+        // Lowercasing ASCII keeps byte offsets identical to the original text
+        let lowered_value = value.to_ascii_lowercase();
+        for (start, _) in lowered_text.match_indices(&lowered_value) {
+            let end = start + value.len();
+            let kind = if &anonymized_text[start..end] == value {
+                LeakKind::Exact
+            } else {
+                LeakKind::CaseInsensitive
+            };
+            leaks.push(Leak {
+                placeholder: placeholder.clone(),
+                kind,
+                start,
+                end,
+            });
+        }
+
+        let encoded = percent_encode(value);
+        if encoded != *value {
+            let lowered_encoded = encoded.to_ascii_lowercase();
+            for (start, _) in lowered_text.match_indices(&lowered_encoded) {
+                leaks.push(Leak {
+                    placeholder: placeholder.clone(),
+                    kind: LeakKind::PercentEncoded,
+                    start,
+                    end: start + encoded.len(),
+                });
+            }
+        }
+    }
+
+    leaks.retain(|leak| {
+        !placeholder_spans
+            .iter()
+            .any(|&(start, end)| start <= leak.start && leak.end <= end)
+    });
+    leaks.sort_by_key(|leak| (leak.start, leak.end));
+    leaks
+}
+
+fn find_placeholder_spans(text: &str, mapping: &HashMap<String, String>) -> Vec<(usize, usize)> {
+    mapping
+        .keys()
+        .filter(|placeholder| !placeholder.is_empty())
+        .flat_map(|placeholder| {
+            text.match_indices(placeholder.as_str())
+                .map(|(start, _)| (start, start + placeholder.len()))
+        })
+        .collect()
+}
+
+/// Percent-encode every byte outside the RFC 3986 unreserved set.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_text_has_no_leaks() {
+        let mapping = mapping(&[("EMAIL_1", "john@email.com")]);
+        assert!(audit_leakage("Contact EMAIL_1 today", &mapping).is_empty());
+    }
+
+    #[test]
+    fn test_detects_exact_and_case_variants() {
+        let mapping = mapping(&[("EMAIL_1", "john@email.com")]);
+        let leaks = audit_leakage("john@email.com and John@Email.com", &mapping);
+
+        assert_eq!(leaks.len(), 2);
+        assert_eq!(leaks[0].kind, LeakKind::Exact);
+        assert_eq!((leaks[0].start, leaks[0].end), (0, 14));
+        assert_eq!(leaks[1].kind, LeakKind::CaseInsensitive);
+    }
+
+    #[test]
+    fn test_detects_percent_encoded_value() {
+        let mapping = mapping(&[("EMAIL_1", "john@email.com")]);
+        let leaks = audit_leakage("GET /?to=john%40email.com", &mapping);
+
+        assert_eq!(leaks.len(), 1);
+        assert_eq!(leaks[0].kind, LeakKind::PercentEncoded);
+    }
+
+    #[test]
+    fn test_ignores_values_inside_placeholders() {
+        let mapping = mapping(&[("PHONE_1", "1"), ("EMAIL_2", "a@b.io")]);
+        assert!(audit_leakage("PHONE_1 EMAIL_2", &mapping).is_empty());
+    }
+}
@@ -0,0 +1,938 @@
+//! Anonymization of documents whose format isn't known in advance.
+//!
+//! Generic ingestion endpoints receive JSON payloads, CSV exports, HTML
+//! pages, logfmt logs and free text alike. [`Anonymizer::anonymize_auto`]
+//! recognizes the format from the content and hands the document to the
+//! matching handler, so markup, keys and delimiters survive and only the
+//! content is anonymized. The result reports the format that was chosen.
+//! Configured [`FieldPolicy`](crate::config::FieldPolicy) selectors apply
+//! in every structured format.
+//!
+//! [`sniff`] tries the formats in this order:
+//!
+//! * JSON: the input parses as one JSON value
+//! * NDJSON: every non-blank line parses as a JSON value
+//! * HTML: the input starts with a tag, a comment or a doctype
+//! * logfmt: the first non-blank line, and at least half of the first 20,
+//!   are each at least two `key=value` pairs
+//! * CSV (feature `csv`): the first 20 records have the same number of
+//!   fields, at least two, for one of the delimiters `,` `;` tab and `|`,
+//!   and no field starts with a space (which rules out most prose)
+//! * Text: anything else
+
+use crate::anonymizer::{Anonymized, Anonymizer};
+use crate::config::FieldAction;
+use crate::entity::{Entity, EntityType, OutputSpan};
+use crate::error::AnonymaskError;
+use crate::replacer::Counters;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Lines or records looked at to recognize logfmt and CSV.
+const SNIFF_LINES: usize = 20;
+
+/// HTML elements that have no end tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// Formats recognized by [`sniff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    /// One JSON document, handled like [`Anonymizer::anonymize_json`]
+    Json,
+    /// Newline-delimited JSON, handled like [`Anonymizer::anonymize_ndjson`]
+    Ndjson,
+    /// Delimited values with a sniffed delimiter, handled like
+    /// `Anonymizer::csv`; only recognized with the `csv` feature
+    Csv,
+    /// HTML or XML: text, comments, `<script>` contents and attribute
+    /// values are anonymized; `<style>` contents are kept
+    Html,
+    /// `key=value` log lines: values are anonymized, and lines that aren't
+    /// logfmt are anonymized as text
+    Logfmt,
+    /// Anything else, handled like [`Anonymizer::anonymize`]
+    Text,
+}
+
+/// Result of anonymizing a document with [`Anonymizer::anonymize_auto`].
+///
+/// # Fields
+///
+/// * `format` - The format the input was recognized as
+/// * `output` - The document with PII replaced by placeholders, in the same format
+/// * `mapping` - HashMap mapping placeholders back to original values
+/// * `entities` - Detected entities, each with its location
+/// * `truncated` - Whether the document hit the `max_entities` limit
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AutoAnonymizationResult {
+    pub format: Format,
+    pub output: String,
+    pub mapping: HashMap<String, String>,
+    pub entities: Vec<AutoEntity>,
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// An entity found by [`Anonymizer::anonymize_auto`].
+///
+/// For text, HTML and logfmt, positions are byte offsets in the input.
+/// For the other formats they are byte offsets within the string or field
+/// that the other fields locate, as in the results of their handlers.
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AutoEntity {
+    /// JSON Pointer of the string, for JSON and NDJSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// 0-based line of the record, for NDJSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 0-based data row, not counting the header, for CSV
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row: Option<usize>,
+    /// 0-based column, for CSV
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// The entity
+    #[serde(flatten)]
+    pub entity: Entity,
+}
+
+impl AutoEntity {
+    fn new(entity: Entity) -> Self {
+        Self {
+            path: None,
+            line: None,
+            row: None,
+            column: None,
+            entity,
+        }
+    }
+}
+
+/// Recognize the format of a document from its content.
+///
+/// See the [module documentation](self) for the rules.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::auto::{sniff, Format};
+///
+/// assert_eq!(sniff("{\"user\": \"jo@x.io\"}"), Format::Json);
+/// assert_eq!(sniff("<p>Hello</p>"), Format::Html);
+/// assert_eq!(sniff("level=info msg=\"login\"\nlevel=warn msg=retry\n"), Format::Logfmt);
+/// assert_eq!(sniff("Hello, world.\nSee you, Jo."), Format::Text);
+/// ```
+pub fn sniff(input: &str) -> Format {
+    let content = input.trim_start_matches('\u{feff}').trim_start();
+    if content.is_empty() {
+        return Format::Text;
+    }
+    if content.starts_with(['{', '[']) {
+        if serde_json::from_str::<Value>(content).is_ok() {
+            return Format::Json;
+        }
+        if content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .all(|line| serde_json::from_str::<Value>(line).is_ok())
+        {
+            return Format::Ndjson;
+        }
+    }
+    let mut chars = content.chars();
+    if chars.next() == Some('<') && chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '!' || c == '?') {
+        return Format::Html;
+    }
+    let logfmt: Vec<bool> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(SNIFF_LINES)
+        .map(|line| logfmt_pairs(line).is_some_and(|pairs| pairs.len() >= 2))
+        .collect();
+    if logfmt[0] && logfmt.iter().filter(|&&is_logfmt| is_logfmt).count() * 2 >= logfmt.len() {
+        return Format::Logfmt;
+    }
+    #[cfg(feature = "csv")]
+    if csv_dialect(content).is_some() {
+        return Format::Csv;
+    }
+    Format::Text
+}
+
+impl Anonymizer {
+    /// Anonymize a document in any supported format.
+    ///
+    /// The format is recognized with [`sniff`] and the document is
+    /// anonymized by that format's handler, with one mapping for the whole
+    /// document. `output` has the input's format: JSON documents with
+    /// changes are re-serialized (pretty-printed if the input spanned
+    /// several lines), which may reorder object keys, and everything else
+    /// is kept byte for byte around the replaced values. Key rules apply to
+    /// JSON keys, CSV headers and logfmt keys.
+    ///
+    /// Placeholders are escaped where the format requires it (in HTML and
+    /// quoted logfmt values, or CSV fields that need quotes), so with plain
+    /// placeholder formats such as the default,
+    /// [`deanonymize`](Self::deanonymize) restores `output` directly.
+    ///
+    /// # Errors
+    ///
+    /// * `AnonymaskError::IoError` - If the input isn't UTF-8
+    /// * The errors of [`Anonymizer::anonymize`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, EntityType};
+    /// use anonymask_core::auto::Format;
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    ///
+    /// let result = anonymizer.anonymize_auto(b"<a href=\"mailto:jo@x.io\">Write us</a>").unwrap();
+    /// assert_eq!(result.format, Format::Html);
+    /// assert!(!result.output.contains("jo@x.io"));
+    ///
+    /// let result = anonymizer.anonymize_auto(b"{\"from\": \"jo@x.io\"}").unwrap();
+    /// assert_eq!(result.format, Format::Json);
+    /// assert_eq!(result.entities[0].path.as_deref(), Some("/from"));
+    /// ```
+    pub fn anonymize_auto(&self, input: &[u8]) -> Result<AutoAnonymizationResult, AnonymaskError> {
+        let input = std::str::from_utf8(input)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let format = sniff(input);
+        match format {
+            Format::Json => {
+                let value: Value = serde_json::from_str(input.trim_start_matches('\u{feff}')).map_err(std::io::Error::from)?;
+                let result = self.anonymize_json(&value)?;
+                let output = if result.entities.is_empty() {
+                    input.to_string()
+                } else if input.trim().contains('\n') {
+                    serde_json::to_string_pretty(&result.value).map_err(std::io::Error::from)?
+                } else {
+                    serde_json::to_string(&result.value).map_err(std::io::Error::from)?
+                };
+                Ok(AutoAnonymizationResult {
+                    format,
+                    output,
+                    mapping: result.mapping,
+                    entities: result
+                        .entities
+                        .into_iter()
+                        .map(|entity| AutoEntity {
+                            path: Some(entity.path),
+                            ..AutoEntity::new(entity.entity)
+                        })
+                        .collect(),
+                    truncated: result.truncated,
+                })
+            }
+            Format::Ndjson => {
+                let mut output = Vec::new();
+                let result = self.anonymize_ndjson(input.trim_start_matches('\u{feff}').as_bytes(), &mut output)?;
+                Ok(AutoAnonymizationResult {
+                    format,
+                    output: String::from_utf8(output)
+                        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+                    mapping: result.mapping,
+                    entities: result
+                        .entities
+                        .into_iter()
+                        .map(|entity| AutoEntity {
+                            path: Some(entity.entity.path),
+                            line: Some(entity.line),
+                            ..AutoEntity::new(entity.entity.entity)
+                        })
+                        .collect(),
+                    truncated: result.truncated,
+                })
+            }
+            #[cfg(feature = "csv")]
+            Format::Csv => {
+                let (delimiter, has_headers) = csv_dialect(input).unwrap_or((',', true));
+                let mut output = Vec::new();
+                let result = self
+                    .csv()
+                    .with_delimiter(delimiter)
+                    .with_headers(has_headers)
+                    .anonymize(input.as_bytes(), &mut output)?;
+                Ok(AutoAnonymizationResult {
+                    format,
+                    output: String::from_utf8(output)
+                        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+                    mapping: result.mapping,
+                    entities: result
+                        .entities
+                        .into_iter()
+                        .map(|entity| AutoEntity {
+                            row: Some(entity.row),
+                            column: Some(entity.column),
+                            ..AutoEntity::new(entity.entity)
+                        })
+                        .collect(),
+                    truncated: result.truncated,
+                })
+            }
+            Format::Html => Splicer::new(self, input).html(),
+            Format::Logfmt => Splicer::new(self, input).logfmt(),
+            // Text, or CSV without the `csv` feature, which `sniff` never returns
+            _ => {
+                let result = self.anonymize(input)?;
+                Ok(AutoAnonymizationResult {
+                    format,
+                    output: result.anonymized_text,
+                    mapping: result.mapping,
+                    entities: result.entities.into_iter().map(AutoEntity::new).collect(),
+                    truncated: result.truncated,
+                })
+            }
+        }
+    }
+}
+
+/// Anonymizes spans of a document in place, copying everything around
+/// them; used for HTML and logfmt.
+struct Splicer<'a> {
+    anonymizer: &'a Anonymizer,
+    input: &'a str,
+    output: String,
+    /// End of the input copied to `output` so far
+    copied: usize,
+    /// Original value -> placeholder, shared by all spans
+    values: HashMap<String, String>,
+    /// Counters of this operation, if reset per operation
+    counters: Option<Counters>,
+    entities: Vec<AutoEntity>,
+    /// Entities left before `max_entities` is reached
+    remaining: Option<usize>,
+    truncated: bool,
+    /// Names from the root to the current element or line, for field policies
+    path: Vec<String>,
+}
+
+/// A tag read by [`Splicer::html_tag`].
+struct Tag<'a> {
+    /// The name as written, with a leading `/` for end tags
+    name: String,
+    /// The field policy applying in the element, if it's a start tag
+    element_action: Option<Option<&'a FieldAction>>,
+    /// Whether the tag ends with `/>`
+    self_closing: bool,
+    /// End of the tag in the input
+    end: usize,
+}
+
+impl<'a> Splicer<'a> {
+    fn new(anonymizer: &'a Anonymizer, input: &'a str) -> Self {
+        Self {
+            anonymizer,
+            input,
+            output: String::with_capacity(input.len()),
+            copied: 0,
+            values: HashMap::new(),
+            counters: anonymizer.operation_counters(),
+            entities: Vec::new(),
+            remaining: anonymizer.entity_limit(),
+            truncated: false,
+            path: Vec::new(),
+        }
+    }
+
+    /// Anonymize `range` of the input, or replace it whole as `whole`.
+    /// Returns `None` if nothing was replaced.
+    fn anonymize(&mut self, range: Range<usize>, whole: Option<&EntityType>) -> Result<Option<Anonymized>, AnonymaskError> {
+        let text = &self.input[range.clone()];
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let anonymized = match whole {
+            Some(_) if self.remaining == Some(0) => {
+                self.truncated = true;
+                return Ok(None);
+            }
+            Some(entity_type) => {
+                let entity = Entity {
+                    entity_type: entity_type.clone(),
+                    value: text.to_string(),
+                    start: 0,
+                    end: text.len(),
+                    confidence: 1.0,
+                };
+                let placeholder = self.anonymizer.placeholder_for(&entity, &mut self.values, self.counters.as_mut());
+                Anonymized {
+                    output_spans: vec![OutputSpan {
+                        placeholder: placeholder.clone(),
+                        start: 0,
+                        end: placeholder.len(),
+                    }],
+                    text: placeholder,
+                    entities: vec![entity],
+                    ..Default::default()
+                }
+            }
+            None => self
+                .anonymizer
+                .anonymize_shared(text, None, None, &mut self.values, self.counters.as_mut(), self.remaining)?,
+        };
+        self.truncated |= anonymized.truncated;
+        if anonymized.entities.is_empty() {
+            return Ok(None);
+        }
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= anonymized.entities.len();
+        }
+        self.entities.extend(anonymized.entities.iter().map(|entity| {
+            AutoEntity::new(Entity {
+                start: range.start + entity.start,
+                end: range.start + entity.end,
+                ..entity.clone()
+            })
+        }));
+        Ok(Some(anonymized))
+    }
+
+    /// Write `replacement` in place of `range` of the input.
+    fn replace(&mut self, range: Range<usize>, replacement: &str) {
+        self.output.push_str(&self.input[self.copied..range.start]);
+        self.output.push_str(replacement);
+        self.copied = range.end;
+    }
+
+    /// Anonymize `range` of the input as `action` says, escaping
+    /// placeholders with `escape`. Whole replacements leave surrounding
+    /// whitespace in place.
+    fn splice(
+        &mut self,
+        range: Range<usize>,
+        action: Option<&FieldAction>,
+        escape: fn(&str) -> Cow<'_, str>,
+    ) -> Result<(), AnonymaskError> {
+        let (range, whole) = match action {
+            Some(FieldAction::Skip) => return Ok(()),
+            Some(FieldAction::Always(entity_type)) => {
+                let text = &self.input[range.clone()];
+                let start = range.start + (text.len() - text.trim_start().len());
+                (start..start + text.trim().len(), Some(entity_type))
+            }
+            _ => (range, None),
+        };
+        if let Some(anonymized) = self.anonymize(range.clone(), whole)? {
+            self.replace(range, &escape_placeholders(&anonymized, escape));
+        }
+        Ok(())
+    }
+
+    fn finish(mut self, format: Format) -> AutoAnonymizationResult {
+        self.output.push_str(&self.input[self.copied..]);
+        AutoAnonymizationResult {
+            format,
+            output: self.output,
+            mapping: self
+                .values
+                .into_iter()
+                .map(|(original, placeholder)| (placeholder, original))
+                .collect(),
+            entities: self.entities,
+            truncated: self.truncated,
+        }
+    }
+
+    fn html(mut self) -> Result<AutoAnonymizationResult, AnonymaskError> {
+        let input = self.input;
+        // ASCII lowercasing keeps byte offsets
+        let lowercase = input.to_ascii_lowercase();
+        // The field policy applying in each element of `self.path`
+        let document_action = self.anonymizer.field_action(&[]);
+        let mut actions: Vec<Option<&FieldAction>> = Vec::new();
+        let mut text_start = 0;
+        let mut i = 0;
+        while let Some(offset) = input[i..].find('<') {
+            let open = i + offset;
+            let rest = &input[open + 1..];
+            let action = actions.last().copied().unwrap_or(document_action);
+            if let Some(comment) = rest.strip_prefix("!--") {
+                self.splice(text_start..open, action, escape_html)?;
+                let body = open + 4;
+                let end = comment.find("-->").map_or(input.len(), |end| body + end);
+                self.splice(body..end, action, escape_html)?;
+                i = (end + 3).min(input.len());
+                text_start = i;
+            } else if rest.starts_with(|c: char| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?')) {
+                self.splice(text_start..open, action, escape_html)?;
+                let tag = self.html_tag(open, action)?;
+                i = tag.end;
+                let name = tag.name.to_ascii_lowercase();
+                if let Some(closed) = tag.name.strip_prefix('/') {
+                    // Elements left open inside are closed too
+                    if let Some(depth) = self.path.iter().rposition(|open| open.eq_ignore_ascii_case(closed)) {
+                        self.path.truncate(depth);
+                        actions.truncate(depth);
+                    }
+                } else if tag.element_action.is_some() && !tag.self_closing && !VOID_ELEMENTS.contains(&name.as_str()) {
+                    self.path.push(tag.name);
+                    actions.push(tag.element_action.flatten());
+                }
+                if name == "script" || name == "style" {
+                    let close = lowercase[i..].find(&format!("</{}", name)).map_or(input.len(), |close| i + close);
+                    if name == "script" {
+                        self.splice(i..close, actions.last().copied().unwrap_or(document_action), unescaped)?;
+                    }
+                    i = close;
+                }
+                text_start = i;
+            } else {
+                i = open + 1;
+            }
+        }
+        let action = actions.last().copied().unwrap_or(document_action);
+        self.splice(text_start..input.len(), action, escape_html)?;
+        Ok(self.finish(Format::Html))
+    }
+
+    /// Anonymize the attribute values of the tag opening at `open`, inside
+    /// an element where `action` applies.
+    fn html_tag(&mut self, open: usize, action: Option<&'a FieldAction>) -> Result<Tag<'a>, AnonymaskError> {
+        let input = self.input;
+        let anonymizer = self.anonymizer;
+        let bytes = input.as_bytes();
+        let is_space = |i: usize| bytes[i].is_ascii_whitespace();
+
+        let mut i = open + 1;
+        while i < bytes.len() && !is_space(i) && bytes[i] != b'>' && !(bytes[i] == b'/' && i > open + 1) {
+            i += 1;
+        }
+        let name = input[open + 1..i].to_string();
+        // Attributes are fields of the element a start tag opens
+        let element_action = name.starts_with(|c: char| c.is_ascii_alphabetic()).then(|| {
+            self.path.push(name.clone());
+            anonymizer.field_action(&self.path).or(action)
+        });
+        let mut self_closing = false;
+        let end = loop {
+            while i < bytes.len() && (is_space(i) || bytes[i] == b'/') {
+                i += 1;
+            }
+            if i == bytes.len() {
+                break i;
+            }
+            if bytes[i] == b'>' {
+                self_closing = bytes[i - 1] == b'/';
+                break i + 1;
+            }
+            let attribute = i;
+            while i < bytes.len() && !is_space(i) && !matches!(bytes[i], b'=' | b'>' | b'/') {
+                i += 1;
+            }
+            let attribute = &input[attribute..i];
+            while i < bytes.len() && is_space(i) {
+                i += 1;
+            }
+            if bytes.get(i) != Some(&b'=') {
+                continue;
+            }
+            i += 1;
+            while i < bytes.len() && is_space(i) {
+                i += 1;
+            }
+            let attribute_action = match element_action {
+                Some(element_action) => {
+                    self.path.push(attribute.to_string());
+                    let attribute_action = anonymizer.field_action(&self.path).or(element_action);
+                    self.path.pop();
+                    attribute_action
+                }
+                None => action,
+            };
+            match bytes.get(i) {
+                Some(&quote @ (b'"' | b'\'')) => {
+                    let value = i + 1;
+                    let end = input[value..].find(quote as char).map_or(input.len(), |end| value + end);
+                    self.splice(value..end, attribute_action, escape_html)?;
+                    i = (end + 1).min(input.len());
+                }
+                _ => {
+                    let value = i;
+                    while i < bytes.len() && !is_space(i) && bytes[i] != b'>' {
+                        i += 1;
+                    }
+                    let whole = match attribute_action {
+                        Some(FieldAction::Skip) => continue,
+                        Some(FieldAction::Always(entity_type)) => Some(entity_type),
+                        _ => None,
+                    };
+                    if let Some(anonymized) = self.anonymize(value..i, whole)? {
+                        let text = escape_placeholders(&anonymized, escape_html);
+                        if text.contains(|c: char| c.is_whitespace() || matches!(c, '=' | '`')) {
+                            self.replace(value..i, &format!("\"{}\"", text));
+                        } else {
+                            self.replace(value..i, &text);
+                        }
+                    }
+                }
+            }
+        };
+        if element_action.is_some() {
+            self.path.pop();
+        }
+        Ok(Tag {
+            name,
+            element_action,
+            self_closing,
+            end,
+        })
+    }
+
+    fn logfmt(mut self) -> Result<AutoAnonymizationResult, AnonymaskError> {
+        let input = self.input;
+        let anonymizer = self.anonymizer;
+        let document_action = anonymizer.field_action(&[]);
+        let mut line_start = 0;
+        for (index, line) in input.split_inclusive('\n').enumerate() {
+            let content = line.trim_end_matches(['\r', '\n']);
+            self.path = vec![index.to_string()];
+            let line_action = anonymizer.field_action(&self.path).or(document_action);
+            let Some(pairs) = logfmt_pairs(content) else {
+                self.splice(line_start..line_start + content.len(), line_action, unescaped)?;
+                line_start += line.len();
+                continue;
+            };
+            for pair in pairs {
+                let range = line_start + pair.value.start..line_start + pair.value.end;
+                let key = &content[pair.key];
+                self.path.push(key.to_string());
+                let action = anonymizer.field_action(&self.path).or(line_action);
+                self.path.pop();
+                // Field policies take precedence over key rules
+                let whole = match action {
+                    Some(FieldAction::Skip) => continue,
+                    Some(FieldAction::Always(entity_type)) => Some(entity_type),
+                    Some(FieldAction::Detect) => None,
+                    None => anonymizer.key_type(key),
+                };
+                let Some(anonymized) = self.anonymize(range.clone(), whole)? else {
+                    continue;
+                };
+                if pair.quoted {
+                    self.replace(range, &escape_placeholders(&anonymized, escape_logfmt));
+                } else if anonymized.text.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '=')) {
+                    self.replace(range, &format!("\"{}\"", escape_logfmt(&anonymized.text)));
+                } else {
+                    self.replace(range, &anonymized.text);
+                }
+            }
+            line_start += line.len();
+        }
+        Ok(self.finish(Format::Logfmt))
+    }
+}
+
+/// One `key=value` pair of a logfmt line, located by byte ranges.
+struct Pair {
+    key: Range<usize>,
+    /// The value, without its quotes if it's quoted
+    value: Range<usize>,
+    quoted: bool,
+}
+
+/// The pairs of a logfmt line, or `None` if the line isn't only
+/// `key=value` pairs.
+fn logfmt_pairs(line: &str) -> Option<Vec<Pair>> {
+    let bytes = line.as_bytes();
+    let mut pairs = Vec::new();
+    let mut i = 0;
+    loop {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i == bytes.len() {
+            return Some(pairs);
+        }
+        let key = i;
+        while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || matches!(bytes[i], b'_' | b'.' | b'-')) {
+            i += 1;
+        }
+        if i == key || bytes.get(i) != Some(&b'=') {
+            return None;
+        }
+        let key = key..i;
+        i += 1;
+
+        if bytes.get(i) == Some(&b'"') {
+            let value = i + 1;
+            i = value;
+            loop {
+                match bytes.get(i)? {
+                    b'\\' => i += 2,
+                    b'"' => break,
+                    _ => i += 1,
+                }
+            }
+            pairs.push(Pair {
+                key,
+                value: value..i,
+                quoted: true,
+            });
+            i += 1;
+            if i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                return None;
+            }
+        } else {
+            let value = i;
+            while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            pairs.push(Pair {
+                key,
+                value: value..i,
+                quoted: false,
+            });
+        }
+    }
+}
+
+/// The delimiter and whether there is a header row, if `content` looks
+/// like CSV.
+///
+/// The first row is taken as a header unless one of its fields contains
+/// a digit or an `@`, which column names rarely do.
+#[cfg(feature = "csv")]
+fn csv_dialect(content: &str) -> Option<(char, bool)> {
+    use crate::csv::{parse_record, read_record, split_line_ending};
+
+    let mut input = content.as_bytes();
+    let mut line = String::new();
+    let mut records = Vec::new();
+    while records.len() < SNIFF_LINES && read_record(&mut input, '"', &mut line).ok()? {
+        let (record, _) = split_line_ending(&line);
+        if !record.trim().is_empty() {
+            records.push(record.to_string());
+        }
+    }
+    if records.len() < 2 {
+        return None;
+    }
+
+    let (delimiter, _) = [',', ';', '\t', '|']
+        .into_iter()
+        .filter_map(|delimiter| {
+            let mut widths = records.iter().map(|record| {
+                let fields = parse_record(record, delimiter, '"');
+                (!fields.iter().any(|field| field.value.starts_with(' '))).then_some(fields.len())
+            });
+            let width = widths.next()??;
+            (width >= 2 && widths.all(|other| other == Some(width))).then_some((delimiter, width))
+        })
+        .max_by_key(|&(_, width)| width)?;
+    let has_headers = parse_record(&records[0], delimiter, '"')
+        .iter()
+        .all(|field| !field.value.contains(|c: char| c.is_ascii_digit() || c == '@'));
+    Some((delimiter, has_headers))
+}
+
+/// The anonymized text, with `escape` applied to each placeholder.
+fn escape_placeholders(anonymized: &Anonymized, escape: fn(&str) -> Cow<'_, str>) -> String {
+    let mut escaped = String::with_capacity(anonymized.text.len());
+    let mut copied = 0;
+    for span in &anonymized.output_spans {
+        escaped.push_str(&anonymized.text[copied..span.start]);
+        escaped.push_str(&escape(&span.placeholder));
+        copied = span.end;
+    }
+    escaped.push_str(&anonymized.text[copied..]);
+    escaped
+}
+
+fn unescaped(text: &str) -> Cow<'_, str> {
+    Cow::Borrowed(text)
+}
+
+fn escape_html(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+fn escape_logfmt(text: &str) -> Cow<'_, str> {
+    if !text.contains(['\\', '"']) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AnonymizerConfig, KeyRule, PlaceholderFormat};
+
+    fn anonymizer(format: PlaceholderFormat) -> Anonymizer {
+        let config = AnonymizerConfig::builder()
+            .with_placeholder_format(format)
+            .with_counter_reset(true)
+            .with_key_rule(KeyRule::new("(?i)^ssn$", EntityType::Ssn))
+            .build();
+        Anonymizer::with_config(vec![EntityType::Email], config).unwrap()
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniff("\u{feff} [1, {\"a\": \"b\"}]"), Format::Json);
+        assert_eq!(sniff("{\"a\":1}\n\n{\"b\":2}\n"), Format::Ndjson);
+        assert_eq!(sniff("[INFO] started\n[INFO] ready"), Format::Text);
+        assert_eq!(sniff("  <!DOCTYPE html><html></html>"), Format::Html);
+        assert_eq!(sniff("<3 you"), Format::Text);
+        assert_eq!(sniff("ts=1 level=info msg=\"a b\"\n\nts=2 level=warn msg=\"c \\\" d\"\n"), Format::Logfmt);
+        assert_eq!(sniff("total=3 of them"), Format::Text);
+        assert_eq!(sniff(""), Format::Text);
+        #[cfg(feature = "csv")]
+        {
+            assert_eq!(sniff("id;mail\n1;a@b.io\n2;\"c;d\"\n"), Format::Csv);
+            assert_eq!(sniff("Hi Jo, thanks.\nSee you, Al."), Format::Text);
+            assert_eq!(csv_dialect("1\t2\t3\n4\t5\t6\n"), Some(('\t', false)));
+        }
+    }
+
+    #[test]
+    fn test_html() {
+        let anonymizer = anonymizer(PlaceholderFormat::Custom("<{type}_{counter}>".to_string()));
+        let input = "<!doctype html><p class=x title='jo@x.io'>Mail <a href=mailto:al@x.io>jo@x.io</a> 1<2</p>\
+                     <!-- al@x.io --><style>a[href='a@b.io']{}</style><script>var to = \"jo@x.io\";</SCRIPT>";
+        let result = anonymizer.anonymize_auto(input.as_bytes()).unwrap();
+
+        assert_eq!(result.format, Format::Html);
+        assert_eq!(
+            result.output,
+            "<!doctype html><p class=x title='&lt;EMAIL_1&gt;'>Mail <a href=mailto:&lt;EMAIL_2&gt;>&lt;EMAIL_1&gt;</a> 1<2</p>\
+             <!-- &lt;EMAIL_2&gt; --><style>a[href='a@b.io']{}</style><script>var to = \"<EMAIL_1>\";</SCRIPT>"
+        );
+        assert_eq!(result.entities.len(), 5);
+        for entity in &result.entities {
+            assert_eq!(&input[entity.entity.start..entity.entity.end], entity.entity.value);
+        }
+        assert_eq!(result.mapping["<EMAIL_2>"], "al@x.io");
+    }
+
+    #[test]
+    fn test_logfmt() {
+        let anonymizer = anonymizer(PlaceholderFormat::Custom("[{type} {counter}]".to_string()));
+        let input = "level=info msg=\"login \\\"jo@x.io\\\"\" user=jo@x.io ssn=123-45\r\n\
+                     \tat handler (mail al@x.io)\n";
+        let result = anonymizer.anonymize_auto(input.as_bytes()).unwrap();
+
+        assert_eq!(result.format, Format::Logfmt);
+        assert_eq!(
+            result.output,
+            "level=info msg=\"login \\\"[EMAIL 1]\\\"\" user=\"[EMAIL 1]\" ssn=\"[SSN 2]\"\r\n\
+             \tat handler (mail [EMAIL 3])\n"
+        );
+        let values: Vec<&str> = result
+            .entities
+            .iter()
+            .map(|entity| &input[entity.entity.start..entity.entity.end])
+            .collect();
+        assert_eq!(values, vec!["jo@x.io", "jo@x.io", "123-45", "al@x.io"]);
+    }
+
+    #[test]
+    fn test_structured_formats() {
+        let anonymizer = anonymizer(PlaceholderFormat::Short);
+
+        let result = anonymizer
+            .anonymize_auto(b"{\n  \"ssn\": \"123\",\n  \"note\": \"jo@x.io\"\n}\n")
+            .unwrap();
+        assert_eq!(result.format, Format::Json);
+        assert_eq!(result.output, "{\n  \"note\": \"EMAIL_1\",\n  \"ssn\": \"SSN_1\"\n}");
+        assert_eq!(result.entities[0].path.as_deref(), Some("/note"));
+
+        let unchanged = "{\"note\": \"hello\"}";
+        assert_eq!(anonymizer.anonymize_auto(unchanged.as_bytes()).unwrap().output, unchanged);
+
+        let result = anonymizer.anonymize_auto(b"{\"a\":1}\n{\"b\":\"jo@x.io\"}\n").unwrap();
+        assert_eq!(result.format, Format::Ndjson);
+        assert_eq!(result.output, "{\"a\":1}\n{\"b\":\"EMAIL_1\"}\n");
+        assert_eq!(result.entities[0].line, Some(1));
+
+        let result = anonymizer.anonymize_auto(b"Write to jo@x.io").unwrap();
+        assert_eq!(result.format, Format::Text);
+        assert_eq!(result.output, "Write to EMAIL_1");
+        assert_eq!(result.entities[0].entity.start, 9);
+
+        #[cfg(feature = "csv")]
+        {
+            let result = anonymizer.anonymize_auto(b"name;mail\nJo;jo@x.io\n").unwrap();
+            assert_eq!(result.format, Format::Csv);
+            assert_eq!(result.output, "name;mail\nJo;EMAIL_1\n");
+            assert_eq!((result.entities[0].row, result.entities[0].column), (Some(0), Some(1)));
+        }
+
+        assert!(matches!(anonymizer.anonymize_auto(b"\xff\xfe"), Err(AnonymaskError::IoError(_))));
+    }
+
+    #[test]
+    fn test_field_policies_across_formats() {
+        let config: AnonymizerConfig = serde_json::from_str(
+            r#"{
+                "case_sensitive": false,
+                "word_boundary_check": true,
+                "placeholder_format": "Short",
+                "max_entities": 0,
+                "reset_counters": true,
+                "field_policies": [
+                    { "selector": "//customer/@ref", "action": "Skip" },
+                    { "selector": "customer", "action": { "Always": { "Custom": "customer" } } },
+                    { "selector": "$..internal", "action": "Skip" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+
+        let result = anonymizer
+            .anonymize_auto(b"{\"customer\": {\"name\": \"Jo\", \"id\": 7}, \"internal\": \"jo@x.io\"}")
+            .unwrap();
+        assert_eq!(result.output, "{\"customer\":{\"id\":\"CUSTOMER_1\",\"name\":\"CUSTOMER_2\"},\"internal\":\"jo@x.io\"}");
+
+        let input = "<order>\n  <customer ref=\"jo@x.io\" mail=jo@x.io>\n    <name>Jo &amp; Al</name>\n  </customer>\n  \
+                     <br><internal>jo@x.io</internal><note>al@x.io</note>\n</order>";
+        let result = anonymizer.anonymize_auto(input.as_bytes()).unwrap();
+        assert_eq!(result.format, Format::Html);
+        assert_eq!(
+            result.output,
+            "<order>\n  <customer ref=\"jo@x.io\" mail=CUSTOMER_1>\n    <name>CUSTOMER_2</name>\n  </customer>\n  \
+             <br><internal>jo@x.io</internal><note>EMAIL_1</note>\n</order>"
+        );
+
+        let result = anonymizer
+            .anonymize_auto(b"level=info customer=\"Jo Doe\" internal=jo@x.io\nlevel=warn msg=jo@x.io\n")
+            .unwrap();
+        assert_eq!(result.format, Format::Logfmt);
+        assert_eq!(result.output, "level=info customer=\"CUSTOMER_1\" internal=jo@x.io\nlevel=warn msg=EMAIL_1\n");
+
+        #[cfg(feature = "csv")]
+        {
+            let result = anonymizer.anonymize_auto(b"customer;internal;note\nJo;jo@x.io;jo@x.io\n").unwrap();
+            assert_eq!(result.format, Format::Csv);
+            assert_eq!(result.output, "customer;internal;note\nCUSTOMER_1;jo@x.io;EMAIL_1\n");
+        }
+    }
+}
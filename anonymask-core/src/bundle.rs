@@ -0,0 +1,444 @@
+//! Signed, versioned policy bundles (feature `policy-bundle`).
+//!
+//! A security team writes the masking policy of an organization once - the
+//! entity types, configuration (patterns, locales, allow-lists, field
+//! policies...) and named profiles for particular services - and signs it
+//! with an Ed25519 key. Every service embedding anonymask ships the public
+//! key and loads the bundle it is given with
+//! [`Anonymizer::from_policy_bundle`], which refuses bundles that weren't
+//! signed by a trusted key or were altered after signing.
+//!
+//! A signed bundle is a JSON document holding the bundle exactly as it was
+//! signed, and the signature in hex:
+//!
+//! ```json
+//! {"payload": "{\"format\":1,\"name\":\"acme\",...}", "signature": "9f2c..."}
+//! ```
+
+use crate::anonymizer::Anonymizer;
+use crate::config::AnonymizerConfig;
+use crate::entity::EntityType;
+use crate::error::AnonymaskError;
+use ed25519_dalek::{Signature, Signer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Version of the bundle format written by this release.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The masking policy of an organization.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::bundle::{PolicyBundle, Profile, SigningKey};
+/// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType};
+///
+/// let mut bundle = PolicyBundle::new("acme", 7, vec![EntityType::Email, EntityType::Phone], AnonymizerConfig::default());
+/// bundle.profiles.insert(
+///     "support".to_string(),
+///     Profile::default().with_entity_types(vec![EntityType::Email]),
+/// );
+///
+/// // By the security team
+/// let key = SigningKey::generate().unwrap();
+/// let signed = bundle.sign(&key).unwrap();
+///
+/// // In every service, which only knows the public key
+/// let trusted = [key.verifying_key()];
+/// let anonymizer = Anonymizer::from_policy_bundle(signed.as_bytes(), &trusted, Some("support")).unwrap();
+/// let result = anonymizer.anonymize("Mail ada@example.com or call 555-123-4567").unwrap();
+/// assert_eq!(result.entities.len(), 1);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyBundle {
+    /// Version of the bundle format, [`FORMAT_VERSION`]
+    pub format: u32,
+    /// Name of the policy
+    pub name: String,
+    /// Version of the policy; increase it on every change, so services
+    /// can refuse to go back to an older policy
+    pub version: u64,
+    /// Entity types detected by default
+    pub entity_types: Vec<EntityType>,
+    /// Configuration used by default
+    pub config: AnonymizerConfig,
+    /// Variants of the policy for particular services, by name
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// A named variant of a [`PolicyBundle`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    /// Entity types detected instead of the bundle's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_types: Option<Vec<EntityType>>,
+    /// Changes to the bundle's configuration, as a JSON merge patch
+    /// (RFC 7396): objects are merged, `null` removes an optional field
+    /// and any other value replaces the bundle's
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub config: Value,
+}
+
+impl Profile {
+    /// Detect `entity_types` instead of the bundle's.
+    pub fn with_entity_types(mut self, entity_types: Vec<EntityType>) -> Self {
+        self.entity_types = Some(entity_types);
+        self
+    }
+
+    /// Change the bundle's configuration with a JSON merge patch.
+    pub fn with_config_patch(mut self, patch: Value) -> Self {
+        self.config = patch;
+        self
+    }
+}
+
+/// A bundle as distributed: the exact JSON signed, and its signature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SignedPolicyBundle {
+    payload: String,
+    signature: String,
+}
+
+impl PolicyBundle {
+    /// Create a bundle without profiles.
+    pub fn new(name: impl Into<String>, version: u64, entity_types: Vec<EntityType>, config: AnonymizerConfig) -> Self {
+        Self {
+            format: FORMAT_VERSION,
+            name: name.into(),
+            version,
+            entity_types,
+            config,
+            profiles: BTreeMap::new(),
+        }
+    }
+
+    /// Sign the bundle, returning the signed bundle to distribute.
+    ///
+    /// # Errors
+    ///
+    /// * `AnonymaskError::PolicyBundleError` - If the bundle can't be
+    ///   serialized, or a profile doesn't produce a valid configuration
+    pub fn sign(&self, key: &SigningKey) -> Result<String, AnonymaskError> {
+        // Catch broken profiles before they reach every service
+        for name in self.profiles.keys() {
+            self.resolve(Some(name))?;
+        }
+        let payload = serde_json::to_string(self).map_err(|e| bundle_error(format!("can't serialize bundle: {}", e)))?;
+        let signature = key.0.sign(payload.as_bytes());
+        let signed = SignedPolicyBundle {
+            signature: to_hex(&signature.to_bytes()),
+            payload,
+        };
+        serde_json::to_string_pretty(&signed).map_err(|e| bundle_error(format!("can't serialize bundle: {}", e)))
+    }
+
+    /// Check the signature of a signed bundle and read it.
+    ///
+    /// The bundle is accepted if any of the `trusted` keys signed it.
+    /// Compare the returned [`version`](Self::version) with the one in use
+    /// to refuse rollbacks to an older policy.
+    ///
+    /// # Errors
+    ///
+    /// * `AnonymaskError::PolicyBundleError` - If the bundle is malformed,
+    ///   isn't signed by a trusted key, or has an unsupported format
+    pub fn verify(signed: &[u8], trusted: &[VerifyingKey]) -> Result<Self, AnonymaskError> {
+        if trusted.is_empty() {
+            return Err(bundle_error("no trusted keys given".to_string()));
+        }
+        let signed: SignedPolicyBundle =
+            serde_json::from_slice(signed).map_err(|e| bundle_error(format!("malformed signed bundle: {}", e)))?;
+        let signature: [u8; 64] = from_hex(&signed.signature)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| bundle_error("malformed signature".to_string()))?;
+        let signature = Signature::from_bytes(&signature);
+        if !trusted
+            .iter()
+            .any(|key| key.0.verify_strict(signed.payload.as_bytes(), &signature).is_ok())
+        {
+            return Err(bundle_error("signature doesn't match any trusted key".to_string()));
+        }
+
+        // Check the format before reading the rest, which it may change
+        let payload: Value =
+            serde_json::from_str(&signed.payload).map_err(|e| bundle_error(format!("malformed bundle: {}", e)))?;
+        match payload.get("format").and_then(Value::as_u64) {
+            Some(format) if format == u64::from(FORMAT_VERSION) => {}
+            Some(format) => return Err(bundle_error(format!("unsupported bundle format {}", format))),
+            None => return Err(bundle_error("bundle format missing".to_string())),
+        }
+        serde_json::from_value(payload).map_err(|e| bundle_error(format!("malformed bundle: {}", e)))
+    }
+
+    /// The entity types and configuration of a profile, or the bundle's
+    /// own with `None`.
+    ///
+    /// # Errors
+    ///
+    /// * `AnonymaskError::PolicyBundleError` - If there's no such profile,
+    ///   or its patch doesn't produce a valid configuration
+    pub fn resolve(&self, profile: Option<&str>) -> Result<(Vec<EntityType>, AnonymizerConfig), AnonymaskError> {
+        let Some(name) = profile else {
+            return Ok((self.entity_types.clone(), self.config.clone()));
+        };
+        let profile = self
+            .profiles
+            .get(name)
+            .ok_or_else(|| bundle_error(format!("unknown profile '{}'", name)))?;
+        let entity_types = profile.entity_types.clone().unwrap_or_else(|| self.entity_types.clone());
+        let config = if profile.config.is_null() {
+            self.config.clone()
+        } else {
+            let mut config = serde_json::to_value(&self.config)
+                .map_err(|e| bundle_error(format!("can't serialize config: {}", e)))?;
+            merge_patch(&mut config, &profile.config);
+            serde_json::from_value(config)
+                .map_err(|e| bundle_error(format!("profile '{}' has an invalid config: {}", name, e)))?
+        };
+        Ok((entity_types, config))
+    }
+
+    /// Create an anonymizer applying a profile, or the bundle's own policy
+    /// with `None`.
+    ///
+    /// # Errors
+    ///
+    /// * `AnonymaskError::PolicyBundleError` - If the profile can't be resolved
+    /// * Any error of [`Anonymizer::with_config`]
+    pub fn anonymizer(&self, profile: Option<&str>) -> Result<Anonymizer, AnonymaskError> {
+        let (entity_types, config) = self.resolve(profile)?;
+        Anonymizer::with_config(entity_types, config)
+    }
+}
+
+impl Anonymizer {
+    /// Create an anonymizer from a signed policy bundle.
+    ///
+    /// Verifies the bundle with [`PolicyBundle::verify`] and applies
+    /// `profile`, or the bundle's own policy with `None`; see
+    /// [`PolicyBundle`] for an example.
+    ///
+    /// # Errors
+    ///
+    /// * `AnonymaskError::PolicyBundleError` - If the bundle isn't signed
+    ///   by a trusted key, is malformed or has no such profile
+    /// * Any error of [`Anonymizer::with_config`]
+    pub fn from_policy_bundle(
+        signed: &[u8],
+        trusted: &[VerifyingKey],
+        profile: Option<&str>,
+    ) -> Result<Self, AnonymaskError> {
+        PolicyBundle::verify(signed, trusted)?.anonymizer(profile)
+    }
+}
+
+/// Private key signing policy bundles. Keep it with the security team.
+#[derive(Clone)]
+pub struct SigningKey(ed25519_dalek::SigningKey);
+
+impl SigningKey {
+    /// A new random key.
+    ///
+    /// # Errors
+    ///
+    /// * `AnonymaskError::PolicyBundleError` - If the system random
+    ///   generator is unavailable
+    pub fn generate() -> Result<Self, AnonymaskError> {
+        let mut secret = [0u8; ed25519_dalek::SECRET_KEY_LENGTH];
+        getrandom::getrandom(&mut secret)
+            .map_err(|e| bundle_error(format!("random generator unavailable: {}", e)))?;
+        Ok(Self(ed25519_dalek::SigningKey::from_bytes(&secret)))
+    }
+
+    /// The public key verifying this key's signatures, for the services.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        VerifyingKey(self.0.verifying_key())
+    }
+
+    /// The key in hex, as parsed by [`FromStr`].
+    pub fn to_hex(&self) -> String {
+        to_hex(self.0.as_bytes())
+    }
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("verifying_key", &self.verifying_key())
+            .finish_non_exhaustive()
+    }
+}
+
+impl FromStr for SigningKey {
+    type Err = AnonymaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let secret: [u8; ed25519_dalek::SECRET_KEY_LENGTH] = from_hex(s.trim())
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| bundle_error("signing key must be 64 hex digits".to_string()))?;
+        Ok(Self(ed25519_dalek::SigningKey::from_bytes(&secret)))
+    }
+}
+
+/// Public key of a trusted policy signer.
+///
+/// Written and parsed as 64 hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyingKey(ed25519_dalek::VerifyingKey);
+
+impl std::fmt::Display for VerifyingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&to_hex(self.0.as_bytes()))
+    }
+}
+
+impl FromStr for VerifyingKey {
+    type Err = AnonymaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes: [u8; ed25519_dalek::PUBLIC_KEY_LENGTH] = from_hex(s.trim())
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| bundle_error("verifying key must be 64 hex digits".to_string()))?;
+        ed25519_dalek::VerifyingKey::from_bytes(&bytes)
+            .map(Self)
+            .map_err(|_| bundle_error("invalid verifying key".to_string()))
+    }
+}
+
+fn bundle_error(reason: String) -> AnonymaskError {
+    AnonymaskError::PolicyBundleError(reason)
+}
+
+/// Apply a JSON merge patch (RFC 7396) to `target`.
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!("target was made an object");
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PlaceholderFormat;
+    use serde_json::json;
+
+    fn sample_bundle() -> PolicyBundle {
+        let config = AnonymizerConfig::builder()
+            .with_placeholder_format(PlaceholderFormat::Short)
+            .build();
+        let mut bundle = PolicyBundle::new("acme", 3, vec![EntityType::Email, EntityType::Phone], config);
+        bundle.profiles.insert(
+            "billing".to_string(),
+            Profile::default()
+                .with_entity_types(vec![EntityType::Email])
+                .with_config_patch(json!({"placeholder_format": "Standard"})),
+        );
+        bundle
+    }
+
+    #[test]
+    fn test_signed_bundle_round_trip() {
+        let key = SigningKey::generate().unwrap();
+        let signed = sample_bundle().sign(&key).unwrap();
+
+        let bundle = PolicyBundle::verify(signed.as_bytes(), &[key.verifying_key()]).unwrap();
+        assert_eq!(serde_json::to_value(&bundle).unwrap(), serde_json::to_value(sample_bundle()).unwrap());
+
+        // Keys survive being written out
+        let restored: SigningKey = key.to_hex().parse().unwrap();
+        let public: VerifyingKey = key.verifying_key().to_string().parse().unwrap();
+        assert_eq!(restored.verifying_key(), public);
+        assert!(!format!("{:?}", key).contains(&key.to_hex()));
+    }
+
+    #[test]
+    fn test_untrusted_or_tampered_bundles_are_rejected() {
+        let key = SigningKey::generate().unwrap();
+        let other = SigningKey::generate().unwrap();
+        let signed = sample_bundle().sign(&key).unwrap();
+
+        let err = PolicyBundle::verify(signed.as_bytes(), &[other.verifying_key()]).unwrap_err();
+        assert!(matches!(err, AnonymaskError::PolicyBundleError(_)));
+        // Any trusted key will do
+        assert!(PolicyBundle::verify(signed.as_bytes(), &[other.verifying_key(), key.verifying_key()]).is_ok());
+
+        let tampered = signed.replace("\\\"version\\\":3", "\\\"version\\\":4");
+        assert_ne!(tampered, signed);
+        assert!(PolicyBundle::verify(tampered.as_bytes(), &[key.verifying_key()]).is_err());
+        assert!(PolicyBundle::verify(b"{}", &[key.verifying_key()]).is_err());
+        assert!(PolicyBundle::verify(signed.as_bytes(), &[]).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_format_is_rejected() {
+        let key = SigningKey::generate().unwrap();
+        let mut bundle = sample_bundle();
+        bundle.format = FORMAT_VERSION + 1;
+        let signed = bundle.sign(&key).unwrap();
+
+        let err = PolicyBundle::verify(signed.as_bytes(), &[key.verifying_key()]).unwrap_err();
+        assert!(err.to_string().contains("unsupported bundle format"), "{}", err);
+    }
+
+    #[test]
+    fn test_profiles() {
+        let bundle = sample_bundle();
+        let (entity_types, config) = bundle.resolve(None).unwrap();
+        assert_eq!(entity_types, vec![EntityType::Email, EntityType::Phone]);
+        assert_eq!(config.placeholder_format, PlaceholderFormat::Short);
+
+        let (entity_types, config) = bundle.resolve(Some("billing")).unwrap();
+        assert_eq!(entity_types, vec![EntityType::Email]);
+        assert_eq!(config.placeholder_format, PlaceholderFormat::Standard);
+        assert_eq!(config.case_sensitive, bundle.config.case_sensitive);
+
+        assert!(matches!(bundle.resolve(Some("nope")), Err(AnonymaskError::PolicyBundleError(_))));
+
+        let mut broken = sample_bundle();
+        broken.profiles.insert(
+            "broken".to_string(),
+            Profile::default().with_config_patch(json!({"placeholder_format": 5})),
+        );
+        assert!(broken.sign(&SigningKey::generate().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        let mut target = json!({"a": {"b": 1, "c": 2}, "d": [1], "e": "x"});
+        merge_patch(&mut target, &json!({"a": {"b": null, "f": 3}, "d": [2], "g": {"h": 1}}));
+        assert_eq!(target, json!({"a": {"c": 2, "f": 3}, "d": [2], "e": "x", "g": {"h": 1}}));
+    }
+}
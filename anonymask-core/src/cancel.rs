@@ -0,0 +1,163 @@
+//! Cooperative cancellation for long-running operations.
+//!
+//! Pass a [`CancellationToken`] to the `*_cancellable` methods of
+//! [`Anonymizer`](crate::Anonymizer). Work is checked between segments of a
+//! document and between documents of a batch; once the token is cancelled
+//! or its deadline passes, processing stops and the result carries an
+//! aborted [`Status`](crate::entity::Status) with everything completed so far.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Why an operation stopped early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum AbortReason {
+    /// [`CancellationToken::cancel`] was called
+    Cancelled,
+    /// The token's [`Deadline`] passed
+    DeadlineExceeded,
+}
+
+/// A point in time after which work should stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    /// A deadline at the given instant.
+    pub fn at(instant: Instant) -> Self {
+        Self(instant)
+    }
+
+    /// A deadline `timeout` from now.
+    pub fn after(timeout: Duration) -> Self {
+        Self(Instant::now() + timeout)
+    }
+
+    /// Whether the deadline has passed.
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.0
+    }
+
+    /// Time left until the deadline, zero if it has passed.
+    pub fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+}
+
+/// Shared flag to abort an operation, optionally with a deadline.
+///
+/// Clones share the same flag, so one clone can be handed to the worker
+/// and another kept to cancel it from a different thread.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::cancel::{AbortReason, CancellationToken, Deadline};
+/// use std::time::Duration;
+///
+/// let token = CancellationToken::new().with_deadline(Deadline::after(Duration::from_secs(30)));
+/// let handle = token.clone();
+/// assert_eq!(token.abort_reason(), None);
+///
+/// handle.cancel();
+/// assert_eq!(token.abort_reason(), Some(AbortReason::Cancelled));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Deadline>,
+}
+
+impl CancellationToken {
+    /// Create a token that is not cancelled and has no deadline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also abort once `deadline` passes.
+    pub fn with_deadline(mut self, deadline: Deadline) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Request cancellation. Affects all clones of this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Whether work should stop, either by cancellation or deadline.
+    pub fn is_cancelled(&self) -> bool {
+        self.abort_reason().is_some()
+    }
+
+    /// Why work should stop, or `None` to continue.
+    pub fn abort_reason(&self) -> Option<AbortReason> {
+        if self.cancelled.load(Ordering::Acquire) {
+            Some(AbortReason::Cancelled)
+        } else if self.deadline.is_some_and(|deadline| deadline.is_expired()) {
+            Some(AbortReason::DeadlineExceeded)
+        } else {
+            None
+        }
+    }
+}
+
+/// End of the next segment of `rest`, of roughly `target` bytes and ending
+/// at a line break.
+///
+/// Segments end after a newline once `target` bytes have been collected;
+/// if there is no newline within `2 * target` bytes, they end at the next
+/// character boundary instead.
+pub(crate) fn segment_end(rest: &str, target: usize) -> usize {
+    let end = if rest.len() <= target {
+        rest.len()
+    } else {
+        let window_end = floor_char_boundary(rest, (2 * target).min(rest.len()));
+        match rest[target..window_end].find('\n') {
+            Some(pos) => target + pos + 1,
+            None => floor_char_boundary(rest, target).max(1),
+        }
+    };
+    ceil_char_boundary(rest, end)
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+pub(crate) fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_expires() {
+        let token = CancellationToken::new().with_deadline(Deadline::after(Duration::ZERO));
+        assert_eq!(token.abort_reason(), Some(AbortReason::DeadlineExceeded));
+    }
+
+    #[test]
+    fn test_segments_end_at_line_breaks() {
+        let text = "line one\nline two\nline three\nüüüüüüüüüüüüüüü";
+        assert_eq!(segment_end(text, 10), "line one\nline two\n".len());
+
+        let mut start = 0;
+        while start < text.len() {
+            let end = start + segment_end(&text[start..], 10);
+            assert!(end > start && text.is_char_boundary(end));
+            start = end;
+        }
+    }
+}
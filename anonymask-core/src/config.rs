@@ -1,4 +1,11 @@
+use crate::detection::EntityDetector;
+use crate::entity::EntityType;
+use crate::error::AnonymaskError;
+use crate::locale::Locale;
+use crate::placeholder::{Template, Var};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
 
 /// Configuration for the anonymizer behavior.
 ///
@@ -15,71 +22,1288 @@ use serde::{Deserialize, Serialize};
 ///     .build();
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AnonymizerConfig {
     /// Whether custom entity matching should be case-sensitive
+    ///
+    /// When false, "acme corp" matches "Acme Corp" (ASCII letters only).
     pub case_sensitive: bool,
 
     /// Whether to check word boundaries for custom entities
     ///
-    /// When true, "John" won't match "Johnson"
+    /// When true, "John" won't match "Johnson". Boundaries are Unicode-aware;
+    /// Han and kana characters always count as boundaries since those
+    /// scripts don't separate words with spaces.
     pub word_boundary_check: bool,
 
     /// Format for placeholder generation
     pub placeholder_format: PlaceholderFormat,
 
+    /// Formats for particular entity types, instead of `placeholder_format`
+    #[serde(default)]
+    pub placeholder_overrides: Vec<PlaceholderOverride>,
+
+    /// Number placeholders from 1 in every call
+    ///
+    /// By default `{counter}` and `{index_by_type}` keep counting across
+    /// calls of an anonymizer, so placeholders of different results never
+    /// collide. With this set, each call, batch, JSON document or CSV file
+    /// starts again at `EMAIL_1`; deanonymize each result with its own
+    /// mapping. Sessions and streams keep counting across turns and chunks.
+    #[serde(default)]
+    pub reset_counters: bool,
+
+    /// Restore placeholders an LLM reformatted
+    ///
+    /// `deanonymize()` then also restores `EMAIL_1` from `Email_1`,
+    /// `email 1` or `[EMAIL_1]`-style drift: case, spaces and punctuation
+    /// inside a placeholder may differ. Leave it off to restore exact
+    /// placeholders only, e.g. when ordinary text could look like a
+    /// reformatted placeholder.
+    #[serde(default)]
+    pub tolerant_deanonymization: bool,
+
     /// Maximum number of entities to detect (0 = unlimited)
+    ///
+    /// Only the first `max_entities` entities by position are replaced;
+    /// results report the cut-off in their `truncated` flag.
     pub max_entities: usize,
+
+    /// Regional pattern packs used for phone numbers, addresses and dates
+    ///
+    /// Patterns of all listed locales are combined.
+    #[serde(default = "default_locales")]
+    pub locales: Vec<Locale>,
+
+    /// Region of phone numbers written without a country code, as an ISO
+    /// 3166 code such as `"GB"`
+    ///
+    /// With the `phone-intl` feature, phone numbers in international
+    /// format (`+44 20 7946 0958`) are detected for every country, and
+    /// national numbers (`020 7946 0958`) for this region, on top of the
+    /// locale patterns. Every such number is validated against
+    /// libphonenumber's metadata. Without the feature it has no effect.
+    #[serde(default)]
+    pub phone_region: Option<String>,
+
+    /// How complete a US phone number must be to be reported
+    #[serde(default)]
+    pub phone_matching: PhoneMatching,
+
+    /// Optional patterns from the [`patterns`](crate::patterns) library to
+    /// enable, by name (e.g. `"aws_arn"`)
+    #[serde(default)]
+    pub named_patterns: Vec<String>,
+
+    /// User-supplied regular expressions, each reported under its name
+    #[serde(default)]
+    pub custom_patterns: Vec<CustomPattern>,
+
+    /// Key names whose values are PII, in `key: value` text and JSON
+    #[serde(default)]
+    pub key_rules: Vec<KeyRule>,
+
+    /// How selected fields of JSON, CSV, HTML/XML and logfmt documents are
+    /// anonymized; the first matching policy applies
+    #[serde(default)]
+    pub field_policies: Vec<FieldPolicy>,
+
+    /// Limits enforced on `custom_patterns`
+    #[serde(default)]
+    pub pattern_limits: PatternLimits,
+
+    /// Known-safe values that are never reported as entities
+    #[serde(default)]
+    pub allow_list: Vec<AllowRule>,
+
+    /// How to choose between detections whose spans overlap
+    #[serde(default)]
+    pub overlap_strategy: OverlapStrategy,
+
+    /// Which type a value gets when it's detected both as a custom type
+    /// and as a built-in type
+    #[serde(default)]
+    pub duplicate_preference: DuplicatePreference,
+
+    /// Keywords that raise or lower the confidence of nearby matches
+    #[serde(default)]
+    pub context_rules: Vec<ContextRule>,
+
+    /// Rules that re-type entities passing the checks of another type
+    #[serde(default)]
+    pub coercion_rules: Vec<CoercionRule>,
+
+    /// Query parameters whose values are reported as `EntityType::Credential`
+    /// (e.g. `"token"`, matched case-insensitively)
+    #[serde(default)]
+    pub credential_query_params: Vec<String>,
+
+    /// Discard `EntityType::Ssn` matches that can't be issued SSNs
+    ///
+    /// Area numbers 000, 666 and 900-999, group 00 and serial 0000 are
+    /// never assigned, so such matches (often invoice or order numbers)
+    /// score as failing a validator and are discarded by default. Other
+    /// matches score as if there were no validator. Turn off to report any
+    /// 3-2-4 digit grouping.
+    #[serde(default = "default_validate_ssn")]
+    pub validate_ssn: bool,
+
+    /// Thresholds of the high-entropy detector used for `EntityType::Secret`
+    #[serde(default)]
+    pub secret_detection: SecretDetection,
+
+    /// Weighting model for entity confidence scores
+    #[serde(default)]
+    pub confidence: ConfidenceModel,
+
+    /// Limits on the detection work of each call
+    #[serde(default)]
+    pub detection_budget: DetectionBudget,
+
+    /// Derive placeholder UUIDs from a keyed hash of the value instead of
+    /// generating them randomly
+    #[serde(default)]
+    pub hashed_tokens: Option<HashedTokens>,
+
+    /// Group the entities of each result by the person they describe
+    ///
+    /// Results then carry a `subject_ids` entry per entity, so that e.g. a
+    /// name and the email and phone number next to it share a number.
+    /// Linking is a heuristic over paragraphs: each email, phone number,
+    /// ID or address joins the nearest name in its paragraph, and equal
+    /// values join across paragraphs.
+    #[serde(default)]
+    pub link_subjects: bool,
+
+    /// Name placeholders after the subject they belong to
+    ///
+    /// Implies `link_subjects`. The values of subject 7 are replaced by
+    /// `PERSON_7`, `PERSON_7_EMAIL`, `PERSON_7_PHONE` and so on instead of
+    /// the configured placeholder format, so a reader can follow who is
+    /// who without learning their identity. Numbers are assigned per
+    /// result, so the same person may get a different number in the next
+    /// call or session turn.
+    #[serde(default)]
+    pub subject_placeholders: bool,
+
+    /// Record why each candidate match was kept or discarded
+    ///
+    /// Results of `anonymize()` and its custom-entity variants then list
+    /// every candidate in `trace`, with the rule that decided it. Meant for
+    /// tuning; tracing copies every candidate, so leave it off in production.
+    #[serde(default)]
+    pub trace: bool,
+
+    /// Characters of context to report on each side of an entity (0 = none)
+    ///
+    /// Results then carry a `contexts` entry per entity with up to this
+    /// many characters before and after its placeholder, so reviewers and
+    /// classifiers can judge a match without reading the whole document.
+    /// The context is taken from the anonymized text: other entities in
+    /// it show as their placeholders.
+    #[serde(default)]
+    pub context_window: usize,
+}
+
+fn default_validate_ssn() -> bool {
+    true
+}
+
+fn default_locales() -> Vec<Locale> {
+    vec![Locale::Us]
+}
+
+/// Weighting model used to score detected entities.
+///
+/// Every pattern match starts at `pattern_match`. If the pattern has a
+/// checksum validator (Luhn, mod 97, national ID check digits), the outcome
+/// adds `validator_pass` or `validator_fail`; SSNs outside the issued
+/// number ranges add `validator_fail`. A type-specific keyword such
+/// as "IBAN" or "card" within `context_window` bytes of the match adds
+/// `context_keyword`; [`ContextRule`]s add keywords with their own weights.
+/// The sum is clamped to `0.0..=1.0` and matches scoring below
+/// `min_confidence` are discarded.
+///
+/// Custom entities supplied by the caller always score `1.0`.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::config::{AnonymizerConfig, ConfidenceModel};
+///
+/// // Keep card numbers that fail the Luhn check, but score them low
+/// let config = AnonymizerConfig::builder()
+///     .with_confidence_model(ConfidenceModel {
+///         validator_fail: -0.2,
+///         ..ConfidenceModel::default()
+///     })
+///     .build();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct ConfidenceModel {
+    /// Base score of a regex match (default: 0.7)
+    pub pattern_match: f32,
+    /// Added when the pattern's validator accepts the match (default: 0.3)
+    pub validator_pass: f32,
+    /// Added when the pattern's validator rejects the match (default: -0.5)
+    pub validator_fail: f32,
+    /// Added when a built-in context keyword is near the match (default: 0.1)
+    pub context_keyword: f32,
+    /// Bytes on either side of a match searched for context keywords (default: 32)
+    pub context_window: usize,
+    /// Matches scoring below this are discarded (default: 0.5)
+    pub min_confidence: f32,
+}
+
+impl Default for ConfidenceModel {
+    fn default() -> Self {
+        Self {
+            pattern_match: 0.7,
+            validator_pass: 0.3,
+            validator_fail: -0.5,
+            context_keyword: 0.1,
+            context_window: 32,
+            min_confidence: 0.5,
+        }
+    }
+}
+
+impl ConfidenceModel {
+    /// Combine the signals for a single match into a score in `0.0..=1.0`.
+    ///
+    /// # Arguments
+    ///
+    /// * `validated` - Validator outcome, or `None` if the pattern has no validator
+    /// * `has_context` - Whether a built-in context keyword is near the match
+    pub fn score(&self, validated: Option<bool>, has_context: bool) -> f32 {
+        self.score_with_context(validated, if has_context { self.context_keyword } else { 0.0 })
+    }
+
+    /// Like [`score`](Self::score), with an explicit context adjustment.
+    ///
+    /// # Arguments
+    ///
+    /// * `validated` - Validator outcome, or `None` if the pattern has no validator
+    /// * `context` - Combined weight of the context keywords near the match
+    pub fn score_with_context(&self, validated: Option<bool>, context: f32) -> f32 {
+        let mut score = self.pattern_match;
+        match validated {
+            Some(true) => score += self.validator_pass,
+            Some(false) => score += self.validator_fail,
+            None => {}
+        }
+        score += context;
+        score.clamp(0.0, 1.0)
+    }
+}
+
+/// Keywords that change the confidence of nearby matches of one type.
+///
+/// A positive weight marks words that announce the entity ("SSN",
+/// "routing number"); a negative weight marks words suggesting the match
+/// is something else ("order", "invoice"). Keywords are matched
+/// case-insensitively within [`ConfidenceModel::context_window`] bytes on
+/// either side of a match, in addition to the built-in keywords of the
+/// type. When several keywords are found, only the strongest boost and
+/// the strongest penalty are added.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::config::ContextRule;
+/// use anonymask_core::entity::EntityType;
+///
+/// let invoices = ContextRule::new(EntityType::Ssn, ["invoice", "order"], -0.3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ContextRule {
+    /// Entity type whose matches are adjusted
+    pub entity_type: EntityType,
+    /// Words to look for, case-insensitive
+    pub keywords: Vec<String>,
+    /// Added to the confidence when a keyword is found
+    pub weight: f32,
+}
+
+impl ContextRule {
+    /// Create a rule adding `weight` to `entity_type` matches near any of `keywords`.
+    pub fn new<I, S>(entity_type: EntityType, keywords: I, weight: f32) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            entity_type,
+            keywords: keywords.into_iter().map(Into::into).collect(),
+            weight,
+        }
+    }
+}
+
+/// Re-types entities that look more like another type.
+///
+/// Some values match the patterns of several types: a 9-digit number may
+/// be a phone number, a tax file number or an SSN. A rule moves entities
+/// of type `from` to type `to` when the validator of `to` (checksums,
+/// SSN area and group rules) accepts the value and one of `keywords`
+/// appears within [`ConfidenceModel::context_window`] bytes of it. Without
+/// keywords, the built-in keywords of `to` are used ("ssn", "social
+/// security" for SSNs). Types without a validator are checked by context
+/// alone, types without keywords by their validator alone.
+///
+/// Rules run after detection, before allow-list and overlap handling and
+/// replacement; the first matching rule wins. With
+/// [`trace`](AnonymizerConfig::trace) on, re-typed entities are recorded
+/// as [`Outcome::Coerced`](crate::detection::Outcome::Coerced) under
+/// their original type.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::config::CoercionRule;
+/// use anonymask_core::entity::EntityType;
+///
+/// let rule = CoercionRule::new(EntityType::Phone, EntityType::Ssn);
+/// let explicit = CoercionRule::new(EntityType::Tfn, EntityType::Ssn).with_keywords(["ssn", "social"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CoercionRule {
+    /// Type of the entities the rule applies to
+    pub from: EntityType,
+    /// Type they are given
+    pub to: EntityType,
+    /// Words of which one must be near the entity, case-insensitive; the
+    /// built-in keywords of `to` if empty
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+impl CoercionRule {
+    /// Create a rule re-typing `from` entities as `to`, using the built-in
+    /// keywords of `to`.
+    pub fn new(from: EntityType, to: EntityType) -> Self {
+        Self {
+            from,
+            to,
+            keywords: Vec::new(),
+        }
+    }
+
+    /// Require one of `keywords` near the entity instead.
+    pub fn with_keywords<I, S>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.keywords = keywords.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// Thresholds of the high-entropy secret detector.
+///
+/// Requesting [`EntityType::Secret`] reports runs of at least `min_length`
+/// base64/hex characters, mixing letters and digits, whose Shannon entropy
+/// is at least `min_entropy` bits per character. Hex strings can't exceed
+/// 4 bits, so lower `min_entropy` to about 3.5 to catch hex keys, at the cost
+/// of also flagging hashes and UUIDs.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::config::SecretDetection;
+///
+/// let strict = SecretDetection {
+///     min_length: 32,
+///     ..SecretDetection::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct SecretDetection {
+    /// Shortest string considered, in characters (default: 20)
+    pub min_length: usize,
+    /// Minimum Shannon entropy in bits per character (default: 4.0)
+    pub min_entropy: f32,
+}
+
+impl Default for SecretDetection {
+    fn default() -> Self {
+        Self {
+            min_length: 20,
+            min_entropy: 4.0,
+        }
+    }
+}
+
+/// A user-supplied regular expression.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CustomPattern {
+    /// Entity type name reported for matches
+    pub name: String,
+    /// The regular expression
+    pub pattern: String,
+}
+
+/// Values under matching key names are PII of one type.
+///
+/// `key` is a regular expression matched against whole key names, e.g.
+/// `(?i)e-?mail` matches "Email" and "e-mail" but not "email_verified".
+/// Values of matching keys are reported whatever their format, with
+/// confidence 1.0:
+///
+/// * in text, after the key and a `:` or `=`, up to the end of the line or
+///   the next comma or semicolon, e.g. `DOB: 04/12/1988` or `"email": "jo@x.io"`
+/// * in [`Anonymizer::anonymize_json`](crate::Anonymizer::anonymize_json),
+///   as the whole string or number under the key
+///
+/// Keys are checked against the [`PatternLimits`] like custom patterns.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::config::KeyRule;
+/// use anonymask_core::entity::EntityType;
+///
+/// let rule = KeyRule::new("(?i)dob|date_of_birth", EntityType::Date);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct KeyRule {
+    /// Regular expression for key names
+    pub key: String,
+    /// Entity type reported for values under matching keys
+    pub entity_type: EntityType,
+}
+
+impl KeyRule {
+    /// Report values under keys matching `key` as `entity_type`.
+    pub fn new(key: impl Into<String>, entity_type: EntityType) -> Self {
+        Self {
+            key: key.into(),
+            entity_type,
+        }
+    }
+}
+
+/// How the fields a selector locates in structured documents are anonymized.
+///
+/// One list of policies governs every structured handler: JSON and NDJSON,
+/// CSV, HTML and XML, and logfmt, including through
+/// [`Anonymizer::anonymize_auto`](crate::Anonymizer::anonymize_auto).
+/// Each format is seen as a tree of named fields:
+///
+/// * JSON: object keys and array indices, e.g. `users`, `0`, `email`
+/// * CSV: one entry per data row, by 0-based index, holding one field per
+///   column, named by its header (or its index without a header row)
+/// * HTML and XML: element names from the root element down, with each
+///   element's attributes as fields of the element
+/// * logfmt: one entry per line, by 0-based index, holding its keys
+///
+/// Selectors are written in one of three forms:
+///
+/// * JSONPath-like: `$.users[*].email`, `$..ssn`, `$['a b'].c`
+/// * XPath-like: `/order/customer/@ssn`, `//email`, `/order/*/phone`
+/// * a bare name, such as a CSV column name: `email` selects fields named
+///   `email` at any depth, like `$..email`
+///
+/// `*` matches any one name, and `..` or `//` any number of levels. Names
+/// are compared exactly. Invalid selectors are rejected when the
+/// anonymizer is created with `AnonymaskError::InvalidConfig`.
+///
+/// A policy applies to the selected field and everything below it, unless
+/// a policy selecting a deeper field applies there. Policies take
+/// precedence over [`KeyRule`]s, and are overridden by the keys and column
+/// policies passed to a single call.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::config::{FieldAction, FieldPolicy};
+/// use anonymask_core::entity::EntityType;
+///
+/// let ssn = FieldPolicy::new("//customer/@ssn", FieldAction::Always(EntityType::Ssn));
+/// let ids = FieldPolicy::new("order_id", FieldAction::Skip);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FieldPolicy {
+    /// Selector of the fields the policy applies to
+    pub selector: String,
+    /// How the selected fields are anonymized
+    pub action: FieldAction,
+}
+
+impl FieldPolicy {
+    /// Anonymize the fields matching `selector` as `action` says.
+    pub fn new(selector: impl Into<String>, action: FieldAction) -> Self {
+        Self {
+            selector: selector.into(),
+            action,
+        }
+    }
+}
+
+/// How the fields selected by a [`FieldPolicy`] are anonymized.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum FieldAction {
+    /// Detect entities in each value, like any text
+    #[default]
+    Detect,
+    /// Replace each non-empty value whole, reported as this type
+    Always(EntityType),
+    /// Leave the values untouched
+    Skip,
+}
+
+/// Limits that keep user-supplied patterns from degrading detection.
+///
+/// The regex engine runs in linear time, so patterns can't backtrack
+/// catastrophically, but a pattern can still compile to a huge program or
+/// be slow on large inputs. Patterns over the size limits are rejected when
+/// the anonymizer is created with `AnonymaskError::PatternRejected`; a
+/// pattern that runs past its time budget on a text fails that call with
+/// `AnonymaskError::PatternBudgetExceeded`.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::config::PatternLimits;
+/// use std::time::Duration;
+///
+/// let limits = PatternLimits {
+///     match_budget: Duration::from_millis(20),
+///     ..PatternLimits::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct PatternLimits {
+    /// Longest accepted pattern source, in bytes (default: 1024)
+    pub max_pattern_len: usize,
+    /// Maximum compiled program size, in bytes (default: 1 MiB)
+    pub size_limit: usize,
+    /// Maximum nesting depth of groups and repetitions (default: 64)
+    pub nest_limit: u32,
+    /// Longest time one pattern may spend on one text (default: 100 ms)
+    pub match_budget: Duration,
+}
+
+impl Default for PatternLimits {
+    fn default() -> Self {
+        Self {
+            max_pattern_len: 1024,
+            size_limit: 1 << 20,
+            nest_limit: 64,
+            match_budget: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Limits on the detection work of one call, for latency-sensitive callers.
+///
+/// Once either limit is reached, detection stops scanning and the text is
+/// anonymized with the entities found so far; the result's
+/// `budget_exceeded` flag is set. Custom entities and dictionaries, which
+/// scan in linear time, are always matched; the remaining patterns, the
+/// high-entropy scan and NER are skipped. Unlike
+/// [`PatternLimits::match_budget`], exceeding the budget is not an error.
+///
+/// Both limits apply to each `anonymize()` call, each document of a batch
+/// and each 64 KiB segment of a cancellable call.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::config::{AnonymizerConfig, DetectionBudget};
+/// use std::time::Duration;
+///
+/// // Keep chat latency predictable on pathological inputs
+/// let config = AnonymizerConfig::builder()
+///     .with_detection_budget(DetectionBudget {
+///         max_time: Some(Duration::from_millis(20)),
+///         max_matches: Some(10_000),
+///     })
+///     .build();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct DetectionBudget {
+    /// Longest time detection may take (default: unlimited)
+    pub max_time: Option<Duration>,
+    /// Most pattern matches examined, a proxy for regex work since the
+    /// engine doesn't count steps (default: unlimited)
+    pub max_matches: Option<usize>,
+}
+
+/// Placeholder UUIDs derived from a keyed hash of the replaced value.
+///
+/// With a key configured, the `{uuid}` part of `Standard` and custom
+/// placeholders is a hash of the entity type and value instead of a random
+/// UUID, so the same value gets the same placeholder in every call,
+/// process and service sharing the key. This lets separately anonymized
+/// records be joined on their placeholders. Anyone holding the key can
+/// test guesses of a value against a placeholder, so keep it as secret as
+/// the data.
+///
+/// The token is `length` characters of the hash in `encoding`; pick the
+/// encoding whose alphabet downstream systems accept. Shorter tokens make
+/// collisions between different values more likely: 32 hex characters
+/// (128 bits) is the default, and tokens need at least 64 bits (16 hex,
+/// 13 base32 or 11 base58 characters). A call whose new value gets a
+/// placeholder already standing for a different value fails.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::config::{AnonymizerConfig, HashAlgorithm, HashedTokens, TokenEncoding};
+///
+/// let config = AnonymizerConfig::builder()
+///     .with_hashed_tokens(HashedTokens {
+///         algorithm: HashAlgorithm::SipHash,
+///         encoding: TokenEncoding::Base58,
+///         length: 22,
+///         ..HashedTokens::new("key from your secret store")
+///     })
+///     .build();
+/// ```
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HashedTokens {
+    /// Secret key of the hash
+    pub key: String,
+    /// Keyed hash function (default: HMAC-SHA-256)
+    #[serde(default)]
+    pub algorithm: HashAlgorithm,
+    /// Alphabet of the token (default: lowercase hex)
+    #[serde(default)]
+    pub encoding: TokenEncoding,
+    /// Characters in the token (default: 32)
+    #[serde(default = "default_token_length")]
+    pub length: usize,
+}
+
+fn default_token_length() -> usize {
+    32
+}
+
+impl HashedTokens {
+    /// Tokens of 32 hex characters of HMAC-SHA-256 under `key`.
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            algorithm: HashAlgorithm::default(),
+            encoding: TokenEncoding::default(),
+            length: default_token_length(),
+        }
+    }
+}
+
+impl std::fmt::Debug for HashedTokens {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HashedTokens")
+            .field("key", &"[REDACTED]")
+            .field("algorithm", &self.algorithm)
+            .field("encoding", &self.encoding)
+            .field("length", &self.length)
+            .finish()
+    }
+}
+
+/// Keyed hash function of [`HashedTokens`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum HashAlgorithm {
+    /// HMAC-SHA-256, 256-bit digests
+    #[default]
+    HmacSha256,
+    /// SipHash-1-3, 128-bit digests; faster, for high-volume pipelines
+    SipHash,
+    /// Keyed BLAKE3, 256-bit digests; fast with the strength of HMAC-SHA-256
+    Blake3,
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = AnonymaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hmac_sha256" | "hmac-sha256" | "sha256" => Ok(HashAlgorithm::HmacSha256),
+            "siphash" => Ok(HashAlgorithm::SipHash),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            _ => Err(AnonymaskError::InvalidConfig(format!(
+                "unknown hash algorithm '{}', expected 'hmac_sha256', 'siphash' or 'blake3'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Alphabet of [`HashedTokens`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum TokenEncoding {
+    /// Lowercase hex, `[0-9a-f]`
+    #[default]
+    Hex,
+    /// Lowercase RFC 4648 base32 without padding, `[a-z2-7]`
+    Base32,
+    /// Bitcoin base58, alphanumeric without `0`, `O`, `I` and `l`
+    Base58,
+}
+
+impl FromStr for TokenEncoding {
+    type Err = AnonymaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hex" => Ok(TokenEncoding::Hex),
+            "base32" => Ok(TokenEncoding::Base32),
+            "base58" => Ok(TokenEncoding::Base58),
+            _ => Err(AnonymaskError::InvalidConfig(format!(
+                "unknown token encoding '{}', expected 'hex', 'base32' or 'base58'",
+                s
+            ))),
+        }
+    }
+}
+
+/// A known-safe value that detection should skip.
+///
+/// Rules match a detected entity's whole value, either exactly or with a
+/// regular expression, and can be restricted to one entity type.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::config::AllowRule;
+/// use anonymask_core::entity::EntityType;
+///
+/// let office = AllowRule::value("+1 415 555 0100").for_type(EntityType::Phone);
+/// let staff = AllowRule::pattern(r"[a-z.]+@ourcompany\.com");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AllowRule {
+    /// How values are matched
+    pub matcher: AllowMatcher,
+    /// Only apply to entities of this type; `None` applies to all types
+    #[serde(default)]
+    pub entity_type: Option<EntityType>,
+}
+
+/// How an [`AllowRule`] matches entity values.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum AllowMatcher {
+    /// The value equals this string exactly
+    Exact(String),
+    /// The whole value matches this regular expression
+    Pattern(String),
+}
+
+impl AllowRule {
+    /// Allow a value exactly as written.
+    pub fn value(value: impl Into<String>) -> Self {
+        Self {
+            matcher: AllowMatcher::Exact(value.into()),
+            entity_type: None,
+        }
+    }
+
+    /// Allow every value fully matched by a regular expression.
+    ///
+    /// The pattern is anchored, so `ourcompany\.com` doesn't allow
+    /// `jane@ourcompany.com`.
+    pub fn pattern(pattern: impl Into<String>) -> Self {
+        Self {
+            matcher: AllowMatcher::Pattern(pattern.into()),
+            entity_type: None,
+        }
+    }
+
+    /// Only apply this rule to entities of `entity_type`.
+    pub fn for_type(mut self, entity_type: EntityType) -> Self {
+        self.entity_type = Some(entity_type);
+        self
+    }
+}
+
+/// How to choose between detections whose spans overlap.
+///
+/// Only one entity is kept for any stretch of text. For example, in
+/// "123-45-6789-0000" a phone pattern and an SSN pattern may match
+/// different but overlapping parts.
+///
+/// Strings parse as `"earliest"`, `"longest"` or `"priority:ssn,phone"`.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::config::OverlapStrategy;
+/// use anonymask_core::entity::EntityType;
+///
+/// let strategy: OverlapStrategy = "priority:ssn,phone".parse().unwrap();
+/// assert_eq!(strategy, OverlapStrategy::Priority(vec![EntityType::Ssn, EntityType::Phone]));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum OverlapStrategy {
+    /// The match starting first wins
+    #[default]
+    Earliest,
+    /// The longest match wins; ties go to the one starting first
+    Longest,
+    /// Types listed first win; unlisted types rank last, and ties are
+    /// broken by length, then position
+    Priority(Vec<EntityType>),
+}
+
+impl FromStr for OverlapStrategy {
+    type Err = AnonymaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(types) = s.strip_prefix("priority:") {
+            return types
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(EntityType::from_str)
+                .collect::<Result<_, _>>()
+                .map(OverlapStrategy::Priority);
+        }
+        match s.to_lowercase().as_str() {
+            "earliest" => Ok(OverlapStrategy::Earliest),
+            "longest" => Ok(OverlapStrategy::Longest),
+            _ => Err(AnonymaskError::InvalidConfig(format!(
+                "unknown overlap strategy '{}', expected 'earliest', 'longest' or 'priority:<types>'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Which type wins when the same value is detected as both a custom and a
+/// built-in type.
+///
+/// Passing "john@email.com" as a custom `contact` value while
+/// `EntityType::Email` is enabled finds it twice. All occurrences of the
+/// value, compared after trimming and, without case sensitivity, case
+/// folding, are then reported under the preferred type, so it gets one
+/// placeholder everywhere.
+///
+/// Strings parse as `"custom"` or `"builtin"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum DuplicatePreference {
+    /// The custom type wins; callers naming a value usually know best
+    #[default]
+    Custom,
+    /// The built-in type wins
+    BuiltIn,
+}
+
+impl DuplicatePreference {
+    /// Whether `entity_type` is of the preferred kind.
+    pub(crate) fn prefers(self, entity_type: &EntityType) -> bool {
+        matches!(entity_type, EntityType::Custom(_)) == (self == DuplicatePreference::Custom)
+    }
+}
+
+impl FromStr for DuplicatePreference {
+    type Err = AnonymaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "custom" => Ok(DuplicatePreference::Custom),
+            "builtin" | "built_in" => Ok(DuplicatePreference::BuiltIn),
+            _ => Err(AnonymaskError::InvalidConfig(format!(
+                "unknown duplicate preference '{}', expected 'custom' or 'builtin'",
+                s
+            ))),
+        }
+    }
+}
+
+/// How complete a US phone number must be to be reported.
+///
+/// The US locale pattern also matches numbers written without their last
+/// four digits, such as `555-123`, which ticket numbers and other short
+/// codes look like too. Other locales' patterns only match complete
+/// numbers either way.
+///
+/// Strings parse as `"loose"` or `"strict"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum PhoneMatching {
+    /// Report partial numbers of six digits and up
+    #[default]
+    Loose,
+    /// Only report numbers of ten digits, or eleven with the country code 1
+    Strict,
+}
+
+impl FromStr for PhoneMatching {
+    type Err = AnonymaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "loose" => Ok(PhoneMatching::Loose),
+            "strict" => Ok(PhoneMatching::Strict),
+            _ => Err(AnonymaskError::InvalidConfig(format!(
+                "unknown phone matching '{}', expected 'loose' or 'strict'",
+                s
+            ))),
+        }
+    }
 }
 
 /// Format for generated placeholders.
 ///
 /// Controls how anonymized placeholders appear in the output text.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum PlaceholderFormat {
     /// Standard format: TYPE_UUID (e.g., "EMAIL_a1b2c3d4...")
     Standard,
 
-    /// Short format: TYPE_COUNTER (e.g., "EMAIL_1", "EMAIL_2")
+    /// Short format: TYPE_INDEX (e.g., "EMAIL_1", "EMAIL_2", "PHONE_1")
     ///
-    /// Uses sequential numbering instead of UUIDs for brevity.
+    /// Uses sequential numbering instead of UUIDs for brevity, counting
+    /// each entity type separately; same as `"{type}_{index_by_type}"`.
     Short,
 
     /// Custom format with template string
     ///
     /// Available placeholders:
     /// - {type}: Entity type (uppercase)
-    /// - {uuid}: UUID v4
+    /// - {type_lower}: Entity type (lowercase)
+    /// - {uuid}: UUID v4, or the hashed token with `hashed_tokens`
+    /// - {hash}: Hashed token; requires `hashed_tokens`
     /// - {counter}: Sequential number
+    /// - {index_by_type}: Sequential number counted separately per type
+    ///
+    /// Numbers can be zero-padded (`{counter:04}` gives `0007`) and UUIDs
+    /// and hashes shortened (`{uuid:8}` keeps the first 8 characters).
+    /// Write `{{` and `}}` for literal braces.
     ///
     /// Example: "[{type}:{counter}]"
     Custom(String),
 }
 
+/// Placeholders of one entity type use their own format.
+///
+/// Types without an override use
+/// [`AnonymizerConfig::placeholder_format`]. `{counter}` is shared by all
+/// formats, so numbers stay unique across types. A format without a
+/// variable telling values apart, like `[REDACTED]`, redacts for good:
+/// all values of the type share the placeholder, and deanonymizing
+/// restores only one of them.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType, PlaceholderFormat, PlaceholderOverride};
+///
+/// let config = AnonymizerConfig::builder()
+///     .with_placeholder_format(PlaceholderFormat::Custom("<{type}_{counter}>".to_string()))
+///     .with_placeholder_override(PlaceholderOverride::new(
+///         EntityType::Ssn,
+///         PlaceholderFormat::Custom("[REDACTED]".to_string()),
+///     ))
+///     .build();
+/// let anonymizer = Anonymizer::with_config(vec![EntityType::Email, EntityType::Ssn], config).unwrap();
+///
+/// let result = anonymizer.anonymize("jo@x.io, SSN 123-45-6789").unwrap();
+/// assert_eq!(result.anonymized_text, "<EMAIL_1>, SSN [REDACTED]");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PlaceholderOverride {
+    /// Entity type the format applies to
+    pub entity_type: EntityType,
+    /// Format of its placeholders
+    pub format: PlaceholderFormat,
+}
+
+impl PlaceholderOverride {
+    /// Generate placeholders of `entity_type` in `format`.
+    pub fn new(entity_type: EntityType, format: PlaceholderFormat) -> Self {
+        Self { entity_type, format }
+    }
+}
+
 impl Default for AnonymizerConfig {
     fn default() -> Self {
         Self {
             case_sensitive: true,
             word_boundary_check: false,
             placeholder_format: PlaceholderFormat::Standard,
+            placeholder_overrides: Vec::new(),
+            reset_counters: false,
+            tolerant_deanonymization: false,
             max_entities: 0, // unlimited
+            locales: default_locales(),
+            phone_region: None,
+            phone_matching: PhoneMatching::default(),
+            named_patterns: Vec::new(),
+            custom_patterns: Vec::new(),
+            key_rules: Vec::new(),
+            field_policies: Vec::new(),
+            pattern_limits: PatternLimits::default(),
+            allow_list: Vec::new(),
+            overlap_strategy: OverlapStrategy::default(),
+            duplicate_preference: DuplicatePreference::default(),
+            context_rules: Vec::new(),
+            coercion_rules: Vec::new(),
+            credential_query_params: Vec::new(),
+            validate_ssn: true,
+            secret_detection: SecretDetection::default(),
+            confidence: ConfidenceModel::default(),
+            detection_budget: DetectionBudget::default(),
+            hashed_tokens: None,
+            link_subjects: false,
+            subject_placeholders: false,
+            trace: false,
+            context_window: 0,
         }
     }
 }
 
-impl AnonymizerConfig {
-    /// Create a new configuration builder.
+impl AnonymizerConfig {
+    /// Create a new configuration builder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::config::AnonymizerConfig;
+    ///
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_case_sensitivity(false)
+    ///     .build();
+    /// ```
+    pub fn builder() -> AnonymizerConfigBuilder {
+        AnonymizerConfigBuilder::default()
+    }
+
+    /// Check for settings that are valid but can't do what they appear to.
+    ///
+    /// Creating an anonymizer rejects invalid settings, such as unknown
+    /// named patterns; this finds the ones that would be accepted but have
+    /// no effect or defeat another setting, e.g. a custom placeholder
+    /// template without `{counter}` or `{uuid}`, an allow-list entry no
+    /// detection can match, or a `min_confidence` no pattern match can
+    /// reach. Run it before deploying a configuration.
     ///
     /// # Examples
     ///
     /// ```
-    /// use anonymask_core::config::AnonymizerConfig;
+    /// use anonymask_core::config::{AllowRule, AnonymizerConfig};
+    /// use anonymask_core::entity::EntityType;
     ///
     /// let config = AnonymizerConfig::builder()
-    ///     .with_case_sensitivity(false)
+    ///     .with_allow_rule(AllowRule::value("+1 415 555 0100").for_type(EntityType::Email))
     ///     .build();
+    ///
+    /// let warnings = config.validate();
+    /// assert_eq!(warnings[0].field, "allow_list[0]");
     /// ```
-    pub fn builder() -> AnonymizerConfigBuilder {
-        AnonymizerConfigBuilder::default()
+    pub fn validate(&self) -> Vec<ConfigWarning> {
+        let mut warnings = Vec::new();
+        let mut warn = |field: String, message: String| warnings.push(ConfigWarning { field, message });
+
+        // Invalid templates are rejected when creating an anonymizer
+        let formats = std::iter::once(("placeholder_format".to_string(), &self.placeholder_format, false)).chain(
+            self.placeholder_overrides
+                .iter()
+                .enumerate()
+                .map(|(i, o)| (format!("placeholder_overrides[{}].format", i), &o.format, true)),
+        );
+        let templates: Vec<(String, Template, bool)> = formats
+            .filter_map(|(field, format, single_type)| Some((field, Template::parse(format).ok()?, single_type)))
+            .collect();
+        for (field, template, single_type) in &templates {
+            // Within one type, {index_by_type} tells values apart on its own
+            let by_type = *single_type || template.uses(Var::Type) || template.uses(Var::TypeLower);
+            let distinct = [Var::Counter, Var::Uuid, Var::Hash].iter().any(|&var| template.uses(var))
+                || (by_type && template.uses(Var::IndexByType));
+            if !distinct {
+                warn(
+                    field.clone(),
+                    "template has no {counter}, {uuid}, {hash} or {type} with {index_by_type}, so different \
+                     values get the same placeholder and can't be told apart when deanonymizing"
+                        .to_string(),
+                );
+            }
+        }
+
+        if let Some(tokens) = &self.hashed_tokens {
+            let parsed = templates.len() == self.placeholder_overrides.len() + 1;
+            if parsed && !templates.iter().any(|(_, template, _)| template.uses(Var::Uuid) || template.uses(Var::Hash)) {
+                warn(
+                    "hashed_tokens".to_string(),
+                    "the placeholder format has no {uuid} or {hash}, so hashed tokens are never used".to_string(),
+                );
+            }
+            let min = crate::token::min_length(tokens.encoding);
+            if tokens.length < min {
+                warn(
+                    "hashed_tokens.length".to_string(),
+                    format!(
+                        "tokens need at least 64 bits, {} characters in {:?}, so different values \
+                         don't share a placeholder",
+                        min, tokens.encoding
+                    ),
+                );
+            }
+        }
+
+        // Detection without the settings that drop matches; the confidence
+        // threshold is checked on its own below
+        let probe = AnonymizerConfig {
+            allow_list: Vec::new(),
+            confidence: ConfidenceModel {
+                min_confidence: 0.0,
+                ..self.confidence
+            },
+            ..self.clone()
+        };
+        for (i, rule) in self.allow_list.iter().enumerate() {
+            let field = format!("allow_list[{}]", i);
+            match &rule.matcher {
+                AllowMatcher::Exact(value) if value.trim().is_empty() => {
+                    warn(field, "value is empty, so it matches no entity".to_string());
+                }
+                AllowMatcher::Exact(value) => {
+                    let Some(entity_type) = &rule.entity_type else {
+                        continue;
+                    };
+                    if matches!(entity_type, EntityType::Custom(_)) || entity_type.requires_ner() {
+                        continue;
+                    }
+                    let Ok(detector) = EntityDetector::with_config(std::slice::from_ref(entity_type), &probe) else {
+                        continue;
+                    };
+                    let Ok(entities) = detector.try_detect(value, None) else {
+                        continue;
+                    };
+                    if !entities.iter().any(|entity| entity.value == *value) {
+                        warn(
+                            field,
+                            format!("'{}' is never detected as {}, so the rule matches nothing", value, entity_type),
+                        );
+                    }
+                }
+                AllowMatcher::Pattern(pattern) => {
+                    if let Err(e) = regex::Regex::new(&format!("^(?:{})$", pattern)) {
+                        warn(field, format!("invalid pattern: {}", e));
+                    }
+                }
+            }
+        }
+
+        for (i, rule) in self.context_rules.iter().enumerate() {
+            let field = format!("context_rules[{}]", i);
+            if rule.keywords.iter().all(|keyword| keyword.trim().is_empty()) {
+                warn(field, "no keywords, so the rule never applies".to_string());
+            } else if rule.weight == 0.0 {
+                warn(field, "weight is 0, so the rule has no effect".to_string());
+            }
+        }
+
+        for (i, rule) in self.coercion_rules.iter().enumerate() {
+            let field = format!("coercion_rules[{}]", i);
+            let has_keywords = if rule.keywords.is_empty() {
+                !crate::context::builtin_keywords(&rule.to).is_empty()
+            } else {
+                rule.keywords.iter().any(|keyword| !keyword.trim().is_empty())
+            };
+            if rule.from == rule.to {
+                warn(field, format!("re-types {} entities as {}, so the rule has no effect", rule.from, rule.to));
+            } else if !has_keywords && crate::validation::for_type(&rule.to).is_none() {
+                warn(
+                    field,
+                    format!("{} has no validator or keywords, so every {} entity is re-typed", rule.to, rule.from),
+                );
+            }
+        }
+
+        if cfg!(not(feature = "phone-intl")) && self.phone_region.is_some() {
+            warn(
+                "phone_region".to_string(),
+                "has no effect without the phone-intl feature".to_string(),
+            );
+        }
+
+        let mut names = std::collections::HashSet::new();
+        for (i, pattern) in self.custom_patterns.iter().enumerate() {
+            if !names.insert(pattern.name.as_str()) {
+                warn(
+                    format!("custom_patterns[{}]", i),
+                    format!("another custom pattern is also named '{}'", pattern.name),
+                );
+            }
+        }
+
+        let confidence = &self.confidence;
+        let boost = self
+            .context_rules
+            .iter()
+            .map(|rule| rule.weight)
+            .fold(confidence.context_keyword, f32::max)
+            .max(0.0);
+        let best = (confidence.pattern_match + confidence.validator_pass.max(0.0) + boost).min(1.0);
+        if confidence.min_confidence > best {
+            warn(
+                "confidence.min_confidence".to_string(),
+                format!(
+                    "pattern matches score at most {}, so only custom entities are detected",
+                    best
+                ),
+            );
+        }
+
+        if self.pattern_limits.match_budget.is_zero() {
+            warn(
+                "pattern_limits.match_budget".to_string(),
+                "budget is 0, so every custom pattern fails".to_string(),
+            );
+        }
+
+        // Secrets are runs of 67 distinct token characters at most
+        let max_entropy = 67f32.log2();
+        if self.secret_detection.min_entropy > max_entropy {
+            warn(
+                "secret_detection.min_entropy".to_string(),
+                format!("no string exceeds {:.2} bits per character, so no secret is detected", max_entropy),
+            );
+        }
+
+        warnings
+    }
+}
+
+/// A setting found by [`AnonymizerConfig::validate`] that won't work as intended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigWarning {
+    /// Path of the setting, e.g. `allow_list[2]`
+    pub field: String,
+    /// What is wrong with it
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
     }
 }
 
@@ -104,7 +1328,33 @@ pub struct AnonymizerConfigBuilder {
     case_sensitive: Option<bool>,
     word_boundary_check: Option<bool>,
     placeholder_format: Option<PlaceholderFormat>,
+    placeholder_overrides: Vec<PlaceholderOverride>,
+    reset_counters: Option<bool>,
+    tolerant_deanonymization: Option<bool>,
     max_entities: Option<usize>,
+    locales: Option<Vec<Locale>>,
+    phone_region: Option<String>,
+    phone_matching: Option<PhoneMatching>,
+    named_patterns: Vec<String>,
+    custom_patterns: Vec<CustomPattern>,
+    key_rules: Vec<KeyRule>,
+    field_policies: Vec<FieldPolicy>,
+    pattern_limits: Option<PatternLimits>,
+    allow_list: Vec<AllowRule>,
+    overlap_strategy: Option<OverlapStrategy>,
+    duplicate_preference: Option<DuplicatePreference>,
+    context_rules: Vec<ContextRule>,
+    coercion_rules: Vec<CoercionRule>,
+    credential_query_params: Vec<String>,
+    validate_ssn: Option<bool>,
+    secret_detection: Option<SecretDetection>,
+    confidence: Option<ConfidenceModel>,
+    detection_budget: Option<DetectionBudget>,
+    hashed_tokens: Option<HashedTokens>,
+    link_subjects: Option<bool>,
+    subject_placeholders: Option<bool>,
+    trace: Option<bool>,
+    context_window: Option<usize>,
 }
 
 impl AnonymizerConfigBuilder {
@@ -132,6 +1382,32 @@ impl AnonymizerConfigBuilder {
         self
     }
 
+    /// Use a different placeholder format for one entity type; see
+    /// [`PlaceholderOverride`].
+    ///
+    /// Default: none
+    pub fn with_placeholder_override(mut self, placeholder_override: PlaceholderOverride) -> Self {
+        self.placeholder_overrides.push(placeholder_override);
+        self
+    }
+
+    /// Set whether placeholders are numbered from 1 in every call.
+    ///
+    /// Default: false
+    pub fn with_counter_reset(mut self, reset_counters: bool) -> Self {
+        self.reset_counters = Some(reset_counters);
+        self
+    }
+
+    /// Set whether `deanonymize()` restores placeholders with changed case
+    /// or separators.
+    ///
+    /// Default: false
+    pub fn with_tolerant_deanonymization(mut self, tolerant: bool) -> Self {
+        self.tolerant_deanonymization = Some(tolerant);
+        self
+    }
+
     /// Set the maximum number of entities to detect.
     ///
     /// Default: `0` (unlimited)
@@ -140,6 +1416,395 @@ impl AnonymizerConfigBuilder {
         self
     }
 
+    /// Set the regional pattern packs to use.
+    ///
+    /// Default: `[Locale::Us]`
+    pub fn with_locales(mut self, locales: Vec<Locale>) -> Self {
+        self.locales = Some(locales);
+        self
+    }
+
+    /// Set the region of phone numbers without a country code (feature
+    /// `phone-intl`).
+    ///
+    /// International numbers are detected for every country either way.
+    /// Default: none, so national numbers are only matched by the locale
+    /// patterns
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::AnonymizerConfig;
+    ///
+    /// // "020 7946 0958" is then read as +44 20 7946 0958
+    /// let config = AnonymizerConfig::builder().with_phone_region("GB").build();
+    /// assert_eq!(config.phone_region.as_deref(), Some("GB"));
+    /// ```
+    pub fn with_phone_region(mut self, region: impl Into<String>) -> Self {
+        self.phone_region = Some(region.into());
+        self
+    }
+
+    /// Set how complete a US phone number must be to be reported.
+    ///
+    /// Default: `PhoneMatching::Loose`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType, PhoneMatching};
+    ///
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_phone_matching(PhoneMatching::Strict)
+    ///     .build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Phone], config).unwrap();
+    ///
+    /// let result = anonymizer.anonymize("Ticket 555-123, call 555-123-4567").unwrap();
+    /// assert_eq!(result.entities.len(), 1);
+    /// assert_eq!(result.entities[0].value, "555-123-4567");
+    /// ```
+    pub fn with_phone_matching(mut self, matching: PhoneMatching) -> Self {
+        self.phone_matching = Some(matching);
+        self
+    }
+
+    /// Enable a pattern from the [`patterns`](crate::patterns) library.
+    ///
+    /// Can be called repeatedly. Unknown names are reported when the
+    /// anonymizer is created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType};
+    ///
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_named_pattern("aws_arn")
+    ///     .build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+    ///
+    /// let result = anonymizer.anonymize("role arn:aws:iam::123456789012:role/admin").unwrap();
+    /// assert_eq!(result.entities[0].entity_type, EntityType::Custom("aws_arn".to_string()));
+    /// ```
+    pub fn with_named_pattern(mut self, name: impl Into<String>) -> Self {
+        self.named_patterns.push(name.into());
+        self
+    }
+
+    /// Add a user-supplied regular expression.
+    ///
+    /// Matches are reported as entities of type `EntityType::from_str(name)`,
+    /// so a built-in name such as `"email"` adds a pattern to that type and
+    /// any other name creates a custom type. A group named `value` narrows
+    /// the reported entity to that group, e.g. `key=(?P<value>\w+)`.
+    /// Patterns are checked against the [`PatternLimits`] when the
+    /// anonymizer is created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType};
+    ///
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_custom_pattern("employee_id", r"\bEMP-\d{6}\b")
+    ///     .build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+    ///
+    /// let result = anonymizer.anonymize("Badge EMP-123456").unwrap();
+    /// assert_eq!(result.entities[0].value, "EMP-123456");
+    /// ```
+    pub fn with_custom_pattern(mut self, name: impl Into<String>, pattern: impl Into<String>) -> Self {
+        self.custom_patterns.push(CustomPattern {
+            name: name.into(),
+            pattern: pattern.into(),
+        });
+        self
+    }
+
+    /// Report values under matching key names as PII.
+    ///
+    /// Can be called repeatedly. See [`KeyRule`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType, KeyRule};
+    ///
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_key_rule(KeyRule::new("(?i)dob", EntityType::Date))
+    ///     .with_key_rule(KeyRule::new("(?i)ssn", EntityType::Ssn))
+    ///     .build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+    ///
+    /// let result = anonymizer.anonymize("DOB: 04/12/1988\nssn=123 45 6789").unwrap();
+    /// let values: Vec<&str> = result.entities.iter().map(|e| e.value.as_str()).collect();
+    /// assert_eq!(values, vec!["04/12/1988", "123 45 6789"]);
+    /// ```
+    pub fn with_key_rule(mut self, rule: KeyRule) -> Self {
+        self.key_rules.push(rule);
+        self
+    }
+
+    /// Anonymize the fields selected by `policy` in structured documents.
+    ///
+    /// Can be called repeatedly; the first policy whose selector matches a
+    /// field applies to it and to everything below it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType, PlaceholderFormat};
+    /// use anonymask_core::config::{FieldAction, FieldPolicy};
+    /// use serde_json::json;
+    ///
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_placeholder_format(PlaceholderFormat::Short)
+    ///     .with_field_policy(FieldPolicy::new("$.customer.id", FieldAction::Always(EntityType::Custom("id".into()))))
+    ///     .with_field_policy(FieldPolicy::new("//internal", FieldAction::Skip))
+    ///     .build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+    ///
+    /// let payload = json!({ "customer": { "id": 4711 }, "internal": { "owner": "ops@x.io" } });
+    /// let result = anonymizer.anonymize_json(&payload).unwrap();
+    /// assert_eq!(result.value, json!({ "customer": { "id": "ID_1" }, "internal": { "owner": "ops@x.io" } }));
+    /// ```
+    pub fn with_field_policy(mut self, policy: FieldPolicy) -> Self {
+        self.field_policies.push(policy);
+        self
+    }
+
+    /// Set the limits enforced on custom patterns.
+    ///
+    /// Default: `PatternLimits::default()`
+    pub fn with_pattern_limits(mut self, limits: PatternLimits) -> Self {
+        self.pattern_limits = Some(limits);
+        self
+    }
+
+    /// Never report values matching `rule`.
+    ///
+    /// Can be called repeatedly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{AllowRule, Anonymizer, AnonymizerConfig, EntityType};
+    ///
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_allow_rule(AllowRule::value("support@ourcompany.com"))
+    ///     .with_allow_rule(AllowRule::pattern(r"\+1 800 \d{3} \d{4}").for_type(EntityType::Phone))
+    ///     .build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+    ///
+    /// let result = anonymizer.anonymize("Write support@ourcompany.com or jane@gmail.com").unwrap();
+    /// assert_eq!(result.entities.len(), 1);
+    /// assert_eq!(result.entities[0].value, "jane@gmail.com");
+    /// ```
+    pub fn with_allow_rule(mut self, rule: AllowRule) -> Self {
+        self.allow_list.push(rule);
+        self
+    }
+
+    /// Set how overlapping detections are resolved.
+    ///
+    /// Default: `OverlapStrategy::Earliest`
+    pub fn with_overlap_strategy(mut self, strategy: OverlapStrategy) -> Self {
+        self.overlap_strategy = Some(strategy);
+        self
+    }
+
+    /// Set which type wins when a value is detected as both a custom and a
+    /// built-in type.
+    ///
+    /// Default: `DuplicatePreference::Custom`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, DuplicatePreference, EntityType};
+    ///
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_duplicate_preference(DuplicatePreference::BuiltIn)
+    ///     .build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+    ///
+    /// let custom = HashMap::from([(
+    ///     EntityType::Custom("contact".to_string()),
+    ///     vec!["john@email.com".to_string()],
+    /// )]);
+    /// let result = anonymizer.anonymize_with_custom("Mail john@email.com", Some(&custom)).unwrap();
+    /// assert_eq!(result.entities.len(), 1);
+    /// assert_eq!(result.entities[0].entity_type, EntityType::Email);
+    /// ```
+    pub fn with_duplicate_preference(mut self, preference: DuplicatePreference) -> Self {
+        self.duplicate_preference = Some(preference);
+        self
+    }
+
+    /// Add context keywords for one entity type.
+    ///
+    /// Can be called repeatedly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, ContextRule, EntityType};
+    ///
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_context_rule(ContextRule::new(EntityType::Ssn, ["order"], -0.4))
+    ///     .build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Ssn], config).unwrap();
+    ///
+    /// let result = anonymizer.anonymize("Order 123-45-6789 shipped. Customer record: SSN 487-65-4321").unwrap();
+    /// assert_eq!(result.entities.len(), 1);
+    /// assert_eq!(result.entities[0].value, "487-65-4321");
+    /// ```
+    pub fn with_context_rule(mut self, rule: ContextRule) -> Self {
+        self.context_rules.push(rule);
+        self
+    }
+
+    /// Add a rule re-typing entities that pass the checks of another type.
+    ///
+    /// Can be called repeatedly; earlier rules take precedence.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, CoercionRule, EntityType};
+    ///
+    /// // Australian tax file numbers and US SSNs are both 9 digits
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_coercion_rule(CoercionRule::new(EntityType::Tfn, EntityType::Ssn))
+    ///     .build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Tfn], config).unwrap();
+    ///
+    /// let text = "TFN 123 456 782 is on the Australian return. The US return lists SSN 123 456 790";
+    /// let result = anonymizer.anonymize(text).unwrap();
+    /// assert_eq!(result.entities[0].entity_type, EntityType::Tfn);
+    /// assert_eq!(result.entities[1].entity_type, EntityType::Ssn);
+    /// ```
+    pub fn with_coercion_rule(mut self, rule: CoercionRule) -> Self {
+        self.coercion_rules.push(rule);
+        self
+    }
+
+    /// Also report the values of these URL query parameters as credentials.
+    ///
+    /// Only used when `EntityType::Credential` is requested; names are
+    /// matched case-insensitively.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType};
+    ///
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_credential_query_params(["token", "key", "password"])
+    ///     .build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Credential], config).unwrap();
+    ///
+    /// let result = anonymizer.anonymize("GET /export?format=csv&token=abc123").unwrap();
+    /// assert_eq!(result.entities[0].value, "abc123");
+    /// ```
+    pub fn with_credential_query_params<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.credential_query_params.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set whether SSN matches in never-assigned number ranges are discarded.
+    ///
+    /// Default: true
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType};
+    ///
+    /// let text = "Invoice 987-65-4321, SSN 123-45-6789";
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Ssn]).unwrap();
+    /// assert_eq!(anonymizer.detect(text).unwrap().len(), 1);
+    ///
+    /// let config = AnonymizerConfig::builder().with_ssn_validation(false).build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Ssn], config).unwrap();
+    /// assert_eq!(anonymizer.detect(text).unwrap().len(), 2);
+    /// ```
+    pub fn with_ssn_validation(mut self, validate_ssn: bool) -> Self {
+        self.validate_ssn = Some(validate_ssn);
+        self
+    }
+
+    /// Set the thresholds of the high-entropy secret detector.
+    ///
+    /// Default: `SecretDetection::default()`
+    pub fn with_secret_detection(mut self, settings: SecretDetection) -> Self {
+        self.secret_detection = Some(settings);
+        self
+    }
+
+    /// Set the confidence weighting model.
+    ///
+    /// Default: `ConfidenceModel::default()`
+    pub fn with_confidence_model(mut self, model: ConfidenceModel) -> Self {
+        self.confidence = Some(model);
+        self
+    }
+
+    /// Limit the detection work of each call; see [`DetectionBudget`].
+    ///
+    /// Default: unlimited
+    pub fn with_detection_budget(mut self, budget: DetectionBudget) -> Self {
+        self.detection_budget = Some(budget);
+        self
+    }
+
+    /// Derive placeholder UUIDs from a keyed hash; see [`HashedTokens`].
+    ///
+    /// Default: random UUIDs
+    pub fn with_hashed_tokens(mut self, tokens: HashedTokens) -> Self {
+        self.hashed_tokens = Some(tokens);
+        self
+    }
+
+    /// Set whether results group entities by the person they describe.
+    ///
+    /// Default: false
+    pub fn with_subject_linking(mut self, link_subjects: bool) -> Self {
+        self.link_subjects = Some(link_subjects);
+        self
+    }
+
+    /// Set whether placeholders are named after their subject, e.g.
+    /// `PERSON_7_EMAIL`; implies subject linking.
+    ///
+    /// Default: false
+    pub fn with_subject_placeholders(mut self, subject_placeholders: bool) -> Self {
+        self.subject_placeholders = Some(subject_placeholders);
+        self
+    }
+
+    /// Record why each candidate match was kept or discarded.
+    ///
+    /// Default: false
+    pub fn with_trace(mut self, trace: bool) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    /// Set how many characters of context results report on each side of
+    /// an entity.
+    ///
+    /// Default: `0` (none)
+    pub fn with_context_window(mut self, chars: usize) -> Self {
+        self.context_window = Some(chars);
+        self
+    }
+
     /// Build the configuration.
     pub fn build(self) -> AnonymizerConfig {
         let default = AnonymizerConfig::default();
@@ -147,7 +1812,33 @@ impl AnonymizerConfigBuilder {
             case_sensitive: self.case_sensitive.unwrap_or(default.case_sensitive),
             word_boundary_check: self.word_boundary_check.unwrap_or(default.word_boundary_check),
             placeholder_format: self.placeholder_format.unwrap_or(default.placeholder_format),
+            placeholder_overrides: self.placeholder_overrides,
+            reset_counters: self.reset_counters.unwrap_or(default.reset_counters),
+            tolerant_deanonymization: self.tolerant_deanonymization.unwrap_or(default.tolerant_deanonymization),
             max_entities: self.max_entities.unwrap_or(default.max_entities),
+            locales: self.locales.unwrap_or(default.locales),
+            phone_region: self.phone_region,
+            phone_matching: self.phone_matching.unwrap_or(default.phone_matching),
+            named_patterns: self.named_patterns,
+            custom_patterns: self.custom_patterns,
+            key_rules: self.key_rules,
+            field_policies: self.field_policies,
+            pattern_limits: self.pattern_limits.unwrap_or(default.pattern_limits),
+            allow_list: self.allow_list,
+            overlap_strategy: self.overlap_strategy.unwrap_or(default.overlap_strategy),
+            duplicate_preference: self.duplicate_preference.unwrap_or(default.duplicate_preference),
+            context_rules: self.context_rules,
+            coercion_rules: self.coercion_rules,
+            credential_query_params: self.credential_query_params,
+            validate_ssn: self.validate_ssn.unwrap_or(default.validate_ssn),
+            secret_detection: self.secret_detection.unwrap_or(default.secret_detection),
+            confidence: self.confidence.unwrap_or(default.confidence),
+            detection_budget: self.detection_budget.unwrap_or(default.detection_budget),
+            hashed_tokens: self.hashed_tokens,
+            link_subjects: self.link_subjects.unwrap_or(default.link_subjects),
+            subject_placeholders: self.subject_placeholders.unwrap_or(default.subject_placeholders),
+            trace: self.trace.unwrap_or(default.trace),
+            context_window: self.context_window.unwrap_or(default.context_window),
         }
     }
 }
@@ -163,6 +1854,7 @@ mod tests {
         assert!(!config.word_boundary_check);
         assert_eq!(config.placeholder_format, PlaceholderFormat::Standard);
         assert_eq!(config.max_entities, 0);
+        assert_eq!(config.locales, vec![Locale::Us]);
     }
 
     #[test]
@@ -172,12 +1864,14 @@ mod tests {
             .with_word_boundary_check(true)
             .with_placeholder_format(PlaceholderFormat::Short)
             .with_max_entities(100)
+            .with_locales(vec![Locale::Us, Locale::De])
             .build();
 
         assert!(!config.case_sensitive);
         assert!(config.word_boundary_check);
         assert_eq!(config.placeholder_format, PlaceholderFormat::Short);
         assert_eq!(config.max_entities, 100);
+        assert_eq!(config.locales, vec![Locale::Us, Locale::De]);
     }
 
     #[test]
@@ -200,4 +1894,84 @@ mod tests {
 
         assert_eq!(config.placeholder_format, format);
     }
+
+    #[test]
+    fn test_confidence_model_scoring() {
+        let model = ConfidenceModel::default();
+        assert_eq!(model.score(None, false), 0.7);
+        assert_eq!(model.score(Some(true), true), 1.0);
+        assert!(model.score(Some(false), false) < model.min_confidence);
+        assert!(model.score(None, true) > model.score(None, false));
+    }
+
+    #[test]
+    fn test_confidence_model_missing_from_json() {
+        let json = r#"{"case_sensitive":true,"word_boundary_check":false,"placeholder_format":"Short","max_entities":0}"#;
+        let config: AnonymizerConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.confidence, ConfidenceModel::default());
+        assert_eq!(config.overlap_strategy, OverlapStrategy::Earliest);
+    }
+
+    #[test]
+    fn test_parse_overlap_strategy() {
+        assert_eq!("Longest".parse::<OverlapStrategy>().unwrap(), OverlapStrategy::Longest);
+        assert_eq!(
+            "priority:".parse::<OverlapStrategy>().unwrap(),
+            OverlapStrategy::Priority(vec![])
+        );
+        assert!("widest".parse::<OverlapStrategy>().is_err());
+        assert_eq!("BuiltIn".parse::<DuplicatePreference>().unwrap(), DuplicatePreference::BuiltIn);
+        assert!("first".parse::<DuplicatePreference>().is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(AnonymizerConfig::default().validate().is_empty());
+
+        let config = AnonymizerConfig::builder()
+            .with_placeholder_format(PlaceholderFormat::Custom("<{type}>".to_string()))
+            .with_placeholder_override(PlaceholderOverride::new(
+                EntityType::Ssn,
+                PlaceholderFormat::Custom("[REDACTED]".to_string()),
+            ))
+            .with_placeholder_override(PlaceholderOverride::new(
+                EntityType::Phone,
+                PlaceholderFormat::Custom("<phone {index_by_type}>".to_string()),
+            ))
+            .with_hashed_tokens(HashedTokens {
+                encoding: TokenEncoding::Base32,
+                length: 12,
+                ..HashedTokens::new("key")
+            })
+            .with_allow_rule(AllowRule::value("support@example.com").for_type(EntityType::Email))
+            .with_allow_rule(AllowRule::value("support@example.com").for_type(EntityType::Phone))
+            .with_allow_rule(AllowRule::pattern("(unclosed"))
+            .with_context_rule(ContextRule::new(EntityType::Ssn, ["ssn"], 0.0))
+            .with_coercion_rule(CoercionRule::new(EntityType::Phone, EntityType::Ssn))
+            .with_coercion_rule(CoercionRule::new(EntityType::Email, EntityType::Email))
+            .with_custom_pattern("ticket", "T-[0-9]+")
+            .with_custom_pattern("ticket", "TK[0-9]+")
+            .with_confidence_model(ConfidenceModel {
+                min_confidence: 0.95,
+                validator_pass: 0.1,
+                ..ConfidenceModel::default()
+            })
+            .build();
+        let fields: Vec<_> = config.validate().into_iter().map(|w| w.field).collect();
+        assert_eq!(
+            fields,
+            [
+                "placeholder_format",
+                "placeholder_overrides[0].format",
+                "hashed_tokens",
+                "hashed_tokens.length",
+                "allow_list[1]",
+                "allow_list[2]",
+                "context_rules[0]",
+                "coercion_rules[1]",
+                "custom_patterns[1]",
+                "confidence.min_confidence",
+            ]
+        );
+    }
 }
@@ -0,0 +1,183 @@
+//! CoNLL-style IOB export of detection results.
+//!
+//! Turns texts and their entities into the token-per-line format read by
+//! most NER training tools: each line holds a token and its IOB2 label
+//! (`B-EMAIL` on the first token of an entity, `I-EMAIL` on the rest, `O`
+//! outside entities), separated by a space, with a blank line after each
+//! sentence. Regex detections over a corpus thus bootstrap training data
+//! for a custom NER model.
+//!
+//! Text outside entities is split into words and single punctuation marks;
+//! entities are split at whitespace only, so `jo@x.io` stays one token.
+//! Sentences end at line breaks and after `.`, `!` or `?` outside entities.
+//! The output contains the detected values: protect it like the corpus.
+
+use crate::entity::Entity;
+use crate::error::AnonymaskError;
+use regex::Regex;
+use std::io::Write;
+use std::sync::OnceLock;
+
+/// Writes documents as one CoNLL file.
+///
+/// Each document starts with a `-DOCSTART- O` line, as in CoNLL-2003.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::conll::ConllWriter;
+/// use anonymask_core::{Anonymizer, EntityType};
+///
+/// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+/// let mut writer = ConllWriter::new(Vec::new());
+/// for text in ["Mail jo@x.io today.", "No PII here"] {
+///     writer.write_document(text, &anonymizer.detect(text).unwrap()).unwrap();
+/// }
+/// let conll = String::from_utf8(writer.into_inner()).unwrap();
+///
+/// assert!(conll.starts_with("-DOCSTART- O\n\nMail O\njo@x.io B-EMAIL\ntoday O\n. O\n\n"));
+/// assert!(conll.ends_with("-DOCSTART- O\n\nNo O\nPII O\nhere O\n\n"));
+/// ```
+pub struct ConllWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> ConllWriter<W> {
+    /// Create a writer appending documents to `out`.
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// Write one document with the entities detected in it.
+    ///
+    /// `entities` must be sorted and non-overlapping, as returned by
+    /// [`Anonymizer::detect`](crate::Anonymizer::detect); an entity
+    /// overlapping the one before it is labeled `O`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::IoError` if writing fails.
+    pub fn write_document(&mut self, text: &str, entities: &[Entity]) -> Result<(), AnonymaskError> {
+        self.out.write_all(b"-DOCSTART- O\n\n")?;
+        self.out.write_all(to_conll(text, entities).as_bytes())?;
+        Ok(())
+    }
+
+    /// The underlying writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Render one text's entities as CoNLL lines, without `-DOCSTART-`.
+///
+/// See [`ConllWriter::write_document`] for the requirements on `entities`.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::conll::to_conll;
+/// use anonymask_core::{Anonymizer, EntityType};
+///
+/// let anonymizer = Anonymizer::new(vec![EntityType::Phone]).unwrap();
+/// let text = "Call 555-123-4567";
+/// assert_eq!(to_conll(text, &anonymizer.detect(text).unwrap()), "Call O\n555-123-4567 B-PHONE\n\n");
+/// ```
+pub fn to_conll(text: &str, entities: &[Entity]) -> String {
+    let mut conll = String::new();
+    for sentence in sentences(text, entities) {
+        for (token, label) in sentence {
+            conll.push_str(token);
+            conll.push(' ');
+            conll.push_str(&label);
+            conll.push('\n');
+        }
+        conll.push('\n');
+    }
+    conll
+}
+
+/// Labeled tokens, split into sentences.
+type Sentence<'a> = Vec<(&'a str, String)>;
+
+/// The tokens of `text` and their labels, by sentence.
+fn sentences<'a>(text: &'a str, entities: &[Entity]) -> Vec<Sentence<'a>> {
+    let mut sentences = Vec::new();
+    let mut sentence = Vec::new();
+    let mut last = 0;
+    for entity in entities {
+        if entity.start < last {
+            continue;
+        }
+        push_words(&text[last..entity.start], &mut sentence, &mut sentences);
+        let tag = entity.entity_type.as_str().to_uppercase();
+        for (i, token) in text[entity.start..entity.end].split_whitespace().enumerate() {
+            let prefix = if i == 0 { "B" } else { "I" };
+            sentence.push((token, format!("{}-{}", prefix, tag)));
+        }
+        last = entity.end;
+    }
+    push_words(&text[last..], &mut sentence, &mut sentences);
+    if !sentence.is_empty() {
+        sentences.push(sentence);
+    }
+    sentences
+}
+
+/// Add the tokens of text outside entities, labeled `O`, ending sentences
+/// at line breaks and sentence punctuation.
+fn push_words<'a>(text: &'a str, sentence: &mut Sentence<'a>, sentences: &mut Vec<Sentence<'a>>) {
+    static WORD: OnceLock<Regex> = OnceLock::new();
+    let word = WORD.get_or_init(|| Regex::new(r"\n|\w+|[^\w\s]").unwrap());
+
+    for token in word.find_iter(text).map(|m| m.as_str()) {
+        if token != "\n" {
+            sentence.push((token, "O".to_string()));
+        }
+        if matches!(token, "\n" | "." | "!" | "?") && !sentence.is_empty() {
+            sentences.push(std::mem::take(sentence));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EntityType;
+
+    fn entity(entity_type: EntityType, text: &str, value: &str) -> Entity {
+        let start = text.find(value).unwrap();
+        Entity {
+            entity_type,
+            value: value.to_string(),
+            start,
+            end: start + value.len(),
+            confidence: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_labels_and_sentences() {
+        let text = "Ask Ada Lovelace (ada@x.io)! She's in.\nBye";
+        let entities = [
+            entity(EntityType::Person, text, "Ada Lovelace"),
+            entity(EntityType::Email, text, "ada@x.io"),
+        ];
+        assert_eq!(
+            to_conll(text, &entities),
+            "Ask O\nAda B-PERSON\nLovelace I-PERSON\n( O\nada@x.io B-EMAIL\n) O\n! O\n\n\
+             She O\n' O\ns O\nin O\n. O\n\nBye O\n\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_and_overlapping() {
+        assert_eq!(to_conll(" \n\n ", &[]), "");
+        let text = "at 10.0.0.1 ok";
+        let entities = [
+            entity(EntityType::IpAddress, text, "10.0.0.1"),
+            entity(EntityType::Phone, text, "0.0.1"),
+        ];
+        assert_eq!(to_conll(text, &entities), "at O\n10.0.0.1 B-IP_ADDRESS\nok O\n\n");
+    }
+}
@@ -0,0 +1,220 @@
+//! Context keywords that adjust the confidence of nearby matches.
+//!
+//! Many patterns are ambiguous on their own: a bare 9-digit number may be
+//! an SSN, an order number or a phone extension. Words around the match
+//! tell them apart, so each entity type carries keywords that raise
+//! ("ssn", "social security") or lower ("order", "invoice") the
+//! confidence of matches found close to them.
+
+use crate::entity::EntityType;
+use crate::gazetteer::is_word_char;
+use std::collections::HashMap;
+
+/// Keywords of each entity type, with the window they are searched in.
+pub(crate) struct ContextEnhancer {
+    /// Bytes on either side of a match that are searched
+    window: usize,
+    /// Lowercased keywords and their weights, by entity type
+    keywords: HashMap<EntityType, Vec<(String, f32)>>,
+}
+
+/// Context keywords found around one match.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct ContextSignal {
+    /// Strongest boost plus strongest penalty found
+    pub adjustment: f32,
+    /// Whether a keyword with a positive weight was found
+    pub supported: bool,
+}
+
+impl ContextEnhancer {
+    pub(crate) fn new(window: usize) -> Self {
+        Self {
+            window,
+            keywords: HashMap::new(),
+        }
+    }
+
+    /// Register a keyword for `entity_type`. Empty keywords are ignored.
+    pub(crate) fn add(&mut self, entity_type: &EntityType, keyword: &str, weight: f32) {
+        let keyword = keyword.trim().to_lowercase();
+        if keyword.is_empty() {
+            return;
+        }
+        self.keywords
+            .entry(entity_type.clone())
+            .or_default()
+            .push((keyword, weight));
+    }
+
+    /// Whether no keywords are registered.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.keywords.is_empty()
+    }
+
+    /// Look for keywords of `entity_type` around `text[start..end]`.
+    ///
+    /// Keywords only count as whole words, so "ip" isn't found in
+    /// "shipping", nor "order" in "border".
+    ///
+    /// Only the strongest boost and the strongest penalty count, so a text
+    /// repeating "SSN" three times isn't more convincing than one mention.
+    pub(crate) fn evaluate(&self, text: &str, start: usize, end: usize, entity_type: &EntityType) -> ContextSignal {
+        let keywords = match self.keywords.get(entity_type) {
+            Some(keywords) => keywords,
+            None => return ContextSignal::default(),
+        };
+
+        let mut window_start = start.saturating_sub(self.window);
+        while !text.is_char_boundary(window_start) {
+            window_start -= 1;
+        }
+        let mut window_end = end.saturating_add(self.window).min(text.len());
+        while !text.is_char_boundary(window_end) {
+            window_end += 1;
+        }
+        let before = text[window_start..start].to_lowercase();
+        let after = text[end..window_end].to_lowercase();
+        // A word cut by the window isn't whole inside it
+        let before_cut = text[..window_start].chars().next_back().is_some_and(continues_keyword);
+        let after_cut = text[window_end..].chars().next().is_some_and(continues_keyword);
+
+        let mut boost = 0.0f32;
+        let mut penalty = 0.0f32;
+        for (keyword, weight) in keywords {
+            if contains_word(&before, keyword, before_cut, false) || contains_word(&after, keyword, false, after_cut) {
+                boost = boost.max(*weight);
+                penalty = penalty.min(*weight);
+            }
+        }
+        ContextSignal {
+            adjustment: boost + penalty,
+            supported: boost > 0.0,
+        }
+    }
+}
+
+/// Whether `keyword` occurs in `window` as a whole word. The ends of the
+/// window count as word boundaries unless a word is cut there.
+fn contains_word(window: &str, keyword: &str, start_cut: bool, end_cut: bool) -> bool {
+    window.match_indices(keyword).any(|(i, _)| {
+        let j = i + keyword.len();
+        let before = window[..i].chars().next_back().map_or(start_cut, continues_keyword);
+        let after = window[j..].chars().next().map_or(end_cut, continues_keyword);
+        !before && !after
+    })
+}
+
+/// Whether `c` continues a word around a keyword. Unlike in dictionary
+/// terms, `_` separates words, as in `API_KEY`.
+fn continues_keyword(c: char) -> bool {
+    c != '_' && is_word_char(c)
+}
+
+/// Built-in keywords that make a nearby match more likely to be a real entity.
+pub(crate) fn builtin_keywords(entity_type: &EntityType) -> &'static [&'static str] {
+    match entity_type {
+        EntityType::Email => &["email", "e-mail", "mail"],
+        EntityType::Phone => &["phone", "tel", "mobile", "cell", "fax", "call", "電話"],
+        EntityType::Ssn => &["ssn", "social security"],
+        EntityType::CreditCard => &["card", "visa", "mastercard", "amex", "cc"],
+        EntityType::IpAddress => &["ip", "host", "server"],
+        EntityType::Url => &["url", "link", "http"],
+        EntityType::Iban => &["iban", "account", "konto", "kontonummer", "bank"],
+        EntityType::SteuerId => &["steuer", "steuernummer", "steueridentifikationsnummer", "idnr", "tax"],
+        EntityType::Nir => &["nir", "sécurité sociale", "insee"],
+        EntityType::Siren => &["siren"],
+        EntityType::Siret => &["siret"],
+        EntityType::Dni => &["dni", "nif"],
+        EntityType::Nie => &["nie"],
+        EntityType::Cpf => &["cpf"],
+        EntityType::Cnpj => &["cnpj"],
+        EntityType::MyNumber => &["my number", "マイナンバー", "個人番号"],
+        EntityType::Tfn => &["tfn", "tax file"],
+        EntityType::Medicare => &["medicare"],
+        EntityType::Address => &["address", "adresse", "addr"],
+        EntityType::Date => &["date", "dob", "born", "datum"],
+        // Also what a date of birth is only reported next to
+        EntityType::DateOfBirth => &[
+            "dob", "d.o.b", "birth", "born", "geboren", "geburtsdatum", "né le", "née le", "naissance", "nacimiento",
+            "nacido", "nacida", "生年月日",
+        ],
+        EntityType::AwsAccessKey => &["aws", "access key", "key id"],
+        EntityType::GithubToken => &["github", "token"],
+        EntityType::SlackToken => &["slack", "token"],
+        EntityType::Jwt => &["token", "bearer", "jwt"],
+        EntityType::Secret => &["secret", "token", "key", "password", "passwd", "pwd"],
+        // Both carry their own marker ("Bearer", "-----BEGIN")
+        EntityType::BearerToken | EntityType::PrivateKey | EntityType::Credential => &[],
+        EntityType::Person | EntityType::Org | EntityType::Location => &[],
+        EntityType::Custom(_) => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enhancer() -> ContextEnhancer {
+        let mut enhancer = ContextEnhancer::new(16);
+        enhancer.add(&EntityType::Ssn, "SSN", 0.2);
+        enhancer.add(&EntityType::Ssn, "order", -0.4);
+        enhancer
+    }
+
+    #[test]
+    fn test_keywords_on_either_side() {
+        let enhancer = enhancer();
+        let text = "123456789 is my ssn";
+        let signal = enhancer.evaluate(text, 0, 9, &EntityType::Ssn);
+        assert_eq!(signal, ContextSignal { adjustment: 0.2, supported: true });
+
+        let text = "Order 123456789";
+        let signal = enhancer.evaluate(text, 6, 15, &EntityType::Ssn);
+        assert_eq!(signal, ContextSignal { adjustment: -0.4, supported: false });
+    }
+
+    #[test]
+    fn test_keywords_match_whole_words() {
+        let mut enhancer = enhancer();
+        enhancer.add(&EntityType::IpAddress, "ip", 0.2);
+        enhancer.add(&EntityType::CreditCard, "cc", 0.2);
+        enhancer.add(&EntityType::Nie, "nie", 0.2);
+
+        let text = "Shipping 10.0.0.1";
+        assert_eq!(enhancer.evaluate(text, 9, 17, &EntityType::IpAddress), ContextSignal::default());
+        let text = "We accept 4111111111111111";
+        assert_eq!(enhancer.evaluate(text, 10, 26, &EntityType::CreditCard), ContextSignal::default());
+        let text = "My niece X1234567L";
+        assert_eq!(enhancer.evaluate(text, 9, 18, &EntityType::Nie), ContextSignal::default());
+        let text = "Border 123456789";
+        assert_eq!(enhancer.evaluate(text, 7, 16, &EntityType::Ssn), ContextSignal::default());
+
+        // Punctuation and the match itself are boundaries
+        let text = "IP:10.0.0.1, cc#4111111111111111";
+        assert!(enhancer.evaluate(text, 3, 11, &EntityType::IpAddress).supported);
+        assert!(enhancer.evaluate(text, 16, 32, &EntityType::CreditCard).supported);
+        assert!(enhancer.evaluate("USER_SSN=123456789", 9, 18, &EntityType::Ssn).supported);
+        // A word cut by the window doesn't count
+        let text = format!("bssn{}123456789", " ".repeat(13));
+        assert!(!enhancer.evaluate(&text, 17, 26, &EntityType::Ssn).supported);
+        assert!(enhancer.evaluate(&text[1..], 16, 25, &EntityType::Ssn).supported);
+    }
+
+    #[test]
+    fn test_window_limits_search() {
+        let enhancer = enhancer();
+        let text = "SSN on file. Unrelated text here: 123456789";
+        let signal = enhancer.evaluate(text, 34, 43, &EntityType::Ssn);
+        assert_eq!(signal, ContextSignal::default());
+        assert_eq!(enhancer.evaluate(text, 34, 43, &EntityType::Phone), ContextSignal::default());
+    }
+
+    #[test]
+    fn test_window_respects_char_boundaries() {
+        let enhancer = enhancer();
+        let text = "ééééééééé ssn 123456789 ééééééééé";
+        let signal = enhancer.evaluate(text, 23, 32, &EntityType::Ssn);
+        assert!(signal.supported);
+    }
+}
@@ -0,0 +1,385 @@
+//! Corpus scrubbing between object stores (feature `object_store`).
+//!
+//! Anonymizes every object under a prefix of one store (S3, GCS, Azure
+//! Blob, local files, ... through the `object_store` crate) and writes the
+//! results under a prefix of another, keeping the relative paths. Objects
+//! are processed concurrently up to a limit, and transient store failures
+//! are retried with exponential backoff.
+//!
+//! The format of an object follows its extension: `.json` is anonymized as
+//! a document, `.jsonl`/`.ndjson` as JSON lines, `.csv` as CSV (with the
+//! `csv` feature), anything else as text. Each object gets its own mapping,
+//! saved to a [`MappingStore`] under the target path if one is set, and
+//! discarded otherwise.
+
+use crate::anonymizer::Anonymizer;
+use crate::error::AnonymaskError;
+use crate::store::MappingStore;
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{ObjectStore, PutPayload};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Outcome of a [`CorpusScrubber::run`].
+///
+/// # Fields
+///
+/// * `objects` - Objects anonymized and written, by source path
+/// * `failed` - Objects that couldn't be processed, by source path
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CorpusReport {
+    pub objects: Vec<ScrubbedObject>,
+    pub failed: Vec<FailedObject>,
+}
+
+/// An object anonymized and written to the target store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ScrubbedObject {
+    /// Path in the source store
+    pub source: String,
+    /// Path in the target store, also the key of its saved mapping
+    pub target: String,
+    /// Number of entities replaced
+    pub entities: usize,
+    /// Whether the object hit the `max_entities` limit
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// An object that couldn't be processed; nothing was written for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FailedObject {
+    /// Path in the source store
+    pub source: String,
+    /// Why it failed, after any retries
+    pub error: String,
+}
+
+/// Anonymizes objects from one store into another; created with
+/// [`Anonymizer::corpus`].
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use anonymask_core::{Anonymizer, EntityType};
+/// use object_store::{memory::InMemory, path::Path, ObjectStore};
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let raw = Arc::new(InMemory::new());
+/// let clean = Arc::new(InMemory::new());
+/// raw.put(&Path::from("tickets/42.txt"), "Contact john@email.com".into()).await.unwrap();
+///
+/// let anonymizer = Arc::new(Anonymizer::new(vec![EntityType::Email]).unwrap());
+/// let report = anonymizer
+///     .corpus(raw, clean.clone())
+///     .with_source_prefix("tickets")
+///     .with_target_prefix("scrubbed/tickets")
+///     .run()
+///     .await
+///     .unwrap();
+///
+/// assert_eq!(report.objects[0].target, "scrubbed/tickets/42.txt");
+/// let text = clean.get(&Path::from("scrubbed/tickets/42.txt")).await.unwrap().bytes().await.unwrap();
+/// assert!(!String::from_utf8(text.to_vec()).unwrap().contains("john@email.com"));
+/// # });
+/// ```
+pub struct CorpusScrubber {
+    anonymizer: Arc<Anonymizer>,
+    source: Arc<dyn ObjectStore>,
+    source_prefix: Path,
+    target: Arc<dyn ObjectStore>,
+    target_prefix: Path,
+    mappings: Option<Arc<dyn MappingStore>>,
+    concurrency: usize,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl Anonymizer {
+    /// Scrub objects from `source` into `target` with this anonymizer.
+    ///
+    /// Both stores may be the same, with different prefixes. Defaults to
+    /// the whole source store, 8 objects at a time and 3 retries; see
+    /// [`CorpusScrubber`] for the options.
+    pub fn corpus(self: &Arc<Self>, source: Arc<dyn ObjectStore>, target: Arc<dyn ObjectStore>) -> CorpusScrubber {
+        CorpusScrubber {
+            anonymizer: Arc::clone(self),
+            source,
+            source_prefix: Path::default(),
+            target,
+            target_prefix: Path::default(),
+            mappings: None,
+            concurrency: 8,
+            retries: 3,
+            backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+impl CorpusScrubber {
+    /// Only process objects under this prefix of the source store.
+    ///
+    /// Default: the whole store
+    pub fn with_source_prefix(mut self, prefix: &str) -> Self {
+        self.source_prefix = Path::from(prefix);
+        self
+    }
+
+    /// Write objects under this prefix of the target store.
+    ///
+    /// Default: the root of the store
+    pub fn with_target_prefix(mut self, prefix: &str) -> Self {
+        self.target_prefix = Path::from(prefix);
+        self
+    }
+
+    /// Save each object's mapping under its target path.
+    ///
+    /// The mapping is saved before the object is written, so nothing is
+    /// published without a way back. Without a store, anonymization can't
+    /// be reversed.
+    pub fn with_mapping_store(mut self, store: Arc<dyn MappingStore>) -> Self {
+        self.mappings = Some(store);
+        self
+    }
+
+    /// Set the number of objects processed at once.
+    ///
+    /// Default: 8; values below 1 are treated as 1
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Set how often a failed read or write is retried, and the delay
+    /// before the first retry; the delay doubles with each attempt.
+    ///
+    /// Missing objects are never retried. Default: 3 retries, 200 ms
+    pub fn with_retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Scrub every object under the source prefix.
+    ///
+    /// Anonymization runs on tokio's blocking pool, so this must be called
+    /// within a tokio runtime. An object that can't be read, anonymized or
+    /// written is reported in [`CorpusReport::failed`] and the others are
+    /// still processed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::StorageError` if listing the source fails.
+    pub async fn run(&self) -> Result<CorpusReport, AnonymaskError> {
+        let locations: Vec<Path> = self
+            .source
+            .list(Some(&self.source_prefix))
+            .map_ok(|meta| meta.location)
+            .try_collect()
+            .await
+            .map_err(storage_error)?;
+
+        let mut outcomes: Vec<Result<ScrubbedObject, FailedObject>> = futures::stream::iter(locations)
+            .map(|location| async move {
+                let source = location.to_string();
+                self.scrub(location)
+                    .await
+                    .map_err(|e| FailedObject { source, error: e.to_string() })
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+        outcomes.sort_by(|a, b| source_of(a).cmp(source_of(b)));
+
+        let mut report = CorpusReport::default();
+        for outcome in outcomes {
+            match outcome {
+                Ok(object) => report.objects.push(object),
+                Err(failed) => report.failed.push(failed),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Read, anonymize and write one object.
+    async fn scrub(&self, location: Path) -> Result<ScrubbedObject, AnonymaskError> {
+        let target = match location.prefix_match(&self.source_prefix) {
+            Some(relative) => self.target_prefix.parts().chain(relative).collect(),
+            None => location.clone(),
+        };
+
+        let bytes = self
+            .retry(|| async { self.source.get(&location).await?.bytes().await })
+            .await
+            .map_err(storage_error)?;
+
+        let anonymizer = Arc::clone(&self.anonymizer);
+        let extension = location.extension().map(str::to_ascii_lowercase);
+        let anonymized = tokio::task::spawn_blocking(move || anonymize(&anonymizer, extension.as_deref(), &bytes))
+            .await
+            .map_err(|e| AnonymaskError::AnonymizationError(format!("blocking task failed: {}", e)))??;
+
+        if let Some(mappings) = &self.mappings {
+            mappings.save(target.as_ref(), &anonymized.mapping)?;
+        }
+        let payload = PutPayload::from(anonymized.content);
+        self.retry(|| self.target.put(&target, payload.clone()))
+            .await
+            .map_err(storage_error)?;
+
+        Ok(ScrubbedObject {
+            source: location.to_string(),
+            target: target.to_string(),
+            entities: anonymized.entities,
+            truncated: anonymized.truncated,
+        })
+    }
+
+    /// Run `op`, retrying failures other than a missing object.
+    async fn retry<T, F, Fut>(&self, mut op: F) -> object_store::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = object_store::Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match op().await {
+                Err(e) if attempt < self.retries && !matches!(e, object_store::Error::NotFound { .. }) => {
+                    tokio::time::sleep(self.backoff * 2u32.saturating_pow(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// One anonymized object.
+struct Anonymized {
+    content: Vec<u8>,
+    mapping: HashMap<String, String>,
+    entities: usize,
+    truncated: bool,
+}
+
+/// Anonymize an object's content according to its extension.
+fn anonymize(anonymizer: &Anonymizer, extension: Option<&str>, bytes: &[u8]) -> Result<Anonymized, AnonymaskError> {
+    match extension {
+        Some("json") => {
+            let value = serde_json::from_slice(bytes).map_err(|e| {
+                AnonymaskError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+            })?;
+            let result = anonymizer.anonymize_json(&value)?;
+            Ok(Anonymized {
+                content: serde_json::to_vec(&result.value).map_err(std::io::Error::from)?,
+                entities: result.entities.len(),
+                mapping: result.mapping,
+                truncated: result.truncated,
+            })
+        }
+        Some("jsonl" | "ndjson") => {
+            let mut content = Vec::with_capacity(bytes.len());
+            let result = anonymizer.anonymize_ndjson(bytes, &mut content)?;
+            Ok(Anonymized {
+                content,
+                entities: result.entities.len(),
+                mapping: result.mapping,
+                truncated: result.truncated,
+            })
+        }
+        #[cfg(feature = "csv")]
+        Some("csv") => {
+            let mut content = Vec::with_capacity(bytes.len());
+            let result = anonymizer.csv().anonymize(bytes, &mut content)?;
+            Ok(Anonymized {
+                content,
+                entities: result.entities.len(),
+                mapping: result.mapping,
+                truncated: result.truncated,
+            })
+        }
+        _ => {
+            let text = std::str::from_utf8(bytes).map_err(|e| {
+                AnonymaskError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+            })?;
+            let result = anonymizer.anonymize(text)?;
+            Ok(Anonymized {
+                content: result.anonymized_text.into_bytes(),
+                entities: result.entities.len(),
+                mapping: result.mapping,
+                truncated: result.truncated,
+            })
+        }
+    }
+}
+
+fn source_of(outcome: &Result<ScrubbedObject, FailedObject>) -> &str {
+    match outcome {
+        Ok(object) => &object.source,
+        Err(failed) => &failed.source,
+    }
+}
+
+fn storage_error(e: object_store::Error) -> AnonymaskError {
+    AnonymaskError::StorageError(format!("object store: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EntityType;
+    use crate::store::MemoryStore;
+    use object_store::memory::InMemory;
+
+    async fn read(store: &InMemory, path: &str) -> String {
+        let bytes = store.get(&Path::from(path)).await.unwrap().bytes().await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_scrubs_prefix_by_format() {
+        let source = Arc::new(InMemory::new());
+        for (path, content) in [
+            ("raw/a.txt", "mail jo@x.io"),
+            ("raw/logs/b.JSONL", "{\"user\":\"jo@x.io\"}\n{\"n\":1}\n"),
+            ("raw/c.json", "{\"to\": [\"ann@x.io\"]}"),
+            ("raw/broken.json", "{"),
+            ("other/d.txt", "mail jo@x.io"),
+        ] {
+            source.put(&Path::from(path), content.into()).await.unwrap();
+        }
+        let target = Arc::new(InMemory::new());
+        let mappings = Arc::new(MemoryStore::new());
+
+        let anonymizer = Arc::new(Anonymizer::new(vec![EntityType::Email]).unwrap());
+        let report = anonymizer
+            .corpus(source, target.clone())
+            .with_source_prefix("raw")
+            .with_target_prefix("clean/v1")
+            .with_mapping_store(mappings.clone())
+            .with_concurrency(2)
+            .run()
+            .await
+            .unwrap();
+
+        let targets: Vec<&str> = report.objects.iter().map(|o| o.target.as_str()).collect();
+        assert_eq!(targets, vec!["clean/v1/a.txt", "clean/v1/c.json", "clean/v1/logs/b.JSONL"]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].source, "raw/broken.json");
+
+        let text = read(&target, "clean/v1/a.txt").await;
+        let mapping = mappings.load("clean/v1/a.txt").unwrap().unwrap();
+        assert_eq!(anonymizer.deanonymize(&text, &mapping), "mail jo@x.io");
+        assert!(read(&target, "clean/v1/logs/b.JSONL").await.ends_with("\n{\"n\":1}\n"));
+        assert!(target.get(&Path::from("clean/v1/broken.json")).await.is_err());
+    }
+}
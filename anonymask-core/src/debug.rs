@@ -0,0 +1,295 @@
+//! `Debug` formatting that keeps PII out of logs.
+//!
+//! Entities, results and mappings hold the original values they replaced,
+//! so formatting one with `{:?}` in an error path would log exactly what
+//! anonymization removed. Their `Debug` output therefore prints
+//! `[REDACTED]` in place of original values, keeping everything else
+//! (types, positions, placeholders, flags). Call `unsafe_debug()` on a
+//! value to format it with the originals, e.g. in a local test failure:
+//!
+//! ```
+//! use anonymask_core::{Anonymizer, EntityType};
+//!
+//! let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+//! let result = anonymizer.anonymize("Mail jo@x.io").unwrap();
+//!
+//! assert!(!format!("{:?}", result).contains("jo@x.io"));
+//! assert!(format!("{:?}", result.unsafe_debug()).contains("jo@x.io"));
+//! ```
+//!
+//! Error messages of [`AnonymaskError`](crate::AnonymaskError) never
+//! include the text being processed, so errors can be logged as they are.
+
+use crate::annotate::Annotated;
+use crate::detection::Decision;
+use crate::entity::{AnonymizationResult, BatchResult, CowAnonymization, Entity};
+use crate::json::{JsonAnonymizationResult, JsonEntity, NdjsonAnonymizationResult, NdjsonEntity};
+use crate::session::SessionState;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Printed in place of original values.
+const REDACTED: &str = "[REDACTED]";
+
+/// `Debug` formatting including original values, returned by
+/// `unsafe_debug()`.
+pub struct UnsafeDebug<'a, T: ?Sized>(&'a T);
+
+impl<T: sealed::PiiDebug + ?Sized> fmt::Debug for UnsafeDebug<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_pii(f, true)
+    }
+}
+
+mod sealed {
+    use std::fmt;
+
+    /// `Debug` with original values printed only when `raw`.
+    pub trait PiiDebug {
+        fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result;
+    }
+}
+
+use sealed::PiiDebug;
+
+/// Formats a nested value in the mode of its parent.
+struct Nested<'a, T: ?Sized>(&'a T, bool);
+
+impl<T: PiiDebug + ?Sized> fmt::Debug for Nested<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_pii(f, self.1)
+    }
+}
+
+impl<T: PiiDebug> PiiDebug for [T] {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_list().entries(self.iter().map(|item| Nested(item, raw))).finish()
+    }
+}
+
+impl<T: PiiDebug> PiiDebug for Vec<T> {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        self.as_slice().fmt_pii(f, raw)
+    }
+}
+
+/// An original value.
+impl PiiDebug for str {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        if raw {
+            fmt::Debug::fmt(self, f)
+        } else {
+            f.write_str(REDACTED)
+        }
+    }
+}
+
+/// A placeholder -> original value mapping; placeholders are always shown.
+impl PiiDebug for HashMap<String, String> {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_map()
+            .entries(self.iter().map(|(placeholder, original)| (placeholder, Nested(original.as_str(), raw))))
+            .finish()
+    }
+}
+
+impl PiiDebug for Entity {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_struct("Entity")
+            .field("entity_type", &self.entity_type)
+            .field("value", &Nested(self.value.as_str(), raw))
+            .field("start", &self.start)
+            .field("end", &self.end)
+            .field("confidence", &self.confidence)
+            .finish()
+    }
+}
+
+impl PiiDebug for Decision {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_struct("Decision")
+            .field("entity", &Nested(&self.entity, raw))
+            .field("outcome", &self.outcome)
+            .finish()
+    }
+}
+
+impl PiiDebug for AnonymizationResult {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_struct("AnonymizationResult")
+            .field("anonymized_text", &self.anonymized_text)
+            .field("mapping", &Nested(&self.mapping, raw))
+            .field("entities", &Nested(&self.entities, raw))
+            .field("output_spans", &self.output_spans)
+            .field("subject_ids", &self.subject_ids)
+            .field("contexts", &self.contexts)
+            .field("status", &self.status)
+            .field("truncated", &self.truncated)
+            .field("budget_exceeded", &self.budget_exceeded)
+            .field("trace", &Nested(&self.trace, raw))
+            .finish()
+    }
+}
+
+impl PiiDebug for CowAnonymization<'_> {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        match self {
+            CowAnonymization::Borrowed(text) => f.debug_tuple("Borrowed").field(text).finish(),
+            CowAnonymization::Owned(result) => f.debug_tuple("Owned").field(&Nested(result, raw)).finish(),
+        }
+    }
+}
+
+impl PiiDebug for BatchResult {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_struct("BatchResult")
+            .field("anonymized_texts", &self.anonymized_texts)
+            .field("mapping", &Nested(&self.mapping, raw))
+            .field("entities", &Nested(&self.entities, raw))
+            .field("output_spans", &self.output_spans)
+            .field("status", &self.status)
+            .field("truncated", &self.truncated)
+            .field("budget_exceeded", &self.budget_exceeded)
+            .finish()
+    }
+}
+
+impl PiiDebug for JsonEntity {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_struct("JsonEntity")
+            .field("path", &self.path)
+            .field("entity", &Nested(&self.entity, raw))
+            .finish()
+    }
+}
+
+impl PiiDebug for JsonAnonymizationResult {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_struct("JsonAnonymizationResult")
+            .field("value", &self.value)
+            .field("mapping", &Nested(&self.mapping, raw))
+            .field("entities", &Nested(&self.entities, raw))
+            .field("truncated", &self.truncated)
+            .finish()
+    }
+}
+
+impl PiiDebug for NdjsonEntity {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_struct("NdjsonEntity")
+            .field("line", &self.line)
+            .field("entity", &Nested(&self.entity, raw))
+            .finish()
+    }
+}
+
+impl PiiDebug for NdjsonAnonymizationResult {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_struct("NdjsonAnonymizationResult")
+            .field("mapping", &Nested(&self.mapping, raw))
+            .field("entities", &Nested(&self.entities, raw))
+            .field("records", &self.records)
+            .field("truncated", &self.truncated)
+            .finish()
+    }
+}
+
+/// An original value -> placeholder table; placeholders are always shown.
+struct ValueTable<'a>(&'a HashMap<String, String>);
+
+impl PiiDebug for ValueTable<'_> {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(original, placeholder)| (Nested(original.as_str(), raw), placeholder)))
+            .finish()
+    }
+}
+
+impl PiiDebug for Annotated {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_struct("Annotated")
+            .field("text", &Nested(self.text.as_str(), raw))
+            .field("entities", &Nested(&self.entities, raw))
+            .finish()
+    }
+}
+
+impl PiiDebug for SessionState {
+    fn fmt_pii(&self, f: &mut fmt::Formatter<'_>, raw: bool) -> fmt::Result {
+        f.debug_struct("SessionState")
+            .field("values", &Nested(&ValueTable(&self.values), raw))
+            .field("counter", &self.counter)
+            .field("indices", &self.indices)
+            .finish()
+    }
+}
+
+/// Redacting `Debug` and `unsafe_debug()` for types holding original values.
+macro_rules! redacting_debug {
+    ($($ty:ty),*) => {$(
+        impl fmt::Debug for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.fmt_pii(f, false)
+            }
+        }
+
+        impl $ty {
+            /// Format with original values instead of `[REDACTED]`.
+            ///
+            /// The output contains the PII this value holds; keep it out of
+            /// logs, error reports and anything else that leaves the
+            /// process. See the [`debug`](crate::debug) module.
+            pub fn unsafe_debug(&self) -> UnsafeDebug<'_, Self> {
+                UnsafeDebug(self)
+            }
+        }
+    )*};
+}
+
+redacting_debug!(
+    Entity,
+    Decision,
+    AnonymizationResult,
+    CowAnonymization<'_>,
+    BatchResult,
+    JsonEntity,
+    JsonAnonymizationResult,
+    NdjsonEntity,
+    NdjsonAnonymizationResult,
+    SessionState,
+    Annotated
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EntityType;
+
+    #[test]
+    fn test_debug_redacts_values() {
+        let entity = Entity {
+            entity_type: EntityType::Email,
+            value: "jo@x.io".to_string(),
+            start: 5,
+            end: 12,
+            confidence: 0.9,
+        };
+        let batch = BatchResult {
+            anonymized_texts: vec!["Mail EMAIL_1".to_string()],
+            mapping: HashMap::from([("EMAIL_1".to_string(), "jo@x.io".to_string())]),
+            entities: vec![vec![entity.clone()]],
+            output_spans: Vec::new(),
+            status: Default::default(),
+            truncated: false,
+            budget_exceeded: false,
+        };
+
+        let redacted = format!("{:?} {:#?}", entity, batch);
+        assert!(!redacted.contains("jo@x.io"), "{}", redacted);
+        assert!(redacted.contains("value: [REDACTED]") && redacted.contains("\"EMAIL_1\": [REDACTED]"));
+        assert!(redacted.contains("start: 5"));
+
+        let raw = format!("{:?}", batch.unsafe_debug());
+        assert!(raw.contains("\"EMAIL_1\": \"jo@x.io\"") && raw.contains("value: \"jo@x.io\""), "{}", raw);
+    }
+}
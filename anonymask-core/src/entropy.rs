@@ -0,0 +1,129 @@
+//! High-entropy string detection for secrets without a known format.
+//!
+//! Vendor-specific patterns catch tokens whose format is known; random
+//! API keys, passwords and session secrets from everywhere else are found
+//! by their randomness instead. Long runs of token characters containing
+//! both letters and digits are reported as [`EntityType::Secret`] when
+//! their Shannon entropy per character reaches the configured threshold.
+
+use crate::config::{ConfidenceModel, SecretDetection};
+use crate::entity::{Entity, EntityType};
+use crate::error::AnonymaskError;
+use regex::Regex;
+
+/// Shannon entropy of `value`, in bits per character.
+///
+/// Random base64 strings score around 4.5-5 bits for typical key lengths,
+/// English words below 3.5. Hex strings can't exceed 4 bits.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::entropy::shannon_entropy;
+///
+/// assert_eq!(shannon_entropy("aaaa"), 0.0);
+/// assert_eq!(shannon_entropy("abcd"), 2.0);
+/// ```
+pub fn shannon_entropy(value: &str) -> f32 {
+    let mut counts = std::collections::HashMap::new();
+    let mut total = 0usize;
+    for c in value.chars() {
+        *counts.entry(c).or_insert(0usize) += 1;
+        total += 1;
+    }
+    if total == 0 {
+        return 0.0;
+    }
+    let total = total as f32;
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f32 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Finds high-entropy candidates in text.
+pub(crate) struct SecretScanner {
+    regex: Regex,
+    min_entropy: f32,
+}
+
+impl SecretScanner {
+    pub(crate) fn new(settings: &SecretDetection) -> Result<Self, AnonymaskError> {
+        // Token characters of base64, base64url and hex; '=' only as padding
+        let pattern = format!(r"[A-Za-z0-9+/_-]{{{},}}={{0,2}}", settings.min_length.max(1));
+        let regex = Regex::new(&pattern).map_err(|e| AnonymaskError::RegexError {
+            pattern: pattern.clone(),
+            source: e,
+        })?;
+        Ok(Self {
+            regex,
+            min_entropy: settings.min_entropy,
+        })
+    }
+
+    /// Candidates in `text`, scored like pattern matches without a validator.
+    ///
+    /// `context` gives the context keyword adjustment of a span.
+    pub(crate) fn find(
+        &self,
+        text: &str,
+        confidence: &ConfidenceModel,
+        context: impl Fn(usize, usize) -> f32,
+    ) -> Vec<Entity> {
+        self.regex
+            .find_iter(text)
+            .filter(|mat| {
+                let value = mat.as_str();
+                // A leading slash means a URL or file path rather than a key
+                !value.starts_with('/')
+                    && value.bytes().any(|b| b.is_ascii_digit())
+                    && value.bytes().any(|b| b.is_ascii_alphabetic())
+                    && shannon_entropy(value) >= self.min_entropy
+            })
+            .filter_map(|mat| {
+                let score = confidence.score_with_context(None, context(mat.start(), mat.end()));
+                (score >= confidence.min_confidence).then(|| Entity {
+                    entity_type: EntityType::Secret,
+                    value: mat.as_str().to_string(),
+                    start: mat.start(),
+                    end: mat.end(),
+                    confidence: score,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_random_tokens_only() {
+        let scanner = SecretScanner::new(&SecretDetection::default()).unwrap();
+        let text = "key=q8Zr2LmX0vTw9KbN4yPcH7sD and internationalization1234 \
+                    or aaaaaaaaaaaaaaaaaaaaaaaa1 or abcdefghijklmnopqrstuvwxyz \
+                    at /api/v1/users/12345/orders/67890";
+        let found = scanner.find(text, &ConfidenceModel::default(), |_, _| 0.0);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].value, "q8Zr2LmX0vTw9KbN4yPcH7sD");
+        assert_eq!(found[0].start, 4);
+    }
+
+    #[test]
+    fn test_thresholds_are_configurable() {
+        let settings = SecretDetection {
+            min_length: 8,
+            min_entropy: 2.5,
+        };
+        let scanner = SecretScanner::new(&settings).unwrap();
+        let found = scanner.find("pin a1b2c3d4", &ConfidenceModel::default(), |_, _| 0.0);
+        assert_eq!(found.len(), 1);
+
+        let scanner = SecretScanner::new(&SecretDetection::default()).unwrap();
+        assert!(scanner.find("pin a1b2c3d4", &ConfidenceModel::default(), |_, _| 0.0).is_empty());
+    }
+}
@@ -3,6 +3,8 @@ use thiserror::Error;
 /// Errors that can occur during anonymization operations.
 ///
 /// All errors provide detailed context to help diagnose issues quickly.
+/// Messages never include the text being processed or original values, so
+/// errors can be logged as they are.
 #[derive(Error, Debug)]
 pub enum AnonymaskError {
     /// Invalid entity type was provided
@@ -30,6 +32,33 @@ pub enum AnonymaskError {
         position: usize,
     },
 
+    /// A caller-supplied entity doesn't describe a valid span of the text
+    ///
+    /// Occurs in `anonymize_entities()` when a span is empty, out of
+    /// bounds, not on character boundaries, doesn't match the entity's
+    /// value, or overlaps another span.
+    #[error("Invalid entity span {start}..{end}: {reason}")]
+    InvalidSpan {
+        /// Start of the offending span (byte index)
+        start: usize,
+        /// End of the offending span (byte index)
+        end: usize,
+        /// Why the span was rejected
+        reason: String,
+    },
+
+    /// Annotated text is malformed
+    ///
+    /// Occurs in [`annotate::parse`](crate::annotate::parse) for broken
+    /// `<pii>` markers and unknown character references.
+    #[error("Invalid annotation at position {position}: {reason}")]
+    InvalidAnnotation {
+        /// Byte position in the annotated text
+        position: usize,
+        /// What is wrong there
+        reason: String,
+    },
+
     /// Regex compilation or execution error
     ///
     /// This indicates an issue with the pattern matching system.
@@ -42,12 +71,86 @@ pub enum AnonymaskError {
         source: regex::Error,
     },
 
+    /// Unknown locale code was provided
+    ///
+    /// Occurs when parsing a locale name that has no pattern pack.
+    #[error("Unsupported locale '{0}'")]
+    UnsupportedLocale(String),
+
     /// Storage backend error
     ///
     /// Occurs when interacting with persistent storage for mappings.
     #[error("Storage error: {0}")]
     StorageError(String),
 
+    /// Not enough key shares were provided to open a sealed mapping
+    ///
+    /// Occurs when fewer distinct shares than the sharing threshold are
+    /// combined during split-key deanonymization.
+    #[error("Insufficient key shares: {provided} provided, {required} required")]
+    InsufficientKeyShares {
+        /// Number of distinct shares provided
+        provided: usize,
+        /// Number of shares required by the sharing scheme
+        required: usize,
+    },
+
+    /// Vault operation failure
+    ///
+    /// Occurs when sealing or opening an encrypted mapping fails, e.g.
+    /// because of mismatched key shares or corrupted ciphertext.
+    #[error("Vault error: {0}")]
+    VaultError(String),
+
+    /// NER model loading or inference failure
+    ///
+    /// Occurs when an ONNX model or tokenizer can't be loaded, or when
+    /// running the model on a text fails.
+    #[error("Model error: {0}")]
+    ModelError(String),
+
+    /// Policy bundle signing or loading failure
+    ///
+    /// Occurs when a bundle isn't signed by a trusted key, was altered
+    /// after signing, is malformed or has an unknown profile.
+    #[error("Policy bundle error: {0}")]
+    PolicyBundleError(String),
+
+    /// A user-supplied pattern violates the configured limits
+    ///
+    /// Occurs when creating an anonymizer with a custom pattern that is
+    /// invalid, too long, nested too deeply or compiles too large.
+    #[error("Pattern '{name}' rejected: {reason}")]
+    PatternRejected {
+        /// Name of the rejected pattern
+        name: String,
+        /// Why it was rejected
+        reason: String,
+    },
+
+    /// A user-supplied pattern ran past its match time budget
+    ///
+    /// Occurs during detection; the text is not anonymized.
+    #[error("Pattern '{name}' exceeded its match time budget of {budget:?}")]
+    PatternBudgetExceeded {
+        /// Name of the slow pattern
+        name: String,
+        /// The configured budget
+        budget: std::time::Duration,
+    },
+
+    /// Invalid configuration value
+    ///
+    /// Occurs when parsing a configuration option from a string fails.
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    /// Reading input or writing output failed
+    ///
+    /// Occurs in the streaming helpers, e.g. CSV anonymization.
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
     /// General anonymization operation failure
     ///
     /// Used for unexpected errors during anonymization.
@@ -59,7 +162,12 @@ impl AnonymaskError {
     /// Helper to suggest valid entity types when an invalid one is provided
     pub fn suggest_entity_type(invalid: &str) -> &'static str {
         let supported = [
-            "email", "phone", "ssn", "credit_card", "ip_address", "url",
+            "email", "phone", "ssn", "credit_card", "ip_address", "url", "iban", "steuer_id",
+            "nir", "siren", "siret", "dni", "nie", "cpf",
+            "cnpj", "my_number", "tfn", "medicare", "address", "date", "date_of_birth",
+            "aws_access_key", "github_token", "slack_token", "bearer_token", "private_key", "jwt",
+            "secret", "credential",
+            "person", "org", "location",
         ];
 
         let invalid_lower = invalid.to_lowercase();
@@ -0,0 +1,323 @@
+//! Dictionary-based detection of known terms.
+//!
+//! A [`Gazetteer`] compiles any number of terms (employee names, project
+//! codenames, customer lists) into a single Aho-Corasick automaton, so the
+//! text is scanned once regardless of how many terms there are.
+
+use crate::entity::{Entity, EntityType};
+use crate::error::AnonymaskError;
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, Match, MatchKind};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Compiled dictionary of terms, each tagged with an entity type.
+///
+/// When terms overlap, the leftmost match wins, and of those the longest,
+/// so "Acme Corp" is preferred over "Acme".
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::gazetteer::Gazetteer;
+/// use anonymask_core::entity::EntityType;
+///
+/// let employee = EntityType::Custom("employee".to_string());
+/// let gazetteer = Gazetteer::builder()
+///     .add_terms(employee.clone(), ["Ada Lovelace", "Alan Turing"])
+///     .case_insensitive(true)
+///     .word_boundary(true)
+///     .build()
+///     .unwrap();
+///
+/// let entities = gazetteer.find("ALAN TURING met Ada Lovelaces");
+/// assert_eq!(entities.len(), 1);
+/// assert_eq!(entities[0].value, "ALAN TURING");
+/// assert_eq!(entities[0].entity_type, employee);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Gazetteer {
+    automaton: AhoCorasick,
+    /// Entity type of each pattern, indexed by pattern ID
+    types: Vec<EntityType>,
+    word_boundary: bool,
+}
+
+impl Gazetteer {
+    /// Entity types of the terms, with repetitions.
+    pub(crate) fn entity_types(&self) -> &[EntityType] {
+        &self.types
+    }
+
+    /// Create a new gazetteer builder.
+    pub fn builder() -> GazetteerBuilder {
+        GazetteerBuilder::default()
+    }
+
+    /// Build a case-sensitive gazetteer without boundary checks from a
+    /// custom entity map, as passed to `anonymize_with_custom()`.
+    pub fn from_custom_entities(
+        custom_entities: &HashMap<EntityType, Vec<String>>,
+    ) -> Result<Self, AnonymaskError> {
+        Self::builder().add_custom_entities(custom_entities).build()
+    }
+
+    /// Number of terms in the dictionary.
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    /// Whether the dictionary has no terms.
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Find all non-overlapping term occurrences in `text`.
+    ///
+    /// Entity values are the matched text, so with case-insensitive
+    /// matching the original casing is kept. Confidence is always `1.0`.
+    pub fn find(&self, text: &str) -> Vec<Entity> {
+        let matches: Vec<Match> = if self.word_boundary {
+            // Check boundaries before choosing between overlapping terms,
+            // so "Acme" is still found in "Acme Corporation" when the longer
+            // "Acme Corp" isn't a whole word there
+            let mut bounded: Vec<Match> = self
+                .automaton
+                .find_overlapping_iter(text)
+                .filter(|mat| is_word_bounded(text, mat.start(), mat.end()))
+                .collect();
+            bounded.sort_by_key(|mat| (mat.start(), Reverse(mat.end())));
+            let mut end = 0;
+            bounded.retain(|mat| {
+                let keep = mat.start() >= end;
+                if keep {
+                    end = mat.end();
+                }
+                keep
+            });
+            bounded
+        } else {
+            self.automaton.find_iter(text).collect()
+        };
+
+        matches
+            .into_iter()
+            .map(|mat| Entity {
+                entity_type: self.types[mat.pattern().as_usize()].clone(),
+                value: text[mat.start()..mat.end()].to_string(),
+                start: mat.start(),
+                end: mat.end(),
+                confidence: 1.0,
+            })
+            .collect()
+    }
+}
+
+/// Builder for creating a [`Gazetteer`].
+#[derive(Debug, Default)]
+pub struct GazetteerBuilder {
+    terms: Vec<(EntityType, String)>,
+    case_insensitive: bool,
+    word_boundary: bool,
+}
+
+impl GazetteerBuilder {
+    /// Add a single term. Empty terms are ignored.
+    pub fn add_term(mut self, entity_type: EntityType, term: impl Into<String>) -> Self {
+        let term = term.into();
+        if !term.is_empty() {
+            self.terms.push((entity_type, term));
+        }
+        self
+    }
+
+    /// Add several terms of the same type. Empty terms are ignored.
+    pub fn add_terms<I, S>(mut self, entity_type: EntityType, terms: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for term in terms {
+            self = self.add_term(entity_type.clone(), term.as_ref());
+        }
+        self
+    }
+
+    /// Add all values of a custom entity map.
+    pub fn add_custom_entities(mut self, custom_entities: &HashMap<EntityType, Vec<String>>) -> Self {
+        for (entity_type, values) in custom_entities {
+            self = self.add_terms(entity_type.clone(), values);
+        }
+        self
+    }
+
+    /// Match ASCII letters regardless of case.
+    ///
+    /// Default: `false`
+    pub fn case_insensitive(mut self, yes: bool) -> Self {
+        self.case_insensitive = yes;
+        self
+    }
+
+    /// Only match whole words, so "John" doesn't match inside "Johnson".
+    ///
+    /// Default: `false`
+    pub fn word_boundary(mut self, yes: bool) -> Self {
+        self.word_boundary = yes;
+        self
+    }
+
+    /// Compile the automaton.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::AnonymizationError` if the dictionary is too
+    /// large for the automaton.
+    pub fn build(self) -> Result<Gazetteer, AnonymaskError> {
+        let (types, terms): (Vec<EntityType>, Vec<String>) = self.terms.into_iter().unzip();
+        // Boundary checks need every candidate, which only standard
+        // matching reports
+        let match_kind = if self.word_boundary {
+            MatchKind::Standard
+        } else {
+            MatchKind::LeftmostLongest
+        };
+        let automaton = AhoCorasickBuilder::new()
+            .match_kind(match_kind)
+            .ascii_case_insensitive(self.case_insensitive)
+            .build(&terms)
+            .map_err(|e| {
+                AnonymaskError::AnonymizationError(format!("failed to build gazetteer: {}", e))
+            })?;
+
+        Ok(Gazetteer {
+            automaton,
+            types,
+            word_boundary: self.word_boundary,
+        })
+    }
+}
+
+/// Whether `text[start..end]` is neither preceded nor followed by a word character.
+pub(crate) fn is_word_bounded(text: &str, start: usize, end: usize) -> bool {
+    let before = text[..start].chars().next_back().is_some_and(is_word_char);
+    let after = text[end..].chars().next().is_some_and(is_word_char);
+    !before && !after
+}
+
+/// Whether `c` continues a word for boundary checks.
+///
+/// Letters, digits, `_` and combining marks continue a word in any script,
+/// so "Jürgen" doesn't match inside "Jürgensen" and a decomposed "é" isn't
+/// split. Han ideographs and kana are written without spaces, so they never
+/// continue a word: "田中" matches in "田中さん".
+pub(crate) fn is_word_char(c: char) -> bool {
+    if is_unspaced_script(c) {
+        return false;
+    }
+    c.is_alphanumeric() || c == '_' || is_combining_mark(c)
+}
+
+fn is_combining_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
+fn is_unspaced_script(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3040}'..='\u{30FF}' // Hiragana, Katakana
+            | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+            | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+            | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+            | '\u{FF66}'..='\u{FF9F}' // Half-width Katakana
+            | '\u{20000}'..='\u{2FFFF}' // CJK Extensions B and later
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn company() -> EntityType {
+        EntityType::Custom("company".to_string())
+    }
+
+    #[test]
+    fn test_prefers_longest_match() {
+        let gazetteer = Gazetteer::builder()
+            .add_terms(company(), ["Acme", "Acme Corp"])
+            .build()
+            .unwrap();
+
+        let entities = gazetteer.find("Acme Corp and Acme");
+        let values: Vec<&str> = entities.iter().map(|e| e.value.as_str()).collect();
+        assert_eq!(values, vec!["Acme Corp", "Acme"]);
+        assert_eq!((entities[1].start, entities[1].end), (14, 18));
+    }
+
+    #[test]
+    fn test_case_insensitive_keeps_original_casing() {
+        let gazetteer = Gazetteer::builder()
+            .add_term(company(), "acme corp")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(gazetteer.find("Meet ACME Corp")[0].value, "ACME Corp");
+    }
+
+    #[test]
+    fn test_word_boundary() {
+        let gazetteer = Gazetteer::builder()
+            .add_term(EntityType::Custom("name".to_string()), "John")
+            .word_boundary(true)
+            .build()
+            .unwrap();
+
+        assert!(gazetteer.find("Johnson and Johnny").is_empty());
+        assert_eq!(gazetteer.find("John, (John)").len(), 2);
+    }
+
+    #[test]
+    fn test_word_boundary_falls_back_to_shorter_term() {
+        let gazetteer = Gazetteer::builder()
+            .add_terms(company(), ["Acme", "Acme Corp"])
+            .word_boundary(true)
+            .build()
+            .unwrap();
+
+        let values = |text| gazetteer.find(text).into_iter().map(|e| e.value).collect::<Vec<_>>();
+        assert_eq!(values("Acme Corporation"), vec!["Acme"]);
+        assert_eq!(values("Acme Corp and Acme, Acmes"), vec!["Acme Corp", "Acme"]);
+    }
+
+    #[test]
+    fn test_unicode_word_boundaries() {
+        assert!(!is_word_bounded("Jürgensen", 0, "Jürgen".len()));
+        assert!(is_word_bounded("Jürgen.", 0, "Jürgen".len()));
+        // "Jose" followed by a combining acute accent is a different word
+        assert!(!is_word_bounded("Jose\u{0301}", 0, 4));
+        assert!(!is_word_bounded("МоскваСити", 0, "Москва".len()));
+        // Japanese is written without spaces
+        assert!(is_word_bounded("田中さん", 0, "田中".len()));
+    }
+
+    #[test]
+    fn test_many_terms() {
+        let terms: Vec<String> = (0..5000).map(|i| format!("project-{:04}", i)).collect();
+        let gazetteer = Gazetteer::builder()
+            .add_terms(company(), &terms)
+            .add_term(company(), "")
+            .build()
+            .unwrap();
+
+        assert_eq!(gazetteer.len(), 5000);
+        assert_eq!(gazetteer.find("see project-4242 and project-0001").len(), 2);
+    }
+}
@@ -0,0 +1,156 @@
+//! HTTP service for sidecar deployments.
+//!
+//! With feature `http`, [`router`] builds an axum router over one
+//! anonymizer, for teams that call anonymask over the network rather than
+//! through bindings. The `anonymask` CLI serves it with
+//! `anonymask serve --http <addr>`.
+//!
+//! | Endpoint | Request | Response |
+//! |----------|---------|----------|
+//! | `POST /anonymize` | [`AnonymizeRequest`] | [`AnonymizationResult`](crate::AnonymizationResult) |
+//! | `POST /deanonymize` | [`DeanonymizeRequest`] | [`DeanonymizeResponse`] |
+//! | `POST /detect` | [`DetectRequest`] | array of [`Entity`](crate::Entity) |
+//! | `GET /realms/{realm}` | | [`RealmResponse`] |
+//! | `DELETE /realms/{realm}` | | `204`, or `404` for an unknown realm |
+//!
+//! With feature `dashboard`, the router also serves a web page for
+//! operators at `GET /dashboard`: request and entity counts with the
+//! latest anonymizations, the detected types and configuration, and a
+//! playground that sends pasted text to `/anonymize`. The page reads:
+//!
+//! | Endpoint | Response |
+//! |----------|----------|
+//! | `GET /dashboard/stats` | [`StatsResponse`] |
+//! | `GET /dashboard/rules` | [`RulesResponse`] |
+//!
+//! Statistics hold counts and sizes only, never text or values. Like the
+//! API, the dashboard has no authentication of its own; expose it only
+//! where the API may be called.
+//!
+//! Requests with an `X-Anonymask-Realm` header share placeholders with
+//! every earlier request of that realm, as in an
+//! [`AnonymizationSession`](crate::AnonymizationSession), and
+//! `/deanonymize` restores them without being sent the mapping. Realms are
+//! kept in memory until deleted. Errors are answered with an
+//! [`ErrorResponse`] body: `400` for invalid requests, `422` if
+//! anonymization fails.
+//!
+//! The payload types are available without the feature, and their JSON
+//! Schemas are published with the others (see the `schema` module).
+
+use crate::config::AnonymizerConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "http")]
+mod server;
+
+#[cfg(feature = "http")]
+pub use server::router;
+
+/// Header naming the realm of a request.
+pub const REALM_HEADER: &str = "x-anonymask-realm";
+
+/// Body of `POST /anonymize`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AnonymizeRequest {
+    /// Text to anonymize
+    pub text: String,
+    /// Additional values to replace, by entity type name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub custom_entities: HashMap<String, Vec<String>>,
+}
+
+/// Body of `POST /deanonymize`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DeanonymizeRequest {
+    /// Text containing placeholders
+    pub text: String,
+    /// Placeholder -> original value; required unless the request has a
+    /// realm, whose mapping is used otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mapping: Option<HashMap<String, String>>,
+}
+
+/// Response of `POST /deanonymize`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DeanonymizeResponse {
+    /// Text with original values restored
+    pub text: String,
+}
+
+/// Body of `POST /detect`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DetectRequest {
+    /// Text to search
+    pub text: String,
+}
+
+/// Response of `GET /realms/{realm}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RealmResponse {
+    /// Placeholder -> original value, for everything the realm anonymized
+    pub mapping: HashMap<String, String>,
+}
+
+/// Response of `GET /dashboard/stats`.
+///
+/// Counts start when the router is built.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StatsResponse {
+    /// Seconds since the router was built
+    pub uptime_secs: u64,
+    /// Requests answered, by route (e.g. `"POST /anonymize"`)
+    pub requests: BTreeMap<String, u64>,
+    /// Requests answered with an error status
+    pub errors: u64,
+    /// Texts anonymized
+    pub scrubs: u64,
+    /// Bytes of text anonymized
+    pub bytes: u64,
+    /// Entities replaced, by type name
+    pub entities: BTreeMap<String, u64>,
+    /// The latest anonymizations, newest first
+    pub recent: Vec<ScrubSummary>,
+}
+
+/// One anonymization in [`StatsResponse::recent`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ScrubSummary {
+    /// When it finished, in milliseconds since the Unix epoch
+    pub finished_at_ms: u64,
+    /// Length of the text in bytes
+    pub bytes: usize,
+    /// Entities replaced, by type name
+    pub entities: BTreeMap<String, usize>,
+    /// Time spent detecting and replacing, in microseconds
+    pub duration_us: u64,
+    /// Whether the request belonged to a realm
+    pub realm: bool,
+}
+
+/// Response of `GET /dashboard/rules`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RulesResponse {
+    /// Names of the entity types the service detects
+    pub entity_types: Vec<String>,
+    /// The anonymizer's configuration; the key of `hashed_tokens` is
+    /// replaced by `[REDACTED]`
+    pub config: AnonymizerConfig,
+}
+
+/// Body of error responses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ErrorResponse {
+    /// What went wrong
+    pub error: String,
+}
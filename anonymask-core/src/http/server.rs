@@ -0,0 +1,306 @@
+use super::{
+    AnonymizeRequest, DeanonymizeRequest, DeanonymizeResponse, DetectRequest, ErrorResponse, RealmResponse,
+    REALM_HEADER,
+};
+use crate::anonymizer::Anonymizer;
+use crate::entity::{AnonymizationResult, Entity, EntityType};
+use crate::error::AnonymaskError;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "dashboard")]
+mod dashboard;
+
+/// Build the router serving `anonymizer`; see the [module docs](super).
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use anonymask_core::{Anonymizer, EntityType};
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let anonymizer = Arc::new(Anonymizer::new(vec![EntityType::Email]).unwrap());
+/// let app = anonymask_core::http::router(anonymizer);
+/// // Serve `app` with axum, e.g. `axum::serve(listener, app)`
+/// # Ok(()) }
+/// ```
+pub fn router(anonymizer: Arc<Anonymizer>) -> Router {
+    let service = Arc::new(Service {
+        anonymizer,
+        realms: Mutex::new(HashMap::new()),
+        #[cfg(feature = "dashboard")]
+        stats: dashboard::Stats::new(),
+    });
+    let router = Router::new()
+        .route("/anonymize", post(anonymize))
+        .route("/deanonymize", post(deanonymize))
+        .route("/detect", post(detect))
+        .route("/realms/{realm}", get(realm_mapping).delete(delete_realm));
+    // Counts the API routes above, not the dashboard's own
+    #[cfg(feature = "dashboard")]
+    let router = router
+        .route_layer(axum::middleware::from_fn_with_state(service.clone(), dashboard::count))
+        .merge(dashboard::routes());
+    router.with_state(service)
+}
+
+struct Service {
+    anonymizer: Arc<Anonymizer>,
+    /// Realm name -> accumulated placeholder mapping
+    realms: Mutex<HashMap<String, HashMap<String, String>>>,
+    #[cfg(feature = "dashboard")]
+    stats: dashboard::Stats,
+}
+
+impl Service {
+    fn anonymize(&self, realm: Option<String>, request: AnonymizeRequest) -> Result<AnonymizationResult, ApiError> {
+        let custom_entities = request
+            .custom_entities
+            .into_iter()
+            .map(|(name, values)| EntityType::from_str(&name).map(|entity_type| (entity_type, values)))
+            .collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+        let custom_entities = (!custom_entities.is_empty()).then_some(&custom_entities);
+
+        #[cfg(feature = "dashboard")]
+        let (started, in_realm) = (std::time::Instant::now(), realm.is_some());
+        let result = match realm {
+            Some(realm) => {
+                // Held for the whole call so concurrent requests of a realm
+                // can't hand out different placeholders
+                let mut realms = self.realms.lock().unwrap();
+                let mapping = realms.entry(realm).or_default();
+                let mut session = self.anonymizer.resume_session(mapping);
+                let result = session.anonymize_with_custom(&request.text, custom_entities)?;
+                *mapping = session.mapping();
+                result
+            }
+            None => self.anonymizer.anonymize_with_custom(&request.text, custom_entities)?,
+        };
+        #[cfg(feature = "dashboard")]
+        self.stats.record_scrub(request.text.len(), &result, started.elapsed(), in_realm);
+        Ok(result)
+    }
+
+    fn realm_mapping(&self, realm: &str) -> Result<HashMap<String, String>, ApiError> {
+        self.realms
+            .lock()
+            .unwrap()
+            .get(realm)
+            .cloned()
+            .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("unknown realm '{}'", realm)))
+    }
+}
+
+/// An error response.
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<AnonymaskError> for ApiError {
+    fn from(e: AnonymaskError) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string())
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, rejection.body_text())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(ErrorResponse { error: self.message })).into_response()
+    }
+}
+
+fn realm(headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+    headers
+        .get(REALM_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .map(str::to_string)
+                .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, "realm must be visible ASCII"))
+        })
+        .transpose()
+}
+
+/// Run CPU-bound detection on the blocking pool rather than the executor.
+async fn blocking<T, F>(work: F) -> Result<T, ApiError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ApiError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+}
+
+async fn anonymize(
+    State(service): State<Arc<Service>>,
+    headers: HeaderMap,
+    request: Result<Json<AnonymizeRequest>, JsonRejection>,
+) -> Result<Json<AnonymizationResult>, ApiError> {
+    let Json(request) = request?;
+    let realm = realm(&headers)?;
+    blocking(move || service.anonymize(realm, request)).await.map(Json)
+}
+
+async fn deanonymize(
+    State(service): State<Arc<Service>>,
+    headers: HeaderMap,
+    request: Result<Json<DeanonymizeRequest>, JsonRejection>,
+) -> Result<Json<DeanonymizeResponse>, ApiError> {
+    let Json(request) = request?;
+    let mapping = match (request.mapping, realm(&headers)?) {
+        (Some(mapping), None) => mapping,
+        (None, Some(realm)) => service.realm_mapping(&realm)?,
+        _ => return Err(ApiError::new(StatusCode::BAD_REQUEST, "expected one of `mapping` and a realm")),
+    };
+    let text = service.anonymizer.deanonymize(&request.text, &mapping);
+    Ok(Json(DeanonymizeResponse { text }))
+}
+
+async fn detect(
+    State(service): State<Arc<Service>>,
+    request: Result<Json<DetectRequest>, JsonRejection>,
+) -> Result<Json<Vec<Entity>>, ApiError> {
+    let Json(request) = request?;
+    blocking(move || Ok(service.anonymizer.detect(&request.text)?)).await.map(Json)
+}
+
+async fn realm_mapping(
+    State(service): State<Arc<Service>>,
+    Path(realm): Path<String>,
+) -> Result<Json<RealmResponse>, ApiError> {
+    Ok(Json(RealmResponse {
+        mapping: service.realm_mapping(&realm)?,
+    }))
+}
+
+async fn delete_realm(State(service): State<Arc<Service>>, Path(realm): Path<String>) -> StatusCode {
+    match service.realms.lock().unwrap().remove(&realm) {
+        Some(_) => StatusCode::NO_CONTENT,
+        None => StatusCode::NOT_FOUND,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    async fn call(app: &Router, method: &str, uri: &str, realm: Option<&str>, body: Value) -> (StatusCode, Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        if let Some(realm) = realm {
+            request = request.header(REALM_HEADER, realm);
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    fn app() -> Router {
+        router(Arc::new(Anonymizer::new(vec![EntityType::Email]).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_endpoints() {
+        let app = app();
+        let (status, result) = call(&app, "POST", "/anonymize", None, json!({ "text": "Mail jo@x.io, Acme", "custom_entities": { "company": ["Acme"] } })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["entities"].as_array().unwrap().len(), 2);
+
+        let request = json!({ "text": result["anonymized_text"], "mapping": result["mapping"] });
+        let (_, restored) = call(&app, "POST", "/deanonymize", None, request).await;
+        assert_eq!(restored["text"], "Mail jo@x.io, Acme");
+
+        let (_, entities) = call(&app, "POST", "/detect", None, json!({ "text": "jo@x.io" })).await;
+        assert_eq!(entities[0]["value"], "jo@x.io");
+
+        let (status, error) = call(&app, "POST", "/detect", None, json!({ "txt": "" })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_realms() {
+        let app = app();
+        let (_, first) = call(&app, "POST", "/anonymize", Some("chat-1"), json!({ "text": "jo@x.io" })).await;
+        let (_, second) = call(&app, "POST", "/anonymize", Some("chat-1"), json!({ "text": "Again jo@x.io" })).await;
+        let placeholder = first["anonymized_text"].as_str().unwrap();
+        assert_eq!(second["anonymized_text"], format!("Again {}", placeholder));
+
+        let (_, restored) = call(&app, "POST", "/deanonymize", Some("chat-1"), json!({ "text": placeholder })).await;
+        assert_eq!(restored["text"], "jo@x.io");
+        let (_, realm) = call(&app, "GET", "/realms/chat-1", None, Value::Null).await;
+        assert_eq!(realm["mapping"][placeholder], "jo@x.io");
+
+        let (status, _) = call(&app, "DELETE", "/realms/chat-1", None, Value::Null).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = call(&app, "GET", "/realms/chat-1", None, Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[cfg(feature = "dashboard")]
+    #[tokio::test]
+    async fn test_dashboard() {
+        let config = crate::AnonymizerConfig::builder()
+            .with_hashed_tokens(crate::HashedTokens::new("s3cret"))
+            .build();
+        let anonymizer = Anonymizer::with_config(vec![EntityType::Email, EntityType::Phone], config).unwrap();
+        let app = router(Arc::new(anonymizer));
+
+        call(&app, "POST", "/anonymize", None, json!({ "text": "Mail jo@x.io or ann@y.org" })).await;
+        call(&app, "POST", "/anonymize", Some("chat-1"), json!({ "text": "Call 555-123-4567" })).await;
+        call(&app, "POST", "/detect", None, json!({ "txt": "" })).await;
+        call(&app, "GET", "/realms/chat-1", None, Value::Null).await;
+
+        let (status, stats) = call(&app, "GET", "/dashboard/stats", None, Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stats["requests"], json!({ "POST /anonymize": 2, "POST /detect": 1, "GET /realms/{realm}": 1 }));
+        assert_eq!(stats["errors"], 1);
+        assert_eq!(stats["scrubs"], 2);
+        assert_eq!(stats["entities"], json!({ "email": 2, "phone": 1 }));
+        assert_eq!(stats["recent"][0]["entities"], json!({ "phone": 1 }));
+        assert_eq!(stats["recent"][0]["realm"], true);
+        assert!(!stats.to_string().contains("jo@x.io"));
+
+        let (_, rules) = call(&app, "GET", "/dashboard/rules", None, Value::Null).await;
+        assert_eq!(rules["entity_types"], json!(["email", "phone"]));
+        assert_eq!(rules["config"]["hashed_tokens"]["key"], "[REDACTED]");
+
+        let (status, _) = call(&app, "GET", "/dashboard", None, Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
@@ -0,0 +1,121 @@
+//! Web dashboard of the service (feature `dashboard`).
+
+use super::Service;
+use crate::entity::AnonymizationResult;
+use crate::http::{RulesResponse, ScrubSummary, StatsResponse};
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::{Html, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Anonymizations kept in [`StatsResponse::recent`].
+const RECENT: usize = 50;
+
+const PAGE: &str = include_str!("dashboard.html");
+
+/// Counters behind `GET /dashboard/stats`.
+pub(super) struct Stats {
+    started: Instant,
+    counts: Mutex<StatsResponse>,
+}
+
+impl Stats {
+    pub(super) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            counts: Mutex::new(StatsResponse {
+                uptime_secs: 0,
+                requests: Default::default(),
+                errors: 0,
+                scrubs: 0,
+                bytes: 0,
+                entities: Default::default(),
+                recent: Vec::new(),
+            }),
+        }
+    }
+
+    /// Count one anonymized text of `bytes` bytes.
+    pub(super) fn record_scrub(&self, bytes: usize, result: &AnonymizationResult, duration: Duration, realm: bool) {
+        let mut summary = ScrubSummary {
+            finished_at_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_millis() as u64),
+            bytes,
+            entities: Default::default(),
+            duration_us: duration.as_micros() as u64,
+            realm,
+        };
+        for entity in &result.entities {
+            *summary.entities.entry(entity.entity_type.to_string()).or_default() += 1;
+        }
+
+        let mut counts = self.counts.lock().unwrap();
+        counts.scrubs += 1;
+        counts.bytes += bytes as u64;
+        for (entity_type, count) in &summary.entities {
+            *counts.entities.entry(entity_type.clone()).or_default() += *count as u64;
+        }
+        counts.recent.insert(0, summary);
+        counts.recent.truncate(RECENT);
+    }
+
+    fn snapshot(&self) -> StatsResponse {
+        StatsResponse {
+            uptime_secs: self.started.elapsed().as_secs(),
+            ..self.counts.lock().unwrap().clone()
+        }
+    }
+}
+
+/// The dashboard page and the endpoints it reads.
+pub(super) fn routes() -> Router<Arc<Service>> {
+    Router::new()
+        .route("/dashboard", get(|| async { Html(PAGE) }))
+        .route("/dashboard/stats", get(stats))
+        .route("/dashboard/rules", get(rules))
+}
+
+/// Middleware counting requests by route and failed responses.
+///
+/// Routes are counted by their pattern (`/realms/{realm}`), so realm
+/// names don't show up in the statistics.
+pub(super) async fn count(
+    State(service): State<Arc<Service>>,
+    path: MatchedPath,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = format!("{} {}", request.method(), path.as_str());
+    let response = next.run(request).await;
+    let mut counts = service.stats.counts.lock().unwrap();
+    *counts.requests.entry(route).or_default() += 1;
+    if response.status().is_client_error() || response.status().is_server_error() {
+        counts.errors += 1;
+    }
+    response
+}
+
+async fn stats(State(service): State<Arc<Service>>) -> Json<StatsResponse> {
+    Json(service.stats.snapshot())
+}
+
+async fn rules(State(service): State<Arc<Service>>) -> Json<RulesResponse> {
+    let mut config = service.anonymizer.config().clone();
+    if let Some(tokens) = &mut config.hashed_tokens {
+        tokens.key = "[REDACTED]".to_string();
+    }
+    Json(RulesResponse {
+        entity_types: service
+            .anonymizer
+            .detector()
+            .entity_types()
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        config,
+    })
+}
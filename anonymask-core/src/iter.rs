@@ -0,0 +1,208 @@
+use crate::anonymizer::Anonymizer;
+use crate::entity::AnonymizationResult;
+use crate::error::AnonymaskError;
+use crate::progress::Progress;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+type ProgressFn<'a> = Box<dyn FnMut(&Progress) + 'a>;
+
+/// Iterator returned by [`Anonymizer::anonymize_iter`].
+///
+/// Pulls up to `parallelism` texts from the source at a time, anonymizes
+/// them on a pool of scoped worker threads, no larger than the available
+/// parallelism of the machine, and yields the results in input order. At
+/// most one chunk of texts and results is held in memory.
+pub struct AnonymizeIter<'a, I> {
+    anonymizer: &'a Anonymizer,
+    texts: I,
+    parallelism: usize,
+    /// Threads anonymizing a chunk
+    workers: usize,
+    /// Results with the byte length of their input text
+    ready: VecDeque<(usize, Result<AnonymizationResult, AnonymaskError>)>,
+    progress: Progress,
+    on_progress: Option<ProgressFn<'a>>,
+}
+
+impl<'a, I> AnonymizeIter<'a, I>
+where
+    I: Iterator<Item = String>,
+{
+    pub(crate) fn new(anonymizer: &'a Anonymizer, texts: I, parallelism: usize) -> Self {
+        Self {
+            anonymizer,
+            texts,
+            parallelism: parallelism.max(1),
+            workers: thread::available_parallelism().map_or(1, |n| n.get()).min(parallelism.max(1)),
+            ready: VecDeque::new(),
+            progress: Progress::default(),
+            on_progress: None,
+        }
+    }
+
+    /// Call `on_progress` each time a result is yielded.
+    ///
+    /// The total size of the source is unknown, so `total_bytes` is `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, EntityType};
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let texts = vec!["a@b.io".to_string(), "no pii".to_string()];
+    ///
+    /// let mut documents = 0;
+    /// for result in anonymizer
+    ///     .anonymize_iter(texts)
+    ///     .with_progress(|p| documents = p.documents_processed)
+    /// {
+    ///     result.unwrap();
+    /// }
+    /// assert_eq!(documents, 2);
+    /// ```
+    pub fn with_progress<F>(mut self, on_progress: F) -> Self
+    where
+        F: FnMut(&Progress) + 'a,
+    {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    fn fill(&mut self) {
+        let chunk: Vec<String> = self.texts.by_ref().take(self.parallelism).collect();
+        if chunk.len() <= 1 {
+            self.ready.extend(
+                chunk
+                    .iter()
+                    .map(|text| (text.len(), self.anonymizer.anonymize(text))),
+            );
+            return;
+        }
+
+        // Workers take the next text of the chunk until none is left
+        let anonymizer = self.anonymizer;
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<Option<Result<AnonymizationResult, AnonymaskError>>> =
+            chunk.iter().map(|_| None).collect();
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.workers.min(chunk.len()))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(text) = chunk.get(i) else {
+                                return done;
+                            };
+                            done.push((i, anonymizer.anonymize(text)));
+                        }
+                    })
+                })
+                .collect();
+            for worker in workers {
+                // Texts of a panicked worker are reported below
+                if let Ok(done) = worker.join() {
+                    for (i, result) in done {
+                        results[i] = Some(result);
+                    }
+                }
+            }
+        });
+        for (text, result) in chunk.iter().zip(results) {
+            let result = result.unwrap_or_else(|| {
+                Err(AnonymaskError::AnonymizationError(
+                    "anonymization worker panicked".to_string(),
+                ))
+            });
+            self.ready.push_back((text.len(), result));
+        }
+    }
+}
+
+impl<I> Iterator for AnonymizeIter<'_, I>
+where
+    I: Iterator<Item = String>,
+{
+    type Item = Result<AnonymizationResult, AnonymaskError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ready.is_empty() {
+            self.fill();
+        }
+        let (bytes, result) = self.ready.pop_front()?;
+
+        if let Some(on_progress) = self.on_progress.as_mut() {
+            let entities = result.as_ref().map_or(0, |result| result.entities.len());
+            self.progress.advance(bytes, entities);
+            self.progress.documents_processed += 1;
+            on_progress(&self.progress);
+        }
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.texts.size_hint();
+        let ready = self.ready.len();
+        (
+            lower.saturating_add(ready),
+            upper.and_then(|upper| upper.checked_add(ready)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Anonymizer, EntityType};
+
+    #[test]
+    fn test_preserves_order_across_chunks() {
+        let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+        let texts: Vec<String> = (0..10).map(|i| format!("user{}@example.com", i)).collect();
+
+        let results: Vec<_> = anonymizer
+            .anonymize_iter_with_parallelism(texts.clone(), 3)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(results.len(), 10);
+        for (text, result) in texts.iter().zip(&results) {
+            assert_eq!(&result.entities[0].value, text);
+        }
+    }
+
+    #[test]
+    fn test_thread_count_is_bounded() {
+        let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+        let texts: Vec<String> = (0..5000).map(|i| format!("user{}@example.com", i)).collect();
+
+        let iter = anonymizer.anonymize_iter_with_parallelism(texts.clone(), 5000);
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        assert!(iter.workers <= cores);
+
+        let results: Vec<_> = iter.collect::<Result<_, _>>().unwrap();
+        for (text, result) in texts.iter().zip(&results) {
+            assert_eq!(&result.entities[0].value, text);
+        }
+    }
+
+    #[test]
+    fn test_is_lazy() {
+        let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+        let mut pulled = 0;
+        let source = std::iter::repeat_with(|| {
+            pulled += 1;
+            "a@b.io".to_string()
+        });
+
+        let first: Vec<_> = anonymizer
+            .anonymize_iter_with_parallelism(source, 2)
+            .take(3)
+            .collect();
+        assert_eq!(first.len(), 3);
+        drop(first);
+        assert_eq!(pulled, 4);
+    }
+}
@@ -0,0 +1,571 @@
+//! Anonymization of structured JSON documents.
+//!
+//! API payloads mix PII with structure that has to survive: anonymizing the
+//! serialized document as text could produce invalid JSON, and stitching
+//! anonymized strings back by hand is error-prone. These methods walk the
+//! document instead, anonymize each string value in place and share one
+//! mapping across the whole document.
+//!
+//! Object keys are never changed. Values under keys known to hold PII (e.g.
+//! `"ssn"`) can be replaced whole, whatever their content, and configured
+//! [`FieldPolicy`](crate::config::FieldPolicy) selectors replace or skip
+//! whole subtrees.
+//!
+//! Newline-delimited JSON (JSON lines, e.g. structured logs) is streamed
+//! record by record with [`Anonymizer::anonymize_ndjson`].
+
+use crate::anonymizer::Anonymizer;
+use crate::config::FieldAction;
+use crate::entity::{Entity, EntityType};
+use crate::error::AnonymaskError;
+use crate::replacer::Counters;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+/// Result of anonymizing a newline-delimited JSON stream.
+///
+/// # Fields
+///
+/// * `mapping` - HashMap mapping placeholders back to original values
+/// * `entities` - Detected entities, each with its line and location
+/// * `records` - Number of records processed, not counting blank lines
+/// * `truncated` - Whether the stream hit the `max_entities` limit
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct NdjsonAnonymizationResult {
+    pub mapping: HashMap<String, String>,
+    pub entities: Vec<NdjsonEntity>,
+    pub records: usize,
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// An entity found in one record of a newline-delimited JSON stream.
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct NdjsonEntity {
+    /// 0-based line of the record in the input
+    pub line: usize,
+    /// The entity and its location within the record
+    #[serde(flatten)]
+    pub entity: JsonEntity,
+}
+
+/// Result of anonymizing a JSON document.
+///
+/// # Fields
+///
+/// * `value` - The document with PII replaced by placeholders
+/// * `mapping` - HashMap mapping placeholders back to original values
+/// * `entities` - Detected entities, each with the location of its string
+/// * `truncated` - Whether the document hit the `max_entities` limit
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct JsonAnonymizationResult {
+    pub value: Value,
+    pub mapping: HashMap<String, String>,
+    pub entities: Vec<JsonEntity>,
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// An entity found in one string of a JSON document.
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct JsonEntity {
+    /// JSON Pointer (RFC 6901) of the string, e.g. `/users/0/email`
+    pub path: String,
+    /// The entity; positions are byte offsets within the string
+    #[serde(flatten)]
+    pub entity: Entity,
+}
+
+impl Anonymizer {
+    /// Anonymize the string values of a JSON document.
+    ///
+    /// Objects and arrays are walked recursively and every string is
+    /// anonymized like [`anonymize`](Self::anonymize), with one mapping for
+    /// the whole document. Numbers, booleans, nulls and object keys are kept,
+    /// except under keys matching a configured [`KeyRule`](crate::config::KeyRule),
+    /// which are replaced whole as in
+    /// [`anonymize_json_with_keys`](Self::anonymize_json_with_keys), and in
+    /// fields selected by a configured [`FieldPolicy`](crate::config::FieldPolicy).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, EntityType};
+    /// use serde_json::json;
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let payload = json!({
+    ///     "from": "john@email.com",
+    ///     "messages": [{ "body": "Reply to john@email.com", "id": 7 }]
+    /// });
+    ///
+    /// let result = anonymizer.anonymize_json(&payload).unwrap();
+    /// assert_eq!(result.mapping.len(), 1);
+    /// assert_eq!(result.entities[0].path, "/from");
+    /// assert_eq!(result.value["messages"][0]["id"], 7);
+    ///
+    /// let restored = anonymizer.deanonymize_json(&result.value, &result.mapping);
+    /// assert_eq!(restored, payload);
+    /// ```
+    pub fn anonymize_json(&self, value: &Value) -> Result<JsonAnonymizationResult, AnonymaskError> {
+        self.anonymize_json_with_keys(value, &HashMap::new())
+    }
+
+    /// Anonymize a JSON document, replacing values under sensitive keys whole.
+    ///
+    /// A string or number under one of `keys` (compared case-insensitively)
+    /// or a key matching a configured [`KeyRule`](crate::config::KeyRule)
+    /// is replaced by a single placeholder of the key's type without running
+    /// detection, so `{"ssn": "123 45 6789"}` is covered even though the
+    /// format isn't recognized. The same applies to strings and numbers in
+    /// arrays under such a key. Numbers become placeholder strings, and are
+    /// restored as strings. Everything else is anonymized like
+    /// [`anonymize_json`](Self::anonymize_json).
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The document to anonymize
+    /// * `keys` - Key names whose values are PII, with the type to report
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use anonymask_core::{Anonymizer, EntityType};
+    /// use serde_json::json;
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let keys = HashMap::from([("ssn".to_string(), EntityType::Ssn)]);
+    ///
+    /// let result = anonymizer
+    ///     .anonymize_json_with_keys(&json!({ "name": "Jo", "SSN": 123456789 }), &keys)
+    ///     .unwrap();
+    /// assert!(result.value["SSN"].as_str().unwrap().starts_with("SSN_"));
+    /// assert_eq!(result.entities[0].entity.value, "123456789");
+    /// ```
+    pub fn anonymize_json_with_keys(
+        &self,
+        value: &Value,
+        keys: &HashMap<String, EntityType>,
+    ) -> Result<JsonAnonymizationResult, AnonymaskError> {
+        let mut walker = JsonWalker::new(self, keys);
+        let value = walker.walk(value, "", None, self.field_action(&[]))?;
+
+        Ok(JsonAnonymizationResult {
+            value,
+            truncated: walker.truncated,
+            entities: std::mem::take(&mut walker.entities),
+            mapping: walker.mapping(),
+        })
+    }
+
+    /// Anonymize a stream of newline-delimited JSON records.
+    ///
+    /// Each line of `input` is parsed as one JSON value, anonymized like
+    /// [`anonymize_json`](Self::anonymize_json) and written to `output` on
+    /// one line. Placeholders are shared across the whole stream, and
+    /// `max_entities` applies to the stream as a whole. Blank lines and
+    /// records without changes are copied unchanged; changed records are
+    /// re-serialized compactly, which may reorder their object keys.
+    ///
+    /// # Errors
+    ///
+    /// * `AnonymaskError::IoError` - If reading or writing fails, or a line
+    ///   isn't valid JSON
+    /// * The errors of [`Anonymizer::anonymize`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, EntityType};
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let logs = "{\"level\":\"info\",\"msg\":\"login jo@x.io\"}\n{\"user\":\"jo@x.io\"}\n";
+    ///
+    /// let mut output = Vec::new();
+    /// let result = anonymizer.anonymize_ndjson(logs.as_bytes(), &mut output).unwrap();
+    /// assert_eq!(result.records, 2);
+    /// assert_eq!(result.mapping.len(), 1);
+    /// assert_eq!(result.entities[1].line, 1);
+    /// assert_eq!(result.entities[1].entity.path, "/user");
+    /// ```
+    pub fn anonymize_ndjson<R: BufRead, W: Write>(
+        &self,
+        input: R,
+        output: W,
+    ) -> Result<NdjsonAnonymizationResult, AnonymaskError> {
+        self.anonymize_ndjson_with_keys(input, output, &HashMap::new())
+    }
+
+    /// Anonymize newline-delimited JSON, replacing values under sensitive
+    /// keys whole.
+    ///
+    /// Like [`anonymize_ndjson`](Self::anonymize_ndjson), with `keys` applied
+    /// to every record as in
+    /// [`anonymize_json_with_keys`](Self::anonymize_json_with_keys).
+    pub fn anonymize_ndjson_with_keys<R: BufRead, W: Write>(
+        &self,
+        mut input: R,
+        mut output: W,
+        keys: &HashMap<String, EntityType>,
+    ) -> Result<NdjsonAnonymizationResult, AnonymaskError> {
+        let mut walker = JsonWalker::new(self, keys);
+        let mut entities = Vec::new();
+        let mut records = 0;
+        let mut line = String::new();
+
+        for index in 0.. {
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                break;
+            }
+            let record = line.trim();
+            if record.is_empty() {
+                output.write_all(line.as_bytes())?;
+                continue;
+            }
+
+            let value: Value = serde_json::from_str(record).map_err(|e| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, format!("line {}: {}", index + 1, e))
+            })?;
+            let anonymized = walker.walk(&value, "", None, self.field_action(&[]))?;
+            if walker.entities.is_empty() {
+                output.write_all(line.as_bytes())?;
+            } else {
+                serde_json::to_writer(&mut output, &anonymized).map_err(std::io::Error::from)?;
+                output.write_all(if line.ends_with("\r\n") { b"\r\n" } else { b"\n" })?;
+                entities.extend(walker.entities.drain(..).map(|entity| NdjsonEntity { line: index, entity }));
+            }
+            records += 1;
+        }
+        output.flush()?;
+
+        Ok(NdjsonAnonymizationResult {
+            truncated: walker.truncated,
+            mapping: walker.mapping(),
+            entities,
+            records,
+        })
+    }
+
+    /// Restore the original values in every string of a JSON document.
+    ///
+    /// The inverse of [`anonymize_json`](Self::anonymize_json); see
+    /// [`deanonymize`](Self::deanonymize).
+    pub fn deanonymize_json(&self, value: &Value, mapping: &HashMap<String, String>) -> Value {
+        match value {
+            Value::String(text) => Value::String(self.deanonymize(text, mapping)),
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.deanonymize_json(item, mapping))
+                    .collect(),
+            ),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, item)| (key.clone(), self.deanonymize_json(item, mapping)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+}
+
+/// State of one [`Anonymizer::anonymize_json_with_keys`] or
+/// [`Anonymizer::anonymize_ndjson_with_keys`] call.
+struct JsonWalker<'a> {
+    anonymizer: &'a Anonymizer,
+    /// Lowercased sensitive key names
+    keys: HashMap<String, EntityType>,
+    /// Keys and indices from the root to the value being walked
+    segments: Vec<String>,
+    /// Original value -> placeholder, shared by all strings
+    values: HashMap<String, String>,
+    /// Counters of this operation, if reset per operation
+    counters: Option<Counters>,
+    entities: Vec<JsonEntity>,
+    /// Entities left before `max_entities` is reached
+    remaining: Option<usize>,
+    truncated: bool,
+}
+
+impl<'a> JsonWalker<'a> {
+    fn new(anonymizer: &'a Anonymizer, keys: &HashMap<String, EntityType>) -> Self {
+        Self {
+            anonymizer,
+            keys: keys
+                .iter()
+                .map(|(key, entity_type)| (key.to_lowercase(), entity_type.clone()))
+                .collect(),
+            segments: Vec::new(),
+            values: HashMap::new(),
+            counters: anonymizer.operation_counters(),
+            entities: Vec::new(),
+            remaining: anonymizer.entity_limit(),
+            truncated: false,
+        }
+    }
+
+    /// Placeholder -> original value, for everything replaced so far.
+    fn mapping(self) -> HashMap<String, String> {
+        self.values
+            .into_iter()
+            .map(|(original, placeholder)| (placeholder, original))
+            .collect()
+    }
+
+    /// Anonymize `value`, located at `path`. `key_type` is set below a
+    /// sensitive key, and `action` in a field selected by a field policy.
+    fn walk(
+        &mut self,
+        value: &Value,
+        path: &str,
+        key_type: Option<&EntityType>,
+        action: Option<&'a FieldAction>,
+    ) -> Result<Value, AnonymaskError> {
+        match value {
+            Value::Object(map) => {
+                let mut anonymized = Map::new();
+                for (key, item) in map {
+                    self.segments.push(key.clone());
+                    let item_action = self.anonymizer.field_action(&self.segments).or(action);
+                    // Field policies take precedence over key rules
+                    let item_type = self
+                        .keys
+                        .get(&key.to_lowercase())
+                        .or_else(|| match item_action {
+                            Some(_) => None,
+                            None => self.anonymizer.key_type(key),
+                        })
+                        .cloned();
+                    let item_path = format!("{}/{}", path, escape_pointer(key));
+                    let item = self.walk(item, &item_path, item_type.as_ref(), item_action);
+                    self.segments.pop();
+                    anonymized.insert(key.clone(), item?);
+                }
+                Ok(Value::Object(anonymized))
+            }
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    self.segments.push(index.to_string());
+                    let item_action = self.anonymizer.field_action(&self.segments).or(action);
+                    let item = self.walk(item, &format!("{}/{}", path, index), key_type, item_action);
+                    self.segments.pop();
+                    item
+                })
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            Value::String(text) => match (key_type, action) {
+                (Some(entity_type), _) | (None, Some(FieldAction::Always(entity_type))) => {
+                    Ok(self.replace_whole(text, entity_type, path).unwrap_or_else(|| value.clone()))
+                }
+                (None, Some(FieldAction::Skip)) => Ok(value.clone()),
+                (None, _) => self.anonymize_text(text, path),
+            },
+            Value::Number(number) => match (key_type, action) {
+                (Some(entity_type), _) | (None, Some(FieldAction::Always(entity_type))) => Ok(self
+                    .replace_whole(&number.to_string(), entity_type, path)
+                    .unwrap_or_else(|| value.clone())),
+                _ => Ok(value.clone()),
+            },
+            Value::Bool(_) | Value::Null => Ok(value.clone()),
+        }
+    }
+
+    /// Run detection on one string.
+    fn anonymize_text(&mut self, text: &str, path: &str) -> Result<Value, AnonymaskError> {
+        let anonymized = self
+            .anonymizer
+            .anonymize_shared(text, None, None, &mut self.values, self.counters.as_mut(), self.remaining)?;
+        self.truncated |= anonymized.truncated;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= anonymized.entities.len();
+        }
+        self.entities.extend(anonymized.entities.into_iter().map(|entity| JsonEntity {
+            path: path.to_string(),
+            entity,
+        }));
+        Ok(Value::String(anonymized.text))
+    }
+
+    /// Replace all of `text` with a placeholder, unless it's empty or the
+    /// entity limit is reached.
+    fn replace_whole(&mut self, text: &str, entity_type: &EntityType, path: &str) -> Option<Value> {
+        if text.is_empty() {
+            return None;
+        }
+        if self.remaining == Some(0) {
+            self.truncated = true;
+            return None;
+        }
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+        let entity = Entity {
+            entity_type: entity_type.clone(),
+            value: text.to_string(),
+            start: 0,
+            end: text.len(),
+            confidence: 1.0,
+        };
+        let placeholder = self.anonymizer.placeholder_for(&entity, &mut self.values, self.counters.as_mut());
+        self.entities.push(JsonEntity {
+            path: path.to_string(),
+            entity,
+        });
+        Some(Value::String(placeholder))
+    }
+}
+
+/// Escape a key for use in a JSON Pointer.
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AnonymizerConfig, FieldAction, FieldPolicy, KeyRule, PlaceholderFormat};
+    use serde_json::json;
+
+    fn anonymizer(max_entities: usize) -> Anonymizer {
+        let config = AnonymizerConfig::builder()
+            .with_placeholder_format(PlaceholderFormat::Short)
+            .with_max_entities(max_entities)
+            .build();
+        Anonymizer::with_config(vec![EntityType::Email], config).unwrap()
+    }
+
+    #[test]
+    fn test_paths_and_shared_placeholders() {
+        let payload = json!({
+            "a/b": ["x", "mail a@b.io"],
+            "user": { "email": "a@b.io", "tags": ["c@d.io", true, null] }
+        });
+        let result = anonymizer(0).anonymize_json(&payload).unwrap();
+
+        assert_eq!(result.value["a/b"][1], "mail EMAIL_1");
+        assert_eq!(result.value["user"]["email"], "EMAIL_1");
+        assert_eq!(result.value["user"]["tags"], json!(["EMAIL_2", true, null]));
+        let paths: Vec<&str> = result.entities.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["/a~1b/1", "/user/email", "/user/tags/0"]);
+        assert_eq!(result.entities[0].entity.start, 5);
+        assert_eq!(result.mapping.len(), 2);
+    }
+
+    #[test]
+    fn test_sensitive_keys() {
+        let keys = HashMap::from([("ssn".to_string(), EntityType::Ssn)]);
+        let payload = json!({
+            "people": [{ "Ssn": "123 45 6789" }, { "ssn": ["", 987654321] }],
+            "note": "ssn unknown"
+        });
+        let result = anonymizer(0).anonymize_json_with_keys(&payload, &keys).unwrap();
+
+        assert_eq!(result.value["people"][0]["Ssn"], "SSN_1");
+        assert_eq!(result.value["people"][1]["ssn"], json!(["", "SSN_2"]));
+        assert_eq!(result.value["note"], "ssn unknown");
+        assert_eq!(result.entities[1].path, "/people/1/ssn/1");
+        assert_eq!(result.entities[1].entity.confidence, 1.0);
+    }
+
+    #[test]
+    fn test_configured_key_rules() {
+        let config = AnonymizerConfig::builder()
+            .with_key_rule(KeyRule::new("(?i)dob|date_of_birth", EntityType::Date))
+            .build();
+        let anonymizer = Anonymizer::with_config(vec![], config).unwrap();
+        let keys = HashMap::from([("dob".to_string(), EntityType::Custom("birthday".to_string()))]);
+        let payload = json!({ "DOB": "4 Dec 1988", "Date_of_Birth": 19881204, "dobby": "free" });
+
+        let result = anonymizer.anonymize_json(&payload).unwrap();
+        assert_eq!(result.entities.len(), 2);
+        assert!(result.entities.iter().all(|e| e.entity.entity_type == EntityType::Date));
+        assert_eq!(result.value["dobby"], "free");
+
+        // Explicit keys take precedence
+        let result = anonymizer.anonymize_json_with_keys(&payload, &keys).unwrap();
+        assert_eq!(result.entities[0].path, "/DOB");
+        assert_eq!(result.entities[0].entity.entity_type, keys["dob"]);
+    }
+
+    #[test]
+    fn test_field_policies() {
+        let config = AnonymizerConfig::builder()
+            .with_placeholder_format(PlaceholderFormat::Short)
+            .with_key_rule(KeyRule::new("(?i)ssn", EntityType::Ssn))
+            .with_field_policy(FieldPolicy::new("$.orders[*].id", FieldAction::Always(EntityType::Custom("order".into()))))
+            .with_field_policy(FieldPolicy::new("$.audit.actor", FieldAction::Detect))
+            .with_field_policy(FieldPolicy::new("/audit", FieldAction::Skip))
+            .with_field_policy(FieldPolicy::new("ssn", FieldAction::Detect))
+            .build();
+        let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+        let payload = json!({
+            "orders": [{ "id": 17, "by": "a@b.io" }, { "id": "" }],
+            "audit": { "actor": "a@b.io", "ip": "c@d.io", "ssn": "123 45 6789" },
+            "owner": { "ssn": "none on file" }
+        });
+
+        let result = anonymizer.anonymize_json(&payload).unwrap();
+        assert_eq!(
+            result.value,
+            json!({
+                "orders": [{ "id": "ORDER_1", "by": "EMAIL_1" }, { "id": "" }],
+                "audit": { "actor": "EMAIL_1", "ip": "c@d.io", "ssn": "123 45 6789" },
+                "owner": { "ssn": "none on file" }
+            })
+        );
+
+        // Explicit keys take precedence over policies
+        let keys = HashMap::from([("ip".to_string(), EntityType::IpAddress)]);
+        let result = anonymizer.anonymize_json_with_keys(&payload, &keys).unwrap();
+        assert_eq!(result.value["audit"]["ip"], "IP_ADDRESS_1");
+
+        let config = AnonymizerConfig::builder()
+            .with_field_policy(FieldPolicy::new("$.a[", FieldAction::Skip))
+            .build();
+        assert!(matches!(Anonymizer::with_config(vec![], config), Err(AnonymaskError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_entity_limit_spans_document() {
+        let keys = HashMap::from([("owner".to_string(), EntityType::Custom("owner".to_string()))]);
+        let payload = json!({ "a": "a@b.io c@d.io", "b": "e@f.io", "owner": "Jo" });
+        let result = anonymizer(2).anonymize_json_with_keys(&payload, &keys).unwrap();
+
+        assert_eq!(result.entities.len(), 2);
+        assert!(result.truncated);
+        assert_eq!(result.value["b"], "e@f.io");
+        assert_eq!(result.value["owner"], "Jo");
+    }
+
+    #[test]
+    fn test_ndjson_stream() {
+        let keys = HashMap::from([("ssn".to_string(), EntityType::Ssn)]);
+        let input = "{\"z\": 1, \"a\": \"plain\"}\r\n\n{\"msg\":\"to a@b.io\",\"ssn\":\"x\"}\n{\"from\":\"a@b.io\"}";
+        let mut output = Vec::new();
+        let result = anonymizer(0)
+            .anonymize_ndjson_with_keys(input.as_bytes(), &mut output, &keys)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"z\": 1, \"a\": \"plain\"}\r\n\n{\"msg\":\"to EMAIL_1\",\"ssn\":\"SSN_1\"}\n{\"from\":\"EMAIL_1\"}\n"
+        );
+        assert_eq!(result.records, 3);
+        assert_eq!(result.mapping.len(), 2);
+        let lines: Vec<usize> = result.entities.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 2, 3]);
+
+        let err = anonymizer(0).anonymize_ndjson("{}\nnot json\n".as_bytes(), Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+}
@@ -0,0 +1,142 @@
+//! Regional pattern packs.
+//!
+//! Identifiers such as phone numbers, dates and postal addresses are written
+//! differently in every country. Each locale pack contributes its own patterns
+//! for these shared entity types; the packs used are selected with
+//! [`AnonymizerConfig::locales`](crate::config::AnonymizerConfig::locales).
+//! Country-specific identifiers (e.g. [`EntityType::SteuerId`]) are always
+//! available regardless of the configured locales.
+
+use crate::entity::EntityType;
+use crate::error::AnonymaskError;
+use crate::validation::Validator;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+mod au;
+mod de;
+mod es;
+mod fr;
+mod jp;
+mod us;
+
+pub(crate) use jp::normalize_width;
+
+/// A regional pattern pack.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::locale::Locale;
+///
+/// let locale: Locale = "de".parse().unwrap();
+/// assert_eq!(locale, Locale::De);
+/// assert_eq!(locale.as_str(), "de");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Locale {
+    /// United States (default): NANP phone numbers and dates
+    Us,
+    /// Germany: German phone numbers, postal addresses and dates
+    De,
+    /// France: French phone numbers
+    Fr,
+    /// Spain: Spanish phone numbers
+    Es,
+    /// Japan: Japanese phone numbers; also folds full-width characters before matching
+    Jp,
+    /// Australia: Australian phone numbers and dates
+    Au,
+}
+
+impl Locale {
+    /// All available locale packs.
+    pub const ALL: [Locale; 6] = [Locale::Us, Locale::De, Locale::Fr, Locale::Es, Locale::Jp, Locale::Au];
+
+    /// Short lowercase code of the locale.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::Us => "us",
+            Locale::De => "de",
+            Locale::Fr => "fr",
+            Locale::Es => "es",
+            Locale::Jp => "jp",
+            Locale::Au => "au",
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = AnonymaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "us" | "en-us" => Ok(Locale::Us),
+            "de" | "de-de" | "de-at" | "de-ch" => Ok(Locale::De),
+            "fr" | "fr-fr" => Ok(Locale::Fr),
+            "es" | "es-es" => Ok(Locale::Es),
+            "jp" | "ja" | "ja-jp" => Ok(Locale::Jp),
+            "au" | "en-au" => Ok(Locale::Au),
+            _ => Err(AnonymaskError::UnsupportedLocale(s.to_string())),
+        }
+    }
+}
+
+/// Dates with English month names, shared by the English-speaking packs:
+/// April 12, 1988, Apr. 12th 1988, 12 Apr 1988 and 12th April, 1988.
+const ENGLISH_NAMED_DATES: [PatternDef; 2] = [
+    PatternDef::new(
+        r"\b(?i:Jan(?:uary)?|Feb(?:ruary)?|Mar(?:ch)?|Apr(?:il)?|May|June?|July?|Aug(?:ust)?|Sep(?:t(?:ember)?)?|Oct(?:ober)?|Nov(?:ember)?|Dec(?:ember)?)\.?\s(?:0?[1-9]|[12][0-9]|3[01])(?:st|nd|rd|th)?,?\s[0-9]{4}\b",
+    ),
+    PatternDef::new(
+        r"\b(?:0?[1-9]|[12][0-9]|3[01])(?:st|nd|rd|th)?\s(?i:Jan(?:uary)?|Feb(?:ruary)?|Mar(?:ch)?|Apr(?:il)?|May|June?|July?|Aug(?:ust)?|Sep(?:t(?:ember)?)?|Oct(?:ober)?|Nov(?:ember)?|Dec(?:ember)?)\.?,?\s[0-9]{4}\b",
+    ),
+];
+
+/// A pattern contributed by a locale pack, with an optional validator.
+pub(crate) struct PatternDef {
+    pub pattern: &'static str,
+    pub validator: Option<Validator>,
+    /// Whether matches are only reported next to a context keyword
+    pub requires_context: bool,
+}
+
+impl PatternDef {
+    pub(crate) const fn new(pattern: &'static str) -> Self {
+        Self {
+            pattern,
+            validator: None,
+            requires_context: false,
+        }
+    }
+
+    pub(crate) const fn validated(pattern: &'static str, validator: Validator) -> Self {
+        Self {
+            pattern,
+            validator: Some(validator),
+            requires_context: false,
+        }
+    }
+
+    /// A pattern too ambiguous to report without a context keyword.
+    pub(crate) const fn contextual(pattern: &'static str) -> Self {
+        Self {
+            pattern,
+            validator: None,
+            requires_context: true,
+        }
+    }
+}
+
+/// Patterns a locale pack contributes for an entity type.
+pub(crate) fn patterns(locale: Locale, entity_type: &EntityType) -> Vec<PatternDef> {
+    match locale {
+        Locale::Us => us::patterns(entity_type),
+        Locale::De => de::patterns(entity_type),
+        Locale::Fr => fr::patterns(entity_type),
+        Locale::Es => es::patterns(entity_type),
+        Locale::Jp => jp::patterns(entity_type),
+        Locale::Au => au::patterns(entity_type),
+    }
+}
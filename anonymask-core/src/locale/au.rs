@@ -0,0 +1,22 @@
+use super::{PatternDef, ENGLISH_NAMED_DATES};
+use crate::entity::EntityType;
+
+pub(super) fn patterns(entity_type: &EntityType) -> Vec<PatternDef> {
+    match entity_type {
+        EntityType::Phone => vec![
+            // Mobile: 0412 345 678, +61 412 345 678
+            PatternDef::new(r"(?:\+61\s?|\b0)4[0-9]{2}\s?[0-9]{3}\s?[0-9]{3}\b"),
+            // Landline: (02) 9876 5432, 02 9876 5432, +61 2 9876 5432
+            PatternDef::new(r"(?:\+61\s?|\(0|\b0)[2378]\)?\s?[0-9]{4}\s?[0-9]{4}\b"),
+        ],
+        EntityType::Date => {
+            let mut defs = vec![
+                // 12/04/1988, 12.04.88, 12-04-1988 (day first)
+                PatternDef::new(r"\b(?:0?[1-9]|[12][0-9]|3[01])[/.-](?:0?[1-9]|1[0-2])[/.-](?:[0-9]{4}|[0-9]{2})\b"),
+            ];
+            defs.extend(ENGLISH_NAMED_DATES);
+            defs
+        }
+        _ => Vec::new(),
+    }
+}
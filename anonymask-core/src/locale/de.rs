@@ -0,0 +1,26 @@
+use super::PatternDef;
+use crate::entity::EntityType;
+
+pub(super) fn patterns(entity_type: &EntityType) -> Vec<PatternDef> {
+    match entity_type {
+        // +49 30 12345678, 0049 171 1234567, 030/1234567, 0171-1234567, +49 (0)30 123456
+        EntityType::Phone => vec![PatternDef::new(
+            r"(?:\+49|\b0049|\b0)\s?(?:\(0\)\s?)?[1-9][0-9]{1,4}(?:[\s/-]?[0-9]{2,8}){1,2}\b",
+        )],
+        // Hauptstraße 5, Karl-Marx-Allee 12a, 10115 Berlin
+        EntityType::Address => vec![PatternDef::new(
+            r"\b[A-ZÄÖÜ][\p{L}-]*(?:straße|strasse|str\.|weg|platz|allee|gasse|ring|damm|ufer)\s+[0-9]{1,4}\s?[a-z]?\b(?:,?\s+[0-9]{5}\s+[A-ZÄÖÜ]\p{L}+)?",
+        )],
+        EntityType::Date => vec![
+            // 04.12.1988, 4.12.88
+            PatternDef::new(
+                r"\b(?:0?[1-9]|[12][0-9]|3[01])\.(?:0?[1-9]|1[0-2])\.(?:[0-9]{4}|[0-9]{2})\b",
+            ),
+            // 4. Dezember 1988
+            PatternDef::new(
+                r"\b(?:0?[1-9]|[12][0-9]|3[01])\.\s?(?:Januar|Februar|März|April|Mai|Juni|Juli|August|September|Oktober|November|Dezember)\s[0-9]{4}\b",
+            ),
+        ],
+        _ => Vec::new(),
+    }
+}
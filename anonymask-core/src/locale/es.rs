@@ -0,0 +1,16 @@
+use super::PatternDef;
+use crate::entity::EntityType;
+
+pub(super) fn patterns(entity_type: &EntityType) -> Vec<PatternDef> {
+    match entity_type {
+        EntityType::Phone => vec![
+            // 612 345 678, +34 612 34 56 78
+            PatternDef::new(
+                r"(?:\+34\s?|\b0034\s?|\b)[6789][0-9]{2}(?:[\s.-]?[0-9]{3}[\s.-]?[0-9]{3}|[\s.-]?[0-9]{2}[\s.-]?[0-9]{2}[\s.-]?[0-9]{2})\b",
+            ),
+            // 91 234 56 78
+            PatternDef::new(r"(?:\+34\s?|\b)[89][0-9][\s.-][0-9]{3}[\s.-][0-9]{2}[\s.-][0-9]{2}\b"),
+        ],
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,12 @@
+use super::PatternDef;
+use crate::entity::EntityType;
+
+pub(super) fn patterns(entity_type: &EntityType) -> Vec<PatternDef> {
+    match entity_type {
+        // 01 23 45 67 89, 06.12.34.56.78, +33 6 12 34 56 78
+        EntityType::Phone => vec![PatternDef::new(
+            r"(?:\+33\s?(?:\(0\)\s?)?|\b0)[1-9](?:[\s.-]?[0-9]{2}){4}\b",
+        )],
+        _ => Vec::new(),
+    }
+}
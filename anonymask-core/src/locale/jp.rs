@@ -0,0 +1,52 @@
+use super::PatternDef;
+use crate::entity::EntityType;
+
+pub(super) fn patterns(entity_type: &EntityType) -> Vec<PatternDef> {
+    match entity_type {
+        EntityType::Phone => vec![
+            // Mobile: 090-1234-5678, +81 90 1234 5678
+            PatternDef::new(r"(?:\+81[\s-]?|\b0)[789]0[\s-]?[0-9]{4}[\s-]?[0-9]{4}\b"),
+            // Landline: 03-1234-5678, 0466-12-3456
+            PatternDef::new(r"(?:\+81[\s-]?|\b0)[1-9][0-9]{0,3}[\s-][0-9]{1,4}[\s-][0-9]{4}\b"),
+            // Toll free: 0120-123-456
+            PatternDef::new(r"\b0120[\s-]?[0-9]{3}[\s-]?[0-9]{3}\b"),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// Fold full-width ASCII variants (U+FF01..U+FF5E) and the ideographic space
+/// to their ASCII equivalents.
+///
+/// Returns `None` when the text contains nothing to fold. Otherwise returns the
+/// folded text and, for every byte offset of the folded text (plus its end),
+/// the corresponding byte offset in the original text.
+pub(crate) fn normalize_width(text: &str) -> Option<(String, Vec<usize>)> {
+    if !text.chars().any(is_wide) {
+        return None;
+    }
+
+    let mut folded = String::with_capacity(text.len());
+    let mut offsets = Vec::with_capacity(text.len() + 1);
+    for (index, c) in text.char_indices() {
+        let narrow = fold(c);
+        for _ in 0..narrow.len_utf8() {
+            offsets.push(index);
+        }
+        folded.push(narrow);
+    }
+    offsets.push(text.len());
+    Some((folded, offsets))
+}
+
+fn is_wide(c: char) -> bool {
+    matches!(c, '\u{FF01}'..='\u{FF5E}' | '\u{3000}')
+}
+
+fn fold(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
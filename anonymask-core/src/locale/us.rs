@@ -0,0 +1,25 @@
+use super::{PatternDef, ENGLISH_NAMED_DATES};
+use crate::entity::EntityType;
+use crate::validation;
+
+pub(super) fn patterns(entity_type: &EntityType) -> Vec<PatternDef> {
+    match entity_type {
+        // Enhanced phone pattern: supports (555) 123-4567, 555-123-4567, 555.123.4567, 555-123, etc.
+        // The length check only applies with `PhoneMatching::Strict`
+        EntityType::Phone => vec![PatternDef::validated(
+            r"\b(?:\+?1[-.\s]?)?\(?([0-9]{3})\)?[-.\s]?([0-9]{3})(?:[-.\s]?([0-9]{4}))?\b",
+            validation::nanp_phone,
+        )],
+        EntityType::Date => {
+            let mut defs = vec![
+                // 04/12/1988, 4/12/88 (month first)
+                PatternDef::new(r"\b(?:0?[1-9]|1[0-2])/(?:0?[1-9]|[12][0-9]|3[01])/(?:[0-9]{4}|[0-9]{2})\b"),
+                // 04-12-1988
+                PatternDef::new(r"\b(?:0?[1-9]|1[0-2])-(?:0?[1-9]|[12][0-9]|3[01])-[0-9]{4}\b"),
+            ];
+            defs.extend(ENGLISH_NAMED_DATES);
+            defs
+        }
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,122 @@
+//! A `log` logger that scrubs PII from records (feature `log`).
+//!
+//! The `log` crate counterpart of [`tracing_layer`](crate::tracing_layer)
+//! for services that don't use `tracing`: [`ScrubLogger`] wraps the logger
+//! that writes records (`env_logger`, `simplelog`, ...) and passes it each
+//! record with its formatted message anonymized.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use anonymask_core::{Anonymizer, EntityType};
+//! use anonymask_core::logger::ScrubLogger;
+//!
+//! # struct Stderr;
+//! # impl log::Log for Stderr {
+//! #     fn enabled(&self, _: &log::Metadata) -> bool { true }
+//! #     fn log(&self, record: &log::Record) { eprintln!("{}", record.args()) }
+//! #     fn flush(&self) {}
+//! # }
+//! let anonymizer = Arc::new(Anonymizer::new(vec![EntityType::Email]).unwrap());
+//! log::set_boxed_logger(Box::new(ScrubLogger::new(anonymizer, Stderr))).unwrap();
+//! log::set_max_level(log::LevelFilter::Info);
+//! ```
+//!
+//! Mappings are discarded, so logs can't be restored. A message that
+//! fails to anonymize is replaced by `[REDACTED]` rather than passed
+//! through.
+
+use crate::anonymizer::Anonymizer;
+use log::{Log, Metadata, Record};
+use std::sync::Arc;
+
+/// Replaces messages that couldn't be anonymized.
+const REDACTED: &str = "[REDACTED]";
+
+/// Logger that anonymizes records before passing them to an inner logger.
+pub struct ScrubLogger<L> {
+    anonymizer: Arc<Anonymizer>,
+    inner: L,
+}
+
+impl<L: Log> ScrubLogger<L> {
+    /// Scrub every record `inner` receives with `anonymizer`.
+    pub fn new(anonymizer: Arc<Anonymizer>, inner: L) -> Self {
+        Self { anonymizer, inner }
+    }
+}
+
+impl<L: Log> Log for ScrubLogger<L> {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        // Skip formatting and detection for records that would be dropped
+        if !self.inner.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        let scrubbed = match self.anonymizer.anonymize(&message) {
+            Ok(result) => result.anonymized_text,
+            Err(_) => REDACTED.to_string(),
+        };
+        self.inner.log(
+            &Record::builder()
+                .args(format_args!("{}", scrubbed))
+                .metadata(record.metadata().clone())
+                .module_path(record.module_path())
+                .file(record.file())
+                .line(record.line())
+                .build(),
+        )
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EntityType;
+    use log::Level;
+    use std::sync::Mutex;
+
+    /// Keeps the messages of records at `Info` or above.
+    #[derive(Default)]
+    struct Capture(Mutex<Vec<String>>);
+
+    impl Log for Capture {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            metadata.level() <= Level::Info
+        }
+
+        fn log(&self, record: &Record<'_>) {
+            self.0.lock().unwrap().push(format!("{}:{:?} {}", record.target(), record.line(), record.args()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn test_scrubs_messages() {
+        let anonymizer = Arc::new(Anonymizer::new(vec![EntityType::Email]).unwrap());
+        let logger = ScrubLogger::new(anonymizer, Capture::default());
+
+        for level in [Level::Warn, Level::Debug] {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("bounce from {}", "jo@x.io"))
+                    .level(level)
+                    .target("mailer")
+                    .line(Some(7))
+                    .build(),
+            );
+        }
+
+        let lines = logger.inner.0.lock().unwrap();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("mailer:Some(7) bounce from EMAIL_"), "{}", lines[0]);
+    }
+}
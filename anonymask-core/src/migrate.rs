@@ -0,0 +1,308 @@
+//! Moving anonymized archives to another placeholder format.
+//!
+//! Placeholders are chosen once, when text is anonymized, and stored texts
+//! and mappings keep them. A [`PlaceholderMigration`] translates every
+//! placeholder of a mapping written under an earlier configuration into
+//! one of the current configuration, and rewrites texts and the mapping to
+//! match, so archives stay restorable after the format changes.
+
+use crate::anonymizer::Anonymizer;
+use crate::config::AnonymizerConfig;
+use crate::entity::EntityType;
+use crate::error::AnonymaskError;
+use crate::placeholder::Template;
+use crate::restore::MappingIndex;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+/// Translation of the placeholders of a mapping to a new format.
+///
+/// Created by [`Anonymizer::migrate_placeholders`].
+#[derive(Debug, Clone)]
+pub struct PlaceholderMigration {
+    /// Old placeholder -> new placeholder
+    placeholders: HashMap<String, String>,
+    /// New placeholder -> original value
+    mapping: HashMap<String, String>,
+    /// `placeholders`, prepared for rewriting texts
+    index: MappingIndex,
+}
+
+impl PlaceholderMigration {
+    /// The new placeholder of every old one.
+    pub fn placeholders(&self) -> &HashMap<String, String> {
+        &self.placeholders
+    }
+
+    /// The migrated mapping, from new placeholders to original values.
+    pub fn mapping(&self) -> &HashMap<String, String> {
+        &self.mapping
+    }
+
+    /// Replace the old placeholders in `text` with the new ones.
+    pub fn rewrite(&self, text: &str) -> String {
+        self.index.deanonymize(text)
+    }
+
+    /// Replace the old placeholders in everything read from `reader`,
+    /// writing the result to `writer`; see
+    /// [`MappingIndex::deanonymize_stream`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of `reader` or `writer`.
+    pub fn rewrite_stream<R: Read, W: Write>(&self, reader: R, writer: W) -> io::Result<()> {
+        self.index.deanonymize_stream(reader, writer)
+    }
+}
+
+impl Anonymizer {
+    /// Translate a mapping written under the configuration `from` into
+    /// this anonymizer's placeholder format.
+    ///
+    /// The entity type of each old placeholder is read from the placeholder
+    /// itself, or from the placeholder override that produced it. New
+    /// placeholders are generated as if the values were anonymized now, in
+    /// the natural order of the old placeholders (`EMAIL_2` before
+    /// `EMAIL_10`), and a value under several old placeholders gets one new
+    /// placeholder. Placeholders `from` can't have produced, such as
+    /// replacements given with custom values, are kept.
+    ///
+    /// Rewrite every text of the archive with the returned migration, then
+    /// replace the old mapping with [`PlaceholderMigration::mapping`].
+    ///
+    /// # Errors
+    ///
+    /// * `AnonymaskError::InvalidConfig` - If a placeholder format of
+    ///   `from` is invalid, the type of an old placeholder can't be told,
+    ///   or the new format gives different values the same placeholder, as
+    ///   `[REDACTED]` does, which couldn't be restored
+    /// * `AnonymaskError::AnonymizationError` - If the mapping is too large
+    ///   to index
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType, PlaceholderFormat};
+    ///
+    /// let old_config = AnonymizerConfig::default();
+    /// let old = Anonymizer::with_config(vec![EntityType::Email], old_config.clone()).unwrap();
+    /// let archived = old.anonymize("Mail ada@example.com").unwrap();
+    ///
+    /// let config = AnonymizerConfig::builder()
+    ///     .with_placeholder_format(PlaceholderFormat::Short)
+    ///     .build();
+    /// let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+    /// let migration = anonymizer.migrate_placeholders(&archived.mapping, &old_config).unwrap();
+    ///
+    /// let text = migration.rewrite(&archived.anonymized_text);
+    /// assert_eq!(text, "Mail EMAIL_1");
+    /// assert_eq!(anonymizer.deanonymize(&text, migration.mapping()), "Mail ada@example.com");
+    /// ```
+    pub fn migrate_placeholders(
+        &self,
+        mapping: &HashMap<String, String>,
+        from: &AnonymizerConfig,
+    ) -> Result<PlaceholderMigration, AnonymaskError> {
+        let readers = readers(from)?;
+
+        let mut old_placeholders: Vec<&String> = mapping.keys().collect();
+        old_placeholders.sort_by(|a, b| natural_order(a, b));
+
+        let mut placeholders = HashMap::new();
+        let mut migrated = HashMap::new();
+        // Value -> new placeholder, like the table of one anonymization
+        let mut unique_values: HashMap<&str, String> = HashMap::new();
+        let mut counters = self.operation_counters();
+        for old in old_placeholders {
+            let original = &mapping[old];
+            let new = match entity_type(&readers, old)? {
+                None => old.clone(),
+                Some(entity_type) => match unique_values.get(original.as_str()) {
+                    Some(placeholder) => placeholder.clone(),
+                    None => {
+                        let placeholder = self.replacer().placeholder(&entity_type, original, counters.as_mut());
+                        unique_values.insert(original, placeholder.clone());
+                        placeholder
+                    }
+                },
+            };
+            if let Some(other) = migrated.insert(new.clone(), original.clone()) {
+                if &other != original {
+                    return Err(AnonymaskError::InvalidConfig(format!(
+                        "the placeholder format gives several values the placeholder '{}', so they couldn't be restored",
+                        new
+                    )));
+                }
+            }
+            placeholders.insert(old.clone(), new);
+        }
+
+        Ok(PlaceholderMigration {
+            index: MappingIndex::new(&placeholders)?,
+            placeholders,
+            mapping: migrated,
+        })
+    }
+}
+
+/// Readers of the placeholders `config` produces: those of its overrides,
+/// with their entity type, then the default format's.
+fn readers(config: &AnonymizerConfig) -> Result<Vec<(Regex, Option<EntityType>)>, AnonymaskError> {
+    let mut readers = Vec::new();
+    for placeholder_override in &config.placeholder_overrides {
+        let reader = Template::parse(&placeholder_override.format)?.reader()?;
+        readers.push((reader, Some(placeholder_override.entity_type.clone())));
+    }
+    readers.push((Template::parse(&config.placeholder_format)?.reader()?, None));
+    Ok(readers)
+}
+
+/// The entity type of a placeholder, or `None` if no reader matches it.
+fn entity_type(
+    readers: &[(Regex, Option<EntityType>)],
+    placeholder: &str,
+) -> Result<Option<EntityType>, AnonymaskError> {
+    for (reader, overridden) in readers {
+        let Some(captures) = reader.captures(placeholder) else {
+            continue;
+        };
+        if let Some(type_name) = captures.name("type") {
+            let named = EntityType::from_str(&type_name.as_str().to_lowercase())?;
+            // An override's placeholder may also fit the default format
+            if overridden.as_ref().is_none_or(|overridden| *overridden == named) {
+                return Ok(Some(named));
+            }
+            continue;
+        }
+        return match overridden {
+            Some(entity_type) => Ok(Some(entity_type.clone())),
+            None => Err(AnonymaskError::InvalidConfig(format!(
+                "can't tell the entity type of placeholder '{}', as its format has no {{type}}",
+                placeholder
+            ))),
+        };
+    }
+    Ok(None)
+}
+
+/// Compare strings with runs of digits ordered by their value.
+fn natural_order(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(x), Some(y)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+            let (da, db) = (digits(a), digits(b));
+            let (na, nb) = (a[..da].trim_start_matches('0'), b[..db].trim_start_matches('0'));
+            let order = na.len().cmp(&nb.len()).then_with(|| na.cmp(nb)).then_with(|| da.cmp(&db));
+            if order != Ordering::Equal {
+                return order;
+            }
+            (a, b) = (&a[da..], &b[db..]);
+        } else {
+            if x != y {
+                return x.cmp(&y);
+            }
+            (a, b) = (&a[x.len_utf8()..], &b[y.len_utf8()..]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{PlaceholderFormat, PlaceholderOverride};
+
+    fn anonymizer(format: PlaceholderFormat) -> Anonymizer {
+        let config = AnonymizerConfig::builder().with_placeholder_format(format).build();
+        Anonymizer::with_config(vec![EntityType::Email, EntityType::Phone], config).unwrap()
+    }
+
+    #[test]
+    fn test_standard_archive_to_short() {
+        let old_config = AnonymizerConfig::default();
+        let old = Anonymizer::with_config(vec![EntityType::Email, EntityType::Phone], old_config.clone()).unwrap();
+        let texts = ["Mail jo@x.io or call 555-123-4567", "Again jo@x.io, cc al@x.io"];
+        let mut mapping = HashMap::new();
+        let mut archive = Vec::new();
+        for text in texts {
+            let result = old.anonymize(text).unwrap();
+            mapping.extend(result.mapping);
+            archive.push(result.anonymized_text);
+        }
+        // Each call placed jo@x.io under its own UUID
+        assert_eq!(mapping.len(), 4);
+
+        let new = anonymizer(PlaceholderFormat::Short);
+        let migration = new.migrate_placeholders(&mapping, &old_config).unwrap();
+        assert_eq!(migration.mapping().len(), 3);
+        for (text, archived) in texts.iter().zip(&archive) {
+            let rewritten = migration.rewrite(archived);
+            assert!(!rewritten.contains(&archived[5..15]));
+            assert_eq!(new.deanonymize(&rewritten, migration.mapping()), *text);
+        }
+        let mut placeholders: Vec<&String> = migration.mapping().keys().collect();
+        placeholders.sort();
+        assert_eq!(placeholders, ["EMAIL_1", "EMAIL_2", "PHONE_1"]);
+
+        let mut rewritten = Vec::new();
+        migration.rewrite_stream(archive[0].as_bytes(), &mut rewritten).unwrap();
+        assert_eq!(String::from_utf8(rewritten).unwrap(), migration.rewrite(&archive[0]));
+    }
+
+    #[test]
+    fn test_overrides_and_kept_placeholders() {
+        let old_config = AnonymizerConfig::builder()
+            .with_placeholder_format(PlaceholderFormat::Short)
+            .with_placeholder_override(PlaceholderOverride::new(
+                EntityType::Phone,
+                PlaceholderFormat::Custom("<tel {counter}>".to_string()),
+            ))
+            .build();
+        let mapping = HashMap::from([
+            ("EMAIL_2".to_string(), "b@x.io".to_string()),
+            ("EMAIL_10".to_string(), "c@x.io".to_string()),
+            ("<tel 3>".to_string(), "555-123-4567".to_string()),
+            // A replacement given with a custom value
+            ("ACME_CORP".to_string(), "Acme".to_string()),
+        ]);
+
+        let new = anonymizer(PlaceholderFormat::Custom("[{type_lower}:{counter}]".to_string()));
+        let migration = new.migrate_placeholders(&mapping, &old_config).unwrap();
+        assert_eq!(
+            migration.rewrite("EMAIL_10, EMAIL_2, <tel 3> and ACME_CORP"),
+            "[email:3], [email:2], [phone:1] and ACME_CORP"
+        );
+    }
+
+    #[test]
+    fn test_irreversible_migrations_are_rejected() {
+        let mapping = HashMap::from([
+            ("EMAIL_1".to_string(), "a@x.io".to_string()),
+            ("EMAIL_2".to_string(), "b@x.io".to_string()),
+        ]);
+        let short = AnonymizerConfig::builder().with_placeholder_format(PlaceholderFormat::Short).build();
+
+        let redacting = anonymizer(PlaceholderFormat::Custom("[REDACTED]".to_string()));
+        let err = redacting.migrate_placeholders(&mapping, &short).unwrap_err();
+        assert!(matches!(err, AnonymaskError::InvalidConfig(_)));
+
+        let untyped = AnonymizerConfig::builder()
+            .with_placeholder_format(PlaceholderFormat::Custom("EMAIL_{counter}".to_string()))
+            .build();
+        let err = anonymizer(PlaceholderFormat::Short).migrate_placeholders(&mapping, &untyped).unwrap_err();
+        assert!(err.to_string().contains("can't tell the entity type"), "{}", err);
+    }
+
+    #[test]
+    fn test_natural_order() {
+        let mut names = vec!["EMAIL_10", "EMAIL_2", "EMAIL_02", "PHONE_1", "EMAIL_1"];
+        names.sort_by(|a, b| natural_order(a, b));
+        assert_eq!(names, ["EMAIL_1", "EMAIL_2", "EMAIL_02", "EMAIL_10", "PHONE_1"]);
+    }
+}
@@ -0,0 +1,101 @@
+//! Named entity recognition backends.
+//!
+//! Person, organization and location names ([`EntityType::Person`],
+//! [`EntityType::Org`], [`EntityType::Location`]) can't be found by regex
+//! patterns. An [`NerBackend`] detects them, and
+//! [`Anonymizer::with_ner`](crate::Anonymizer::with_ner) merges its results
+//! with the regex pipeline.
+//!
+//! Built-in backends:
+//!
+//! - `NerModel`: a BERT-style ONNX model run on CPU via tract (feature `ner`)
+//! - `HttpNer`: a hosted token-classification endpoint (feature `ner-http`)
+//!
+//! Wrap a backend in [`ChunkedNer`] to split long documents into chunks
+//! that fit its window.
+//!
+//! Implement the trait to plug in any other model or service without
+//! linking a model runtime into anonymask.
+
+use crate::entity::{Entity, EntityType};
+use crate::error::AnonymaskError;
+use std::sync::Arc;
+
+#[cfg(any(feature = "ner", feature = "ner-http"))]
+mod bio;
+mod chunk;
+#[cfg(feature = "ner-http")]
+mod http;
+#[cfg(feature = "ner")]
+mod onnx;
+
+pub use chunk::{ChunkOptions, ChunkedNer};
+#[cfg(feature = "ner-http")]
+pub use http::HttpNer;
+#[cfg(feature = "ner")]
+pub use onnx::{NerModel, NerModelOptions};
+
+/// A named entity recognizer: text in, labeled spans out.
+///
+/// Implementations must be safe to share between threads; `detect` takes
+/// `&self`.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::ner::NerBackend;
+/// use anonymask_core::{AnonymaskError, Anonymizer, AnonymizerConfig, Entity, EntityType};
+///
+/// /// Stands in for a call to an in-house NER service
+/// struct KnownNames;
+///
+/// impl NerBackend for KnownNames {
+///     fn detect(&self, text: &str, entity_types: &[EntityType]) -> Result<Vec<Entity>, AnonymaskError> {
+///         if !entity_types.contains(&EntityType::Person) {
+///             return Ok(Vec::new());
+///         }
+///         Ok(text
+///             .match_indices("Ada Lovelace")
+///             .map(|(start, value)| Entity {
+///                 entity_type: EntityType::Person,
+///                 value: value.to_string(),
+///                 start,
+///                 end: start + value.len(),
+///                 confidence: 0.9,
+///             })
+///             .collect())
+///     }
+/// }
+///
+/// let anonymizer = Anonymizer::with_ner(
+///     vec![EntityType::Person, EntityType::Email],
+///     AnonymizerConfig::default(),
+///     KnownNames,
+/// ).unwrap();
+/// let result = anonymizer.anonymize("Ada Lovelace <ada@example.com>").unwrap();
+/// assert_eq!(result.entities.len(), 2);
+/// ```
+pub trait NerBackend: Send + Sync {
+    /// Detect entities of the given types in `text`.
+    ///
+    /// Entities carry their byte positions in `text` and the text between
+    /// them as value, sorted by position, with a confidence in
+    /// `0.0..=1.0` that is compared with `min_confidence`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::ModelError` if the model or service fails;
+    /// the anonymization call fails with it.
+    fn detect(&self, text: &str, entity_types: &[EntityType]) -> Result<Vec<Entity>, AnonymaskError>;
+}
+
+impl<B: NerBackend + ?Sized> NerBackend for Arc<B> {
+    fn detect(&self, text: &str, entity_types: &[EntityType]) -> Result<Vec<Entity>, AnonymaskError> {
+        (**self).detect(text, entity_types)
+    }
+}
+
+#[cfg(any(feature = "ner", feature = "ner-http"))]
+fn model_error(e: impl std::fmt::Display) -> AnonymaskError {
+    AnonymaskError::ModelError(e.to_string())
+}
@@ -0,0 +1,138 @@
+//! BIO decoding shared by the NER backends.
+
+use crate::entity::{Entity, EntityType};
+
+/// A classified token with its byte span in the input text.
+#[derive(Debug, Clone)]
+pub(super) struct TokenLabel {
+    pub label: String,
+    pub probability: f32,
+    pub start: usize,
+    pub end: usize,
+    /// Index of the word this sub-word token belongs to
+    pub word: Option<u32>,
+}
+
+/// Map a label such as `B-PER` to its BIO prefix and entity type.
+fn parse_label(label: &str) -> (Option<char>, Option<EntityType>) {
+    let (prefix, name) = match label.split_once('-') {
+        Some((prefix, name)) if prefix.len() == 1 => (prefix.chars().next(), name),
+        _ => (None, label),
+    };
+    let entity_type = match name.to_ascii_uppercase().as_str() {
+        "PER" | "PERSON" => Some(EntityType::Person),
+        "ORG" | "ORGANIZATION" => Some(EntityType::Org),
+        "LOC" | "LOCATION" | "GPE" => Some(EntityType::Location),
+        _ => None,
+    };
+    (prefix, entity_type)
+}
+
+/// Group labelled tokens into entity spans.
+///
+/// A span starts at a `B-` token or a change of type and is extended by
+/// `I-` tokens of the same type. Sub-word tokens always follow the label of
+/// their word's first token.
+pub(super) fn decode_bio(text: &str, tokens: &[TokenLabel]) -> Vec<Entity> {
+    let mut entities = Vec::new();
+    let mut current: Option<(EntityType, usize, usize, Vec<f32>)> = None;
+    let mut previous_word = None;
+
+    let mut flush = |current: &mut Option<(EntityType, usize, usize, Vec<f32>)>| {
+        if let Some((entity_type, start, end, probabilities)) = current.take() {
+            entities.push(Entity {
+                entity_type,
+                value: text[start..end].to_string(),
+                start,
+                end,
+                confidence: probabilities.iter().sum::<f32>() / probabilities.len() as f32,
+            });
+        }
+    };
+
+    for token in tokens {
+        let continues_word = token.word.is_some() && token.word == previous_word;
+        previous_word = token.word;
+
+        if continues_word {
+            if let Some((_, _, end, probabilities)) = current.as_mut() {
+                *end = token.end;
+                probabilities.push(token.probability);
+            }
+            continue;
+        }
+
+        let (prefix, entity_type) = parse_label(&token.label);
+        match (entity_type, current.as_mut()) {
+            (Some(entity_type), Some((current_type, _, end, probabilities)))
+                if prefix != Some('B') && *current_type == entity_type =>
+            {
+                *end = token.end;
+                probabilities.push(token.probability);
+            }
+            (Some(entity_type), _) => {
+                flush(&mut current);
+                current = Some((entity_type, token.start, token.end, vec![token.probability]));
+            }
+            (None, _) => flush(&mut current),
+        }
+    }
+    flush(&mut current);
+
+    entities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(label: &str, start: usize, end: usize, word: u32) -> TokenLabel {
+        TokenLabel {
+            label: label.to_string(),
+            probability: 0.9,
+            start,
+            end,
+            word: Some(word),
+        }
+    }
+
+    #[test]
+    fn test_decode_bio_groups_spans() {
+        let text = "Ada Lovelace met Babbage at Acme Corp in London";
+        let tokens = vec![
+            token("B-PER", 0, 3, 0),
+            token("I-PER", 4, 12, 1),
+            token("O", 13, 16, 2),
+            token("B-PER", 17, 21, 3),
+            token("O", 21, 24, 3), // sub-word follows its word
+            token("O", 25, 27, 4),
+            token("B-ORG", 28, 32, 5),
+            token("I-ORG", 33, 37, 6),
+            token("O", 38, 40, 7),
+            token("LOC", 41, 47, 8),
+        ];
+
+        let entities = decode_bio(text, &tokens);
+        let spans: Vec<(&EntityType, &str)> = entities
+            .iter()
+            .map(|e| (&e.entity_type, e.value.as_str()))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (&EntityType::Person, "Ada Lovelace"),
+                (&EntityType::Person, "Babbage"),
+                (&EntityType::Org, "Acme Corp"),
+                (&EntityType::Location, "London"),
+            ]
+        );
+        assert!((entities[0].confidence - 0.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_b_prefix_splits_adjacent_entities() {
+        let text = "Alice Bob";
+        let tokens = vec![token("B-PER", 0, 5, 0), token("B-PER", 6, 9, 1)];
+        assert_eq!(decode_bio(text, &tokens).len(), 2);
+    }
+}
@@ -0,0 +1,276 @@
+//! Chunking of long documents for NER backends.
+
+use super::NerBackend;
+use crate::entity::{Entity, EntityType};
+use crate::error::AnonymaskError;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Chunk sizes of a [`ChunkedNer`].
+///
+/// Sizes are in bytes. Pick a `chunk_size` whose text fits the model's
+/// window: English averages about four bytes per BERT token, so 1500 bytes
+/// stay within 512 tokens with room to spare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkOptions {
+    /// Longest chunk passed to the backend (default: 1500)
+    pub chunk_size: usize,
+    /// Text repeated at the start of the next chunk, so entities cut at
+    /// a chunk's end are seen whole (default: 200)
+    pub overlap: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 1500,
+            overlap: 200,
+        }
+    }
+}
+
+/// An [`NerBackend`] that runs another backend on chunks of long texts.
+///
+/// Models see a limited window, and services limit request sizes; past
+/// the limit, text is truncated or cut mid-sentence, and names at the cut
+/// are missed or split. `ChunkedNer` splits texts longer than
+/// `chunk_size` at paragraph breaks, else sentence ends, else whitespace,
+/// repeating `overlap` bytes of context between chunks. Entities are
+/// moved back to document positions, and the parts of one entity found in
+/// two chunks are merged.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::ner::{ChunkOptions, ChunkedNer, NerBackend};
+/// use anonymask_core::{AnonymaskError, Entity, EntityType};
+///
+/// /// Stands in for a model with a short window
+/// struct KnownNames;
+///
+/// impl NerBackend for KnownNames {
+///     fn detect(&self, text: &str, _: &[EntityType]) -> Result<Vec<Entity>, AnonymaskError> {
+///         assert!(text.len() <= 60);
+///         Ok(text
+///             .match_indices("Ada Lovelace")
+///             .map(|(start, value)| Entity {
+///                 entity_type: EntityType::Person,
+///                 value: value.to_string(),
+///                 start,
+///                 end: start + value.len(),
+///                 confidence: 0.9,
+///             })
+///             .collect())
+///     }
+/// }
+///
+/// let ner = ChunkedNer::new(KnownNames, ChunkOptions { chunk_size: 60, overlap: 20 }).unwrap();
+/// let text = "Notes. ".repeat(20) + "Ada Lovelace wrote them. " + &"More notes. ".repeat(20);
+/// let entities = ner.detect(&text, &[EntityType::Person]).unwrap();
+/// assert_eq!(entities.len(), 1);
+/// assert_eq!(&text[entities[0].start..entities[0].end], "Ada Lovelace");
+/// ```
+pub struct ChunkedNer<B> {
+    backend: B,
+    options: ChunkOptions,
+}
+
+impl<B: NerBackend> ChunkedNer<B> {
+    /// Wrap `backend`, passing it chunks of at most `options.chunk_size` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::InvalidConfig` if `chunk_size` is 0 or
+    /// `overlap` isn't less than half of it.
+    pub fn new(backend: B, options: ChunkOptions) -> Result<Self, AnonymaskError> {
+        if options.chunk_size == 0 || options.overlap * 2 >= options.chunk_size {
+            return Err(AnonymaskError::InvalidConfig(
+                "NER chunk_size must be positive and more than twice the overlap".to_string(),
+            ));
+        }
+        Ok(Self { backend, options })
+    }
+}
+
+impl<B: NerBackend> NerBackend for ChunkedNer<B> {
+    fn detect(&self, text: &str, entity_types: &[EntityType]) -> Result<Vec<Entity>, AnonymaskError> {
+        let mut entities = Vec::new();
+        for chunk in chunks(text, &self.options) {
+            for mut entity in self.backend.detect(&text[chunk.clone()], entity_types)? {
+                entity.start += chunk.start;
+                entity.end += chunk.start;
+                entities.push(entity);
+            }
+        }
+        Ok(merge(text, entities))
+    }
+}
+
+/// Split `text` into overlapping chunks at the most natural boundaries.
+fn chunks(text: &str, options: &ChunkOptions) -> Vec<Range<usize>> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    loop {
+        if text.len() - start <= options.chunk_size {
+            chunks.push(start..text.len());
+            return chunks;
+        }
+        let end = cut(text, start, start + options.chunk_size);
+        chunks.push(start..end);
+        // Resume at the start of the word `overlap` bytes back
+        let back = floor_boundary(text, end.saturating_sub(options.overlap).max(start));
+        let resume = text[start..back]
+            .rfind(char::is_whitespace)
+            .map_or(back, |space| after_char(text, start + space));
+        start = if resume > start { resume } else { end };
+    }
+}
+
+/// The end of a chunk starting at `start` and ending at or before `limit`:
+/// after the last paragraph break, sentence end or whitespace in its
+/// second half, or at `limit` if there is none.
+fn cut(text: &str, start: usize, limit: usize) -> usize {
+    let limit = floor_boundary(text, limit);
+    let window = &text[start..limit];
+    let half = floor_boundary(window, window.len() / 2);
+    let candidates = [
+        window.rfind("\n\n").map(|i| i + 2),
+        window
+            .rmatch_indices(['.', '!', '?', '\n'])
+            .find(|(i, _)| window[i + 1..].starts_with(char::is_whitespace))
+            .map(|(i, _)| after_char(window, i + 1)),
+        window.rfind(char::is_whitespace).map(|i| after_char(window, i)),
+    ];
+    match candidates.into_iter().flatten().find(|&end| end > half) {
+        Some(end) => start + end,
+        // A chunk size below one character still has to advance
+        None if window.is_empty() => after_char(text, start),
+        None => limit,
+    }
+}
+
+/// The index after the character at `index`.
+fn after_char(text: &str, index: usize) -> usize {
+    index + text[index..].chars().next().map_or(0, char::len_utf8)
+}
+
+/// The closest char boundary at or before `index`.
+fn floor_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Sort entities and merge overlapping ones of the same type, as found in
+/// the overlap of two chunks.
+fn merge(text: &str, mut entities: Vec<Entity>) -> Vec<Entity> {
+    entities.sort_by_key(|entity| (entity.start, entity.end));
+    let mut merged: Vec<Entity> = Vec::with_capacity(entities.len());
+    for entity in entities {
+        // Merged entities of one type are disjoint, so only the last can overlap
+        let same = merged
+            .iter_mut()
+            .rev()
+            .find(|earlier| earlier.entity_type == entity.entity_type)
+            .filter(|earlier| earlier.end > entity.start);
+        match same {
+            Some(earlier) => {
+                earlier.end = earlier.end.max(entity.end);
+                earlier.value = text[earlier.start..earlier.end].to_string();
+                earlier.confidence = earlier.confidence.max(entity.confidence);
+            }
+            None => merged.push(entity),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::sync::Mutex;
+
+    /// Labels runs of capitalized words as persons, recording its inputs
+    #[derive(Default)]
+    struct Capitalized {
+        inputs: Mutex<Vec<String>>,
+    }
+
+    impl NerBackend for Capitalized {
+        fn detect(&self, text: &str, _: &[EntityType]) -> Result<Vec<Entity>, AnonymaskError> {
+            self.inputs.lock().unwrap().push(text.to_string());
+            let mut entities: Vec<Entity> = Vec::new();
+            for (start, word) in text.split(' ').scan(0, |offset, word| {
+                let start = *offset;
+                *offset += word.len() + 1;
+                Some((start, word))
+            }) {
+                if !word.starts_with(char::is_uppercase) {
+                    continue;
+                }
+                match entities.last_mut() {
+                    Some(last) if last.end + 1 == start => last.end = start + word.len(),
+                    _ => entities.push(Entity {
+                        entity_type: EntityType::Person,
+                        value: String::new(),
+                        start,
+                        end: start + word.len(),
+                        confidence: 0.8,
+                    }),
+                }
+            }
+            for entity in &mut entities {
+                entity.value = text[entity.start..entity.end].to_string();
+            }
+            Ok(entities)
+        }
+    }
+
+    #[test]
+    fn test_chunks_respect_limits_and_boundaries() {
+        let text = "first sentence here. second one follows.\n\nnew paragraph with words";
+        let options = ChunkOptions { chunk_size: 30, overlap: 8 };
+        let ranges = chunks(text, &options);
+
+        assert_eq!(&text[ranges[0].clone()], "first sentence here. ");
+        assert!(ranges.iter().all(|chunk| chunk.len() <= 30));
+        assert!(ranges.windows(2).all(|pair| pair[1].start < pair[0].end));
+        assert_eq!(ranges.last().unwrap().end, text.len());
+        assert_eq!(chunks(text, &ChunkOptions::default()), vec![0..text.len()]);
+        assert!(ChunkedNer::new(Capitalized::default(), ChunkOptions { chunk_size: 10, overlap: 5 }).is_err());
+    }
+
+    proptest! {
+        /// Chunks cover the text in order, within the size limit
+        #[test]
+        fn prop_chunks_cover_text(text in "\\PC{0,300}", chunk_size in 4usize..80, overlap in 0usize..40) {
+            let options = ChunkOptions { chunk_size, overlap: overlap.min((chunk_size - 1) / 2) };
+            let ranges = chunks(&text, &options);
+            prop_assert_eq!(ranges[0].start, 0);
+            prop_assert_eq!(ranges.last().unwrap().end, text.len());
+            for pair in ranges.windows(2) {
+                prop_assert!(pair[0].start < pair[1].start && pair[1].start <= pair[0].end);
+            }
+            prop_assert!(ranges.iter().all(|chunk| chunk.len() <= chunk_size));
+        }
+    }
+
+    #[test]
+    fn test_entities_across_chunk_boundaries() {
+        let text = "met with them again and then Grace Brewster Murray Hopper joined ünd äll of us";
+        let ner = ChunkedNer::new(Capitalized::default(), ChunkOptions { chunk_size: 24, overlap: 8 }).unwrap();
+        let entities = ner.detect(text, &[EntityType::Person]).unwrap();
+
+        let inputs = ner.backend.inputs.lock().unwrap();
+        assert!(inputs.len() > 1);
+        assert!(inputs.iter().all(|input| input.len() <= 24));
+        assert!(!inputs.iter().any(|input| input.contains("Grace Brewster Murray Hopper")));
+
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].value, "Grace Brewster Murray Hopper");
+        assert_eq!(&text[entities[0].start..entities[0].end], entities[0].value);
+    }
+}
@@ -0,0 +1,290 @@
+//! ONNX token-classification models run on CPU via tract.
+
+use super::bio::{decode_bio, TokenLabel};
+use super::{model_error, NerBackend};
+use crate::entity::{Entity, EntityType};
+use crate::error::AnonymaskError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokenizers::{Encoding, Tokenizer, TruncationParams};
+use tract_onnx::prelude::*;
+
+type Plan = TypedRunnableModel<TypedModel>;
+
+/// A loaded NER model and its tokenizer.
+///
+/// Texts longer than the model's window are split into consecutive
+/// windows of `max_length` tokens, each scored separately.
+///
+/// # Examples
+///
+/// ```no_run
+/// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType};
+/// use anonymask_core::ner::NerModel;
+///
+/// let labels = ["O", "B-MISC", "I-MISC", "B-PER", "I-PER", "B-ORG", "I-ORG", "B-LOC", "I-LOC"]
+///     .iter()
+///     .map(|l| l.to_string())
+///     .collect();
+/// let model = NerModel::load("model.onnx", "tokenizer.json", labels, 128).unwrap();
+///
+/// let anonymizer = Anonymizer::with_ner(
+///     vec![EntityType::Person, EntityType::Email],
+///     AnonymizerConfig::default(),
+///     model,
+/// ).unwrap();
+/// let result = anonymizer.anonymize("Ada Lovelace <ada@example.com>").unwrap();
+/// ```
+///
+/// # Thread Safety
+///
+/// This type is `Send + Sync`; share it between anonymizers with an `Arc`.
+pub struct NerModel {
+    plan: Plan,
+    tokenizer: Tokenizer,
+    labels: Vec<String>,
+    max_length: usize,
+    batch_size: usize,
+    input_count: usize,
+}
+
+/// Inference settings of an [`NerModel`].
+///
+/// Inference runs on CPU via tract, which has no GPU execution providers;
+/// to run a model on CUDA or CoreML, e.g. with onnxruntime, wrap that
+/// runtime in an [`NerBackend`] instead.
+///
+/// # Examples
+///
+/// ```no_run
+/// use anonymask_core::ner::{NerModel, NerModelOptions};
+///
+/// // Long logs: score up to 16 windows of 512 tokens per model run
+/// let options = NerModelOptions { max_length: 512, batch_size: 16 };
+/// let labels = ["O", "B-PER", "I-PER"].iter().map(|l| l.to_string()).collect();
+/// let model = NerModel::load_with_options("model.onnx", "tokenizer.json", labels, &options).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NerModelOptions {
+    /// Tokens per inference window, typically 128 or 512 (default: 128)
+    pub max_length: usize,
+    /// Windows scored per model run (default: 8)
+    ///
+    /// Texts longer than one window are split into several; larger
+    /// batches trade memory for throughput on long texts.
+    pub batch_size: usize,
+}
+
+impl Default for NerModelOptions {
+    fn default() -> Self {
+        Self {
+            max_length: 128,
+            batch_size: 8,
+        }
+    }
+}
+
+impl NerModel {
+    /// Load an ONNX model and its tokenizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_path` - Path to the `.onnx` token-classification model
+    /// * `tokenizer_path` - Path to the matching `tokenizer.json`
+    /// * `labels` - Output labels in model order, in BIO notation
+    ///   (e.g. `["O", "B-PER", "I-PER", "B-ORG", "I-ORG", "B-LOC", "I-LOC"]`)
+    /// * `max_length` - Tokens per inference window (typically 128 or 512)
+    ///
+    /// `PER`/`PERSON`, `ORG`/`ORGANIZATION` and `LOC`/`LOCATION`/`GPE`
+    /// labels are recognized; all others are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::ModelError` if the model or tokenizer can't
+    /// be loaded, or the model doesn't accept `[batch, max_length]` inputs.
+    pub fn load(
+        model_path: impl AsRef<Path>,
+        tokenizer_path: impl AsRef<Path>,
+        labels: Vec<String>,
+        max_length: usize,
+    ) -> Result<Self, AnonymaskError> {
+        let options = NerModelOptions {
+            max_length,
+            ..NerModelOptions::default()
+        };
+        Self::load_with_options(model_path, tokenizer_path, labels, &options)
+    }
+
+    /// Load an ONNX model and its tokenizer with custom inference settings.
+    ///
+    /// See [`load`](Self::load) for the arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::InvalidConfig` if `max_length` or
+    /// `batch_size` is 0, and `AnonymaskError::ModelError` as [`load`](Self::load).
+    pub fn load_with_options(
+        model_path: impl AsRef<Path>,
+        tokenizer_path: impl AsRef<Path>,
+        labels: Vec<String>,
+        options: &NerModelOptions,
+    ) -> Result<Self, AnonymaskError> {
+        let NerModelOptions { max_length, batch_size } = *options;
+        if max_length == 0 || batch_size == 0 {
+            return Err(AnonymaskError::InvalidConfig(
+                "NER max_length and batch_size must be at least 1".to_string(),
+            ));
+        }
+
+        let mut tokenizer = Tokenizer::from_file(tokenizer_path.as_ref()).map_err(model_error)?;
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length,
+                ..TruncationParams::default()
+            }))
+            .map_err(model_error)?;
+        tokenizer.with_padding(None);
+
+        let mut model = tract_onnx::onnx()
+            .model_for_path(model_path.as_ref())
+            .map_err(model_error)?;
+        // input_ids, attention_mask and (for BERT) token_type_ids; the batch
+        // dimension is left open so short texts don't pay for full batches
+        let input_count = model.inputs.len().min(3);
+        let batch = model.symbol_table.sym("batch");
+        for input in 0..input_count {
+            model
+                .set_input_fact(input, i64::fact([batch.to_dim(), max_length.to_dim()]).into())
+                .map_err(model_error)?;
+        }
+        let plan = model
+            .into_optimized()
+            .and_then(|model| model.into_runnable())
+            .map_err(model_error)?;
+
+        Ok(Self {
+            plan,
+            tokenizer,
+            labels,
+            max_length,
+            batch_size,
+            input_count,
+        })
+    }
+
+    /// Detect entities of the given types in `text`.
+    ///
+    /// Returned entities are sorted by position and carry the mean
+    /// probability of their tokens as confidence.
+    pub fn detect(
+        &self,
+        text: &str,
+        entity_types: &[EntityType],
+    ) -> Result<Vec<Entity>, AnonymaskError> {
+        let encoding = self.tokenizer.encode(text, true).map_err(model_error)?;
+        let windows: Vec<&Encoding> = std::iter::once(&encoding).chain(encoding.get_overflowing()).collect();
+
+        let mut tokens = Vec::new();
+        for batch in windows.chunks(self.batch_size) {
+            tokens.extend(self.classify(batch)?);
+        }
+
+        Ok(decode_bio(text, &tokens)
+            .into_iter()
+            .filter(|entity| entity_types.contains(&entity.entity_type))
+            .collect())
+    }
+
+    /// Run the model on a batch of windows and return their labelled
+    /// tokens, in order.
+    fn classify(&self, windows: &[&Encoding]) -> Result<Vec<TokenLabel>, AnonymaskError> {
+        let pad = |values: fn(&Encoding) -> &[u32]| -> Result<TValue, AnonymaskError> {
+            let mut padded = Vec::with_capacity(windows.len() * self.max_length);
+            for window in windows {
+                let row = padded.len();
+                padded.extend(values(window).iter().map(|&v| v as i64));
+                padded.resize(row + self.max_length, 0);
+            }
+            tract_ndarray::Array2::from_shape_vec((windows.len(), self.max_length), padded)
+                .map(|array| TValue::from(Tensor::from(array)))
+                .map_err(model_error)
+        };
+
+        let inputs: [fn(&Encoding) -> &[u32]; 3] = [
+            Encoding::get_ids,
+            Encoding::get_attention_mask,
+            Encoding::get_type_ids,
+        ];
+        let inputs: TVec<TValue> = inputs[..self.input_count]
+            .iter()
+            .map(|&values| pad(values))
+            .collect::<Result<_, _>>()?;
+
+        let outputs = self.plan.run(inputs).map_err(model_error)?;
+        let logits = outputs[0].to_array_view::<f32>().map_err(model_error)?;
+
+        let mut tokens = Vec::new();
+        for (row, encoding) in windows.iter().enumerate() {
+            for index in 0..encoding.get_ids().len() {
+                if encoding.get_special_tokens_mask()[index] == 1 {
+                    continue;
+                }
+                let scores: Vec<f32> = (0..self.labels.len())
+                    .map(|label| logits[[row, index, label]])
+                    .collect();
+                let (label, probability) = softmax_argmax(&scores);
+                let (start, end) = encoding.get_offsets()[index];
+                tokens.push(TokenLabel {
+                    label: self.labels[label].clone(),
+                    probability,
+                    start,
+                    end,
+                    word: encoding.get_word_ids()[index],
+                });
+            }
+        }
+        Ok(tokens)
+    }
+}
+
+impl NerBackend for NerModel {
+    fn detect(&self, text: &str, entity_types: &[EntityType]) -> Result<Vec<Entity>, AnonymaskError> {
+        NerModel::detect(self, text, entity_types)
+    }
+}
+
+fn softmax_argmax(scores: &[f32]) -> (usize, f32) {
+    let max = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let sum: f32 = scores.iter().map(|s| (s - max).exp()).sum();
+    let (index, best) = scores
+        .iter()
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |acc, (i, &s)| if s > acc.1 { (i, s) } else { acc });
+    (index, (best - max).exp() / sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_softmax_argmax() {
+        let (index, probability) = softmax_argmax(&[0.0, 2.0, 0.0]);
+        assert_eq!(index, 1);
+        assert!(probability > 0.7 && probability < 0.8);
+    }
+
+    #[test]
+    fn test_options_defaults_and_validation() {
+        let options: NerModelOptions = serde_json::from_str(r#"{"batch_size": 32}"#).unwrap();
+        assert_eq!(options, NerModelOptions { max_length: 128, batch_size: 32 });
+
+        let options = NerModelOptions { batch_size: 0, ..options };
+        let labels = vec!["O".to_string()];
+        assert!(matches!(
+            NerModel::load_with_options("missing.onnx", "missing.json", labels, &options),
+            Err(AnonymaskError::InvalidConfig(_))
+        ));
+    }
+}
@@ -0,0 +1,181 @@
+//! Offset mapping between original and anonymized text.
+//!
+//! Pipelines that compute spans on one version of a text (NER labels,
+//! highlights, citations from a model reading the anonymized text) need
+//! them on the other. [`OffsetMap`] translates byte offsets in both
+//! directions: outside entities, text is shifted by the length changes of
+//! the replacements before it; a span reaching into an entity or
+//! placeholder is widened to cover all of it, as only whole values have a
+//! counterpart on the other side.
+
+use crate::entity::{AnonymizationResult, Entity, OutputSpan};
+use std::ops::Range;
+
+/// Translates byte offsets between an original text and its anonymized
+/// version.
+///
+/// Built from a result with [`AnonymizationResult::offset_map`], or from
+/// the entities and output spans of one document of a
+/// [`BatchResult`](crate::BatchResult) with [`OffsetMap::new`].
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType, PlaceholderFormat};
+///
+/// let config = AnonymizerConfig::builder().with_placeholder_format(PlaceholderFormat::Short).build();
+/// let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+/// let text = "Mail jo@x.io about Friday";
+/// let result = anonymizer.anonymize(text).unwrap();
+/// assert_eq!(result.anonymized_text, "Mail EMAIL_1 about Friday");
+///
+/// let map = result.offset_map();
+/// let friday = map.to_anonymized(19..25);
+/// assert_eq!(&result.anonymized_text[friday.clone()], "Friday");
+/// assert_eq!(&text[map.to_original(friday)], "Friday");
+///
+/// // Spans touching a placeholder cover the whole original value
+/// assert_eq!(&text[map.to_original(7..25)], "jo@x.io about Friday");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OffsetMap {
+    /// Span of each replaced entity in the original and the anonymized
+    /// text, in order
+    replaced: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl OffsetMap {
+    /// Map the `entities` of a text to the `output_spans` of their
+    /// placeholders.
+    ///
+    /// Both must be in the same order, one span per entity, as in
+    /// [`AnonymizationResult`]. Extra items on either side are ignored.
+    pub fn new(entities: &[Entity], output_spans: &[OutputSpan]) -> Self {
+        Self {
+            replaced: entities
+                .iter()
+                .zip(output_spans)
+                .map(|(entity, span)| (entity.start..entity.end, span.start..span.end))
+                .collect(),
+        }
+    }
+
+    /// The span of anonymized text corresponding to `span` of the original.
+    ///
+    /// A span starting or ending inside an entity is widened to its whole
+    /// placeholder.
+    pub fn to_anonymized(&self, span: Range<usize>) -> Range<usize> {
+        let start = self.project(span.start, false, |(original, _)| original, |(_, anonymized)| anonymized);
+        let end = self.project(span.end, true, |(original, _)| original, |(_, anonymized)| anonymized);
+        start..end.max(start)
+    }
+
+    /// The span of original text corresponding to `span` of the anonymized
+    /// text.
+    ///
+    /// A span starting or ending inside a placeholder is widened to the
+    /// whole original value.
+    pub fn to_original(&self, span: Range<usize>) -> Range<usize> {
+        let start = self.project(span.start, false, |(_, anonymized)| anonymized, |(original, _)| original);
+        let end = self.project(span.end, true, |(_, anonymized)| anonymized, |(original, _)| original);
+        start..end.max(start)
+    }
+
+    /// Translate one offset from the `from` side to the `to` side, rounding
+    /// offsets inside a replaced span to its start or, for `end`, its end.
+    fn project(
+        &self,
+        offset: usize,
+        end: bool,
+        from: impl Fn(&(Range<usize>, Range<usize>)) -> &Range<usize>,
+        to: impl Fn(&(Range<usize>, Range<usize>)) -> &Range<usize>,
+    ) -> usize {
+        // Last replaced span starting before the offset
+        let i = self.replaced.partition_point(|pair| from(pair).start < offset);
+        let Some(pair) = i.checked_sub(1).map(|i| &self.replaced[i]) else {
+            return offset;
+        };
+        let (source, target) = (from(pair), to(pair));
+        if offset >= source.end {
+            offset - source.end + target.end
+        } else if end {
+            target.end
+        } else {
+            target.start
+        }
+    }
+}
+
+impl AnonymizationResult {
+    /// Offset mapping between the original text and `anonymized_text`.
+    ///
+    /// See [`OffsetMap`].
+    pub fn offset_map(&self) -> OffsetMap {
+        OffsetMap::new(&self.entities, &self.output_spans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EntityType;
+
+    fn map(pairs: &[(Range<usize>, Range<usize>)]) -> OffsetMap {
+        let entities: Vec<_> = pairs
+            .iter()
+            .map(|(original, _)| Entity {
+                entity_type: EntityType::Email,
+                value: String::new(),
+                start: original.start,
+                end: original.end,
+                confidence: 1.0,
+            })
+            .collect();
+        let spans: Vec<_> = pairs
+            .iter()
+            .map(|(_, anonymized)| OutputSpan {
+                placeholder: String::new(),
+                start: anonymized.start,
+                end: anonymized.end,
+            })
+            .collect();
+        OffsetMap::new(&entities, &spans)
+    }
+
+    #[test]
+    fn test_projection_both_ways() {
+        // "ab[longvalue]cd[xy]ef" -> "ab[P1]cd[PLACEHOLDER2]ef"
+        let map = map(&[(2..11, 2..4), (13..15, 6..18)]);
+
+        assert_eq!(map.to_anonymized(0..2), 0..2);
+        assert_eq!(map.to_anonymized(11..13), 4..6);
+        assert_eq!(map.to_anonymized(15..17), 18..20);
+        assert_eq!(map.to_anonymized(2..11), 2..4);
+        assert_eq!(map.to_anonymized(5..6), 2..4);
+        assert_eq!(map.to_anonymized(1..14), 1..18);
+        assert_eq!(map.to_anonymized(11..11), 4..4);
+
+        assert_eq!(map.to_original(4..6), 11..13);
+        assert_eq!(map.to_original(18..20), 15..17);
+        assert_eq!(map.to_original(3..3), 2..11);
+        assert_eq!(map.to_original(7..19), 13..16);
+
+        assert_eq!(OffsetMap::default().to_original(3..5), 3..5);
+    }
+
+    #[test]
+    fn test_offset_map_of_result() {
+        let anonymizer = crate::Anonymizer::new(vec![EntityType::Email, EntityType::Phone]).unwrap();
+        let text = "a@b.io or 555-123-4567, then x@y.io.";
+        let result = anonymizer.anonymize(text).unwrap();
+        let map = result.offset_map();
+
+        for entity in &result.entities {
+            let span = map.to_anonymized(entity.start..entity.end);
+            assert!(result.mapping.contains_key(&result.anonymized_text[span.clone()]));
+            assert_eq!(map.to_original(span), entity.start..entity.end);
+        }
+        let then = map.to_anonymized(24..28);
+        assert_eq!(&result.anonymized_text[then], "then");
+    }
+}
@@ -0,0 +1,158 @@
+//! Curated library of optional named patterns.
+//!
+//! Patterns for identifiers that aren't detected by default (cloud
+//! credentials, vehicle numbers, bank account numbers, ...) can be enabled by
+//! name with [`AnonymizerConfigBuilder::with_named_pattern`](crate::config::AnonymizerConfigBuilder::with_named_pattern).
+//! Matches are reported as [`EntityType::Custom`](crate::entity::EntityType::Custom)
+//! entities named after the pattern, e.g. `Custom("aws_arn")`.
+//!
+//! Names of built-in entity types (e.g. `"iban"`, `"github_token"`) are
+//! accepted as well and enable that type's regular patterns.
+
+use crate::validation::{self, Validator};
+
+/// A pattern from the library.
+#[derive(Debug, Clone, Copy)]
+pub struct NamedPattern {
+    name: &'static str,
+    description: &'static str,
+    pattern: &'static str,
+    validator: Option<Validator>,
+    context: &'static [&'static str],
+    requires_context: bool,
+}
+
+impl NamedPattern {
+    /// Name used to enable the pattern, also the custom entity type name.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Short human-readable description.
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    /// The regular expression.
+    pub fn pattern(&self) -> &'static str {
+        self.pattern
+    }
+
+    pub(crate) fn validator(&self) -> Option<Validator> {
+        self.validator
+    }
+
+    /// Keywords that raise confidence when found shortly before a match.
+    pub fn context(&self) -> &'static [&'static str] {
+        self.context
+    }
+
+    /// Whether matches are only reported next to a context keyword.
+    ///
+    /// Set for patterns too generic to be useful on their own, such as
+    /// bare digit runs.
+    pub fn requires_context(&self) -> bool {
+        self.requires_context
+    }
+}
+
+const LIBRARY: &[NamedPattern] = &[
+    NamedPattern {
+        name: "aws_arn",
+        description: "AWS resource names (arn:aws:iam::123456789012:user/alice)",
+        pattern: r"\barn:aws[a-z-]*:[a-z0-9-]+:[a-z0-9-]*:(?:[0-9]{12})?:[^\s'\x22<>,;]+",
+        validator: None,
+        context: &["arn"],
+        requires_context: false,
+    },
+    NamedPattern {
+        name: "mac_address",
+        description: "MAC addresses (00:1A:2B:3C:4D:5E)",
+        pattern: r"\b[0-9A-Fa-f]{2}(?:[:-][0-9A-Fa-f]{2}){5}\b",
+        validator: None,
+        context: &["mac", "hwaddr", "ether"],
+        requires_context: false,
+    },
+    NamedPattern {
+        name: "uk_nino",
+        description: "UK National Insurance numbers (AB 12 34 56 C)",
+        pattern: r"\b[A-CEGHJ-PR-TW-Z][A-CEGHJ-NPR-TW-Z] ?[0-9]{2} ?[0-9]{2} ?[0-9]{2} ?[A-D]\b",
+        validator: None,
+        context: &["nino", "national insurance", "ni number"],
+        requires_context: false,
+    },
+    NamedPattern {
+        name: "us_bank_account",
+        description: "US bank account numbers, only next to a keyword such as \"account\"",
+        pattern: r"\b[0-9]{8,17}\b",
+        validator: None,
+        context: &["account", "acct", "checking", "savings"],
+        requires_context: true,
+    },
+    NamedPattern {
+        name: "us_routing_number",
+        description: "US ABA routing numbers, checksum validated (021000021)",
+        pattern: r"\b[0-9]{9}\b",
+        validator: Some(validation::aba_routing),
+        context: &["routing", "aba", "rtn"],
+        requires_context: false,
+    },
+    NamedPattern {
+        name: "vin",
+        description: "Vehicle identification numbers, check digit validated (1M8GDM9AXKP042788)",
+        pattern: r"\b[A-HJ-NPR-Z0-9]{17}\b",
+        validator: Some(validation::vin),
+        context: &["vin", "vehicle", "chassis"],
+        requires_context: false,
+    },
+];
+
+/// Look up a pattern by name (case-insensitive).
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::patterns;
+///
+/// let arn = patterns::named("aws_arn").unwrap();
+/// assert!(arn.description().contains("AWS"));
+/// assert!(patterns::named("no_such_pattern").is_none());
+/// ```
+pub fn named(name: &str) -> Option<&'static NamedPattern> {
+    LIBRARY
+        .iter()
+        .find(|pattern| pattern.name.eq_ignore_ascii_case(name))
+}
+
+/// All patterns in the library, sorted by name.
+pub fn all() -> &'static [NamedPattern] {
+    LIBRARY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn test_library_compiles_and_is_sorted() {
+        for pattern in all() {
+            Regex::new(pattern.pattern()).unwrap();
+        }
+        assert!(all().windows(2).all(|w| w[0].name() < w[1].name()));
+    }
+
+    #[test]
+    fn test_examples_match() {
+        let cases = [
+            ("aws_arn", "arn:aws:iam::123456789012:user/alice"),
+            ("mac_address", "00:1A:2B:3C:4D:5E"),
+            ("uk_nino", "AB 12 34 56 C"),
+            ("vin", "1M8GDM9AXKP042788"),
+        ];
+        for (name, example) in cases {
+            let regex = Regex::new(named(name).unwrap().pattern()).unwrap();
+            assert_eq!(regex.find(example).map(|m| m.as_str()), Some(example), "{}", name);
+        }
+    }
+}
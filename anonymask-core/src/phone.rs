@@ -0,0 +1,162 @@
+//! International phone numbers (feature `phone-intl`).
+//!
+//! The locale packs match the national formats of a few countries. This
+//! scanner finds numbers of every country written in international format
+//! (`+44 20 7946 0958`, `0044 20 7946 0958`), and national-format numbers
+//! of one default region. Each candidate is parsed and validated against
+//! libphonenumber's metadata, so only numbers that can exist are reported.
+
+use crate::config::ConfidenceModel;
+use crate::entity::{Entity, EntityType};
+use crate::error::AnonymaskError;
+use phonenumber::country::Id;
+use regex::Regex;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+/// Runs of digits and the separators written inside phone numbers.
+const CANDIDATE: &str = r"(?:\+|\(|\b)[0-9][0-9 \t\u{a0}().\-/]*[0-9]\)?";
+
+/// Digits in the shortest and longest numbers tried.
+const MIN_DIGITS: usize = 6;
+const MAX_DIGITS: usize = 17;
+
+/// Finds and validates phone numbers of any country.
+pub(crate) struct PhoneScanner {
+    /// Region of numbers without a country code, if national numbers are
+    /// looked for
+    region: Option<Id>,
+}
+
+impl PhoneScanner {
+    /// A scanner for international numbers, and national numbers of
+    /// `region` (an ISO 3166 alpha-2 code such as `"GB"`) if given.
+    ///
+    /// # Errors
+    ///
+    /// * `AnonymaskError::InvalidConfig` - If `region` isn't a known region code
+    pub(crate) fn new(region: Option<&str>) -> Result<Self, AnonymaskError> {
+        let region = region
+            .map(|code| {
+                code.trim().to_ascii_uppercase().parse::<Id>().map_err(|_| {
+                    AnonymaskError::InvalidConfig(format!("phone_region: unknown region code '{}'", code))
+                })
+            })
+            .transpose()?;
+        Ok(Self { region })
+    }
+
+    /// Valid numbers in `text`, scored like pattern matches passing their
+    /// validator.
+    ///
+    /// `context` gives the context keyword adjustment of a span.
+    pub(crate) fn find(
+        &self,
+        text: &str,
+        confidence: &ConfidenceModel,
+        context: impl Fn(usize, usize) -> f32,
+    ) -> Vec<Entity> {
+        static REGEX: OnceLock<Regex> = OnceLock::new();
+        let regex = REGEX.get_or_init(|| Regex::new(CANDIDATE).expect("phone candidate pattern is valid"));
+
+        let mut entities = Vec::new();
+        for run in regex.find_iter(text) {
+            let groups = groups(run.as_str(), run.start());
+            let mut first = 0;
+            // A run may hold several numbers, or digits around one
+            'starts: while first < groups.len() {
+                let start = groups[first].start;
+                let international = text[start..].starts_with('+') || text[start..].starts_with("00");
+                if international || self.region.is_some() {
+                    for last in (first..groups.len()).rev() {
+                        let span = start..groups[last].end;
+                        let digits = text[span.clone()].bytes().filter(u8::is_ascii_digit).count();
+                        if digits < MIN_DIGITS {
+                            break;
+                        }
+                        if digits <= MAX_DIGITS && self.is_valid(&text[span.clone()]) {
+                            let score = confidence.score_with_context(Some(true), context(span.start, span.end));
+                            if score >= confidence.min_confidence {
+                                entities.push(Entity {
+                                    entity_type: EntityType::Phone,
+                                    value: text[span.clone()].to_string(),
+                                    start: span.start,
+                                    end: span.end,
+                                    confidence: score,
+                                });
+                            }
+                            first = last + 1;
+                            continue 'starts;
+                        }
+                    }
+                }
+                first += 1;
+            }
+        }
+        entities
+    }
+
+    fn is_valid(&self, candidate: &str) -> bool {
+        // The 00 prefix is only understood with a region, so read it as +
+        let candidate = match candidate.strip_prefix("00") {
+            Some(rest) => format!("+{}", rest),
+            None => candidate.to_string(),
+        };
+        phonenumber::parse(self.region, &candidate).is_ok_and(|number| phonenumber::is_valid(&number))
+    }
+}
+
+/// The groups of a run separated by whitespace, `.`, `-` or `/`, as byte
+/// ranges offset by `offset`. Parentheses stay with their group.
+fn groups(run: &str, offset: usize) -> Vec<Range<usize>> {
+    let mut groups: Vec<Range<usize>> = Vec::new();
+    let mut current: Option<usize> = None;
+    for (i, c) in run.char_indices() {
+        let separator = c.is_whitespace() || matches!(c, '.' | '-' | '/');
+        match (separator, current) {
+            (true, Some(start)) => {
+                groups.push(offset + start..offset + i);
+                current = None;
+            }
+            (false, None) => current = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(start) = current {
+        groups.push(offset + start..offset + run.len());
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(scanner: &PhoneScanner, text: &str) -> Vec<String> {
+        scanner
+            .find(text, &ConfidenceModel::default(), |_, _| 0.0)
+            .into_iter()
+            .map(|entity| {
+                assert_eq!(&text[entity.start..entity.end], entity.value);
+                entity.value
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_international_numbers() {
+        let scanner = PhoneScanner::new(None).unwrap();
+        let text = "London +44 20 7946 0958 x2, Paris 0033 1 42 68 53 00, \
+                    Tokyo +81-3-1234-5678. Not +44 20 1234 5, nor 020 7946 0958 or 2024-01-15.";
+        assert_eq!(find(&scanner, text), vec!["+44 20 7946 0958", "0033 1 42 68 53 00", "+81-3-1234-5678"]);
+    }
+
+    #[test]
+    fn test_default_region() {
+        let scanner = PhoneScanner::new(Some("gb")).unwrap();
+        let text = "Office 020 7946 0958 or mobile 07911 123456, order 12 34 ok, ref (020) 7946 0959 1234";
+        assert_eq!(find(&scanner, text), vec!["020 7946 0958", "07911 123456", "(020) 7946 0959"]);
+
+        assert!(matches!(PhoneScanner::new(Some("XX")), Err(AnonymaskError::InvalidConfig(_))));
+    }
+}
@@ -0,0 +1,345 @@
+use crate::config::PlaceholderFormat;
+use crate::error::AnonymaskError;
+use regex::Regex;
+
+const TYPE_PATTERN: &str = "[A-Z][A-Z0-9_]*";
+const TYPE_LOWER_PATTERN: &str = "[a-z][a-z0-9_]*";
+const COUNTER_PATTERN: &str = "[0-9]";
+const UUID_ALPHABET: &str = "[0-9a-f]";
+const UUID_LENGTH: usize = 32;
+/// Characters of UUIDs and hashed tokens in any encoding
+const ANY_TOKEN_ALPHABET: &str = "[0-9A-Za-z+/=-]";
+
+/// A template variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Var {
+    /// `{type}`, uppercase
+    Type,
+    /// `{type_lower}`
+    TypeLower,
+    /// `{uuid}`, random unless hashed tokens are configured
+    Uuid,
+    /// `{hash}`, always the hashed token
+    Hash,
+    /// `{counter}`, shared by all types
+    Counter,
+    /// `{index_by_type}`, counting each type separately
+    IndexByType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    /// A variable with its width modifier, if any
+    Var(Var, Option<usize>),
+}
+
+/// Values substituted for the variables of a [`Template`].
+pub(crate) struct Values<'a> {
+    pub type_name: &'a str,
+    pub uuid: &'a str,
+    pub hash: &'a str,
+    pub counter: usize,
+    pub index_by_type: usize,
+}
+
+/// Alphabet and length of the UUIDs and hashes a replacer generates.
+pub(crate) struct Token<'a> {
+    pub alphabet: &'a str,
+    pub length: usize,
+}
+
+/// Alphabet and length of random UUIDs.
+pub(crate) const UUID: Token<'static> = Token {
+    alphabet: UUID_ALPHABET,
+    length: UUID_LENGTH,
+};
+
+/// A placeholder format parsed into literal text and variables.
+#[derive(Debug, Clone)]
+pub(crate) struct Template {
+    segments: Vec<Segment>,
+    /// Whether placeholders must stand alone as words, for the built-in formats
+    word_bounded: bool,
+}
+
+impl Template {
+    /// Parse a placeholder format.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::InvalidConfig` for unknown variables,
+    /// unsupported modifiers and unmatched braces.
+    pub(crate) fn parse(format: &PlaceholderFormat) -> Result<Self, AnonymaskError> {
+        let (template, word_bounded) = match format {
+            PlaceholderFormat::Standard => ("{type}_{uuid}", true),
+            PlaceholderFormat::Short => ("{type}_{index_by_type}", true),
+            PlaceholderFormat::Custom(template) => (template.as_str(), false),
+        };
+        let invalid = |message: String| {
+            AnonymaskError::InvalidConfig(format!("invalid placeholder template '{}': {}", template, message))
+        };
+
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+        while let Some(i) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..i]);
+            let brace = &rest[i..i + 1];
+            rest = &rest[i + 1..];
+            if let Some(after) = rest.strip_prefix(brace) {
+                literal.push_str(brace);
+                rest = after;
+                continue;
+            }
+            if brace == "}" {
+                return Err(invalid("unmatched '}', write '}}' for a literal brace".to_string()));
+            }
+            let Some(end) = rest.find('}') else {
+                return Err(invalid("unmatched '{', write '{{' for a literal brace".to_string()));
+            };
+            let (name, modifier) = match rest[..end].split_once(':') {
+                Some((name, modifier)) => (name, Some(modifier)),
+                None => (&rest[..end], None),
+            };
+            rest = &rest[end + 1..];
+
+            let var = match name {
+                "type" => Var::Type,
+                "type_lower" => Var::TypeLower,
+                "uuid" => Var::Uuid,
+                "hash" => Var::Hash,
+                "counter" => Var::Counter,
+                "index_by_type" => Var::IndexByType,
+                _ => {
+                    return Err(invalid(format!(
+                        "unknown variable '{{{}}}', expected type, type_lower, uuid, hash, counter or index_by_type",
+                        name
+                    )))
+                }
+            };
+            let width = match (var, modifier) {
+                (_, None) => None,
+                // Zero-padded numbers, e.g. {counter:04}
+                (Var::Counter | Var::IndexByType, Some(modifier)) if modifier.len() > 1 && modifier.starts_with('0') => {
+                    modifier[1..].parse().ok().filter(|&width| width > 0)
+                }
+                // Leading characters, e.g. {uuid:8}
+                (Var::Uuid | Var::Hash, Some(modifier)) => modifier.parse().ok().filter(|&width| width > 0),
+                _ => None,
+            };
+            if modifier.is_some() && width.is_none() {
+                return Err(invalid(format!(
+                    "unsupported modifier '{}' of {{{}}}, expected e.g. {{counter:04}} or {{uuid:8}}",
+                    modifier.unwrap_or_default(),
+                    name
+                )));
+            }
+
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Var(var, width));
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments, word_bounded })
+    }
+
+    /// Whether the template contains `var`.
+    pub(crate) fn uses(&self, var: Var) -> bool {
+        self.vars().any(|(v, _)| v == var)
+    }
+
+    fn vars(&self) -> impl Iterator<Item = (Var, Option<usize>)> + '_ {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Var(var, width) => Some((*var, *width)),
+            Segment::Literal(_) => None,
+        })
+    }
+
+    /// Check that `{uuid}` and `{hash}` are at most as wide as the tokens
+    /// filling them; `hash` is `None` without hashed tokens.
+    pub(crate) fn check_widths(&self, uuid: &Token, hash: Option<&Token>) -> Result<(), AnonymaskError> {
+        for (var, width) in self.vars() {
+            let token = match var {
+                Var::Uuid => uuid,
+                Var::Hash => hash.ok_or_else(|| {
+                    AnonymaskError::InvalidConfig(
+                        "the placeholder template uses {hash}, which needs hashed_tokens".to_string(),
+                    )
+                })?,
+                _ => continue,
+            };
+            if width.is_some_and(|width| width > token.length) {
+                return Err(AnonymaskError::InvalidConfig(format!(
+                    "placeholder template asks for {} characters of {{{}}}, which has {}",
+                    width.unwrap_or_default(),
+                    if var == Var::Uuid { "uuid" } else { "hash" },
+                    token.length
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The placeholder for `values`.
+    pub(crate) fn render(&self, values: &Values) -> String {
+        let mut placeholder = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => placeholder.push_str(text),
+                Segment::Var(var, width) => {
+                    let number = |n: usize| format!("{:0width$}", n, width = width.unwrap_or(0));
+                    let prefix = |token: &str| token[..width.unwrap_or(token.len()).min(token.len())].to_string();
+                    placeholder.push_str(&match var {
+                        Var::Type => values.type_name.to_string(),
+                        Var::TypeLower => values.type_name.to_lowercase(),
+                        Var::Uuid => prefix(values.uuid),
+                        Var::Hash => prefix(values.hash),
+                        Var::Counter => number(values.counter),
+                        Var::IndexByType => number(values.index_by_type),
+                    });
+                }
+            }
+        }
+        placeholder
+    }
+
+    /// Build a regex matching any placeholder the template can produce.
+    ///
+    /// Used to find placeholder-looking tokens in text regardless of
+    /// whether they appear in a mapping.
+    pub(crate) fn pattern(&self, uuid: &Token, hash: Option<&Token>) -> Result<Regex, AnonymaskError> {
+        let mut pattern = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => pattern.push_str(&regex::escape(text)),
+                Segment::Var(var, width) => {
+                    let token = |token: &Token| format!("{}{{{}}}", token.alphabet, width.unwrap_or(token.length));
+                    pattern.push_str(&match var {
+                        Var::Type => TYPE_PATTERN.to_string(),
+                        Var::TypeLower => TYPE_LOWER_PATTERN.to_string(),
+                        Var::Uuid => token(uuid),
+                        Var::Hash => token(hash.unwrap_or(uuid)),
+                        Var::Counter | Var::IndexByType => format!("{}{{{},}}", COUNTER_PATTERN, width.unwrap_or(1)),
+                    });
+                }
+            }
+        }
+        if self.word_bounded {
+            pattern = format!(r"\b{}\b", pattern);
+        }
+
+        Regex::new(&pattern).map_err(|e| AnonymaskError::RegexError { pattern, source: e })
+    }
+
+    /// Build a regex matching exactly the placeholders the template can
+    /// produce, capturing the first `{type}` or `{type_lower}` as `type`.
+    ///
+    /// UUIDs and hashes may be in any encoding, for reading placeholders
+    /// generated under another configuration.
+    pub(crate) fn reader(&self) -> Result<Regex, AnonymaskError> {
+        let mut pattern = String::from("^");
+        let mut type_captured = false;
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => pattern.push_str(&regex::escape(text)),
+                Segment::Var(var, width) => {
+                    let type_name = |type_pattern: &str, captured: &mut bool| match std::mem::replace(captured, true) {
+                        false => format!("(?P<type>{})", type_pattern),
+                        true => type_pattern.to_string(),
+                    };
+                    pattern.push_str(&match var {
+                        Var::Type => type_name(TYPE_PATTERN, &mut type_captured),
+                        Var::TypeLower => type_name(TYPE_LOWER_PATTERN, &mut type_captured),
+                        Var::Uuid | Var::Hash => match width {
+                            Some(width) => format!("{}{{{}}}", ANY_TOKEN_ALPHABET, width),
+                            None => format!("{}+", ANY_TOKEN_ALPHABET),
+                        },
+                        Var::Counter | Var::IndexByType => format!("{}{{{},}}", COUNTER_PATTERN, width.unwrap_or(1)),
+                    });
+                }
+            }
+        }
+        pattern.push('$');
+
+        Regex::new(&pattern).map_err(|e| AnonymaskError::RegexError { pattern, source: e })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(template: &str) -> Result<Template, AnonymaskError> {
+        Template::parse(&PlaceholderFormat::Custom(template.to_string()))
+    }
+
+    fn matches(format: PlaceholderFormat, text: &str) -> Vec<String> {
+        Template::parse(&format)
+            .unwrap()
+            .pattern(&UUID, None)
+            .unwrap()
+            .find_iter(text)
+            .map(|m| m.as_str().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_standard_and_short_formats() {
+        assert_eq!(
+            matches(
+                PlaceholderFormat::Standard,
+                "EMAIL_0123456789abcdef0123456789abcdef and EMAIL_1"
+            ),
+            vec!["EMAIL_0123456789abcdef0123456789abcdef"]
+        );
+        assert_eq!(
+            matches(PlaceholderFormat::Short, "CREDIT_CARD_12, PHONE_3 and email_4"),
+            vec!["CREDIT_CARD_12", "PHONE_3"]
+        );
+    }
+
+    #[test]
+    fn test_custom_template_is_escaped() {
+        let format = PlaceholderFormat::Custom("[{type}:{counter}]".to_string());
+        assert_eq!(
+            matches(format, "Call [PHONE:1] or [EMAIL:x]"),
+            vec!["[PHONE:1]"]
+        );
+    }
+
+    #[test]
+    fn test_variables_and_modifiers() {
+        let template = template("{{{type_lower}-{index_by_type:03}}}#{counter:02}/{uuid:6}").unwrap();
+        let values = Values {
+            type_name: "CREDIT_CARD",
+            uuid: "0123456789abcdef0123456789abcdef",
+            hash: "",
+            counter: 7,
+            index_by_type: 12,
+        };
+        let placeholder = template.render(&values);
+        assert_eq!(placeholder, "{credit_card-012}#07/012345");
+        assert!(template.pattern(&UUID, None).unwrap().is_match(&placeholder));
+        assert!(template.uses(Var::IndexByType) && !template.uses(Var::Hash));
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        for invalid in ["{name}", "<{type", "{type}>}", "{type:04}", "{counter:4}", "{uuid:x}", "{uuid:0}"] {
+            assert!(
+                matches!(template(invalid), Err(AnonymaskError::InvalidConfig(_))),
+                "{}",
+                invalid
+            );
+        }
+        assert!(template("{uuid:33}").unwrap().check_widths(&UUID, None).is_err());
+        assert!(template("{hash}").unwrap().check_widths(&UUID, None).is_err());
+        assert!(template("{hash:8}").unwrap().check_widths(&UUID, Some(&UUID)).is_ok());
+    }
+}
@@ -0,0 +1,54 @@
+//! Progress reporting for long-running jobs.
+//!
+//! The `*_with_progress` methods of [`Anonymizer`](crate::Anonymizer) and
+//! [`AnonymizeIter::with_progress`](crate::iter::AnonymizeIter::with_progress)
+//! call a closure with a [`Progress`] snapshot after each unit of work
+//! (a segment of a large text or a whole document), so callers can render
+//! progress bars or estimate remaining time.
+
+use serde::{Deserialize, Serialize};
+
+/// Snapshot of a job's progress.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    /// Input bytes processed so far
+    pub bytes_processed: usize,
+    /// Total input bytes, if known up front
+    pub total_bytes: Option<usize>,
+    /// Documents completed so far
+    pub documents_processed: usize,
+    /// Entities detected so far
+    pub entities_found: usize,
+}
+
+impl Progress {
+    /// Fraction of input processed in `0.0..=1.0`, if the total is known.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::progress::Progress;
+    ///
+    /// let progress = Progress {
+    ///     bytes_processed: 250,
+    ///     total_bytes: Some(1000),
+    ///     ..Progress::default()
+    /// };
+    /// assert_eq!(progress.fraction(), Some(0.25));
+    /// ```
+    pub fn fraction(&self) -> Option<f64> {
+        self.total_bytes.map(|total| {
+            if total == 0 {
+                1.0
+            } else {
+                self.bytes_processed as f64 / total as f64
+            }
+        })
+    }
+
+    /// Record a completed unit of work.
+    pub(crate) fn advance(&mut self, bytes: usize, entities: usize) {
+        self.bytes_processed += bytes;
+        self.entities_found += entities;
+    }
+}
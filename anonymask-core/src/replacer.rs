@@ -0,0 +1,794 @@
+//! Placeholder generation, replacement and restoration.
+//!
+//! The [`Replacer`] is the half of the [`Anonymizer`](crate::Anonymizer)
+//! that doesn't involve detection. Use it directly to restore text or to
+//! generate placeholders when entities come from elsewhere.
+
+use crate::config::{HashedTokens, PlaceholderFormat, PlaceholderOverride};
+use crate::entity::{AnonymizationResult, Entity, EntityType, OutputSpan, Status};
+use crate::error::AnonymaskError;
+use crate::placeholder::{self, Template, Values, Var};
+use crate::token::Tokenizer;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Replacement engine: generates placeholders and restores original values.
+///
+/// # Thread Safety
+///
+/// This type is `Send + Sync` and can be safely shared across threads.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::replacer::Replacer;
+/// use anonymask_core::{EntityType, PlaceholderFormat};
+/// use std::collections::HashMap;
+///
+/// let replacer = Replacer::new(PlaceholderFormat::Short).unwrap();
+/// let placeholder = replacer.generate_placeholder(&EntityType::Email, "a@b.io");
+/// assert_eq!(placeholder, "EMAIL_1");
+///
+/// let mapping = HashMap::from([(placeholder, "a@b.io".to_string())]);
+/// assert_eq!(replacer.deanonymize("Mail EMAIL_1", &mapping), "Mail a@b.io");
+/// ```
+#[derive(Debug)]
+pub struct Replacer {
+    template: Template,
+    /// Templates of entity types with their own format
+    overrides: Vec<(EntityType, Template)>,
+    counter: AtomicUsize,
+    /// Counters of `{index_by_type}`, by type
+    indices: Mutex<HashMap<String, usize>>,
+    /// Sequence standing in for random UUIDs, in deterministic mode
+    uuids: Option<AtomicU64>,
+    /// Keyed hashes standing in for UUIDs, if configured
+    tokens: Option<Tokenizer>,
+    /// Whether each operation numbers its placeholders from 1
+    reset_counters: bool,
+    /// Whether `deanonymize` restores reformatted placeholders
+    tolerant: bool,
+    placeholder_pattern: Regex,
+}
+
+/// Counters of one operation, when counters are reset per operation.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    counter: usize,
+    indices: HashMap<String, usize>,
+}
+
+impl Replacer {
+    /// Create a replacer generating placeholders in `format`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::InvalidConfig` if a custom template is
+    /// invalid or uses `{hash}` (see [`Replacer::hashed`]), and
+    /// `AnonymaskError::RegexError` if it can't be turned into a pattern
+    /// for recognizing its placeholders.
+    pub fn new(format: PlaceholderFormat) -> Result<Self, AnonymaskError> {
+        Self::build(&format, None)
+    }
+
+    /// Create a replacer using keyed hashes of the values for `{hash}`
+    /// and in place of UUIDs.
+    ///
+    /// The same value then gets the same placeholder in every replacer
+    /// with the same settings; see [`HashedTokens`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::replacer::Replacer;
+    /// use anonymask_core::{EntityType, HashedTokens, PlaceholderFormat};
+    ///
+    /// let tokens = HashedTokens::new("secret");
+    /// let a = Replacer::hashed(PlaceholderFormat::Standard, &tokens).unwrap();
+    /// let b = Replacer::hashed(PlaceholderFormat::Standard, &tokens).unwrap();
+    /// assert_eq!(
+    ///     a.generate_placeholder(&EntityType::Email, "a@b.io"),
+    ///     b.generate_placeholder(&EntityType::Email, "a@b.io")
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::InvalidConfig` if the key is empty or
+    /// `length` exceeds what the algorithm's digest can fill in the
+    /// encoding, and as for [`Replacer::new`].
+    pub fn hashed(format: PlaceholderFormat, tokens: &HashedTokens) -> Result<Self, AnonymaskError> {
+        Self::build(&format, Some(Tokenizer::new(tokens)?))
+    }
+
+    fn build(format: &PlaceholderFormat, tokens: Option<Tokenizer>) -> Result<Self, AnonymaskError> {
+        let template = Template::parse(format)?;
+        let hash = tokens.as_ref().map(Tokenizer::shape);
+        // {uuid} is filled with hashed tokens when there are any
+        let uuid = hash.as_ref().unwrap_or(&placeholder::UUID);
+        template.check_widths(uuid, hash.as_ref())?;
+        Ok(Self {
+            placeholder_pattern: template.pattern(uuid, hash.as_ref())?,
+            template,
+            overrides: Vec::new(),
+            counter: AtomicUsize::new(0),
+            indices: Mutex::new(HashMap::new()),
+            uuids: None,
+            tokens,
+            reset_counters: false,
+            tolerant: false,
+        })
+    }
+
+    /// Create a replacer whose placeholders are the same on every run.
+    ///
+    /// For snapshot tests of anonymized output. UUIDs are replaced by a
+    /// sequence (`00000000000000000000000000000001`, then `...02`, ...) and
+    /// counters start at 1, so the placeholders only depend on the order in
+    /// which values are replaced. They are still unique, but predictable:
+    /// never use this mode on real data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::replacer::Replacer;
+    /// use anonymask_core::{EntityType, PlaceholderFormat};
+    ///
+    /// let replacer = Replacer::deterministic(PlaceholderFormat::Standard).unwrap();
+    /// assert_eq!(
+    ///     replacer.generate_placeholder(&EntityType::Email, "a@b.io"),
+    ///     "EMAIL_00000000000000000000000000000001"
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// As for [`Replacer::new`].
+    pub fn deterministic(format: PlaceholderFormat) -> Result<Self, AnonymaskError> {
+        Ok(Self {
+            uuids: Some(AtomicU64::new(0)),
+            ..Self::new(format)?
+        })
+    }
+
+    /// Generate placeholders of some entity types in their own format.
+    ///
+    /// See [`PlaceholderOverride`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::replacer::Replacer;
+    /// use anonymask_core::{EntityType, PlaceholderFormat, PlaceholderOverride};
+    ///
+    /// let replacer = Replacer::new(PlaceholderFormat::Short)
+    ///     .unwrap()
+    ///     .with_overrides(&[PlaceholderOverride::new(
+    ///         EntityType::Email,
+    ///         PlaceholderFormat::Custom("<{type}_{counter}>".to_string()),
+    ///     )])
+    ///     .unwrap();
+    /// assert_eq!(replacer.generate_placeholder(&EntityType::Email, "a@b.io"), "<EMAIL_1>");
+    /// assert_eq!(replacer.generate_placeholder(&EntityType::Phone, "555-0100"), "PHONE_1");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::InvalidConfig` if a type has two overrides,
+    /// and as for [`Replacer::new`] for each format.
+    pub fn with_overrides(mut self, overrides: &[PlaceholderOverride]) -> Result<Self, AnonymaskError> {
+        if overrides.is_empty() {
+            return Ok(self);
+        }
+        let hash = self.tokens.as_ref().map(Tokenizer::shape);
+        let uuid = hash.as_ref().unwrap_or(&placeholder::UUID);
+        for placeholder_override in overrides {
+            let entity_type = &placeholder_override.entity_type;
+            if self.overrides.iter().any(|(existing, _)| existing == entity_type) {
+                return Err(AnonymaskError::InvalidConfig(format!(
+                    "placeholder_overrides has more than one format for {}",
+                    entity_type
+                )));
+            }
+            let template = Template::parse(&placeholder_override.format)?;
+            template.check_widths(uuid, hash.as_ref())?;
+            self.overrides.push((entity_type.clone(), template));
+        }
+
+        // Any placeholder of any of the formats
+        let mut patterns = Vec::new();
+        for template in self.overrides.iter().map(|(_, template)| template).chain([&self.template]) {
+            patterns.push(format!("(?:{})", template.pattern(uuid, hash.as_ref())?.as_str()));
+        }
+        let pattern = patterns.join("|");
+        self.placeholder_pattern =
+            Regex::new(&pattern).map_err(|e| AnonymaskError::RegexError { pattern, source: e })?;
+        Ok(self)
+    }
+
+    /// Number the placeholders of each operation from 1 instead of
+    /// continuing across operations.
+    ///
+    /// An operation is one call of
+    /// [`anonymize_entities`](Self::anonymize_entities) here, or one
+    /// anonymization call, batch, JSON document or CSV file of an
+    /// [`Anonymizer`](crate::Anonymizer). Sessions and streams keep counting.
+    /// Placeholders of different operations then collide, so only
+    /// deanonymize text with the mapping of its own result.
+    /// [`generate_placeholder`](Self::generate_placeholder) always
+    /// continues counting.
+    pub fn with_counter_reset(mut self, reset_counters: bool) -> Self {
+        self.reset_counters = reset_counters;
+        self
+    }
+
+    /// Matches any placeholder this replacer generates.
+    pub(crate) fn placeholder_pattern(&self) -> &Regex {
+        &self.placeholder_pattern
+    }
+
+    /// Restore placeholders an LLM reformatted, with changed case or
+    /// separators; see [`deanonymize_tolerant`](crate::restore::deanonymize_tolerant).
+    pub fn with_tolerant_deanonymization(mut self, tolerant: bool) -> Self {
+        self.tolerant = tolerant;
+        self
+    }
+
+    /// Whether `deanonymize` restores reformatted placeholders.
+    pub(crate) fn is_tolerant(&self) -> bool {
+        self.tolerant
+    }
+
+    /// Fresh counters for an operation, if counters are reset per operation.
+    pub(crate) fn operation_counters(&self) -> Option<Counters> {
+        self.reset_counters.then(Counters::default)
+    }
+
+    /// The last counter value handed out, and the last `{index_by_type}`
+    /// of each type.
+    pub(crate) fn counters(&self) -> (usize, HashMap<String, usize>) {
+        (self.counter.load(Ordering::SeqCst), self.indices.lock().unwrap().clone())
+    }
+
+    /// Continue counting after `counter` and `indices` unless already
+    /// past them.
+    pub(crate) fn advance_counters(&self, counter: usize, indices: &HashMap<String, usize>) {
+        self.counter.fetch_max(counter, Ordering::SeqCst);
+        let mut own = self.indices.lock().unwrap();
+        for (type_name, &index) in indices {
+            let own = own.entry(type_name.clone()).or_default();
+            *own = index.max(*own);
+        }
+    }
+
+    /// The hashed token of the value, the next in the sequence in
+    /// deterministic mode, or a random UUID.
+    fn uuid(&self, type_prefix: &str, value: &str) -> String {
+        if let Some(tokens) = &self.tokens {
+            return tokens.token(type_prefix, value);
+        }
+        match &self.uuids {
+            Some(sequence) => format!("{:032x}", sequence.fetch_add(1, Ordering::SeqCst) + 1),
+            None => Uuid::new_v4().simple().to_string(),
+        }
+    }
+
+    /// Replace caller-supplied entities without running detection.
+    ///
+    /// Placeholders are generated, replaced and mapped exactly as for
+    /// detected entities. Entities may be given in any order; they are
+    /// returned sorted by position.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The original text
+    /// * `entities` - Spans to replace, with byte offsets into `text`
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::InvalidSpan` if a span is empty, out of
+    /// bounds, not on character boundaries, differs from the entity's
+    /// `value`, or overlaps another span. Nothing is replaced in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::replacer::Replacer;
+    /// use anonymask_core::{Entity, EntityType, PlaceholderFormat};
+    ///
+    /// let replacer = Replacer::new(PlaceholderFormat::Short).unwrap();
+    /// let text = "Ada met Grace";
+    /// let person = |value: &str, start: usize| Entity {
+    ///     entity_type: EntityType::Person,
+    ///     value: value.to_string(),
+    ///     start,
+    ///     end: start + value.len(),
+    ///     confidence: 0.9,
+    /// };
+    ///
+    /// let result = replacer
+    ///     .anonymize_entities(text, vec![person("Grace", 8), person("Ada", 0)])
+    ///     .unwrap();
+    /// assert_eq!(result.anonymized_text, "PERSON_1 met PERSON_2");
+    /// ```
+    pub fn anonymize_entities(
+        &self,
+        text: &str,
+        mut entities: Vec<Entity>,
+    ) -> Result<AnonymizationResult, AnonymaskError> {
+        for entity in &entities {
+            validate_span(text, entity)?;
+        }
+        entities.sort_by_key(|entity| entity.start);
+        for pair in entities.windows(2) {
+            if pair[1].start < pair[0].end {
+                return Err(AnonymaskError::InvalidSpan {
+                    start: pair[1].start,
+                    end: pair[1].end,
+                    reason: format!("overlaps {}..{}", pair[0].start, pair[0].end),
+                });
+            }
+        }
+
+        let mut unique_values = HashMap::new();
+        let mut counters = self.operation_counters();
+        let (anonymized_text, output_spans) =
+            self.replace(text, &entities, &mut unique_values, None, counters.as_mut())?;
+
+        Ok(AnonymizationResult {
+            anonymized_text,
+            mapping: unique_values
+                .into_iter()
+                .map(|(original, placeholder)| (placeholder, original))
+                .collect(),
+            entities,
+            output_spans,
+            subject_ids: Vec::new(),
+            contexts: Vec::new(),
+            status: Status::Complete,
+            truncated: false,
+            budget_exceeded: false,
+            trace: Vec::new(),
+        })
+    }
+
+    /// Replace entity spans in `text`, reusing and extending a
+    /// value -> placeholder table.
+    ///
+    /// `entities` must be sorted, non-overlapping and on character
+    /// boundaries of `text`. New values take their placeholder from
+    /// `replacements` when present, and are numbered with `counters` if
+    /// given. Returns the new text and the position of each placeholder in
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::AnonymizationError` if a hashed or UUID
+    /// placeholder of a new value already stands for a different value,
+    /// which short tokens make possible.
+    pub(crate) fn replace(
+        &self,
+        text: &str,
+        entities: &[Entity],
+        unique_values: &mut HashMap<String, String>,
+        replacements: Option<&HashMap<String, String>>,
+        mut counters: Option<&mut Counters>,
+    ) -> Result<(String, Vec<OutputSpan>), AnonymaskError> {
+        let mut anonymized_text = String::with_capacity(text.len());
+        let mut output_spans = Vec::with_capacity(entities.len());
+        let mut last_end = 0;
+        // Placeholders in use, collected when the first token is checked
+        let mut taken: Option<HashSet<String>> = None;
+        for entity in entities {
+            if !unique_values.contains_key(&entity.value) {
+                let placeholder = match replacements.and_then(|r| r.get(&entity.value)) {
+                    Some(replacement) => replacement.clone(),
+                    None => self.placeholder(&entity.entity_type, &entity.value, counters.as_deref_mut()),
+                };
+                // Templates without tokens may share a placeholder on
+                // purpose, like "[REDACTED]"
+                let template = self.template(&entity.entity_type);
+                if template.uses(Var::Uuid) || template.uses(Var::Hash) {
+                    let taken = taken.get_or_insert_with(|| unique_values.values().cloned().collect());
+                    if !taken.insert(placeholder.clone()) {
+                        return Err(AnonymaskError::AnonymizationError(format!(
+                            "placeholder '{}' already stands for a different value; use longer tokens",
+                            placeholder
+                        )));
+                    }
+                } else if let Some(taken) = &mut taken {
+                    taken.insert(placeholder.clone());
+                }
+                unique_values.insert(entity.value.clone(), placeholder);
+            }
+            let placeholder = &unique_values[&entity.value];
+
+            anonymized_text.push_str(&text[last_end..entity.start]);
+            let start = anonymized_text.len();
+            anonymized_text.push_str(placeholder);
+            output_spans.push(OutputSpan {
+                placeholder: placeholder.clone(),
+                start,
+                end: anonymized_text.len(),
+            });
+            last_end = entity.end;
+        }
+        anonymized_text.push_str(&text[last_end..]);
+
+        Ok((anonymized_text, output_spans))
+    }
+
+    /// Restore original values using a placeholder -> original mapping.
+    ///
+    /// See [`Anonymizer::deanonymize`](crate::Anonymizer::deanonymize).
+    pub fn deanonymize(&self, text: &str, mapping: &HashMap<String, String>) -> String {
+        match self.tolerant {
+            true => crate::restore::deanonymize_tolerant(text, mapping),
+            false => deanonymize_exact(text, mapping),
+        }
+    }
+
+    /// Restore original values, failing on placeholders missing from the mapping.
+    ///
+    /// See [`Anonymizer::deanonymize_strict`](crate::Anonymizer::deanonymize_strict).
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::MappingNotFound` with the first unknown
+    /// placeholder and its byte position.
+    pub fn deanonymize_strict(
+        &self,
+        text: &str,
+        mapping: &HashMap<String, String>,
+    ) -> Result<String, AnonymaskError> {
+        if let Some(unknown) = self
+            .placeholder_pattern
+            .find_iter(text)
+            .find(|m| !mapping.contains_key(m.as_str()))
+        {
+            return Err(AnonymaskError::MappingNotFound {
+                placeholder: unknown.as_str().to_string(),
+                position: unknown.start(),
+            });
+        }
+
+        Ok(self.deanonymize(text, mapping))
+    }
+
+    /// Generate a unique placeholder for an entity.
+    ///
+    /// Creates a placeholder based on the format configured for the type.
+    /// Supports Standard (TYPE_UUID), Short (TYPE_COUNTER), and Custom formats.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_type` - The type of entity being replaced
+    /// * `value` - The actual PII value, hashed into the placeholder by [`Replacer::hashed`]
+    ///
+    /// # Returns
+    ///
+    /// A unique placeholder string based on the configuration.
+    ///
+    /// # Examples
+    ///
+    /// - Standard: "EMAIL_a1b2c3d4e5f6..."
+    /// - Short: "EMAIL_1", "EMAIL_2", etc.
+    /// - Custom: "[EMAIL:1]" (with template "[{type}:{counter}]")
+    pub fn generate_placeholder(&self, entity_type: &EntityType, value: &str) -> String {
+        self.placeholder(entity_type, value, None)
+    }
+
+    /// The template of placeholders of `entity_type`.
+    fn template(&self, entity_type: &EntityType) -> &Template {
+        self.overrides
+            .iter()
+            .find(|(overridden, _)| overridden == entity_type)
+            .map_or(&self.template, |(_, template)| template)
+    }
+
+    /// Generate a placeholder, numbered with `counters` if given and with
+    /// the replacer's own counters otherwise.
+    pub(crate) fn placeholder(&self, entity_type: &EntityType, value: &str, counters: Option<&mut Counters>) -> String {
+        let type_prefix = match entity_type {
+            EntityType::Email => "EMAIL",
+            EntityType::Phone => "PHONE",
+            EntityType::Ssn => "SSN",
+            EntityType::CreditCard => "CREDIT_CARD",
+            EntityType::IpAddress => "IP_ADDRESS",
+            EntityType::Url => "URL",
+            EntityType::Iban => "IBAN",
+            EntityType::SteuerId => "STEUER_ID",
+            EntityType::Nir => "NIR",
+            EntityType::Siren => "SIREN",
+            EntityType::Siret => "SIRET",
+            EntityType::Dni => "DNI",
+            EntityType::Nie => "NIE",
+            EntityType::Cpf => "CPF",
+            EntityType::Cnpj => "CNPJ",
+            EntityType::MyNumber => "MY_NUMBER",
+            EntityType::Tfn => "TFN",
+            EntityType::Medicare => "MEDICARE",
+            EntityType::Address => "ADDRESS",
+            EntityType::Date => "DATE",
+            EntityType::DateOfBirth => "DATE_OF_BIRTH",
+            EntityType::AwsAccessKey => "AWS_ACCESS_KEY",
+            EntityType::GithubToken => "GITHUB_TOKEN",
+            EntityType::SlackToken => "SLACK_TOKEN",
+            EntityType::BearerToken => "BEARER_TOKEN",
+            EntityType::PrivateKey => "PRIVATE_KEY",
+            EntityType::Jwt => "JWT",
+            EntityType::Secret => "SECRET",
+            EntityType::Credential => "CREDENTIAL",
+            EntityType::Person => "PERSON",
+            EntityType::Org => "ORG",
+            EntityType::Location => "LOCATION",
+            EntityType::Custom(name) => name,
+        };
+
+        let type_prefix = type_prefix.to_uppercase();
+        let template = self.template(entity_type);
+        let (counter, index_by_type) = match counters {
+            Some(counters) => {
+                let counter = match template.uses(Var::Counter) {
+                    true => {
+                        counters.counter += 1;
+                        counters.counter
+                    }
+                    false => 0,
+                };
+                let index_by_type = match template.uses(Var::IndexByType) {
+                    true => {
+                        let index = counters.indices.entry(type_prefix.clone()).or_default();
+                        *index += 1;
+                        *index
+                    }
+                    false => 0,
+                };
+                (counter, index_by_type)
+            }
+            None => {
+                let counter = match template.uses(Var::Counter) {
+                    true => self.counter.fetch_add(1, Ordering::SeqCst) + 1,
+                    false => 0,
+                };
+                let index_by_type = match template.uses(Var::IndexByType) {
+                    true => {
+                        let mut indices = self.indices.lock().unwrap();
+                        let index = indices.entry(type_prefix.clone()).or_default();
+                        *index += 1;
+                        *index
+                    }
+                    false => 0,
+                };
+                (counter, index_by_type)
+            }
+        };
+        let uuid = match template.uses(Var::Uuid) {
+            true => self.uuid(&type_prefix, value),
+            false => String::new(),
+        };
+        let hash = match &self.tokens {
+            Some(tokens) if template.uses(Var::Hash) => tokens.token(&type_prefix, value),
+            _ => String::new(),
+        };
+
+        template.render(&Values {
+            type_name: &type_prefix,
+            uuid: &uuid,
+            hash: &hash,
+            counter,
+            index_by_type,
+        })
+    }
+}
+
+/// Check that `entity` describes a non-empty span of `text` holding its value.
+fn validate_span(text: &str, entity: &Entity) -> Result<(), AnonymaskError> {
+    let invalid = |reason: &str| AnonymaskError::InvalidSpan {
+        start: entity.start,
+        end: entity.end,
+        reason: reason.to_string(),
+    };
+    if entity.start >= entity.end {
+        return Err(invalid("span is empty"));
+    }
+    if entity.end > text.len() {
+        return Err(invalid("span extends past the end of the text"));
+    }
+    match text.get(entity.start..entity.end) {
+        None => Err(invalid("span is not on character boundaries")),
+        Some(value) if value != entity.value => Err(invalid("text at span differs from the entity value")),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Replace each exact occurrence of a placeholder, longest first.
+pub(crate) fn deanonymize_exact(text: &str, mapping: &HashMap<String, String>) -> String {
+    let mut deanonymized_text = text.to_string();
+
+    // Sort placeholders by length descending to avoid partial replacements
+    let mut placeholders: Vec<_> = mapping.keys().collect();
+    placeholders.sort_by_key(|p| std::cmp::Reverse(p.len()));
+
+    for placeholder in placeholders {
+        if let Some(original) = mapping.get(placeholder) {
+            deanonymized_text = deanonymized_text.replace(placeholder, original);
+        }
+    }
+
+    deanonymized_text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{HashAlgorithm, TokenEncoding};
+
+    fn entity(value: &str, start: usize) -> Entity {
+        Entity {
+            entity_type: EntityType::Email,
+            value: value.to_string(),
+            start,
+            end: start + value.len(),
+            confidence: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_replace_reuses_placeholders() {
+        let replacer = Replacer::new(PlaceholderFormat::Short).unwrap();
+        let text = "a@b.io, c@d.io, a@b.io";
+        let entities = [entity("a@b.io", 0), entity("c@d.io", 8), entity("a@b.io", 16)];
+
+        let mut values = HashMap::new();
+        let (replaced, spans) = replacer.replace(text, &entities, &mut values, None, None).unwrap();
+        assert_eq!(replaced, "EMAIL_1, EMAIL_2, EMAIL_1");
+        assert_eq!((spans[2].start, spans[2].end), (18, 25));
+        assert_eq!(values.len(), 2);
+    }
+
+    #[test]
+    fn test_deterministic_placeholders_repeat() {
+        let format = PlaceholderFormat::Custom("<{type}:{counter}:{uuid}>".to_string());
+        let run = || {
+            let replacer = Replacer::deterministic(format.clone()).unwrap();
+            let entities = vec![entity("a@b.io", 0), entity("c@d.io", 8)];
+            replacer.anonymize_entities("a@b.io, c@d.io", entities).unwrap().anonymized_text
+        };
+
+        assert_eq!(run(), run());
+        assert_eq!(run(), format!("<EMAIL:1:{:032x}>, <EMAIL:2:{:032x}>", 1, 2));
+    }
+
+    #[test]
+    fn test_hashed_tokens() {
+        let replacer = |tokens: &HashedTokens| {
+            Replacer::hashed(PlaceholderFormat::Standard, tokens).unwrap()
+        };
+        let tokens = HashedTokens::new("secret");
+        let placeholder = replacer(&tokens).generate_placeholder(&EntityType::Email, "a@b.io");
+        assert_eq!(placeholder, replacer(&tokens).generate_placeholder(&EntityType::Email, "a@b.io"));
+        assert_ne!(placeholder, replacer(&tokens).generate_placeholder(&EntityType::Email, "c@d.io"));
+        assert_ne!(placeholder, replacer(&HashedTokens::new("other")).generate_placeholder(&EntityType::Email, "a@b.io"));
+        assert!(!format!("{:?}", replacer(&tokens)).contains("secret"));
+
+        for (algorithm, encoding) in [
+            (HashAlgorithm::HmacSha256, TokenEncoding::Base32),
+            (HashAlgorithm::SipHash, TokenEncoding::Base58),
+            (HashAlgorithm::Blake3, TokenEncoding::Hex),
+        ] {
+            let replacer = replacer(&HashedTokens {
+                algorithm,
+                encoding,
+                length: 20,
+                ..tokens.clone()
+            });
+            let result = replacer
+                .anonymize_entities("a@b.io, c@d.io", vec![entity("a@b.io", 0), entity("c@d.io", 8)])
+                .unwrap();
+            assert_eq!(result.anonymized_text.len(), "EMAIL_".len() * 2 + 20 * 2 + 2);
+            // Placeholders are recognized, so unknown ones are reported
+            assert!(replacer.deanonymize_strict(&result.anonymized_text, &HashMap::new()).is_err());
+            assert_eq!(
+                replacer.deanonymize_strict(&result.anonymized_text, &result.mapping).unwrap(),
+                "a@b.io, c@d.io"
+            );
+        }
+    }
+
+    #[test]
+    fn test_colliding_tokens_are_rejected() {
+        // 256 possible placeholders, so some of 100 values share one
+        let format = PlaceholderFormat::Custom("<{hash:2}>".to_string());
+        let replacer = Replacer::hashed(format, &HashedTokens::new("secret")).unwrap();
+        let text: Vec<String> = (0..100).map(|i| format!("u{:02}@b.io", i)).collect();
+        let entities = text.iter().enumerate().map(|(i, value)| entity(value, i * 9)).collect();
+
+        let error = replacer.anonymize_entities(&text.join(" "), entities).unwrap_err();
+        assert!(matches!(error, AnonymaskError::AnonymizationError(_)));
+        assert!(!error.to_string().contains("@b.io"));
+
+        // Templates without tokens share placeholders on purpose
+        let redacted = Replacer::new(PlaceholderFormat::Custom("[REDACTED]".to_string())).unwrap();
+        let result = redacted
+            .anonymize_entities("a@b.io c@d.io", vec![entity("a@b.io", 0), entity("c@d.io", 7)])
+            .unwrap();
+        assert_eq!(result.anonymized_text, "[REDACTED] [REDACTED]");
+    }
+
+    #[test]
+    fn test_template_variables() {
+        let format = PlaceholderFormat::Custom("<{type_lower}-{index_by_type:02}-{hash:10}>".to_string());
+        assert!(matches!(Replacer::new(format.clone()), Err(AnonymaskError::InvalidConfig(_))));
+
+        let replacer = Replacer::hashed(format, &HashedTokens::new("secret")).unwrap();
+        let placeholders: Vec<_> = [(EntityType::Email, "a@b.io"), (EntityType::Phone, "555-0100"), (EntityType::Email, "c@d.io")]
+            .iter()
+            .map(|(entity_type, value)| replacer.generate_placeholder(entity_type, value))
+            .collect();
+        let prefixes: Vec<_> = placeholders.iter().map(|p| &p[..p.len() - 12]).collect();
+        assert_eq!(prefixes, ["<email-01", "<phone-01", "<email-02"]);
+        assert_eq!(placeholders[0].len(), "<email-01-".len() + 10 + 1);
+
+        let text = placeholders.join(" ");
+        assert!(replacer.deanonymize_strict(&text, &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_placeholder_overrides() {
+        let custom = |template: &str| PlaceholderFormat::Custom(template.to_string());
+        let replacer = Replacer::new(PlaceholderFormat::Short)
+            .unwrap()
+            .with_overrides(&[
+                PlaceholderOverride::new(EntityType::Email, custom("<{type}_{index_by_type}>")),
+                PlaceholderOverride::new(EntityType::Ssn, custom("[REDACTED]")),
+            ])
+            .unwrap();
+        let mut ssn = entity("123-45-6789", 16);
+        ssn.entity_type = EntityType::Ssn;
+        let mut phone = entity("555-0100", 29);
+        phone.entity_type = EntityType::Phone;
+        let text = "a@b.io, c@d.io, 123-45-6789, 555-0100";
+        let result = replacer
+            .anonymize_entities(text, vec![entity("a@b.io", 0), entity("c@d.io", 8), ssn, phone])
+            .unwrap();
+        assert_eq!(result.anonymized_text, "<EMAIL_1>, <EMAIL_2>, [REDACTED], PHONE_1");
+        assert_eq!(replacer.deanonymize_strict(&result.anonymized_text, &result.mapping).unwrap(), text);
+        // Placeholders of every format are recognized
+        assert!(replacer.deanonymize_strict("<EMAIL_9>", &result.mapping).is_err());
+        assert!(replacer.deanonymize_strict("PHONE_9", &result.mapping).is_err());
+
+        let twice = [
+            PlaceholderOverride::new(EntityType::Ssn, custom("[SSN]")),
+            PlaceholderOverride::new(EntityType::Ssn, custom("[REDACTED]")),
+        ];
+        let replacer = Replacer::new(PlaceholderFormat::Short).unwrap().with_overrides(&twice);
+        assert!(matches!(replacer, Err(AnonymaskError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_anonymize_entities_validates_spans() {
+        let replacer = Replacer::new(PlaceholderFormat::Short).unwrap();
+        let text = "Zoë <a@b.io>";
+        let invalid = |entities: Vec<Entity>| {
+            matches!(
+                replacer.anonymize_entities(text, entities),
+                Err(AnonymaskError::InvalidSpan { .. })
+            )
+        };
+
+        assert!(invalid(vec![entity("a@b.io", 5)])); // value differs
+        assert!(invalid(vec![entity("a@b.io", 20)])); // out of bounds
+        assert!(invalid(vec![entity("", 3)])); // empty
+        let mut split = entity("Zo", 0);
+        split.end = 3; // inside "ë"
+        assert!(invalid(vec![split]));
+        assert!(invalid(vec![entity("a@b.io", 6), entity("b.io", 8)]));
+
+        let result = replacer.anonymize_entities(text, vec![entity("a@b.io", 6)]).unwrap();
+        assert_eq!(result.anonymized_text, "Zoë <EMAIL_1>");
+    }
+}
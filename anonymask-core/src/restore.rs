@@ -0,0 +1,366 @@
+//! Bulk and streaming deanonymization.
+//!
+//! [`Anonymizer::deanonymize`](crate::Anonymizer::deanonymize) prepares
+//! the mapping on every call, which dominates when restoring many short
+//! texts against one large mapping. A [`MappingIndex`] prepares it once
+//! and restores any number of texts, or a whole stream, in a single pass
+//! each.
+//!
+//! Both match placeholders exactly. With
+//! [`AnonymizerConfig::tolerant_deanonymization`](crate::config::AnonymizerConfig::tolerant_deanonymization),
+//! `deanonymize` also restores placeholders an LLM reformatted; see
+//! [`deanonymize_tolerant`].
+
+use crate::anonymizer::Anonymizer;
+use crate::error::AnonymaskError;
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use regex::RegexBuilder;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+/// Bytes read from a stream at a time.
+const CHUNK: usize = 64 * 1024;
+
+/// A placeholder -> original mapping prepared for repeated restoration.
+///
+/// Placeholders are found in one pass over the text, preferring the
+/// longest at each position (so `EMAIL_10` is not read as `EMAIL_1`
+/// followed by `0`). Unlike repeated
+/// [`deanonymize`](crate::Anonymizer::deanonymize) calls, restored values
+/// are never searched again, so an original value that happens to contain
+/// a placeholder is restored as is.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::restore::MappingIndex;
+/// use std::collections::HashMap;
+///
+/// let mapping = HashMap::from([
+///     ("EMAIL_1".to_string(), "a@b.io".to_string()),
+///     ("EMAIL_10".to_string(), "c@d.io".to_string()),
+/// ]);
+/// let index = MappingIndex::new(&mapping).unwrap();
+///
+/// assert_eq!(index.deanonymize("EMAIL_10 and EMAIL_1"), "c@d.io and a@b.io");
+///
+/// let mut restored = Vec::new();
+/// index.deanonymize_stream("Reply to EMAIL_1".as_bytes(), &mut restored).unwrap();
+/// assert_eq!(restored, b"Reply to a@b.io");
+/// ```
+#[derive(Debug, Clone)]
+pub struct MappingIndex {
+    automaton: AhoCorasick,
+    /// Original values, by pattern index of the automaton
+    originals: Vec<String>,
+    /// Length in bytes of the longest placeholder
+    longest: usize,
+}
+
+impl MappingIndex {
+    /// Prepare `mapping` for restoration. Empty placeholders are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::AnonymizationError` if the mapping is too
+    /// large for the automaton.
+    pub fn new(mapping: &HashMap<String, String>) -> Result<Self, AnonymaskError> {
+        let (placeholders, originals): (Vec<&str>, Vec<String>) = mapping
+            .iter()
+            .filter(|(placeholder, _)| !placeholder.is_empty())
+            .map(|(placeholder, original)| (placeholder.as_str(), original.clone()))
+            .unzip();
+        let automaton = AhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(&placeholders)
+            .map_err(|e| AnonymaskError::AnonymizationError(format!("failed to index mapping: {}", e)))?;
+
+        Ok(Self {
+            automaton,
+            longest: placeholders.iter().map(|p| p.len()).max().unwrap_or(0),
+            originals,
+        })
+    }
+
+    /// Restore the original values in `text`.
+    pub fn deanonymize(&self, text: &str) -> String {
+        let mut restored = String::with_capacity(text.len());
+        self.automaton.replace_all_with(text, &mut restored, |m, _, out| {
+            out.push_str(&self.originals[m.pattern().as_usize()]);
+            true
+        });
+        restored
+    }
+
+    /// Restore the original values in everything read from `reader`,
+    /// writing the result to `writer`.
+    ///
+    /// Memory use is bounded by the chunk size plus the longest
+    /// placeholder, whatever the length of the stream. Bytes are passed
+    /// through unchanged apart from placeholders, so the stream doesn't
+    /// need to be valid UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of `reader` or `writer`.
+    pub fn deanonymize_stream<R: Read, W: Write>(&self, mut reader: R, mut writer: W) -> io::Result<()> {
+        let mut buffer = Vec::with_capacity(CHUNK + self.longest);
+        let mut chunk = vec![0; CHUNK];
+        loop {
+            let read = match reader.read(&mut chunk) {
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            buffer.extend_from_slice(&chunk[..read]);
+            let end = read == 0;
+
+            // A placeholder starting before `settled` lies entirely in the
+            // buffer, so no later byte can change how it matches
+            let settled = if end {
+                buffer.len()
+            } else {
+                buffer.len().saturating_sub(self.longest.saturating_sub(1))
+            };
+            let mut written = 0;
+            for m in self.automaton.find_iter(&buffer) {
+                if m.start() >= settled {
+                    break;
+                }
+                writer.write_all(&buffer[written..m.start()])?;
+                writer.write_all(self.originals[m.pattern().as_usize()].as_bytes())?;
+                written = m.end();
+            }
+            let consumed = written.max(settled);
+            writer.write_all(&buffer[written..consumed])?;
+            buffer.drain(..consumed);
+
+            if end {
+                return writer.flush();
+            }
+        }
+    }
+}
+
+/// Separators an LLM may add, drop or swap inside a placeholder.
+const DRIFT: &str = r"[ \t\p{P}\p{S}]*";
+
+/// Restore placeholders written with different case or separators.
+///
+/// `EMAIL_1` is also restored from `Email_1`, `email 1`, `EMAIL - 1` or
+/// `EMAIL1`: letters and digits must match up to case, while spaces,
+/// punctuation and symbols between them may change. Brackets around a
+/// placeholder, as in `[EMAIL:1]`, may be dropped or padded with spaces.
+/// A match running into further letters or digits, like `EMAIL_1` in
+/// `Email_12`, is left alone. Placeholders without letters or digits are
+/// matched exactly.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::restore::deanonymize_tolerant;
+/// use std::collections::HashMap;
+///
+/// let mapping = HashMap::from([("EMAIL_1".to_string(), "a@b.io".to_string())]);
+/// assert_eq!(
+///     deanonymize_tolerant("Email_1, email 1 and EMAIL_12", &mapping),
+///     "a@b.io, a@b.io and EMAIL_12"
+/// );
+/// ```
+pub fn deanonymize_tolerant(text: &str, mapping: &HashMap<String, String>) -> String {
+    // Longest first, so that the longest placeholder wins at a position
+    let mut placeholders: Vec<&String> = mapping.keys().filter(|p| !p.is_empty()).collect();
+    placeholders.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+
+    let mut originals = HashMap::new();
+    let mut patterns = Vec::new();
+    for placeholder in placeholders {
+        let Some(first) = placeholder.find(char::is_alphanumeric) else {
+            patterns.push(regex::escape(placeholder));
+            originals.entry(placeholder.clone()).or_insert(&mapping[placeholder]);
+            continue;
+        };
+        let last = placeholder.rfind(char::is_alphanumeric).unwrap();
+        let last = last + placeholder[last..].chars().next().unwrap().len_utf8();
+
+        let mut pattern = String::new();
+        if first > 0 {
+            pattern.push_str(&format!(r"(?:{}[ \t]*)?", regex::escape(&placeholder[..first])));
+        }
+        let mut separator = false;
+        for c in placeholder[first..last].chars() {
+            if c.is_alphanumeric() {
+                if separator {
+                    pattern.push_str(DRIFT);
+                    separator = false;
+                }
+                pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+            } else {
+                separator = true;
+            }
+        }
+        if last < placeholder.len() {
+            pattern.push_str(&format!(r"(?:[ \t]*{})?", regex::escape(&placeholder[last..])));
+        }
+        patterns.push(pattern);
+        originals.entry(key(placeholder)).or_insert(&mapping[placeholder]);
+    }
+    if patterns.is_empty() {
+        return text.to_string();
+    }
+
+    let pattern = patterns.iter().map(|p| format!("(?:{})", p)).collect::<Vec<_>>().join("|");
+    let Ok(regex) = RegexBuilder::new(&pattern).case_insensitive(true).size_limit(1 << 26).build() else {
+        // Too many placeholders for one automaton
+        return crate::replacer::deanonymize_exact(text, mapping);
+    };
+
+    let mut restored = String::with_capacity(text.len());
+    let mut written = 0;
+    for m in regex.find_iter(text) {
+        let joined = |before: Option<char>, edge: Option<char>| {
+            before.zip(edge).is_some_and(|(a, b)| a.is_alphanumeric() && b.is_alphanumeric())
+        };
+        let matched = m.as_str();
+        if joined(text[..m.start()].chars().next_back(), matched.chars().next())
+            || joined(text[m.end()..].chars().next(), matched.chars().next_back())
+        {
+            continue;
+        }
+        let original = originals
+            .get(&key(matched))
+            .or_else(|| originals.get(matched));
+        if let Some(original) = original {
+            restored.push_str(&text[written..m.start()]);
+            restored.push_str(original);
+            written = m.end();
+        }
+    }
+    restored.push_str(&text[written..]);
+    restored
+}
+
+/// Letters and digits of a placeholder, lowercased.
+fn key(placeholder: &str) -> String {
+    placeholder
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl Anonymizer {
+    /// Restore the original values in many texts against one mapping.
+    ///
+    /// Equivalent to calling [`deanonymize`](Self::deanonymize) on each
+    /// text, but the mapping is prepared once; see [`MappingIndex`], which
+    /// can also be kept and reused directly. With tolerant deanonymization
+    /// configured, each text is restored like `deanonymize` does.
+    ///
+    /// # Errors
+    ///
+    /// As for [`MappingIndex::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::{Anonymizer, EntityType};
+    ///
+    /// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+    /// let mut session = anonymizer.session();
+    /// let a = session.anonymize("From a@b.io").unwrap().anonymized_text;
+    /// let b = session.anonymize("To c@d.io").unwrap().anonymized_text;
+    ///
+    /// let restored = anonymizer.deanonymize_batch(&[&a, &b], &session.mapping()).unwrap();
+    /// assert_eq!(restored, ["From a@b.io", "To c@d.io"]);
+    /// ```
+    pub fn deanonymize_batch(&self, texts: &[&str], mapping: &HashMap<String, String>) -> Result<Vec<String>, AnonymaskError> {
+        if self.replacer().is_tolerant() {
+            return Ok(texts.iter().map(|text| self.deanonymize(text, mapping)).collect());
+        }
+        let index = MappingIndex::new(mapping)?;
+        Ok(texts.iter().map(|text| index.deanonymize(text)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads one byte at a time, splitting every placeholder.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((&first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_stream_matches_whole_text() {
+        let mapping = HashMap::from([
+            ("EMAIL_1".to_string(), "a@b.io".to_string()),
+            ("EMAIL_12".to_string(), "c@d.io".to_string()),
+            ("PHONE_2".to_string(), "555-0100".to_string()),
+        ]);
+        let index = MappingIndex::new(&mapping).unwrap();
+        let text = "EMAIL_12,EMAIL_1 EMAIL_13 PHONE_2EMAIL_1";
+
+        let mut restored = Vec::new();
+        index.deanonymize_stream(Trickle(text.as_bytes()), &mut restored).unwrap();
+        assert_eq!(String::from_utf8(restored).unwrap(), index.deanonymize(text));
+        assert_eq!(index.deanonymize(text), "c@d.io,a@b.io a@b.io3 555-0100a@b.io");
+    }
+
+    #[test]
+    fn test_tolerant_matching() {
+        let mapping = HashMap::from([
+            ("EMAIL_1".to_string(), "a@b.io".to_string()),
+            ("EMAIL_12".to_string(), "c@d.io".to_string()),
+            ("[SSN:3]".to_string(), "123-45-6789".to_string()),
+            ("EMAIL_0a1b2c3d".to_string(), "e@f.io".to_string()),
+            ("***".to_string(), "secret".to_string()),
+        ]);
+        let text = "Email_1, email 12, EMAIL - 1; [ ssn: 3 ], SSN 3, (ssn:3). \
+                    Email_0A1B2C3D, ***, EMAIL_13, XEMAIL_1, email\n1";
+        assert_eq!(
+            deanonymize_tolerant(text, &mapping),
+            "a@b.io, c@d.io, a@b.io; 123-45-6789, 123-45-6789, (123-45-6789). \
+             e@f.io, secret, EMAIL_13, XEMAIL_1, email\n1"
+        );
+        assert_eq!(deanonymize_tolerant("EMAIL_1", &HashMap::new()), "EMAIL_1");
+    }
+
+    #[test]
+    fn test_tolerant_deanonymization_is_configurable() {
+        let config = |tolerant| {
+            crate::AnonymizerConfig::builder()
+                .with_placeholder_format(crate::PlaceholderFormat::Short)
+                .with_tolerant_deanonymization(tolerant)
+                .build()
+        };
+        for tolerant in [false, true] {
+            let anonymizer = Anonymizer::with_config(vec![crate::EntityType::Email], config(tolerant)).unwrap();
+            let result = anonymizer.anonymize("Mail a@b.io").unwrap();
+            let reply = "Wrote to Email 1";
+            let restored = if tolerant { "Wrote to a@b.io" } else { reply };
+            assert_eq!(anonymizer.deanonymize(reply, &result.mapping), restored);
+            assert_eq!(anonymizer.deanonymize_batch(&[reply], &result.mapping).unwrap(), [restored]);
+        }
+    }
+
+    #[test]
+    fn test_empty_mapping() {
+        let index = MappingIndex::new(&HashMap::from([(String::new(), "x".to_string())])).unwrap();
+        assert_eq!(index.deanonymize("unchanged"), "unchanged");
+
+        let mut restored = Vec::new();
+        index.deanonymize_stream("unchanged".as_bytes(), &mut restored).unwrap();
+        assert_eq!(restored, b"unchanged");
+    }
+}
@@ -0,0 +1,368 @@
+//! SARIF output for detection results.
+//!
+//! [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+//! is the format read by GitHub code scanning and most vulnerability
+//! dashboards. Each detected entity becomes a result whose rule is the
+//! entity type, located by line and column in the scanned file.
+//!
+//! Reports never contain the detected values themselves, only where they
+//! are, so they can be uploaded to systems that shouldn't see the data.
+
+use crate::entity::Entity;
+use serde::ser::{Serialize, Serializer};
+use serde::Serialize as DeriveSerialize;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/gokul-viswanathan/anonymask";
+
+/// A SARIF log with one run, built up file by file.
+///
+/// Serializes to the SARIF JSON document; use [`to_json`](Self::to_json)
+/// or any serde serializer.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::{Anonymizer, EntityType};
+/// use anonymask_core::sarif::SarifReport;
+///
+/// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+/// let text = "owner:\n  email: jane@example.com\n";
+/// let entities = anonymizer.detect(text).unwrap();
+///
+/// let mut report = SarifReport::new();
+/// report.add_file("config/team.yaml", text, &entities);
+/// let json = report.to_json();
+///
+/// assert!(json.contains("\"startLine\": 2"));
+/// assert!(!json.contains("jane@example.com"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SarifReport {
+    rules: Vec<String>,
+    artifacts: Vec<String>,
+    results: Vec<SarifResult>,
+}
+
+impl SarifReport {
+    /// Create an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the entities detected in one file.
+    ///
+    /// Files without entities are still listed as scanned artifacts.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - Path of the file, relative to the repository root for code scanning
+    /// * `text` - The scanned text, used to turn byte offsets into lines and columns
+    /// * `entities` - Entities detected in `text`
+    pub fn add_file(&mut self, uri: impl Into<String>, text: &str, entities: &[Entity]) -> &mut Self {
+        let artifact_index = self.artifacts.len();
+        self.artifacts.push(uri.into());
+        let lines = LineIndex::new(text);
+
+        for entity in entities {
+            let rule_id = entity.entity_type.to_string();
+            let rule_index = match self.rules.iter().position(|rule| *rule == rule_id) {
+                Some(index) => index,
+                None => {
+                    self.rules.push(rule_id.clone());
+                    self.rules.len() - 1
+                }
+            };
+            let (start_line, start_column) = lines.position(text, entity.start);
+            let (end_line, end_column) = lines.position(text, entity.end);
+            self.results.push(SarifResult {
+                rule_index,
+                artifact_index,
+                region: Region {
+                    start_line,
+                    start_column,
+                    end_line,
+                    end_column,
+                    byte_offset: entity.start,
+                    byte_length: entity.end - entity.start,
+                },
+                confidence: entity.confidence,
+            });
+        }
+        self
+    }
+
+    /// Number of results in the report.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Whether the report has no results.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Render the report as pretty-printed SARIF JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("SARIF reports always serialize")
+    }
+}
+
+/// Render a single file's entities as SARIF JSON.
+///
+/// Shorthand for a [`SarifReport`] with one file.
+pub fn to_sarif(uri: &str, text: &str, entities: &[Entity]) -> String {
+    let mut report = SarifReport::new();
+    report.add_file(uri, text, entities);
+    report.to_json()
+}
+
+/// Byte offsets of line starts, for offset to line/column conversion.
+struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(text: &str) -> Self {
+        let mut starts = vec![0];
+        starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        Self { starts }
+    }
+
+    /// 1-based line and column (in Unicode code points) of a byte offset.
+    fn position(&self, text: &str, offset: usize) -> (usize, usize) {
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let column = text[self.starts[line]..offset].chars().count() + 1;
+        (line + 1, column)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SarifResult {
+    rule_index: usize,
+    artifact_index: usize,
+    region: Region,
+    confidence: f32,
+}
+
+#[derive(Debug, Clone, Copy, DeriveSerialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+    byte_offset: usize,
+    byte_length: usize,
+}
+
+// The wire format, borrowed from the report when serializing
+
+#[derive(DeriveSerialize)]
+struct Log<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: [Run<'a>; 1],
+}
+
+#[derive(DeriveSerialize)]
+#[serde(rename_all = "camelCase")]
+struct Run<'a> {
+    tool: Tool<'a>,
+    column_kind: &'static str,
+    artifacts: Vec<Artifact<'a>>,
+    results: Vec<ResultOut<'a>>,
+}
+
+#[derive(DeriveSerialize)]
+struct Tool<'a> {
+    driver: Driver<'a>,
+}
+
+#[derive(DeriveSerialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver<'a> {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<Rule<'a>>,
+}
+
+#[derive(DeriveSerialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule<'a> {
+    id: &'a str,
+    short_description: Message,
+    default_configuration: Configuration,
+}
+
+#[derive(DeriveSerialize)]
+struct Configuration {
+    level: &'static str,
+}
+
+#[derive(DeriveSerialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(DeriveSerialize)]
+struct Artifact<'a> {
+    location: ArtifactLocation<'a>,
+}
+
+#[derive(DeriveSerialize)]
+struct ArtifactLocation<'a> {
+    uri: &'a str,
+    index: usize,
+}
+
+#[derive(DeriveSerialize)]
+#[serde(rename_all = "camelCase")]
+struct ResultOut<'a> {
+    rule_id: &'a str,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: [Location<'a>; 1],
+    /// SARIF's 0-100 relevance score, from the entity confidence
+    rank: f32,
+}
+
+#[derive(DeriveSerialize)]
+#[serde(rename_all = "camelCase")]
+struct Location<'a> {
+    physical_location: PhysicalLocation<'a>,
+}
+
+#[derive(DeriveSerialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation<'a> {
+    artifact_location: ArtifactLocation<'a>,
+    region: Region,
+}
+
+impl Serialize for SarifReport {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let rules = self
+            .rules
+            .iter()
+            .map(|id| Rule {
+                id,
+                short_description: Message {
+                    text: format!("Personal data of type '{}'", id),
+                },
+                default_configuration: Configuration { level: "warning" },
+            })
+            .collect();
+        let artifacts = self
+            .artifacts
+            .iter()
+            .enumerate()
+            .map(|(index, uri)| Artifact {
+                location: ArtifactLocation { uri, index },
+            })
+            .collect();
+        let results = self
+            .results
+            .iter()
+            .map(|result| {
+                let rule_id = &self.rules[result.rule_index];
+                ResultOut {
+                    rule_id,
+                    rule_index: result.rule_index,
+                    level: "warning",
+                    message: Message {
+                        text: format!("Detected {} (confidence {:.2})", rule_id, result.confidence),
+                    },
+                    locations: [Location {
+                        physical_location: PhysicalLocation {
+                            artifact_location: ArtifactLocation {
+                                uri: &self.artifacts[result.artifact_index],
+                                index: result.artifact_index,
+                            },
+                            region: result.region,
+                        },
+                    }],
+                    rank: (result.confidence * 100.0).round(),
+                }
+            })
+            .collect();
+
+        Log {
+            schema: SCHEMA,
+            version: "2.1.0",
+            runs: [Run {
+                tool: Tool {
+                    driver: Driver {
+                        name: "anonymask",
+                        version: env!("CARGO_PKG_VERSION"),
+                        information_uri: INFORMATION_URI,
+                        rules,
+                    },
+                },
+                column_kind: "unicodeCodePoints",
+                artifacts,
+                results,
+            }],
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EntityType;
+
+    fn entity(entity_type: EntityType, text: &str, value: &str) -> Entity {
+        let start = text.find(value).unwrap();
+        Entity {
+            entity_type,
+            value: value.to_string(),
+            start,
+            end: start + value.len(),
+            confidence: 0.9,
+        }
+    }
+
+    #[test]
+    fn test_report_structure() {
+        let text = "Zoë: zoe@example.com\nSSN 123-45-6789\n";
+        let entities = vec![
+            entity(EntityType::Email, text, "zoe@example.com"),
+            entity(EntityType::Ssn, text, "123-45-6789"),
+        ];
+        let mut report = SarifReport::new();
+        report.add_file("notes.txt", text, &entities);
+        report.add_file("empty.txt", "", &[]);
+        report.add_file("more.txt", "a@b.io", &[entity(EntityType::Email, "a@b.io", "a@b.io")]);
+
+        let log: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 2);
+        assert_eq!(run["artifacts"].as_array().unwrap().len(), 3);
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        let region = &results[0]["locations"][0]["physicalLocation"]["region"];
+        assert_eq!((region["startLine"].as_u64(), region["startColumn"].as_u64()), (Some(1), Some(6)));
+        assert_eq!(region["endColumn"], 21);
+        assert_eq!(region["byteOffset"], 6);
+        let region = &results[1]["locations"][0]["physicalLocation"]["region"];
+        assert_eq!((region["startLine"].as_u64(), region["startColumn"].as_u64()), (Some(2), Some(5)));
+        assert_eq!(results[1]["ruleId"], "ssn");
+        assert_eq!(results[2]["ruleIndex"], 0);
+        assert_eq!(results[2]["locations"][0]["physicalLocation"]["artifactLocation"]["index"], 2);
+        assert_eq!(results[0]["rank"], 90.0);
+    }
+
+    #[test]
+    fn test_values_are_not_included() {
+        let text = "key 123-45-6789";
+        let json = to_sarif("a.txt", text, &[entity(EntityType::Ssn, text, "123-45-6789")]);
+        assert!(!json.contains("6789"));
+    }
+}
@@ -0,0 +1,151 @@
+//! JSON Schemas of the crate's wire formats (feature `schema`).
+//!
+//! Integrations in other languages can validate what they send and receive
+//! against these. The same schemas are published in the crate's `schemas/`
+//! directory as `<name>.schema.json`.
+//!
+//! | Name | Format |
+//! |------|--------|
+//! | `anonymization_result` | [`AnonymizationResult`] |
+//! | `auto_result` | [`AutoAnonymizationResult`] |
+//! | `batch_result` | [`BatchResult`] |
+//! | `entity` | [`Entity`] |
+//! | `json_result` | [`JsonAnonymizationResult`] |
+//! | `ndjson_result` | [`NdjsonAnonymizationResult`] |
+//! | `config` | [`AnonymizerConfig`], e.g. a config file |
+//! | `anonymize_request` | [`AnonymizeRequest`], body of `POST /anonymize` (see [`http`](crate::http)) |
+//! | `deanonymize_request` | [`DeanonymizeRequest`] |
+//! | `deanonymize_response` | [`DeanonymizeResponse`] |
+//! | `detect_request` | [`DetectRequest`] |
+//! | `realm_response` | [`RealmResponse`] |
+//! | `stats_response` | [`StatsResponse`], `GET /dashboard/stats` |
+//! | `rules_response` | [`RulesResponse`], `GET /dashboard/rules` |
+//! | `session_state` | [`SessionState`], an exported session |
+//! | `error_response` | [`ErrorResponse`] |
+
+use crate::auto::AutoAnonymizationResult;
+use crate::config::AnonymizerConfig;
+use crate::entity::{AnonymizationResult, BatchResult, Entity};
+use crate::http::{
+    AnonymizeRequest, DeanonymizeRequest, DeanonymizeResponse, DetectRequest, ErrorResponse, RealmResponse,
+    RulesResponse, StatsResponse,
+};
+use crate::json::{JsonAnonymizationResult, NdjsonAnonymizationResult};
+use crate::session::SessionState;
+use schemars::schema_for;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Names of all available schemas.
+pub const SCHEMA_NAMES: &[&str] = &[
+    "anonymization_result",
+    "anonymize_request",
+    "auto_result",
+    "batch_result",
+    "config",
+    "deanonymize_request",
+    "deanonymize_response",
+    "detect_request",
+    "entity",
+    "error_response",
+    "json_result",
+    "ndjson_result",
+    "realm_response",
+    "rules_response",
+    "session_state",
+    "stats_response",
+];
+
+/// Get the JSON Schema of one format by name.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::schema;
+///
+/// let entity = schema::schema("entity").unwrap();
+/// assert_eq!(entity["title"], "Entity");
+/// assert!(schema::schema("unknown").is_none());
+/// ```
+pub fn schema(name: &str) -> Option<Value> {
+    let root = match name {
+        "anonymization_result" => schema_for!(AnonymizationResult),
+        "anonymize_request" => schema_for!(AnonymizeRequest),
+        "auto_result" => schema_for!(AutoAnonymizationResult),
+        "batch_result" => schema_for!(BatchResult),
+        "config" => schema_for!(AnonymizerConfig),
+        "deanonymize_request" => schema_for!(DeanonymizeRequest),
+        "deanonymize_response" => schema_for!(DeanonymizeResponse),
+        "detect_request" => schema_for!(DetectRequest),
+        "entity" => schema_for!(Entity),
+        "error_response" => schema_for!(ErrorResponse),
+        "json_result" => schema_for!(JsonAnonymizationResult),
+        "ndjson_result" => schema_for!(NdjsonAnonymizationResult),
+        "realm_response" => schema_for!(RealmResponse),
+        "rules_response" => schema_for!(RulesResponse),
+        "session_state" => schema_for!(SessionState),
+        "stats_response" => schema_for!(StatsResponse),
+        _ => return None,
+    };
+    Some(serde_json::to_value(root).expect("schemas always serialize"))
+}
+
+/// Get the JSON Schemas of all formats, by name.
+pub fn schemas() -> BTreeMap<&'static str, Value> {
+    SCHEMA_NAMES
+        .iter()
+        .map(|name| (*name, schema(name).expect("listed schemas exist")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// The published files match the types.
+    ///
+    /// Run with `ANONYMASK_UPDATE_SCHEMAS=1` to regenerate them.
+    #[test]
+    fn test_published_schemas_are_current() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schemas");
+        let update = std::env::var_os("ANONYMASK_UPDATE_SCHEMAS").is_some();
+        for (name, schema) in schemas() {
+            let path = dir.join(format!("{}.schema.json", name));
+            let generated = serde_json::to_string_pretty(&schema).unwrap() + "\n";
+            if update {
+                std::fs::create_dir_all(&dir).unwrap();
+                std::fs::write(&path, &generated).unwrap();
+                continue;
+            }
+            let published = std::fs::read_to_string(&path).unwrap_or_default();
+            assert!(
+                published == generated,
+                "{} is out of date; rerun the tests with ANONYMASK_UPDATE_SCHEMAS=1",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_schema_accepts_serialized_values() {
+        let result = AnonymizationResult {
+            anonymized_text: "EMAIL_1".to_string(),
+            mapping: [("EMAIL_1".to_string(), "a@b.io".to_string())].into(),
+            entities: Vec::new(),
+            output_spans: Vec::new(),
+            subject_ids: Vec::new(),
+            contexts: Vec::new(),
+            status: Default::default(),
+            truncated: false,
+            budget_exceeded: false,
+            trace: Vec::new(),
+        };
+        let value = serde_json::to_value(&result).unwrap();
+        let schema = schema("anonymization_result").unwrap();
+        let required = schema["required"].as_array().unwrap();
+        for field in required {
+            assert!(value.get(field.as_str().unwrap()).is_some(), "missing {}", field);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Selectors of [`FieldPolicy`](crate::config::FieldPolicy), which locate
+//! fields by the path of names from a document's root; see its
+//! documentation for the syntax.
+
+use crate::error::AnonymaskError;
+
+/// A parsed selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Selector {
+    steps: Vec<Step>,
+}
+
+/// One level of a selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    /// Whether any number of levels may come before this one
+    descendant: bool,
+    /// The name matched, or `None` for any name
+    name: Option<String>,
+}
+
+impl Selector {
+    /// Parse a selector in any of the supported forms.
+    ///
+    /// # Errors
+    ///
+    /// * `AnonymaskError::InvalidConfig` - If the selector is empty or malformed
+    pub(crate) fn parse(selector: &str) -> Result<Self, AnonymaskError> {
+        let invalid = |reason: &str| {
+            AnonymaskError::InvalidConfig(format!("field_policies: invalid selector '{}': {}", selector, reason))
+        };
+        let steps = if let Some(rest) = selector.strip_prefix('$') {
+            parse_jsonpath(rest).map_err(invalid)?
+        } else if selector.starts_with('/') {
+            parse_xpath(selector).map_err(invalid)?
+        } else if selector.is_empty() {
+            return Err(invalid("empty selector"));
+        } else {
+            vec![Step {
+                descendant: true,
+                name: Some(selector.to_string()),
+            }]
+        };
+        Ok(Self { steps })
+    }
+
+    /// Whether the selector selects the field at `path`, given as the
+    /// names of its levels from the root.
+    pub(crate) fn matches(&self, path: &[String]) -> bool {
+        matches_steps(&self.steps, path)
+    }
+}
+
+fn matches_steps(steps: &[Step], path: &[String]) -> bool {
+    let Some((step, rest)) = steps.split_first() else {
+        return path.is_empty();
+    };
+    let name_matches = |name: &String| step.name.as_ref().is_none_or(|expected| expected == name);
+    if step.descendant {
+        (0..path.len()).any(|i| name_matches(&path[i]) && matches_steps(rest, &path[i + 1..]))
+    } else {
+        path.first().is_some_and(name_matches) && matches_steps(rest, &path[1..])
+    }
+}
+
+/// Parse the part of a JSONPath-like selector after `$`.
+fn parse_jsonpath(selector: &str) -> Result<Vec<Step>, &'static str> {
+    let mut steps = Vec::new();
+    let mut rest = selector;
+    while !rest.is_empty() {
+        let descendant = rest.starts_with("..");
+        if descendant {
+            rest = &rest[2..];
+        } else if let Some(after) = rest.strip_prefix('.') {
+            rest = after;
+        } else if !rest.starts_with('[') {
+            return Err("expected '.', '..' or '['");
+        }
+
+        let name;
+        if let Some(bracket) = rest.strip_prefix('[') {
+            let end = bracket.find(']').ok_or("unclosed '['")?;
+            let inner = bracket[..end].trim();
+            name = if inner == "*" {
+                None
+            } else if let Some(quoted) = unquote(inner) {
+                Some(quoted.to_string())
+            } else if !inner.is_empty() && inner.bytes().all(|b| b.is_ascii_digit()) {
+                Some(inner.to_string())
+            } else {
+                return Err("expected '*', an index or a quoted name in brackets");
+            };
+            rest = &bracket[end + 1..];
+        } else {
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            name = match &rest[..end] {
+                "" => return Err("expected a name"),
+                "*" => None,
+                name => Some(name.to_string()),
+            };
+            rest = &rest[end..];
+        }
+        steps.push(Step { descendant, name });
+    }
+    Ok(steps)
+}
+
+/// Parse an XPath-like selector; `@` before attribute names is optional.
+fn parse_xpath(selector: &str) -> Result<Vec<Step>, &'static str> {
+    let mut steps = Vec::new();
+    let mut descendant = false;
+    // The selector starts with '/', so the first part is empty
+    for part in selector.split('/').skip(1) {
+        if part.is_empty() {
+            if descendant {
+                return Err("'///' isn't allowed");
+            }
+            descendant = true;
+            continue;
+        }
+        if part.contains(['[', ']']) {
+            return Err("predicates aren't supported");
+        }
+        let name = part.strip_prefix('@').unwrap_or(part);
+        steps.push(Step {
+            descendant,
+            name: (name != "*").then(|| name.to_string()),
+        });
+        descendant = false;
+    }
+    if descendant || steps.is_empty() {
+        return Err("expected a name after '/'");
+    }
+    Ok(steps)
+}
+
+/// The text between matching single or double quotes.
+fn unquote(text: &str) -> Option<&str> {
+    ['\'', '"']
+        .into_iter()
+        .find_map(|quote| text.strip_prefix(quote)?.strip_suffix(quote))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selects(selector: &str, path: &str) -> bool {
+        let path: Vec<String> = path.split('/').filter(|s| !s.is_empty()).map(String::from).collect();
+        Selector::parse(selector).unwrap().matches(&path)
+    }
+
+    #[test]
+    fn test_forms_are_equivalent() {
+        for selector in ["$.users[*].email", "$['users'][*][\"email\"]", "/users/*/email", "$.users.*.email"] {
+            assert!(selects(selector, "users/0/email"), "{}", selector);
+            assert!(!selects(selector, "users/email"), "{}", selector);
+            assert!(!selects(selector, "users/0/email/x"), "{}", selector);
+        }
+        for selector in ["email", "$..email", "//email", "//@email"] {
+            assert!(selects(selector, "email"), "{}", selector);
+            assert!(selects(selector, "3/email"), "{}", selector);
+            assert!(!selects(selector, "email/3"), "{}", selector);
+        }
+        assert!(selects("$.a..c[1]", "a/b/b/c/1"));
+        assert!(selects("$", ""));
+        assert!(selects("order id", "7/order id"));
+    }
+
+    #[test]
+    fn test_invalid_selectors() {
+        for selector in ["", "$.", "$.a[", "$.a[b]", "$a", "/a/", "/a///b", "/a[1]"] {
+            let err = Selector::parse(selector).unwrap_err();
+            assert!(matches!(err, AnonymaskError::InvalidConfig(_)), "{}", selector);
+        }
+    }
+}
@@ -0,0 +1,482 @@
+use crate::session::AnonymizationSession;
+use serde::ser::{
+    self, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant, Serializer,
+};
+use std::cell::RefCell;
+use std::fmt::Display;
+
+/// Serializer adapter that anonymizes every string on the way out.
+///
+/// Wraps any serde `Serializer` (JSON, YAML, MessagePack, ...) so that a
+/// `#[derive(Serialize)]` value is written with each string, including map
+/// keys, anonymized as text. Field names, numbers and all other values are
+/// passed through unchanged. Placeholders come from an
+/// [`AnonymizationSession`], so a value gets the same placeholder wherever
+/// it appears and the session's mapping can restore the output.
+///
+/// [`AnonymizationSession::serialize`] covers the common case; construct
+/// the adapter directly to hand it to code that expects a `Serializer`.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::{Anonymizer, AnonymizingSerializer, EntityType};
+/// use serde::Serialize;
+/// use std::cell::RefCell;
+///
+/// #[derive(Serialize)]
+/// struct AuditEvent {
+///     actor: String,
+///     action: String,
+///     status: u16,
+/// }
+///
+/// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+/// let session = RefCell::new(anonymizer.session());
+/// let event = AuditEvent { actor: "jo@x.io".into(), action: "mailed ann@y.org".into(), status: 200 };
+///
+/// let mut out = Vec::new();
+/// event
+///     .serialize(AnonymizingSerializer::new(&mut serde_json::Serializer::new(&mut out), &session))
+///     .unwrap();
+///
+/// let json = String::from_utf8(out).unwrap();
+/// assert!(!json.contains("jo@x.io") && json.contains("\"status\":200"));
+/// assert_eq!(session.borrow().deanonymize(&json), r#"{"actor":"jo@x.io","action":"mailed ann@y.org","status":200}"#);
+/// ```
+pub struct AnonymizingSerializer<'s, 'a, S> {
+    inner: S,
+    session: &'s RefCell<AnonymizationSession<'a>>,
+}
+
+impl<'s, 'a, S> AnonymizingSerializer<'s, 'a, S> {
+    /// Anonymize what `inner` writes with placeholders from `session`.
+    pub fn new(inner: S, session: &'s RefCell<AnonymizationSession<'a>>) -> Self {
+        Self { inner, session }
+    }
+}
+
+impl<'a> AnonymizationSession<'a> {
+    /// Serialize `value` with `serializer`, anonymizing every string; see
+    /// [`AnonymizingSerializer`].
+    ///
+    /// # Errors
+    ///
+    /// The errors of `serializer`; anonymization errors are converted with
+    /// its `custom` constructor.
+    pub fn serialize<T, S>(&mut self, value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize + ?Sized,
+        S: Serializer,
+    {
+        let session = RefCell::new(std::mem::replace(self, AnonymizationSession::new(self.anonymizer)));
+        let result = value.serialize(AnonymizingSerializer::new(serializer, &session));
+        *self = session.into_inner();
+        result
+    }
+}
+
+/// A value serialized through the adapter.
+struct Wrapped<'v, 's, 'a, T: ?Sized> {
+    value: &'v T,
+    session: &'s RefCell<AnonymizationSession<'a>>,
+}
+
+impl<T: Serialize + ?Sized> Serialize for Wrapped<'_, '_, '_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(AnonymizingSerializer::new(serializer, self.session))
+    }
+}
+
+/// A compound serializer whose elements go through the adapter.
+pub struct Compound<'s, 'a, C> {
+    inner: C,
+    session: &'s RefCell<AnonymizationSession<'a>>,
+}
+
+impl<'s, 'a, C> Compound<'s, 'a, C> {
+    fn wrap<'v, T: ?Sized>(&self, value: &'v T) -> Wrapped<'v, 's, 'a, T> {
+        Wrapped {
+            value,
+            session: self.session,
+        }
+    }
+}
+
+impl<'s, 'a, S: Serializer> AnonymizingSerializer<'s, 'a, S> {
+    fn wrap<'v, T: ?Sized>(&self, value: &'v T) -> Wrapped<'v, 's, 'a, T> {
+        Wrapped {
+            value,
+            session: self.session,
+        }
+    }
+}
+
+impl<'s, 'a, S: Serializer> Serializer for AnonymizingSerializer<'s, 'a, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Compound<'s, 'a, S::SerializeSeq>;
+    type SerializeTuple = Compound<'s, 'a, S::SerializeTuple>;
+    type SerializeTupleStruct = Compound<'s, 'a, S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Compound<'s, 'a, S::SerializeTupleVariant>;
+    type SerializeMap = Compound<'s, 'a, S::SerializeMap>;
+    type SerializeStruct = Compound<'s, 'a, S::SerializeStruct>;
+    type SerializeStructVariant = Compound<'s, 'a, S::SerializeStructVariant>;
+
+    fn serialize_str(self, v: &str) -> Result<S::Ok, S::Error> {
+        let anonymized = self
+            .session
+            .borrow_mut()
+            .anonymize(v)
+            .map_err(ser::Error::custom)?
+            .anonymized_text;
+        self.inner.serialize_str(&anonymized)
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_bool(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i8(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i16(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i32(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i64(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i128(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u8(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u16(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u32(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u64(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u128(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_f32(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_f64(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_char(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_bytes(v)
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_none()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_some(&value)
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_newtype_struct(name, &value)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_newtype_variant(name, variant_index, variant, &value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        let session = self.session;
+        Ok(Compound {
+            inner: self.inner.serialize_seq(len)?,
+            session,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        let session = self.session;
+        Ok(Compound {
+            inner: self.inner.serialize_tuple(len)?,
+            session,
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        let session = self.session;
+        Ok(Compound {
+            inner: self.inner.serialize_tuple_struct(name, len)?,
+            session,
+        })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        let session = self.session;
+        Ok(Compound {
+            inner: self.inner.serialize_tuple_variant(name, variant_index, variant, len)?,
+            session,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        let session = self.session;
+        Ok(Compound {
+            inner: self.inner.serialize_map(len)?,
+            session,
+        })
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct, S::Error> {
+        let session = self.session;
+        Ok(Compound {
+            inner: self.inner.serialize_struct(name, len)?,
+            session,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        let session = self.session;
+        Ok(Compound {
+            inner: self.inner.serialize_struct_variant(name, variant_index, variant, len)?,
+            session,
+        })
+    }
+
+    fn collect_str<T: Display + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.serialize_str(&value.to_string())
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+impl<S: SerializeSeq> SerializeSeq for Compound<'_, '_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_element(&value)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: SerializeTuple> SerializeTuple for Compound<'_, '_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_element(&value)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: SerializeTupleStruct> SerializeTupleStruct for Compound<'_, '_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_field(&value)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: SerializeTupleVariant> SerializeTupleVariant for Compound<'_, '_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_field(&value)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: SerializeMap> SerializeMap for Compound<'_, '_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), S::Error> {
+        let key = self.wrap(key);
+        self.inner.serialize_key(&key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_value(&value)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: SerializeStruct> SerializeStruct for Compound<'_, '_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), S::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_field(key, &value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: SerializeStructVariant> SerializeStructVariant for Compound<'_, '_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), S::Error> {
+        let value = self.wrap(value);
+        self.inner.serialize_field(key, &value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::anonymizer::Anonymizer;
+    use crate::entity::EntityType;
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    enum Target {
+        Mailbox { address: String },
+        Phone(String, u8),
+    }
+
+    #[derive(Serialize)]
+    struct Event {
+        actor: Option<String>,
+        targets: Vec<Target>,
+        tags: BTreeMap<String, f64>,
+        retries: u32,
+    }
+
+    #[test]
+    fn test_anonymizes_nested_strings() {
+        let anonymizer = Anonymizer::new(vec![EntityType::Email, EntityType::Phone]).unwrap();
+        let mut session = anonymizer.session();
+        let event = Event {
+            actor: Some("jo@x.io".to_string()),
+            targets: vec![
+                Target::Mailbox { address: "jo@x.io".to_string() },
+                Target::Phone("555-123-4567".to_string(), 1),
+            ],
+            tags: [("ann@y.org".to_string(), 0.5)].into(),
+            retries: 3,
+        };
+
+        let json = session.serialize(&event, serde_json::value::Serializer).unwrap();
+        let placeholder = json["actor"].as_str().unwrap();
+        assert!(placeholder.starts_with("EMAIL_"));
+        assert_eq!(json["targets"][0]["Mailbox"]["address"], placeholder);
+        assert!(json["targets"][1]["Phone"][0].as_str().unwrap().starts_with("PHONE_"));
+        assert_eq!(json["targets"][1]["Phone"][1], 1);
+        assert_eq!(json["retries"], 3);
+
+        let restored: serde_json::Value = serde_json::from_str(&session.deanonymize(&json.to_string())).unwrap();
+        assert_eq!(restored, serde_json::to_value(&event).unwrap());
+        assert_eq!(session.len(), 3);
+    }
+}
@@ -0,0 +1,184 @@
+use crate::anonymizer::Anonymizer;
+use crate::entity::{AnonymizationResult, EntityType, Status};
+use crate::error::AnonymaskError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Stateful anonymization across multiple calls.
+///
+/// Accumulates the mapping over several `anonymize()` calls, as in a
+/// multi-turn conversation proxied to an LLM: a value seen in turn 1 receives
+/// the same placeholder when it reappears in turn 5, and model replies can be
+/// restored against everything seen so far.
+///
+/// Created with [`Anonymizer::session`].
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::Anonymizer;
+/// use anonymask_core::entity::EntityType;
+///
+/// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+/// let mut session = anonymizer.session();
+///
+/// let turn1 = session.anonymize("I'm john@email.com").unwrap();
+/// let turn2 = session.anonymize("Did you email john@email.com?").unwrap();
+///
+/// let placeholder = turn1.mapping.keys().next().unwrap();
+/// assert!(turn2.anonymized_text.contains(placeholder.as_str()));
+///
+/// let reply = format!("Yes, I emailed {}", placeholder);
+/// assert_eq!(session.deanonymize(&reply), "Yes, I emailed john@email.com");
+/// ```
+pub struct AnonymizationSession<'a> {
+    pub(crate) anonymizer: &'a Anonymizer,
+    /// Original value -> placeholder, accumulated over all calls
+    values: HashMap<String, String>,
+}
+
+impl<'a> AnonymizationSession<'a> {
+    pub(crate) fn new(anonymizer: &'a Anonymizer) -> Self {
+        Self {
+            anonymizer,
+            values: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn import(anonymizer: &'a Anonymizer, state: &SessionState) -> Self {
+        anonymizer.replacer().advance_counters(state.counter, &state.indices);
+        Self {
+            anonymizer,
+            values: state.values.clone(),
+        }
+    }
+
+    pub(crate) fn resume(anonymizer: &'a Anonymizer, mapping: &HashMap<String, String>) -> Self {
+        Self {
+            anonymizer,
+            values: mapping
+                .iter()
+                .map(|(placeholder, original)| (original.clone(), placeholder.clone()))
+                .collect(),
+        }
+    }
+
+    /// Anonymize text, reusing placeholders for previously seen values.
+    ///
+    /// The returned mapping covers the placeholders used in this text; the
+    /// accumulated mapping is available via [`mapping`](Self::mapping).
+    pub fn anonymize(&mut self, text: &str) -> Result<AnonymizationResult, AnonymaskError> {
+        self.anonymize_with_custom(text, None)
+    }
+
+    /// Anonymize text with custom entities, reusing placeholders for previously seen values.
+    pub fn anonymize_with_custom(
+        &mut self,
+        text: &str,
+        custom_entities: Option<&HashMap<EntityType, Vec<String>>>,
+    ) -> Result<AnonymizationResult, AnonymaskError> {
+        let limit = self.anonymizer.entity_limit();
+        let anonymized =
+            self.anonymizer
+                .anonymize_shared(text, custom_entities, None, &mut self.values, None, limit)?;
+
+        let mapping = anonymized
+            .entities
+            .iter()
+            .map(|entity| (self.values[&entity.value].clone(), entity.value.clone()))
+            .collect();
+
+        Ok(self.anonymizer.finish(AnonymizationResult {
+            anonymized_text: anonymized.text,
+            mapping,
+            entities: anonymized.entities,
+            output_spans: anonymized.output_spans,
+            subject_ids: Vec::new(),
+            contexts: Vec::new(),
+            status: Status::Complete,
+            truncated: anonymized.truncated,
+            budget_exceeded: anonymized.budget_exceeded,
+            trace: anonymized.trace,
+        }))
+    }
+
+    /// Restore original values using the mapping accumulated so far.
+    pub fn deanonymize(&self, text: &str) -> String {
+        self.anonymizer.deanonymize(text, &self.mapping())
+    }
+
+    /// The accumulated placeholder -> original value mapping.
+    pub fn mapping(&self) -> HashMap<String, String> {
+        self.values
+            .iter()
+            .map(|(original, placeholder)| (placeholder.clone(), original.clone()))
+            .collect()
+    }
+
+    /// Export the session's state, to continue it with
+    /// [`Anonymizer::import_session`] on another node.
+    pub fn export_state(&self) -> SessionState {
+        let (counter, indices) = self.anonymizer.replacer().counters();
+        SessionState {
+            values: self.values.clone(),
+            counter,
+            indices,
+        }
+    }
+
+    /// Number of distinct values seen in this session.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether no values have been anonymized yet.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// The state of an [`AnonymizationSession`], exported to continue it on
+/// another node.
+///
+/// Lets a load-balanced fleet keep placeholders consistent for one logical
+/// conversation without a shared database: serialize the state after each
+/// request (e.g. with serde_json into a cookie or the client's session
+/// store) and import it on whichever node takes the next. Unlike
+/// [`Anonymizer::resume_session`], importing also advances the placeholder
+/// counters past every value the exporting node handed out, so `Short`,
+/// `{counter}` and `{index_by_type}` placeholders of new values can't
+/// collide with those already in the session.
+///
+/// The state holds the original values; protect it like the mapping.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::{Anonymizer, AnonymizerConfig, EntityType, PlaceholderFormat, SessionState};
+///
+/// let config = AnonymizerConfig::builder().with_placeholder_format(PlaceholderFormat::Short).build();
+/// let node_a = Anonymizer::with_config(vec![EntityType::Email], config.clone()).unwrap();
+/// let node_b = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+///
+/// let mut session = node_a.session();
+/// session.anonymize("From a@b.io").unwrap();
+/// let state = serde_json::to_string(&session.export_state()).unwrap();
+///
+/// let state: SessionState = serde_json::from_str(&state).unwrap();
+/// let mut session = node_b.import_session(&state);
+/// let result = session.anonymize("From a@b.io to c@d.io").unwrap();
+/// assert_eq!(result.anonymized_text, "From EMAIL_1 to EMAIL_2");
+/// ```
+#[derive(Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SessionState {
+    /// Original value -> placeholder, for every value seen so far
+    pub values: HashMap<String, String>,
+    /// Placeholder counter of the exporting anonymizer
+    #[serde(default)]
+    pub counter: usize,
+    /// Last `{index_by_type}` of each type handed out by the exporting
+    /// anonymizer, by uppercase type name
+    #[serde(default)]
+    pub indices: HashMap<String, usize>,
+}
@@ -0,0 +1,266 @@
+//! Persistent storage for placeholder mappings.
+//!
+//! A [`MappingStore`] keeps mappings under a caller-chosen key (a document,
+//! conversation or request id) so they survive process restarts and can be
+//! shared between the service that anonymizes and the one that restores.
+//!
+//! Built-in backends:
+//!
+//! - [`MemoryStore`]: in-process, for tests and single-process use
+//! - [`JsonFileStore`]: a single JSON file, locked and rewritten atomically
+//! - `SledStore`: embedded sled database (feature `sled`)
+//! - `SqliteStore`: SQLite database (feature `sqlite`)
+//!
+//! To anonymize several documents as one unit, stage them in a [`Batch`]
+//! and commit it once: the store gets every mapping or none.
+
+use crate::error::AnonymaskError;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+mod batch;
+mod json;
+#[cfg(feature = "sled")]
+mod sled;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use batch::Batch;
+pub use json::JsonFileStore;
+#[cfg(feature = "sled")]
+pub use self::sled::SledStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+/// Storage backend for placeholder mappings.
+///
+/// Implementations must be safe to share between threads; all methods take
+/// `&self`.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::{Anonymizer, EntityType};
+/// use anonymask_core::store::{MappingStore, MemoryStore};
+///
+/// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+/// let store = MemoryStore::new();
+///
+/// let result = anonymizer.anonymize("Contact john@email.com").unwrap();
+/// store.save("ticket-42", &result.mapping).unwrap();
+///
+/// // Later, possibly in another process
+/// let mapping = store.load("ticket-42").unwrap().unwrap();
+/// let restored = anonymizer.deanonymize(&result.anonymized_text, &mapping);
+/// assert_eq!(restored, "Contact john@email.com");
+/// ```
+pub trait MappingStore: Send + Sync {
+    /// Save a mapping under a key.
+    ///
+    /// Entries are merged into any mapping already stored under the key;
+    /// existing placeholders are overwritten.
+    fn save(&self, key: &str, mapping: &HashMap<String, String>) -> Result<(), AnonymaskError>;
+
+    /// Save several mappings as one transaction: all of them or none.
+    ///
+    /// Entries are merged as with [`save`](Self::save). The default
+    /// implementation saves one key at a time and, if a save fails, puts
+    /// back what was stored before under the keys already written. That
+    /// covers errors but not a crash midway; the built-in backends override
+    /// it with a single atomic write.
+    fn save_all(&self, mappings: &[(&str, &HashMap<String, String>)]) -> Result<(), AnonymaskError> {
+        let mut previous = Vec::with_capacity(mappings.len());
+        for (key, mapping) in mappings {
+            previous.push((*key, self.load(key)?));
+            if let Err(e) = self.save(key, mapping) {
+                for (key, mapping) in previous.into_iter().rev() {
+                    let _ = self.delete(key);
+                    if let Some(mapping) = mapping {
+                        let _ = self.save(key, &mapping);
+                    }
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the mapping stored under a key, if any.
+    fn load(&self, key: &str) -> Result<Option<HashMap<String, String>>, AnonymaskError>;
+
+    /// Look up the original value of a single placeholder.
+    fn lookup(&self, key: &str, placeholder: &str) -> Result<Option<String>, AnonymaskError> {
+        Ok(self
+            .load(key)?
+            .and_then(|mut mapping| mapping.remove(placeholder)))
+    }
+
+    /// Delete the mapping stored under a key. Returns `false` if there was none.
+    fn delete(&self, key: &str) -> Result<bool, AnonymaskError>;
+
+    /// All keys with a stored mapping.
+    fn keys(&self) -> Result<Vec<String>, AnonymaskError>;
+}
+
+/// In-memory mapping store.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    mappings: Mutex<HashMap<String, HashMap<String, String>>>,
+}
+
+impl MemoryStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl MappingStore for MemoryStore {
+    fn save(&self, key: &str, mapping: &HashMap<String, String>) -> Result<(), AnonymaskError> {
+        let mut mappings = lock(&self.mappings)?;
+        mappings
+            .entry(key.to_string())
+            .or_default()
+            .extend(mapping.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Ok(())
+    }
+
+    fn save_all(&self, batch: &[(&str, &HashMap<String, String>)]) -> Result<(), AnonymaskError> {
+        let mut mappings = lock(&self.mappings)?;
+        for (key, mapping) in batch {
+            mappings
+                .entry(key.to_string())
+                .or_default()
+                .extend(mapping.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        Ok(())
+    }
+
+    fn load(&self, key: &str) -> Result<Option<HashMap<String, String>>, AnonymaskError> {
+        Ok(lock(&self.mappings)?.get(key).cloned())
+    }
+
+    fn lookup(&self, key: &str, placeholder: &str) -> Result<Option<String>, AnonymaskError> {
+        Ok(lock(&self.mappings)?
+            .get(key)
+            .and_then(|mapping| mapping.get(placeholder))
+            .cloned())
+    }
+
+    fn delete(&self, key: &str) -> Result<bool, AnonymaskError> {
+        Ok(lock(&self.mappings)?.remove(key).is_some())
+    }
+
+    fn keys(&self) -> Result<Vec<String>, AnonymaskError> {
+        Ok(lock(&self.mappings)?.keys().cloned().collect())
+    }
+}
+
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> Result<std::sync::MutexGuard<'_, T>, AnonymaskError> {
+    mutex
+        .lock()
+        .map_err(|_| AnonymaskError::StorageError("store lock poisoned".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    /// Behaviour every backend must satisfy.
+    pub(crate) fn check_store(store: &dyn MappingStore) {
+        assert_eq!(store.load("doc").unwrap(), None);
+
+        store.save("doc", &mapping(&[("EMAIL_1", "a@b.io")])).unwrap();
+        store.save("doc", &mapping(&[("PHONE_2", "555-1234")])).unwrap();
+        store.save("other", &mapping(&[("EMAIL_1", "c@d.io")])).unwrap();
+
+        assert_eq!(
+            store.load("doc").unwrap().unwrap(),
+            mapping(&[("EMAIL_1", "a@b.io"), ("PHONE_2", "555-1234")])
+        );
+        assert_eq!(store.lookup("other", "EMAIL_1").unwrap().as_deref(), Some("c@d.io"));
+        assert_eq!(store.lookup("other", "PHONE_2").unwrap(), None);
+
+        let mut keys = store.keys().unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["doc", "other"]);
+
+        assert!(store.delete("doc").unwrap());
+        assert!(!store.delete("doc").unwrap());
+        assert_eq!(store.load("doc").unwrap(), None);
+
+        let first = mapping(&[("EMAIL_1", "e@f.io")]);
+        let second = mapping(&[("PHONE_2", "555-9876")]);
+        store.save_all(&[("doc", &first), ("other", &second)]).unwrap();
+        assert_eq!(store.load("doc").unwrap().unwrap(), first);
+        assert_eq!(
+            store.load("other").unwrap().unwrap(),
+            mapping(&[("EMAIL_1", "c@d.io"), ("PHONE_2", "555-9876")])
+        );
+    }
+
+    #[test]
+    fn test_memory_store() {
+        check_store(&MemoryStore::new());
+    }
+
+    #[test]
+    fn test_json_file_store_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mappings.json");
+        check_store(&JsonFileStore::open(&path).unwrap());
+
+        let store = JsonFileStore::open(&path).unwrap();
+        store.save("doc", &mapping(&[("EMAIL_1", "a@b.io")])).unwrap();
+        drop(store);
+
+        let reopened = JsonFileStore::open(&path).unwrap();
+        assert_eq!(reopened.lookup("doc", "EMAIL_1").unwrap().as_deref(), Some("a@b.io"));
+    }
+
+    #[test]
+    fn test_json_file_stores_share_updates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mappings.json");
+        let first = JsonFileStore::open(&path).unwrap();
+        let second = JsonFileStore::open(&path).unwrap();
+
+        first.save("doc", &mapping(&[("EMAIL_1", "a@b.io")])).unwrap();
+        second.save("doc", &mapping(&[("PHONE_2", "555-1234")])).unwrap();
+        assert_eq!(
+            first.load("doc").unwrap().unwrap(),
+            mapping(&[("EMAIL_1", "a@b.io"), ("PHONE_2", "555-1234")])
+        );
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let path = &path;
+                scope.spawn(move || {
+                    let store = JsonFileStore::open(path).unwrap();
+                    store.save(&format!("doc-{}", i), &mapping(&[("EMAIL_1", "a@b.io")])).unwrap();
+                });
+            }
+        });
+        assert_eq!(second.keys().unwrap().len(), 9);
+    }
+
+    #[cfg(feature = "sled")]
+    #[test]
+    fn test_sled_store() {
+        let dir = tempfile::tempdir().unwrap();
+        check_store(&SledStore::open(dir.path().join("db")).unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_store() {
+        check_store(&SqliteStore::open_in_memory().unwrap());
+    }
+}
@@ -0,0 +1,255 @@
+use super::MappingStore;
+use crate::anonymizer::Anonymizer;
+use crate::entity::{AnonymizationResult, EntityType, Status};
+use crate::error::AnonymaskError;
+use crate::replacer::Counters;
+use std::collections::HashMap;
+
+/// Several documents anonymized under one transaction against a store.
+///
+/// Mappings are staged in memory while documents are anonymized and only
+/// written by [`commit`](Self::commit), all at once: either every
+/// document's mapping is stored or none is. A job that fails or crashes
+/// halfway leaves the store as it was, instead of holding mappings for
+/// some documents while the rest can't be restored.
+///
+/// Anonymized texts reference mappings that don't exist in the store until
+/// the commit succeeds, so don't publish them before. Dropping the batch
+/// without committing discards the staged mappings.
+///
+/// Created with [`Anonymizer::batch`].
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::{Anonymizer, EntityType};
+/// use anonymask_core::store::{MappingStore, MemoryStore};
+///
+/// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+/// let store = MemoryStore::new();
+///
+/// let mut batch = anonymizer.batch(&store);
+/// let first = batch.anonymize("doc-1", "From john@email.com").unwrap();
+/// batch.anonymize("doc-2", "To jane@email.com").unwrap();
+/// assert!(store.keys().unwrap().is_empty());
+///
+/// batch.commit().unwrap();
+/// let mapping = store.load("doc-1").unwrap().unwrap();
+/// assert_eq!(anonymizer.deanonymize(&first.anonymized_text, &mapping), "From john@email.com");
+/// ```
+pub struct Batch<'a> {
+    anonymizer: &'a Anonymizer,
+    store: &'a dyn MappingStore,
+    /// Staged mappings by store key, in first-use order
+    staged: Vec<Staged>,
+}
+
+/// The documents of one store key so far.
+struct Staged {
+    key: String,
+    /// Placeholder -> original value, as stored on commit
+    mapping: HashMap<String, String>,
+    /// Original value -> placeholder, shared by the key's documents
+    values: HashMap<String, String>,
+    /// Counters of the key, when counters are reset per operation
+    counters: Option<Counters>,
+}
+
+impl<'a> Batch<'a> {
+    pub(crate) fn new(anonymizer: &'a Anonymizer, store: &'a dyn MappingStore) -> Self {
+        Self {
+            anonymizer,
+            store,
+            staged: Vec::new(),
+        }
+    }
+
+    /// Anonymize a document, staging its mapping under `key`.
+    pub fn anonymize(&mut self, key: impl Into<String>, text: &str) -> Result<AnonymizationResult, AnonymaskError> {
+        self.anonymize_with_custom(key, text, None)
+    }
+
+    /// Anonymize a document with custom entities, staging its mapping under `key`.
+    ///
+    /// Documents anonymized under the same key share one stored mapping,
+    /// so, as in a session, a value keeps its placeholder across them and
+    /// new values are numbered on from the key's earlier documents.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Anonymizer::anonymize`], or
+    /// `AnonymaskError::AnonymizationError` if a placeholder of the
+    /// document already stands for a different value under `key`, as
+    /// subject placeholders can.
+    pub fn anonymize_with_custom(
+        &mut self,
+        key: impl Into<String>,
+        text: &str,
+        custom_entities: Option<&HashMap<EntityType, Vec<String>>>,
+    ) -> Result<AnonymizationResult, AnonymaskError> {
+        let key = key.into();
+        let index = match self.staged.iter().position(|staged| staged.key == key) {
+            Some(index) => index,
+            None => {
+                self.staged.push(Staged {
+                    key,
+                    mapping: HashMap::new(),
+                    values: HashMap::new(),
+                    counters: self.anonymizer.operation_counters(),
+                });
+                self.staged.len() - 1
+            }
+        };
+        let staged = &mut self.staged[index];
+
+        let limit = self.anonymizer.entity_limit();
+        let anonymized = self.anonymizer.anonymize_shared(
+            text,
+            custom_entities,
+            None,
+            &mut staged.values,
+            staged.counters.as_mut(),
+            limit,
+        )?;
+        let mapping = anonymized
+            .entities
+            .iter()
+            .map(|entity| (staged.values[&entity.value].clone(), entity.value.clone()))
+            .collect();
+        let result = self.anonymizer.finish(AnonymizationResult {
+            anonymized_text: anonymized.text,
+            mapping,
+            entities: anonymized.entities,
+            output_spans: anonymized.output_spans,
+            subject_ids: Vec::new(),
+            contexts: Vec::new(),
+            status: Status::Complete,
+            truncated: anonymized.truncated,
+            budget_exceeded: anonymized.budget_exceeded,
+            trace: anonymized.trace,
+        });
+
+        for (placeholder, original) in &result.mapping {
+            if staged.mapping.get(placeholder).is_some_and(|staged| staged != original) {
+                return Err(AnonymaskError::AnonymizationError(format!(
+                    "placeholder '{}' stands for different values under key '{}'",
+                    placeholder, staged.key
+                )));
+            }
+        }
+        staged.mapping.extend(result.mapping.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Ok(result)
+    }
+
+    /// Number of keys with a staged mapping.
+    pub fn len(&self) -> usize {
+        self.staged.len()
+    }
+
+    /// Whether nothing is staged.
+    pub fn is_empty(&self) -> bool {
+        self.staged.is_empty()
+    }
+
+    /// Write all staged mappings to the store in one transaction.
+    ///
+    /// Returns the number of keys written.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::StorageError` if the store fails; nothing
+    /// is stored in that case.
+    pub fn commit(self) -> Result<usize, AnonymaskError> {
+        let mappings: Vec<(&str, &HashMap<String, String>)> = self
+            .staged
+            .iter()
+            .map(|staged| (staged.key.as_str(), &staged.mapping))
+            .collect();
+        self.store.save_all(&mappings)?;
+        Ok(mappings.len())
+    }
+
+    /// Discard the staged mappings. Same as dropping the batch.
+    pub fn rollback(self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AnonymizerConfig, PlaceholderFormat};
+    use crate::store::MemoryStore;
+
+    /// Fails every write, as a full disk would.
+    struct FailingStore(MemoryStore);
+
+    impl MappingStore for FailingStore {
+        fn save(&self, _: &str, _: &HashMap<String, String>) -> Result<(), AnonymaskError> {
+            Err(AnonymaskError::StorageError("disk full".to_string()))
+        }
+
+        fn load(&self, key: &str) -> Result<Option<HashMap<String, String>>, AnonymaskError> {
+            self.0.load(key)
+        }
+
+        fn delete(&self, key: &str) -> Result<bool, AnonymaskError> {
+            self.0.delete(key)
+        }
+
+        fn keys(&self) -> Result<Vec<String>, AnonymaskError> {
+            self.0.keys()
+        }
+    }
+
+    #[test]
+    fn test_commit_merges_documents_by_key() {
+        let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+        let store = MemoryStore::new();
+        let mut batch = anonymizer.batch(&store);
+        batch.anonymize("a", "x@y.io").unwrap();
+        batch.anonymize("a", "z@y.io").unwrap();
+        batch.anonymize("b", "x@y.io").unwrap();
+        assert_eq!(batch.len(), 2);
+
+        assert_eq!(batch.commit().unwrap(), 2);
+        assert_eq!(store.load("a").unwrap().unwrap().len(), 2);
+        assert_eq!(store.load("b").unwrap().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_documents_under_one_key_share_counters() {
+        let config = AnonymizerConfig::builder()
+            .with_placeholder_format(PlaceholderFormat::Short)
+            .with_counter_reset(true)
+            .build();
+        let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+        let store = MemoryStore::new();
+        let mut batch = anonymizer.batch(&store);
+        let first = batch.anonymize("a", "x@y.io").unwrap();
+        let second = batch.anonymize("a", "z@y.io and x@y.io").unwrap();
+        let other = batch.anonymize("b", "z@y.io").unwrap();
+        assert_eq!(first.anonymized_text, "EMAIL_1");
+        assert_eq!(second.anonymized_text, "EMAIL_2 and EMAIL_1");
+        assert_eq!(other.anonymized_text, "EMAIL_1");
+
+        batch.commit().unwrap();
+        let mapping = store.load("a").unwrap().unwrap();
+        assert_eq!(mapping.len(), 2);
+        assert_eq!(anonymizer.deanonymize(&second.anonymized_text, &mapping), "z@y.io and x@y.io");
+    }
+
+    #[test]
+    fn test_failed_or_abandoned_batches_store_nothing() {
+        let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+        let store = FailingStore(MemoryStore::new());
+        let mut batch = anonymizer.batch(&store);
+        batch.anonymize("a", "x@y.io").unwrap();
+        assert!(batch.commit().is_err());
+        assert!(store.keys().unwrap().is_empty());
+
+        let store = MemoryStore::new();
+        let mut batch = anonymizer.batch(&store);
+        batch.anonymize("a", "x@y.io").unwrap();
+        batch.rollback();
+        assert!(store.keys().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+use super::MappingStore;
+use crate::error::AnonymaskError;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+type Mappings = HashMap<String, HashMap<String, String>>;
+
+/// Mapping store backed by a single JSON file.
+///
+/// Every operation reads the file under a lock on a `.lock` file next to
+/// it, and every change rewrites it via a temporary file and rename, so
+/// several processes (or stores) can share the file without losing each
+/// other's updates, and readers never see a half-written file. Suited to
+/// small deployments and tooling; use a database backend for large or
+/// highly concurrent workloads.
+#[derive(Debug)]
+pub struct JsonFileStore {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl JsonFileStore {
+    /// Open a store at `path`, creating an empty one if the file doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AnonymaskError> {
+        let path = path.as_ref().to_path_buf();
+        let mut lock_path = path.clone().into_os_string();
+        lock_path.push(".lock");
+        let store = Self {
+            path,
+            lock_path: lock_path.into(),
+        };
+        // Fail early on a file that isn't a mapping store
+        store.read()?;
+        Ok(store)
+    }
+
+    /// Lock the store, shared for reading or exclusive for changes; the
+    /// lock is released when the returned file is dropped.
+    fn lock(&self, exclusive: bool) -> Result<File, AnonymaskError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&self.lock_path)
+            .map_err(|e| io_error(&self.lock_path, e))?;
+        let locked = if exclusive { file.lock() } else { file.lock_shared() };
+        locked.map_err(|e| io_error(&self.lock_path, e))?;
+        Ok(file)
+    }
+
+    fn read(&self) -> Result<Mappings, AnonymaskError> {
+        let _lock = self.lock(false)?;
+        self.load_file()
+    }
+
+    /// Apply `change` to the current contents and write them back if it
+    /// returns `true`.
+    fn update(&self, change: impl FnOnce(&mut Mappings) -> bool) -> Result<bool, AnonymaskError> {
+        let _lock = self.lock(true)?;
+        let mut mappings = self.load_file()?;
+        if !change(&mut mappings) {
+            return Ok(false);
+        }
+        self.persist(&mappings)?;
+        Ok(true)
+    }
+
+    fn load_file(&self) -> Result<Mappings, AnonymaskError> {
+        match fs::read(&self.path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                AnonymaskError::StorageError(format!("invalid mapping file {}: {}", self.path.display(), e))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Mappings::new()),
+            Err(e) => Err(io_error(&self.path, e)),
+        }
+    }
+
+    fn persist(&self, mappings: &Mappings) -> Result<(), AnonymaskError> {
+        let bytes = serde_json::to_vec(mappings)
+            .map_err(|e| AnonymaskError::StorageError(format!("failed to encode mappings: {}", e)))?;
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, bytes).map_err(|e| io_error(&self.path, e))?;
+        fs::rename(&tmp, &self.path).map_err(|e| io_error(&self.path, e))
+    }
+}
+
+impl MappingStore for JsonFileStore {
+    fn save(&self, key: &str, mapping: &HashMap<String, String>) -> Result<(), AnonymaskError> {
+        self.save_all(&[(key, mapping)])
+    }
+
+    fn save_all(&self, batch: &[(&str, &HashMap<String, String>)]) -> Result<(), AnonymaskError> {
+        self.update(|mappings| {
+            for (key, mapping) in batch {
+                mappings
+                    .entry(key.to_string())
+                    .or_default()
+                    .extend(mapping.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+            true
+        })?;
+        Ok(())
+    }
+
+    fn load(&self, key: &str) -> Result<Option<HashMap<String, String>>, AnonymaskError> {
+        Ok(self.read()?.remove(key))
+    }
+
+    fn lookup(&self, key: &str, placeholder: &str) -> Result<Option<String>, AnonymaskError> {
+        Ok(self
+            .read()?
+            .remove(key)
+            .and_then(|mut mapping| mapping.remove(placeholder)))
+    }
+
+    fn delete(&self, key: &str) -> Result<bool, AnonymaskError> {
+        self.update(|mappings| mappings.remove(key).is_some())
+    }
+
+    fn keys(&self) -> Result<Vec<String>, AnonymaskError> {
+        Ok(self.read()?.into_keys().collect())
+    }
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> AnonymaskError {
+    AnonymaskError::StorageError(format!("{}: {}", path.display(), e))
+}
@@ -0,0 +1,113 @@
+use super::MappingStore;
+use crate::error::AnonymaskError;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Mapping store backed by an embedded sled database.
+///
+/// Each placeholder is stored as its own record under `key \0 placeholder`,
+/// so lookups don't load the whole mapping.
+pub struct SledStore {
+    db: sled::Db,
+}
+
+impl SledStore {
+    /// Open (or create) a database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AnonymaskError> {
+        let db = sled::open(path).map_err(storage_error)?;
+        Ok(Self { db })
+    }
+
+    /// Use an already opened database.
+    pub fn from_db(db: sled::Db) -> Self {
+        Self { db }
+    }
+}
+
+fn record_key(key: &str, placeholder: &str) -> Vec<u8> {
+    let mut record = Vec::with_capacity(key.len() + placeholder.len() + 1);
+    record.extend_from_slice(key.as_bytes());
+    record.push(0);
+    record.extend_from_slice(placeholder.as_bytes());
+    record
+}
+
+fn prefix(key: &str) -> Vec<u8> {
+    record_key(key, "")
+}
+
+fn storage_error(e: impl std::fmt::Display) -> AnonymaskError {
+    AnonymaskError::StorageError(format!("sled: {}", e))
+}
+
+fn utf8(bytes: &[u8]) -> Result<String, AnonymaskError> {
+    String::from_utf8(bytes.to_vec()).map_err(storage_error)
+}
+
+impl MappingStore for SledStore {
+    fn save(&self, key: &str, mapping: &HashMap<String, String>) -> Result<(), AnonymaskError> {
+        let mut batch = sled::Batch::default();
+        for (placeholder, original) in mapping {
+            batch.insert(record_key(key, placeholder), original.as_bytes());
+        }
+        self.db.apply_batch(batch).map_err(storage_error)?;
+        self.db.flush().map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn save_all(&self, mappings: &[(&str, &HashMap<String, String>)]) -> Result<(), AnonymaskError> {
+        let mut batch = sled::Batch::default();
+        for (key, mapping) in mappings {
+            for (placeholder, original) in *mapping {
+                batch.insert(record_key(key, placeholder), original.as_bytes());
+            }
+        }
+        self.db.apply_batch(batch).map_err(storage_error)?;
+        self.db.flush().map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn load(&self, key: &str) -> Result<Option<HashMap<String, String>>, AnonymaskError> {
+        let prefix = prefix(key);
+        let mut mapping = HashMap::new();
+        for record in self.db.scan_prefix(&prefix) {
+            let (k, v) = record.map_err(storage_error)?;
+            mapping.insert(utf8(&k[prefix.len()..])?, utf8(&v)?);
+        }
+        Ok(if mapping.is_empty() { None } else { Some(mapping) })
+    }
+
+    fn lookup(&self, key: &str, placeholder: &str) -> Result<Option<String>, AnonymaskError> {
+        self.db
+            .get(record_key(key, placeholder))
+            .map_err(storage_error)?
+            .map(|v| utf8(&v))
+            .transpose()
+    }
+
+    fn delete(&self, key: &str) -> Result<bool, AnonymaskError> {
+        let mut batch = sled::Batch::default();
+        let mut found = false;
+        for record in self.db.scan_prefix(prefix(key)) {
+            let (k, _) = record.map_err(storage_error)?;
+            batch.remove(k);
+            found = true;
+        }
+        self.db.apply_batch(batch).map_err(storage_error)?;
+        self.db.flush().map_err(storage_error)?;
+        Ok(found)
+    }
+
+    fn keys(&self) -> Result<Vec<String>, AnonymaskError> {
+        let mut keys: Vec<String> = Vec::new();
+        for record in self.db.iter() {
+            let (k, _) = record.map_err(storage_error)?;
+            let end = k.iter().position(|&b| b == 0).unwrap_or(k.len());
+            let key = utf8(&k[..end])?;
+            if keys.last() != Some(&key) {
+                keys.push(key);
+            }
+        }
+        Ok(keys)
+    }
+}
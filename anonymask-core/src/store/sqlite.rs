@@ -0,0 +1,116 @@
+use super::{lock, MappingStore};
+use crate::error::AnonymaskError;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Mapping store backed by a SQLite database.
+///
+/// Mappings live in a single `anonymask_mappings` table with one row per
+/// placeholder; saves run in a transaction.
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Open (or create) a database file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AnonymaskError> {
+        Self::from_connection(Connection::open(path).map_err(storage_error)?)
+    }
+
+    /// Open a private in-memory database.
+    pub fn open_in_memory() -> Result<Self, AnonymaskError> {
+        Self::from_connection(Connection::open_in_memory().map_err(storage_error)?)
+    }
+
+    /// Use an existing connection, creating the mapping table if needed.
+    pub fn from_connection(conn: Connection) -> Result<Self, AnonymaskError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS anonymask_mappings (
+                key TEXT NOT NULL,
+                placeholder TEXT NOT NULL,
+                original TEXT NOT NULL,
+                PRIMARY KEY (key, placeholder)
+            )",
+        )
+        .map_err(storage_error)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+fn storage_error(e: rusqlite::Error) -> AnonymaskError {
+    AnonymaskError::StorageError(format!("sqlite: {}", e))
+}
+
+impl MappingStore for SqliteStore {
+    fn save(&self, key: &str, mapping: &HashMap<String, String>) -> Result<(), AnonymaskError> {
+        self.save_all(&[(key, mapping)])
+    }
+
+    fn save_all(&self, mappings: &[(&str, &HashMap<String, String>)]) -> Result<(), AnonymaskError> {
+        let mut conn = lock(&self.conn)?;
+        let tx = conn.transaction().map_err(storage_error)?;
+        {
+            let mut stmt = tx
+                .prepare_cached(
+                    "INSERT OR REPLACE INTO anonymask_mappings (key, placeholder, original) VALUES (?1, ?2, ?3)",
+                )
+                .map_err(storage_error)?;
+            for (key, mapping) in mappings {
+                for (placeholder, original) in *mapping {
+                    stmt.execute(params![key, placeholder, original])
+                        .map_err(storage_error)?;
+                }
+            }
+        }
+        tx.commit().map_err(storage_error)
+    }
+
+    fn load(&self, key: &str) -> Result<Option<HashMap<String, String>>, AnonymaskError> {
+        let conn = lock(&self.conn)?;
+        let mut stmt = conn
+            .prepare_cached("SELECT placeholder, original FROM anonymask_mappings WHERE key = ?1")
+            .map_err(storage_error)?;
+        let mapping = stmt
+            .query_map(params![key], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(storage_error)?
+            .collect::<Result<HashMap<String, String>, _>>()
+            .map_err(storage_error)?;
+        Ok(if mapping.is_empty() { None } else { Some(mapping) })
+    }
+
+    fn lookup(&self, key: &str, placeholder: &str) -> Result<Option<String>, AnonymaskError> {
+        let conn = lock(&self.conn)?;
+        conn.query_row(
+            "SELECT original FROM anonymask_mappings WHERE key = ?1 AND placeholder = ?2",
+            params![key, placeholder],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(storage_error)
+    }
+
+    fn delete(&self, key: &str) -> Result<bool, AnonymaskError> {
+        let conn = lock(&self.conn)?;
+        let deleted = conn
+            .execute("DELETE FROM anonymask_mappings WHERE key = ?1", params![key])
+            .map_err(storage_error)?;
+        Ok(deleted > 0)
+    }
+
+    fn keys(&self) -> Result<Vec<String>, AnonymaskError> {
+        let conn = lock(&self.conn)?;
+        let mut stmt = conn
+            .prepare_cached("SELECT DISTINCT key FROM anonymask_mappings ORDER BY key")
+            .map_err(storage_error)?;
+        let keys = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(storage_error)?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(storage_error)?;
+        Ok(keys)
+    }
+}
@@ -0,0 +1,214 @@
+use crate::anonymizer::Anonymizer;
+use crate::error::AnonymaskError;
+use std::collections::HashMap;
+use std::ops::Deref;
+
+/// Default number of trailing bytes held back until more text arrives.
+const DEFAULT_WINDOW: usize = 256;
+
+/// Chunk-by-chunk anonymization of text that arrives in pieces.
+///
+/// Push chunks as they arrive (keystrokes in a text area, parts of a
+/// network body) and emit the masked text each push returns. An entity can
+/// be split between chunks, so the last `window` bytes are held back until
+/// more text or [`flush`](Self::flush) shows where it ends; only that
+/// window and the mapping are kept in memory. Entities longer than the
+/// window are still found, but delay output until they end.
+///
+/// Placeholders are shared across the whole stream, and `max_entities`
+/// applies to the stream as a whole. Result hooks don't run, as no
+/// [`AnonymizationResult`](crate::entity::AnonymizationResult) is produced.
+///
+/// Created with [`Anonymizer::stream`], or with [`new`](Self::new) from an
+/// owning handle such as `Arc<Anonymizer>`.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::{Anonymizer, EntityType};
+///
+/// let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+/// let mut stream = anonymizer.stream().with_window(16);
+///
+/// let mut masked = String::new();
+/// for chunk in ["Write to jo", "hn@email.com or ", "call me later, thanks!"] {
+///     masked += &stream.push(chunk).unwrap();
+/// }
+/// masked += &stream.flush().unwrap();
+///
+/// assert!(!masked.contains("john@email.com"));
+/// assert_eq!(stream.deanonymize(&masked), "Write to john@email.com or call me later, thanks!");
+/// ```
+pub struct AnonymizationStream<A: Deref<Target = Anonymizer>> {
+    anonymizer: A,
+    window: usize,
+    /// Text received but not emitted yet
+    pending: String,
+    /// Original value -> placeholder, shared by all chunks
+    values: HashMap<String, String>,
+    /// Entities left before `max_entities` is reached
+    remaining: Option<usize>,
+    truncated: bool,
+}
+
+impl Anonymizer {
+    /// Start anonymizing text that arrives in chunks; see [`AnonymizationStream`].
+    pub fn stream(&self) -> AnonymizationStream<&Anonymizer> {
+        AnonymizationStream::new(self)
+    }
+}
+
+impl<A: Deref<Target = Anonymizer>> AnonymizationStream<A> {
+    /// Create a stream over any handle to an anonymizer.
+    pub fn new(anonymizer: A) -> Self {
+        let remaining = anonymizer.entity_limit();
+        Self {
+            anonymizer,
+            window: DEFAULT_WINDOW,
+            pending: String::new(),
+            values: HashMap::new(),
+            remaining,
+            truncated: false,
+        }
+    }
+
+    /// Set how many trailing bytes are held back, at least the longest
+    /// entity expected.
+    ///
+    /// Larger windows catch longer entities without delay; smaller ones
+    /// emit text sooner. Default: 256
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Add a chunk and return the masked text that is ready to emit,
+    /// possibly empty.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`Anonymizer::anonymize`]. The chunk is kept, so the
+    /// stream can still be flushed.
+    pub fn push(&mut self, chunk: &str) -> Result<String, AnonymaskError> {
+        self.pending.push_str(chunk);
+        if self.pending.len() <= self.window {
+            return Ok(String::new());
+        }
+        self.emit(self.pending.len() - self.window, false)
+    }
+
+    /// Return the masked text of everything held back, e.g. at the end of
+    /// the input. The stream can be used again afterwards.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`Anonymizer::anonymize`].
+    pub fn flush(&mut self) -> Result<String, AnonymaskError> {
+        self.emit(self.pending.len(), true)
+    }
+
+    /// Mask and remove pending text up to about `limit` bytes. Unless
+    /// `all`, the cut moves back to a whitespace and before any entity
+    /// crossing it.
+    fn emit(&mut self, limit: usize, all: bool) -> Result<String, AnonymaskError> {
+        let mut entities = self.anonymizer.detector().detect_all(&self.pending, None)?;
+
+        let mut cut = limit;
+        if !all {
+            while !self.pending.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            if let Some((index, c)) = self.pending[..cut].char_indices().rev().find(|(_, c)| c.is_whitespace()) {
+                cut = index + c.len_utf8();
+            }
+            // Entities don't overlap, so those before a crossing one end before it
+            if let Some(crossing) = entities.iter().find(|e| e.start < cut && e.end > cut) {
+                cut = crossing.start;
+            }
+            entities.retain(|e| e.end <= cut);
+        }
+        if let Some(remaining) = &mut self.remaining {
+            if entities.len() > *remaining {
+                entities.truncate(*remaining);
+                self.truncated = true;
+            }
+            *remaining -= entities.len();
+        }
+
+        let (masked, _) = self
+            .anonymizer
+            .replacer()
+            .replace(&self.pending[..cut], &entities, &mut self.values, None, None)?;
+        self.pending.drain(..cut);
+        Ok(masked)
+    }
+
+    /// Restore original values using the mapping accumulated so far.
+    pub fn deanonymize(&self, text: &str) -> String {
+        self.anonymizer.deanonymize(text, &self.mapping())
+    }
+
+    /// The accumulated placeholder -> original value mapping.
+    pub fn mapping(&self) -> HashMap<String, String> {
+        self.values
+            .iter()
+            .map(|(original, placeholder)| (placeholder.clone(), original.clone()))
+            .collect()
+    }
+
+    /// Number of bytes received but not emitted yet.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether the stream hit the `max_entities` limit.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AnonymizerConfig, PlaceholderFormat};
+    use crate::entity::EntityType;
+    use std::sync::Arc;
+
+    fn anonymizer(max_entities: usize) -> Anonymizer {
+        let config = AnonymizerConfig::builder()
+            .with_placeholder_format(PlaceholderFormat::Short)
+            .with_max_entities(max_entities)
+            .build();
+        Anonymizer::with_config(vec![EntityType::Email, EntityType::Phone], config).unwrap()
+    }
+
+    /// Push `text` one character at a time, as keystrokes would.
+    fn masked_by_char<A: Deref<Target = Anonymizer>>(stream: &mut AnonymizationStream<A>, text: &str) -> String {
+        let mut masked = String::new();
+        for c in text.chars() {
+            masked += &stream.push(c.encode_utf8(&mut [0; 4])).unwrap();
+            assert!(stream.pending_len() <= 24 + 16);
+        }
+        masked + &stream.flush().unwrap()
+    }
+
+    #[test]
+    fn test_matches_whole_text_anonymization() {
+        let text = "Café owner jo@x.io wrote: call 555-123-4567 or mail jo@x.io, ann@y.org — thanks";
+        let expected = anonymizer(0).anonymize(text).unwrap().anonymized_text;
+        let anonymizer = anonymizer(0);
+        let mut stream = anonymizer.stream().with_window(24);
+
+        assert_eq!(masked_by_char(&mut stream, text), expected);
+        assert_eq!(stream.mapping().len(), 3);
+    }
+
+    #[test]
+    fn test_entity_limit_spans_stream() {
+        let mut stream = AnonymizationStream::new(Arc::new(anonymizer(2))).with_window(24);
+        let masked = masked_by_char(&mut stream, "a@b.io then c@d.io and later e@f.io");
+
+        assert_eq!(masked, "EMAIL_1 then EMAIL_2 and later e@f.io");
+        assert!(stream.is_truncated());
+    }
+}
@@ -0,0 +1,292 @@
+//! Linking the entities of one result that describe the same person.
+
+use crate::entity::{AnonymizationResult, EntityType};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+/// Whether entities of `entity_type` identify a person, as opposed to an
+/// organization, a place, a date or a credential.
+fn is_personal(entity_type: &EntityType) -> bool {
+    matches!(
+        entity_type,
+        EntityType::Person
+            | EntityType::Email
+            | EntityType::Phone
+            | EntityType::Ssn
+            | EntityType::CreditCard
+            | EntityType::Iban
+            | EntityType::SteuerId
+            | EntityType::Nir
+            | EntityType::Dni
+            | EntityType::Nie
+            | EntityType::Cpf
+            | EntityType::MyNumber
+            | EntityType::Tfn
+            | EntityType::Medicare
+            | EntityType::Address
+            | EntityType::DateOfBirth
+    )
+}
+
+/// Subject of each entity of `result`, in the order of its entities.
+///
+/// Within a paragraph, each personal entity belongs to the nearest name
+/// before it, or else the first name after it; in a paragraph without
+/// names, all personal entities form one subject. Entities with the same
+/// value always share a subject, which links a name across paragraphs.
+/// Subjects are numbered from 1 in order of first appearance; other
+/// entities get `None`.
+pub(crate) fn link(result: &AnonymizationResult) -> Vec<Option<usize>> {
+    static PARAGRAPH_BREAK: OnceLock<Regex> = OnceLock::new();
+    let breaks: Vec<usize> = PARAGRAPH_BREAK
+        .get_or_init(|| Regex::new(r"\n[ \t\r]*\n").unwrap())
+        .find_iter(&result.anonymized_text)
+        .map(|m| m.start())
+        .collect();
+
+    let entities = &result.entities;
+    // Positions in the anonymized text, where paragraphs are intact
+    let starts: Vec<usize> = match result.output_spans.len() == entities.len() {
+        true => result.output_spans.iter().map(|span| span.start).collect(),
+        false => return vec![None; entities.len()],
+    };
+    let paragraph = |i: usize| breaks.partition_point(|&at| at < starts[i]);
+
+    let mut subjects = UnionFind::new(entities.len());
+    let mut by_value: HashMap<&str, usize> = HashMap::new();
+    let personal: Vec<usize> = (0..entities.len()).filter(|&i| is_personal(&entities[i].entity_type)).collect();
+    for (n, &i) in personal.iter().enumerate() {
+        if let Some(&same) = by_value.get(entities[i].value.as_str()) {
+            subjects.union(i, same);
+        }
+        by_value.entry(&entities[i].value).or_insert(i);
+        if entities[i].entity_type == EntityType::Person {
+            continue;
+        }
+
+        let in_paragraph = |&&j: &&usize| paragraph(j) == paragraph(i);
+        let before = personal[..n].iter().rev().take_while(in_paragraph);
+        let after = personal[n + 1..].iter().take_while(in_paragraph);
+        let name = |&&j: &&usize| entities[j].entity_type == EntityType::Person;
+        match before.clone().find(name).or_else(|| after.clone().find(name)) {
+            Some(&person) => subjects.union(i, person),
+            // No names: everything personal in the paragraph is one subject
+            None => {
+                if let Some(&first) = before.last() {
+                    subjects.union(i, first);
+                }
+            }
+        }
+    }
+
+    let mut numbers = HashMap::new();
+    (0..entities.len())
+        .map(|i| {
+            is_personal(&entities[i].entity_type).then(|| {
+                let next = numbers.len() + 1;
+                *numbers.entry(subjects.find(i)).or_insert(next)
+            })
+        })
+        .collect()
+}
+
+/// Rename the placeholders of linked entities after their subject.
+///
+/// The name of subject 7 becomes `PERSON_7` and its other values
+/// `PERSON_7_EMAIL`, `PERSON_7_PHONE` and so on, with `_2`, `_3` appended
+/// to further values of one type. Placeholders of entities without a
+/// subject are kept. Expects `subject_ids` from [`link`].
+pub(crate) fn rename(result: &mut AnonymizationResult) {
+    if result.subject_ids.len() != result.entities.len() || result.output_spans.len() != result.entities.len() {
+        return;
+    }
+
+    let linked = |i: &usize| result.subject_ids[*i].is_some();
+    let old: HashSet<&str> = (0..result.entities.len())
+        .filter(linked)
+        .map(|i| result.output_spans[i].placeholder.as_str())
+        .collect();
+    let mut taken: HashSet<String> = result
+        .mapping
+        .keys()
+        .filter(|placeholder| !old.contains(placeholder.as_str()))
+        .cloned()
+        .collect();
+    let mut renamed: HashMap<String, String> = HashMap::new();
+    for i in (0..result.entities.len()).filter(linked) {
+        let placeholder = &result.output_spans[i].placeholder;
+        if renamed.contains_key(placeholder) {
+            continue;
+        }
+        let subject = result.subject_ids[i].unwrap_or_default();
+        let base = match &result.entities[i].entity_type {
+            EntityType::Person => format!("PERSON_{}", subject),
+            entity_type => format!("PERSON_{}_{}", subject, entity_type.as_str().to_uppercase()),
+        };
+        let mut name = base.clone();
+        for n in 2.. {
+            if !taken.contains(&name) {
+                break;
+            }
+            name = format!("{}_{}", base, n);
+        }
+        taken.insert(name.clone());
+        renamed.insert(placeholder.clone(), name);
+    }
+
+    let mut order: Vec<usize> = (0..result.output_spans.len()).collect();
+    order.sort_by_key(|&i| result.output_spans[i].start);
+    let mut text = String::with_capacity(result.anonymized_text.len());
+    let mut cursor = 0;
+    for i in order {
+        let span = &mut result.output_spans[i];
+        text.push_str(&result.anonymized_text[cursor..span.start]);
+        cursor = span.end;
+        if let Some(name) = renamed.get(&span.placeholder) {
+            span.placeholder = name.clone();
+        }
+        span.start = text.len();
+        text.push_str(&span.placeholder);
+        span.end = text.len();
+    }
+    text.push_str(&result.anonymized_text[cursor..]);
+    result.anonymized_text = text;
+
+    result.mapping = std::mem::take(&mut result.mapping)
+        .into_iter()
+        .map(|(placeholder, original)| (renamed.get(&placeholder).cloned().unwrap_or(placeholder), original))
+        .collect();
+}
+
+/// Disjoint sets of entity indices.
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self { parents: (0..len).collect() }
+    }
+
+    fn find(&mut self, i: usize) -> usize {
+        let parent = self.parents[i];
+        if parent == i {
+            return i;
+        }
+        let root = self.find(parent);
+        self.parents[i] = root;
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a.max(b)] = a.min(b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{AnonymizerConfig, PlaceholderFormat};
+    use crate::entity::{AnonymizationResult, Entity, EntityType};
+    use crate::Anonymizer;
+
+    /// Anonymize `values` marked in `text` as the given types.
+    fn anonymize(config: AnonymizerConfig, text: &str, values: &[(EntityType, &str)]) -> AnonymizationResult {
+        let mut from = 0;
+        let entities = values
+            .iter()
+            .map(|(entity_type, value)| {
+                let start = from + text[from..].find(value).unwrap();
+                from = start + value.len();
+                Entity {
+                    entity_type: entity_type.clone(),
+                    value: value.to_string(),
+                    start,
+                    end: from,
+                    confidence: 1.0,
+                }
+            })
+            .collect();
+        let anonymizer = Anonymizer::with_config(vec![EntityType::Email], config).unwrap();
+        anonymizer.anonymize_entities(text, entities).unwrap()
+    }
+
+    /// Subject IDs for `values` marked in `text` as the given types.
+    fn subjects(text: &str, values: &[(EntityType, &str)]) -> Vec<Option<usize>> {
+        let config = AnonymizerConfig::builder().with_subject_linking(true).build();
+        anonymize(config, text, values).subject_ids
+    }
+
+    #[test]
+    fn test_paragraph_links_to_nearest_name() {
+        let text = "Ann Lee (ann@x.io) works with Bob Ray, 555-0100, at Acme.\n\n\
+                    Reach Cy Sun on 555-0199.";
+        let ids = subjects(
+            text,
+            &[
+                (EntityType::Person, "Ann Lee"),
+                (EntityType::Email, "ann@x.io"),
+                (EntityType::Person, "Bob Ray"),
+                (EntityType::Phone, "555-0100"),
+                (EntityType::Org, "Acme"),
+                (EntityType::Person, "Cy Sun"),
+                (EntityType::Phone, "555-0199"),
+            ],
+        );
+        assert_eq!(ids, vec![Some(1), Some(1), Some(2), Some(2), None, Some(3), Some(3)]);
+    }
+
+    #[test]
+    fn test_equal_values_link_across_paragraphs() {
+        let text = "Call 555-0100 or mail jo@x.io.\n\nJo Kim: jo@x.io\n\nJo Kim again";
+        let ids = subjects(
+            text,
+            &[
+                (EntityType::Phone, "555-0100"),
+                (EntityType::Email, "jo@x.io"),
+                (EntityType::Person, "Jo Kim"),
+                (EntityType::Email, "jo@x.io"),
+                (EntityType::Person, "Jo Kim"),
+            ],
+        );
+        assert_eq!(ids, vec![Some(1); 5]);
+    }
+
+    #[test]
+    fn test_subject_placeholders() {
+        let text = "Ann Lee <ann@x.io, a.lee@y.io>, 555-0100, re 2024-01-02.\n\nAnn Lee again";
+        let config = AnonymizerConfig::builder()
+            .with_placeholder_format(PlaceholderFormat::Short)
+            .with_subject_placeholders(true)
+            .build();
+        let result = anonymize(
+            config,
+            text,
+            &[
+                (EntityType::Person, "Ann Lee"),
+                (EntityType::Email, "ann@x.io"),
+                (EntityType::Email, "a.lee@y.io"),
+                (EntityType::Phone, "555-0100"),
+                (EntityType::Date, "2024-01-02"),
+                (EntityType::Person, "Ann Lee"),
+            ],
+        );
+        assert_eq!(
+            result.anonymized_text,
+            "PERSON_1 <PERSON_1_EMAIL, PERSON_1_EMAIL_2>, PERSON_1_PHONE, re DATE_1.\n\nPERSON_1 again"
+        );
+        for span in &result.output_spans {
+            assert_eq!(&result.anonymized_text[span.start..span.end], span.placeholder);
+        }
+        assert_eq!(result.mapping.len(), 5);
+        let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+        assert_eq!(anonymizer.deanonymize(&result.anonymized_text, &result.mapping), text);
+    }
+
+    #[test]
+    fn test_off_by_default() {
+        let anonymizer = Anonymizer::new(vec![EntityType::Email]).unwrap();
+        assert!(anonymizer.anonymize("Mail jo@x.io").unwrap().subject_ids.is_empty());
+    }
+}
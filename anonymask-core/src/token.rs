@@ -0,0 +1,254 @@
+//! Keyed-hash tokens standing in for placeholder UUIDs.
+
+use crate::config::{HashAlgorithm, HashedTokens, TokenEncoding};
+use crate::error::AnonymaskError;
+use crate::placeholder::Token;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use siphasher::sip128::{Hasher128, SipHasher13};
+use std::fmt;
+use std::hash::Hasher;
+
+/// Context string deriving BLAKE3 keys from configured keys
+const BLAKE3_CONTEXT: &str = "anonymask 2024-01-01 hashed tokens";
+
+const BASE32_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Generates the tokens configured by [`HashedTokens`].
+#[derive(Clone)]
+pub(crate) struct Tokenizer {
+    key: Key,
+    encoding: TokenEncoding,
+    length: usize,
+}
+
+#[derive(Clone)]
+enum Key {
+    HmacSha256(Hmac<Sha256>),
+    /// First 16 bytes of SHA-256 of the configured key
+    SipHash([u8; 16]),
+    /// Derived from the configured key with BLAKE3's key derivation
+    Blake3([u8; 32]),
+}
+
+impl Tokenizer {
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::InvalidConfig` if the key is empty, the
+    /// tokens would carry fewer than 64 bits or the digest of the algorithm
+    /// can't fill `length` characters.
+    pub(crate) fn new(config: &HashedTokens) -> Result<Self, AnonymaskError> {
+        if config.key.is_empty() {
+            return Err(AnonymaskError::InvalidConfig("hashed_tokens.key is empty".to_string()));
+        }
+        let (key, digest_len) = match config.algorithm {
+            HashAlgorithm::HmacSha256 => (
+                Key::HmacSha256(
+                    Hmac::new_from_slice(config.key.as_bytes()).expect("HMAC accepts keys of any length"),
+                ),
+                32,
+            ),
+            HashAlgorithm::SipHash => {
+                let digest = Sha256::digest(config.key.as_bytes());
+                let mut key = [0; 16];
+                key.copy_from_slice(&digest[..16]);
+                (Key::SipHash(key), 16)
+            }
+            HashAlgorithm::Blake3 => (Key::Blake3(blake3::derive_key(BLAKE3_CONTEXT, config.key.as_bytes())), 32),
+        };
+        let (min, max) = (min_length(config.encoding), max_length(config.encoding, digest_len));
+        if config.length < min || config.length > max {
+            return Err(AnonymaskError::InvalidConfig(format!(
+                "hashed_tokens.length must be between {} and {} for {:?} in {:?}, got {}",
+                min, max, config.algorithm, config.encoding, config.length
+            )));
+        }
+
+        Ok(Self {
+            key,
+            encoding: config.encoding,
+            length: config.length,
+        })
+    }
+
+    /// The token of `value` detected as `type_prefix`.
+    ///
+    /// The type is hashed too, so a value detected as two types gets
+    /// unrelated tokens.
+    pub(crate) fn token(&self, type_prefix: &str, value: &str) -> String {
+        let digest = match &self.key {
+            Key::HmacSha256(mac) => {
+                let mut mac = mac.clone();
+                mac.update(type_prefix.as_bytes());
+                mac.update(&[0]);
+                mac.update(value.as_bytes());
+                mac.finalize().into_bytes().to_vec()
+            }
+            Key::SipHash(key) => {
+                let mut hasher = SipHasher13::new_with_key(key);
+                hasher.write(type_prefix.as_bytes());
+                hasher.write(&[0]);
+                hasher.write(value.as_bytes());
+                hasher.finish128().as_bytes().to_vec()
+            }
+            Key::Blake3(key) => {
+                let mut hasher = blake3::Hasher::new_keyed(key);
+                hasher.update(type_prefix.as_bytes());
+                hasher.update(&[0]);
+                hasher.update(value.as_bytes());
+                hasher.finalize().as_bytes().to_vec()
+            }
+        };
+
+        let mut token = encode(self.encoding, &digest);
+        match self.encoding {
+            // The leading base58 digit only covers part of the alphabet,
+            // so keep the trailing ones
+            TokenEncoding::Base58 => token.split_off(token.len() - self.length),
+            TokenEncoding::Hex | TokenEncoding::Base32 => {
+                token.truncate(self.length);
+                token
+            }
+        }
+    }
+
+    /// Alphabet (as a regex class) and length of the tokens, for
+    /// recognizing placeholders.
+    pub(crate) fn shape(&self) -> Token<'static> {
+        let alphabet = match self.encoding {
+            TokenEncoding::Hex => "[0-9a-f]",
+            TokenEncoding::Base32 => "[a-z2-7]",
+            TokenEncoding::Base58 => "[1-9A-HJ-NP-Za-km-z]",
+        };
+        Token {
+            alphabet,
+            length: self.length,
+        }
+    }
+}
+
+impl fmt::Debug for Tokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let algorithm = match self.key {
+            Key::HmacSha256(_) => HashAlgorithm::HmacSha256,
+            Key::SipHash(_) => HashAlgorithm::SipHash,
+            Key::Blake3(_) => HashAlgorithm::Blake3,
+        };
+        f.debug_struct("Tokenizer")
+            .field("algorithm", &algorithm)
+            .field("encoding", &self.encoding)
+            .field("length", &self.length)
+            .finish()
+    }
+}
+
+/// Fewest characters carrying 64 bits, below which different values are
+/// likely to share a token once there are a few billion of them.
+pub(crate) fn min_length(encoding: TokenEncoding) -> usize {
+    match encoding {
+        TokenEncoding::Hex => 16,
+        TokenEncoding::Base32 => 13,
+        TokenEncoding::Base58 => 11,
+    }
+}
+
+/// Characters in the full encoding of a digest of `len` bytes.
+fn max_length(encoding: TokenEncoding, len: usize) -> usize {
+    match encoding {
+        TokenEncoding::Hex => len * 2,
+        TokenEncoding::Base32 => (len * 8).div_ceil(5),
+        TokenEncoding::Base58 => (len as f64 * 8.0 / 58f64.log2()).ceil() as usize,
+    }
+}
+
+/// Encode `bytes` in full, base58 left-padded to a fixed width.
+fn encode(encoding: TokenEncoding, bytes: &[u8]) -> String {
+    match encoding {
+        TokenEncoding::Hex => bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+        TokenEncoding::Base32 => {
+            let mut encoded = String::with_capacity(max_length(encoding, bytes.len()));
+            let (mut buffer, mut bits) = (0u32, 0);
+            for &byte in bytes {
+                buffer = (buffer << 8) | byte as u32;
+                bits += 8;
+                while bits >= 5 {
+                    bits -= 5;
+                    encoded.push(BASE32_ALPHABET[(buffer >> bits) as usize & 31] as char);
+                }
+            }
+            if bits > 0 {
+                encoded.push(BASE32_ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+            }
+            encoded
+        }
+        TokenEncoding::Base58 => {
+            // Little-endian base58 digits of the big-endian number `bytes`
+            let mut digits: Vec<u8> = Vec::new();
+            for &byte in bytes {
+                let mut carry = byte as u32;
+                for digit in digits.iter_mut() {
+                    carry += (*digit as u32) << 8;
+                    *digit = (carry % 58) as u8;
+                    carry /= 58;
+                }
+                while carry > 0 {
+                    digits.push((carry % 58) as u8);
+                    carry /= 58;
+                }
+            }
+            digits.resize(max_length(encoding, bytes.len()), 0);
+            digits.iter().rev().map(|&digit| BASE58_ALPHABET[digit as usize] as char).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encodings() {
+        assert_eq!(encode(TokenEncoding::Hex, b"\x00\xff"), "00ff");
+        // RFC 4648 test vector, lowercased
+        assert_eq!(encode(TokenEncoding::Base32, b"foobar"), "mzxw6ytboi");
+        assert_eq!(encode(TokenEncoding::Base58, b"\x00\x00\x01\x00"), "11115R");
+        assert_eq!(encode(TokenEncoding::Base58, &[0xff; 16]).len(), 22);
+    }
+
+    #[test]
+    fn test_lengths() {
+        for (algorithm, encoding, max) in [
+            (HashAlgorithm::HmacSha256, TokenEncoding::Hex, 64),
+            (HashAlgorithm::HmacSha256, TokenEncoding::Base58, 44),
+            (HashAlgorithm::SipHash, TokenEncoding::Base32, 26),
+            (HashAlgorithm::Blake3, TokenEncoding::Hex, 64),
+        ] {
+            let config = |length| HashedTokens {
+                algorithm,
+                encoding,
+                length,
+                ..HashedTokens::new("k")
+            };
+            let tokenizer = Tokenizer::new(&config(max)).unwrap();
+            let token = tokenizer.token("EMAIL", "a@b.io");
+            assert_eq!(token.len(), max);
+            let shape = tokenizer.shape();
+            assert!(regex::Regex::new(&format!("^{}{{{}}}$", shape.alphabet, shape.length))
+                .unwrap()
+                .is_match(&token));
+            assert!(Tokenizer::new(&config(max + 1)).is_err());
+            let min = min_length(encoding);
+            assert!(Tokenizer::new(&config(min)).is_ok());
+            assert!(Tokenizer::new(&config(min - 1)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_minimum_lengths_carry_64_bits() {
+        for (encoding, bits) in [(TokenEncoding::Hex, 4.0), (TokenEncoding::Base32, 5.0), (TokenEncoding::Base58, 58f64.log2())] {
+            let min = min_length(encoding) as f64;
+            assert!(min * bits >= 64.0 && (min - 1.0) * bits < 64.0);
+        }
+    }
+}
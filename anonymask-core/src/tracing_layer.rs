@@ -0,0 +1,318 @@
+//! A `tracing-subscriber` layer that scrubs PII from logs (feature `tracing`).
+//!
+//! [`ScrubLayer`] wraps the layer that writes or exports events (a `fmt`
+//! layer, an OpenTelemetry layer, ...) and hands it copies of events and
+//! span fields with PII replaced:
+//!
+//! ```
+//! use std::sync::Arc;
+//! use anonymask_core::{Anonymizer, EntityType};
+//! use anonymask_core::tracing_layer::ScrubLayer;
+//! use tracing_subscriber::layer::SubscriberExt;
+//!
+//! # let exporter = tracing_subscriber::layer::Identity::new();
+//! let anonymizer = Arc::new(Anonymizer::new(vec![EntityType::Email]).unwrap());
+//! let subscriber = tracing_subscriber::registry().with(ScrubLayer::new(anonymizer, exporter));
+//! ```
+//!
+//! The message and every string, `Debug` and error field are anonymized;
+//! numbers and booleans are passed through. Mappings are discarded, so
+//! logs can't be restored. A value that fails to anonymize is replaced by
+//! `[REDACTED]` rather than passed through. Like `tracing`'s own macros,
+//! at most 32 fields per event or span are supported; further fields are
+//! dropped.
+
+use crate::anonymizer::Anonymizer;
+use std::any::TypeId;
+use std::fmt;
+use std::sync::Arc;
+use tracing_core::field::{display, DisplayValue, Field, FieldSet, Value, ValueSet, Visit};
+use tracing_core::span::{self, Attributes, Record};
+use tracing_core::subscriber::Interest;
+use tracing_core::{Dispatch, Event, LevelFilter, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Fields a `ValueSet` can hold.
+const MAX_FIELDS: usize = 32;
+
+/// Replaces values that couldn't be anonymized.
+const REDACTED: &str = "[REDACTED]";
+
+/// Layer that anonymizes events and span fields before passing them to
+/// an inner layer.
+pub struct ScrubLayer<L> {
+    anonymizer: Arc<Anonymizer>,
+    inner: L,
+}
+
+impl<L> ScrubLayer<L> {
+    /// Scrub everything `inner` receives with `anonymizer`.
+    pub fn new(anonymizer: Arc<Anonymizer>, inner: L) -> Self {
+        Self { anonymizer, inner }
+    }
+
+    /// Anonymize `text`, or redact it whole if that fails.
+    fn scrub(&self, text: &str) -> String {
+        match self.anonymizer.anonymize(text) {
+            Ok(result) => result.anonymized_text,
+            Err(_) => REDACTED.to_string(),
+        }
+    }
+
+    /// Call `f` with a scrubbed copy of the values recorded by `record`,
+    /// or with `None` if nothing was recorded.
+    fn with_scrubbed<R>(
+        &self,
+        fields: &FieldSet,
+        record: impl FnOnce(&mut dyn Visit),
+        f: impl FnOnce(Option<&ValueSet<'_>>) -> R,
+    ) -> R {
+        let mut visitor = ScrubVisitor {
+            layer: self,
+            values: Vec::new(),
+        };
+        record(&mut visitor);
+        let values = visitor.values;
+        let Some((pad, _)) = values.first() else {
+            return f(None);
+        };
+
+        let mut entries: [(&Field, Option<&dyn Value>); MAX_FIELDS] = [(pad, None); MAX_FIELDS];
+        for (entry, (field, value)) in entries.iter_mut().zip(&values) {
+            *entry = (field, Some(value.as_value()));
+        }
+        f(Some(&fields.value_set(&entries)))
+    }
+}
+
+/// A recorded value, with text already scrubbed.
+enum Scrubbed {
+    Str(String),
+    /// Formatted with `Debug` or `Display`; passed on as `Display` so it
+    /// isn't quoted
+    Formatted(DisplayValue<String>),
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    F64(f64),
+    Bool(bool),
+}
+
+impl Scrubbed {
+    fn as_value(&self) -> &dyn Value {
+        match self {
+            Scrubbed::Str(value) => value,
+            Scrubbed::Formatted(value) => value,
+            Scrubbed::I64(value) => value,
+            Scrubbed::U64(value) => value,
+            Scrubbed::I128(value) => value,
+            Scrubbed::U128(value) => value,
+            Scrubbed::F64(value) => value,
+            Scrubbed::Bool(value) => value,
+        }
+    }
+}
+
+struct ScrubVisitor<'a, L> {
+    layer: &'a ScrubLayer<L>,
+    values: Vec<(Field, Scrubbed)>,
+}
+
+impl<L> ScrubVisitor<'_, L> {
+    fn push(&mut self, field: &Field, value: Scrubbed) {
+        if self.values.len() < MAX_FIELDS {
+            self.values.push((field.clone(), value));
+        }
+    }
+}
+
+impl<L> Visit for ScrubVisitor<'_, L> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        let scrubbed = self.layer.scrub(value);
+        self.push(field, Scrubbed::Str(scrubbed));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let scrubbed = self.layer.scrub(&format!("{:?}", value));
+        self.push(field, Scrubbed::Formatted(display(scrubbed)));
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        let scrubbed = self.layer.scrub(&value.to_string());
+        self.push(field, Scrubbed::Formatted(display(scrubbed)));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, Scrubbed::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, Scrubbed::U64(value));
+    }
+
+    fn record_i128(&mut self, field: &Field, value: i128) {
+        self.push(field, Scrubbed::I128(value));
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        self.push(field, Scrubbed::U128(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push(field, Scrubbed::F64(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, Scrubbed::Bool(value));
+    }
+}
+
+impl<S, L> Layer<S> for ScrubLayer<L>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    L: Layer<S>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        self.with_scrubbed(metadata.fields(), |visitor| event.record(visitor), |values| match values {
+            None => self.inner.on_event(event, ctx),
+            Some(values) => {
+                let scrubbed = if event.is_contextual() {
+                    Event::new(metadata, values)
+                } else {
+                    Event::new_child_of(event.parent().cloned(), metadata, values)
+                };
+                self.inner.on_event(&scrubbed, ctx)
+            }
+        })
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let metadata = attrs.metadata();
+        self.with_scrubbed(metadata.fields(), |visitor| attrs.record(visitor), |values| match values {
+            None => self.inner.on_new_span(attrs, id, ctx),
+            Some(values) => {
+                let scrubbed = match attrs.parent() {
+                    Some(parent) => Attributes::child_of(parent.clone(), metadata, values),
+                    None if attrs.is_root() => Attributes::new_root(metadata, values),
+                    None => Attributes::new(metadata, values),
+                };
+                self.inner.on_new_span(&scrubbed, id, ctx)
+            }
+        })
+    }
+
+    fn on_record(&self, span: &span::Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(metadata) = ctx.metadata(span) else {
+            return;
+        };
+        self.with_scrubbed(metadata.fields(), |visitor| values.record(visitor), |scrubbed| {
+            if let Some(scrubbed) = scrubbed {
+                self.inner.on_record(span, &Record::new(scrubbed), ctx)
+            }
+        })
+    }
+
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        self.inner.on_register_dispatch(subscriber)
+    }
+
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber)
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, ctx)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        self.inner.max_level_hint()
+    }
+
+    fn on_follows_from(&self, span: &span::Id, follows: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_follows_from(span, follows, ctx)
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx)
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx)
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        self.inner.on_close(id, ctx)
+    }
+
+    fn on_id_change(&self, old: &span::Id, new: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx)
+    }
+
+    // Lets the subscriber find the inner layer, e.g. its per-layer filters
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            Some(self as *const _ as *const ())
+        } else {
+            self.inner.downcast_raw(id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EntityType;
+    use std::sync::Mutex;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Records what reaches it as `name=value` lines.
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Capture {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.lock().unwrap().push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Capture {
+        fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+            event.record(&mut self.clone());
+        }
+
+        fn on_new_span(&self, attrs: &Attributes<'_>, _: &span::Id, _: Context<'_, S>) {
+            attrs.record(&mut self.clone());
+        }
+    }
+
+    #[test]
+    fn test_scrubs_events_and_spans() {
+        let capture = Capture::default();
+        let anonymizer = Arc::new(Anonymizer::new(vec![EntityType::Email]).unwrap());
+        let subscriber = tracing_subscriber::registry().with(ScrubLayer::new(anonymizer, capture.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", user = "jo@x.io", attempt = 2);
+            let _guard = span.enter();
+            let error = std::io::Error::other("no mailbox jo@x.io");
+            tracing::warn!(to = ?["ann@y.org"], ok = false, error = &error as &dyn std::error::Error, "mail to {} failed", "jo@x.io");
+        });
+
+        let lines = capture.0.lock().unwrap().join("\n");
+        assert!(!lines.contains("jo@x.io") && !lines.contains("ann@y.org"), "{}", lines);
+        assert!(lines.contains("message=mail to EMAIL_"), "{}", lines);
+        assert!(lines.contains("user=\"EMAIL_"), "{}", lines);
+        assert!(lines.contains("attempt=2") && lines.contains("ok=false"), "{}", lines);
+    }
+}
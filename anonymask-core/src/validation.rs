@@ -0,0 +1,614 @@
+//! Checksum and structural validators for detected identifiers.
+//!
+//! Regex patterns find candidates; validators confirm them so that random
+//! digit sequences aren't reported as national identifiers. Every validator
+//! ignores spaces, dots and dashes used as group separators.
+
+use crate::entity::EntityType;
+
+/// Signature shared by all validators.
+pub type Validator = fn(&str) -> bool;
+
+/// The validator confirming values of a built-in type, if it has one.
+pub(crate) fn for_type(entity_type: &EntityType) -> Option<Validator> {
+    let validator: Validator = match entity_type {
+        EntityType::Ssn => ssn,
+        EntityType::CreditCard => luhn,
+        EntityType::Iban => iban,
+        EntityType::SteuerId => steuer_id,
+        EntityType::Nir => nir,
+        EntityType::Siren => siren,
+        EntityType::Siret => siret,
+        EntityType::Dni => dni,
+        EntityType::Nie => nie,
+        EntityType::Cpf => cpf,
+        EntityType::Cnpj => cnpj,
+        EntityType::MyNumber => my_number,
+        EntityType::Tfn => tfn,
+        EntityType::Medicare => medicare,
+        EntityType::Jwt => jwt_header,
+        _ => return None,
+    };
+    Some(validator)
+}
+
+/// Extract the decimal digits of a candidate, ignoring separators.
+fn digits(value: &str) -> Vec<u32> {
+    value.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+/// Validate a German tax identification number (Steuerliche Identifikationsnummer).
+///
+/// Checks the ISO 7064 MOD 11,10 check digit and the digit distribution rule:
+/// among the first ten digits exactly one digit occurs two or three times.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::steuer_id;
+///
+/// assert!(steuer_id("86095742719"));
+/// assert!(!steuer_id("86095742711"));
+/// ```
+pub fn steuer_id(value: &str) -> bool {
+    let digits = digits(value);
+    if digits.len() != 11 || digits[0] == 0 {
+        return false;
+    }
+
+    let mut counts = [0u8; 10];
+    for &d in &digits[..10] {
+        counts[d as usize] += 1;
+    }
+    let repeated: Vec<u8> = counts.iter().copied().filter(|&c| c > 1).collect();
+    if repeated.len() != 1 || repeated[0] > 3 {
+        return false;
+    }
+
+    let mut product = 10;
+    for &d in &digits[..10] {
+        let mut sum = (d + product) % 10;
+        if sum == 0 {
+            sum = 10;
+        }
+        product = (sum * 2) % 11;
+    }
+    let check = (11 - product) % 10;
+    check == digits[10]
+}
+
+/// Validate an International Bank Account Number with the ISO 13616 MOD 97 check.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::iban;
+///
+/// assert!(iban("DE89 3704 0044 0532 0130 00"));
+/// assert!(!iban("DE89 3704 0044 0532 0130 01"));
+/// ```
+pub fn iban(value: &str) -> bool {
+    let compact: Vec<char> = value
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if compact.len() < 15 || compact.len() > 34 {
+        return false;
+    }
+    if !compact[..2].iter().all(|c| c.is_ascii_alphabetic())
+        || !compact[2..4].iter().all(|c| c.is_ascii_digit())
+    {
+        return false;
+    }
+
+    // Move the country code and check digits to the end, letters become 10..35
+    let mut remainder = 0u32;
+    for c in compact[4..].iter().chain(&compact[..4]) {
+        let value = match c.to_digit(36) {
+            Some(v) => v,
+            None => return false,
+        };
+        remainder = if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+/// Validate a digit sequence with the Luhn (mod 10) algorithm.
+///
+/// Used by credit card numbers and French SIREN/SIRET company identifiers.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::luhn;
+///
+/// assert!(luhn("4539 1488 0343 6467"));
+/// assert!(!luhn("4539 1488 0343 6468"));
+/// ```
+pub fn luhn(value: &str) -> bool {
+    let digits = digits(value);
+    if digits.len() < 2 {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Validate a French SIREN company number (9 digits, Luhn).
+pub fn siren(value: &str) -> bool {
+    digits(value).len() == 9 && luhn(value)
+}
+
+/// Validate a French SIRET establishment number (14 digits, Luhn).
+pub fn siret(value: &str) -> bool {
+    digits(value).len() == 14 && luhn(value)
+}
+
+/// Validate a French social security number (NIR) with its two-digit key.
+///
+/// The key equals `97 - (number mod 97)` computed over the first 13
+/// characters, with the Corsican department codes `2A`/`2B` replaced by
+/// `19`/`18`.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::nir;
+///
+/// assert!(nir("2 69 05 49 588 157 80"));
+/// assert!(!nir("2 69 05 49 588 157 81"));
+/// ```
+pub fn nir(value: &str) -> bool {
+    let compact: String = value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if compact.len() != 15 || !compact.is_ascii() {
+        return false;
+    }
+
+    let (body, key) = compact.split_at(13);
+    let numeric_body = match &body[5..7] {
+        "2A" => format!("{}19{}", &body[..5], &body[7..]),
+        "2B" => format!("{}18{}", &body[..5], &body[7..]),
+        _ => body.to_string(),
+    };
+
+    let (number, key) = match (numeric_body.parse::<u64>(), key.parse::<u64>()) {
+        (Ok(number), Ok(key)) => (number, key),
+        _ => return false,
+    };
+    97 - number % 97 == key
+}
+
+/// Validate a Brazilian individual taxpayer number (CPF) with its two check digits.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::cpf;
+///
+/// assert!(cpf("529.982.247-25"));
+/// assert!(!cpf("529.982.247-26"));
+/// assert!(!cpf("111.111.111-11"));
+/// ```
+pub fn cpf(value: &str) -> bool {
+    let digits = digits(value);
+    if digits.len() != 11 || digits.iter().all(|&d| d == digits[0]) {
+        return false;
+    }
+
+    let check = |len: usize| {
+        let sum: u32 = digits[..len]
+            .iter()
+            .enumerate()
+            .map(|(i, &d)| d * (len as u32 + 1 - i as u32))
+            .sum();
+        (sum * 10 % 11) % 10
+    };
+    check(9) == digits[9] && check(10) == digits[10]
+}
+
+/// Validate a Brazilian company registration number (CNPJ) with its two check digits.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::cnpj;
+///
+/// assert!(cnpj("11.222.333/0001-81"));
+/// assert!(!cnpj("11.222.333/0001-82"));
+/// ```
+pub fn cnpj(value: &str) -> bool {
+    const WEIGHTS: [u32; 13] = [6, 5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2];
+
+    let digits = digits(value);
+    if digits.len() != 14 || digits.iter().all(|&d| d == digits[0]) {
+        return false;
+    }
+
+    let check = |len: usize| {
+        let weights = &WEIGHTS[WEIGHTS.len() - len..];
+        let sum: u32 = digits[..len].iter().zip(weights).map(|(d, w)| d * w).sum();
+        match sum % 11 {
+            0 | 1 => 0,
+            r => 11 - r,
+        }
+    };
+    check(12) == digits[12] && check(13) == digits[13]
+}
+
+/// Validate a Japanese individual number (My Number): 12 digits with a check digit.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::my_number;
+///
+/// assert!(my_number("1234 5678 9018"));
+/// assert!(!my_number("1234 5678 9012"));
+/// ```
+pub fn my_number(value: &str) -> bool {
+    let digits = digits(value);
+    if digits.len() != 12 {
+        return false;
+    }
+
+    // Weights run from the right: 2..=7 for the first six digits, then 2..=6
+    let sum: u32 = digits[..11]
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(n, &d)| d * if n < 6 { n as u32 + 2 } else { n as u32 - 4 })
+        .sum();
+    let check = match sum % 11 {
+        0 | 1 => 0,
+        r => 11 - r,
+    };
+    check == digits[11]
+}
+
+/// Validate an Australian Tax File Number (8 or 9 digits, weighted mod 11).
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::tfn;
+///
+/// assert!(tfn("123 456 782"));
+/// assert!(!tfn("123 456 783"));
+/// ```
+pub fn tfn(value: &str) -> bool {
+    let digits = digits(value);
+    let weights: &[u32] = match digits.len() {
+        9 => &[1, 4, 3, 7, 5, 8, 6, 9, 10],
+        8 => &[10, 7, 8, 4, 6, 3, 5, 1],
+        _ => return false,
+    };
+    let sum: u32 = digits.iter().zip(weights).map(|(d, w)| d * w).sum();
+    sum.is_multiple_of(11)
+}
+
+/// Validate an Australian Medicare card number (10 digits, optional issue number).
+///
+/// The first digit must be 2-6 and the ninth digit is a weighted mod 10
+/// check digit over the first eight.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::medicare;
+///
+/// assert!(medicare("2123 45670 1"));
+/// assert!(!medicare("2123 45671 1"));
+/// ```
+pub fn medicare(value: &str) -> bool {
+    const WEIGHTS: [u32; 8] = [1, 3, 7, 9, 1, 3, 7, 9];
+
+    let digits = digits(value);
+    if !(10..=11).contains(&digits.len()) || !(2..=6).contains(&digits[0]) {
+        return false;
+    }
+    let sum: u32 = digits[..8].iter().zip(WEIGHTS).map(|(d, w)| d * w).sum();
+    sum % 10 == digits[8]
+}
+
+const DNI_LETTERS: &[u8; 23] = b"TRWAGMYFPDXBNJZSQVHLCKE";
+
+/// Validate a Spanish national identity number (DNI): 8 digits and a control letter.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::dni;
+///
+/// assert!(dni("12345678Z"));
+/// assert!(!dni("12345678A"));
+/// ```
+pub fn dni(value: &str) -> bool {
+    let compact: Vec<char> = value.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    if compact.len() != 9 || !compact[..8].iter().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    let number: String = compact[..8].iter().collect();
+    control_letter(&number) == Some(compact[8].to_ascii_uppercase())
+}
+
+/// Validate a Spanish foreigner identity number (NIE): X/Y/Z, 7 digits and a control letter.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::nie;
+///
+/// assert!(nie("X1234567L"));
+/// assert!(!nie("X1234567A"));
+/// ```
+pub fn nie(value: &str) -> bool {
+    let compact: Vec<char> = value.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    if compact.len() != 9 || !compact[1..8].iter().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    let prefix = match compact[0].to_ascii_uppercase() {
+        'X' => '0',
+        'Y' => '1',
+        'Z' => '2',
+        _ => return false,
+    };
+    let number: String = std::iter::once(prefix).chain(compact[1..8].iter().copied()).collect();
+    control_letter(&number) == Some(compact[8].to_ascii_uppercase())
+}
+
+/// Validate a US ABA routing number (9 digits, weighted 3-7-1 checksum).
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::aba_routing;
+///
+/// assert!(aba_routing("021000021"));
+/// assert!(!aba_routing("021000022"));
+/// ```
+pub fn aba_routing(value: &str) -> bool {
+    let digits = digits(value);
+    if digits.len() != 9 {
+        return false;
+    }
+    let sum: u32 = digits
+        .chunks(3)
+        .map(|d| 3 * d[0] + 7 * d[1] + d[2])
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Validate a North American phone number by its length: ten digits, or
+/// eleven with the country code 1.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::nanp_phone;
+///
+/// assert!(nanp_phone("(555) 123-4567"));
+/// assert!(nanp_phone("+1 555 123 4567"));
+/// assert!(!nanp_phone("555-123"));
+/// ```
+pub fn nanp_phone(value: &str) -> bool {
+    let digits = digits(value);
+    digits.len() == 10 || (digits.len() == 11 && digits[0] == 1)
+}
+
+/// Validate a US social security number (9 digits).
+///
+/// SSNs carry no check digit; this checks the issuance rules instead: the
+/// area (first three digits) is never 000, 666 or 900-999, the group
+/// (next two) never 00 and the serial (last four) never 0000.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::ssn;
+///
+/// assert!(ssn("123-45-6789"));
+/// assert!(!ssn("666-45-6789"));
+/// assert!(!ssn("123-00-6789"));
+/// ```
+pub fn ssn(value: &str) -> bool {
+    let digits = digits(value);
+    if digits.len() != 9 {
+        return false;
+    }
+    let number = |range: std::ops::Range<usize>| digits[range].iter().fold(0, |n, d| n * 10 + d);
+    let area = number(0..3);
+    area != 0 && area != 666 && area < 900 && number(3..5) != 0 && number(5..9) != 0
+}
+
+/// Validate a vehicle identification number (17 characters, check digit in
+/// position 9).
+///
+/// The check digit is mandatory for North American VINs; VINs from other
+/// regions may not carry one.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::vin;
+///
+/// assert!(vin("1M8GDM9AXKP042788"));
+/// assert!(!vin("1M8GDM9A1KP042788"));
+/// ```
+pub fn vin(value: &str) -> bool {
+    const WEIGHTS: [u32; 17] = [8, 7, 6, 5, 4, 3, 2, 10, 0, 9, 8, 7, 6, 5, 4, 3, 2];
+
+    let chars: Vec<char> = value.chars().map(|c| c.to_ascii_uppercase()).collect();
+    if chars.len() != 17 {
+        return false;
+    }
+    let mut sum = 0;
+    for (c, weight) in chars.iter().zip(WEIGHTS) {
+        let value = match c {
+            '0'..='9' => *c as u32 - '0' as u32,
+            'A' | 'J' => 1,
+            'B' | 'K' | 'S' => 2,
+            'C' | 'L' | 'T' => 3,
+            'D' | 'M' | 'U' => 4,
+            'E' | 'N' | 'V' => 5,
+            'F' | 'W' => 6,
+            'G' | 'P' | 'X' => 7,
+            'H' | 'Y' => 8,
+            'R' | 'Z' => 9,
+            _ => return false,
+        };
+        sum += value * weight;
+    }
+    let check = match sum % 11 {
+        10 => 'X',
+        digit => char::from_digit(digit, 10).unwrap_or('?'),
+    };
+    chars[8] == check
+}
+
+/// Check that a JSON Web Token has a well-formed header.
+///
+/// The header (first segment) must decode from base64url to a JSON object
+/// with an `"alg"` string, which rules out dotted identifiers that merely
+/// look like tokens.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::validation::jwt_header;
+///
+/// assert!(jwt_header("eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.eyJzdWIiOiIxMjM0NTY3ODkwIn0.c2ln"));
+/// assert!(!jwt_header("eyJmb28iOiJiYXIifQ.eyJzdWIiOiIxMjM0NTY3ODkwIn0.c2ln"));
+/// ```
+pub fn jwt_header(value: &str) -> bool {
+    let header = match value.split('.').next().and_then(base64url_decode) {
+        Some(header) => header,
+        None => return false,
+    };
+    match serde_json::from_slice::<serde_json::Value>(&header) {
+        Ok(serde_json::Value::Object(header)) => header.get("alg").is_some_and(|alg| alg.is_string()),
+        _ => false,
+    }
+}
+
+/// Decode unpadded base64url, as used in JWT segments.
+fn base64url_decode(input: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in input.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'-' => 62,
+            b'_' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(output)
+}
+
+fn control_letter(number: &str) -> Option<char> {
+    let number: usize = number.parse().ok()?;
+    Some(DNI_LETTERS[number % 23] as char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jwt_header() {
+        assert_eq!(base64url_decode("eyJhbGciOiJub25lIn0").unwrap(), br#"{"alg":"none"}"#);
+        assert!(jwt_header("eyJhbGciOiJub25lIn0.eyJzdWIiOiIxMjM0NTY3ODkwIn0."));
+        assert!(!jwt_header("eyJhbGciOiJub25lIn0$.eyJzdWIiOiIxMjM0NTY3ODkwIn0."));
+        assert!(!jwt_header("eyJzdWIiOiIxMjM0NTY3ODkwIn0.x.y")); // no "alg"
+    }
+
+    #[test]
+    fn test_steuer_id() {
+        assert!(steuer_id("86095742719"));
+        assert!(steuer_id("86 095 742 719"));
+        assert!(!steuer_id("06095742719")); // leading zero
+        assert!(!steuer_id("12345678901")); // no repeated digit
+    }
+
+    #[test]
+    fn test_iban() {
+        assert!(iban("DE89370400440532013000"));
+        assert!(iban("GB82 WEST 1234 5698 7654 32"));
+        assert!(!iban("DE89370400440532013001"));
+        assert!(!iban("DE8937"));
+    }
+
+    #[test]
+    fn test_luhn_company_numbers() {
+        assert!(siren("732 829 320"));
+        assert!(siret("732 829 320 00074"));
+        assert!(!siren("732 829 321"));
+        assert!(!siret("732 829 320"));
+    }
+
+    #[test]
+    fn test_nir_corsica() {
+        assert!(nir("1 85 05 2A 004 028 54"));
+        assert!(!nir("1 85 05 2A 004 028 55"));
+    }
+
+    #[test]
+    fn test_dni_and_nie() {
+        assert!(dni("12345678-Z"));
+        assert!(nie("Y1234567X"));
+        assert!(!nie("A1234567L"));
+    }
+
+    #[test]
+    fn test_aba_routing_and_vin() {
+        assert!(aba_routing("011000015"));
+        assert!(!aba_routing("01100001"));
+        assert!(vin("11111111111111111"));
+        assert!(!vin("1M8GDM9AXKP04278O")); // letter O is never used
+    }
+
+    #[test]
+    fn test_ssn() {
+        assert!(ssn("078051120"));
+        assert!(!ssn("000-12-3456"));
+        assert!(!ssn("912-34-5678"));
+        assert!(!ssn("123-45-0000"));
+        assert!(!ssn("12345678"));
+    }
+}
@@ -0,0 +1,778 @@
+use crate::error::AnonymaskError;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// An encrypted placeholder mapping.
+///
+/// Produced by [`seal_mapping`]. The mapping can only be restored by combining
+/// at least `threshold` of the key shares that were issued alongside it, so no
+/// single operator holding one share can re-identify archived data.
+///
+/// Safe to archive next to the anonymized text: it contains no plaintext PII.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedMapping {
+    /// Number of key shares required to open the mapping
+    pub threshold: u8,
+    /// Random nonce used for encryption
+    pub nonce: Vec<u8>,
+    /// ChaCha20-Poly1305 ciphertext of the JSON-encoded mapping
+    pub ciphertext: Vec<u8>,
+}
+
+/// One part of a key split with Shamir's secret sharing.
+///
+/// Hand each share to a different operator. Any `threshold` distinct shares
+/// reconstruct the key; fewer reveal nothing about it.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyShare {
+    /// Share index (x coordinate), never zero
+    pub index: u8,
+    /// Number of shares required to reconstruct the key
+    pub threshold: u8,
+    /// Share data, one byte per key byte
+    pub data: Vec<u8>,
+}
+
+impl std::fmt::Debug for KeyShare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyShare")
+            .field("index", &self.index)
+            .field("threshold", &self.threshold)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Encrypt a mapping and split the key into shares (two-person rule).
+///
+/// # Arguments
+///
+/// * `mapping` - HashMap mapping placeholders to original values
+/// * `threshold` - Number of shares required to open the mapping (at least 2)
+/// * `share_count` - Number of shares to issue (at least `threshold`)
+///
+/// # Returns
+///
+/// The sealed mapping and `share_count` key shares.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::vault::{open_mapping, seal_mapping};
+/// use std::collections::HashMap;
+///
+/// let mut mapping = HashMap::new();
+/// mapping.insert("EMAIL_1".to_string(), "john@email.com".to_string());
+///
+/// // Three custodians, any two of them can restore identities
+/// let (sealed, shares) = seal_mapping(&mapping, 2, 3).unwrap();
+///
+/// assert!(open_mapping(&sealed, &shares[..1]).is_err());
+/// let restored = open_mapping(&sealed, &[shares[0].clone(), shares[2].clone()]).unwrap();
+/// assert_eq!(restored, mapping);
+/// ```
+///
+/// # Errors
+///
+/// Returns `AnonymaskError::VaultError` if the threshold parameters are invalid
+/// or encryption fails.
+pub fn seal_mapping(
+    mapping: &HashMap<String, String>,
+    threshold: u8,
+    share_count: u8,
+) -> Result<(SealedMapping, Vec<KeyShare>), AnonymaskError> {
+    if threshold < 2 || share_count < threshold {
+        return Err(AnonymaskError::VaultError(format!(
+            "invalid sharing scheme: threshold {} of {} shares (threshold must be at least 2)",
+            threshold, share_count
+        )));
+    }
+
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    fill_random(key.as_mut())?;
+
+    let sealed = encrypt_mapping(mapping, key.as_ref(), threshold, &[])?;
+    let shares = split_secret(key.as_ref(), threshold, share_count)?;
+    Ok((sealed, shares))
+}
+
+/// Restore a sealed mapping from key shares.
+///
+/// # Arguments
+///
+/// * `sealed` - The sealed mapping
+/// * `shares` - Key shares; at least `sealed.threshold` distinct ones are required
+///
+/// # Errors
+///
+/// * `AnonymaskError::InsufficientKeyShares` - Fewer distinct shares than required
+/// * `AnonymaskError::VaultError` - Shares are malformed or don't belong to this mapping
+pub fn open_mapping(
+    sealed: &SealedMapping,
+    shares: &[KeyShare],
+) -> Result<HashMap<String, String>, AnonymaskError> {
+    let key = Zeroizing::new(combine_shares(shares, sealed.threshold)?);
+    decrypt_mapping(sealed, &key, &[])
+}
+
+/// A sealed mapping stored in a [`Vault`] with its retention metadata.
+///
+/// The metadata is authenticated along with the ciphertext: an entry whose
+/// id, creation time or expiry was changed fails to open.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultEntry {
+    /// Unique identifier of the entry within its scope
+    pub id: String,
+    /// When the mapping was stored
+    pub created_at: SystemTime,
+    /// When the mapping stops being readable (`None` = kept until shredded)
+    pub expires_at: Option<SystemTime>,
+    /// The encrypted mapping
+    pub sealed: SealedMapping,
+}
+
+impl VaultEntry {
+    /// Whether the entry's TTL has elapsed at the given time.
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Associated data binding the metadata to the ciphertext, so an
+    /// archived entry whose id or TTL was edited no longer decrypts.
+    fn associated_data(id: &str, created_at: SystemTime, expires_at: Option<SystemTime>) -> Vec<u8> {
+        serde_json::to_vec(&(id, created_at, expires_at)).expect("entry metadata is serializable")
+    }
+}
+
+/// What [`Vault::purge_value`] removed.
+///
+/// Holds placeholders but no original values, so it can be kept as a
+/// record of an erasure request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurgeReport {
+    /// Every placeholder that stood for the value, sorted by scope and entry
+    pub tokens: Vec<PurgedToken>,
+    /// Entries left without mappings, and removed
+    pub removed_entries: usize,
+    /// Scopes whose key was replaced; their previously exported entries
+    /// no longer open
+    pub rekeyed_scopes: Vec<String>,
+}
+
+/// A placeholder removed from a stored mapping.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurgedToken {
+    /// Scope of the entry
+    pub scope: String,
+    /// Identifier of the entry
+    pub entry_id: String,
+    /// The placeholder
+    pub placeholder: String,
+}
+
+struct Scope {
+    key: Zeroizing<[u8; KEY_LEN]>,
+    entries: Vec<VaultEntry>,
+}
+
+fn new_key() -> Result<Zeroizing<[u8; KEY_LEN]>, AnonymaskError> {
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    fill_random(key.as_mut())?;
+    Ok(key)
+}
+
+/// Per-scope encrypted mapping storage with expiry and crypto-shredding.
+///
+/// Each scope (a tenant, a session, ...) gets its own random data key. Mappings
+/// are stored encrypted under that key with optional TTL metadata. Shredding a
+/// scope destroys its key, so any copies of its sealed entries (backups,
+/// archives) become permanently unreadable and old anonymized data can no
+/// longer be linked back to identities.
+///
+/// # Examples
+///
+/// ```
+/// use anonymask_core::vault::Vault;
+/// use std::collections::HashMap;
+/// use std::time::Duration;
+///
+/// let mut mapping = HashMap::new();
+/// mapping.insert("EMAIL_1".to_string(), "john@email.com".to_string());
+///
+/// let mut vault = Vault::new();
+/// let id = vault.store("tenant-a", &mapping, Some(Duration::from_secs(3600))).unwrap();
+/// assert_eq!(vault.load("tenant-a", &id).unwrap(), mapping);
+///
+/// assert!(vault.shred("tenant-a"));
+/// assert!(vault.load("tenant-a", &id).is_err());
+/// ```
+#[derive(Default)]
+pub struct Vault {
+    scopes: HashMap<String, Scope>,
+}
+
+impl Vault {
+    /// Create an empty vault.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encrypt and store a mapping under a scope.
+    ///
+    /// A data key is generated the first time a scope is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `scope` - Tenant or session the mapping belongs to
+    /// * `mapping` - HashMap mapping placeholders to original values
+    /// * `ttl` - How long the mapping stays readable (`None` = until shredded)
+    ///
+    /// # Returns
+    ///
+    /// The identifier of the stored entry.
+    pub fn store(
+        &mut self,
+        scope: &str,
+        mapping: &HashMap<String, String>,
+        ttl: Option<Duration>,
+    ) -> Result<String, AnonymaskError> {
+        let created_at = SystemTime::now();
+        let expires_at = ttl.map(|ttl| created_at + ttl);
+
+        if !self.scopes.contains_key(scope) {
+            self.scopes.insert(
+                scope.to_string(),
+                Scope {
+                    key: new_key()?,
+                    entries: Vec::new(),
+                },
+            );
+        }
+        let entry_scope = self.scopes.get_mut(scope).expect("scope was just inserted");
+
+        let id = Uuid::new_v4().simple().to_string();
+        let aad = VaultEntry::associated_data(&id, created_at, expires_at);
+        let sealed = encrypt_mapping(mapping, entry_scope.key.as_ref(), 1, &aad)?;
+        entry_scope.entries.push(VaultEntry {
+            id: id.clone(),
+            created_at,
+            expires_at,
+            sealed,
+        });
+        Ok(id)
+    }
+
+    /// Decrypt a stored mapping.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::VaultError` if the scope was shredded, the
+    /// entry doesn't exist, or its TTL has elapsed.
+    pub fn load(&self, scope: &str, id: &str) -> Result<HashMap<String, String>, AnonymaskError> {
+        let entry_scope = self.scope(scope)?;
+        let entry = entry_scope
+            .entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .ok_or_else(|| AnonymaskError::VaultError(format!("no entry '{}' in scope", id)))?;
+        self.open(scope, entry)
+    }
+
+    /// Decrypt and merge all unexpired mappings of a scope.
+    pub fn load_scope(&self, scope: &str) -> Result<HashMap<String, String>, AnonymaskError> {
+        let now = SystemTime::now();
+        let entry_scope = self.scope(scope)?;
+
+        let mut merged = HashMap::new();
+        for entry in entry_scope.entries.iter().filter(|e| !e.is_expired_at(now)) {
+            merged.extend(decrypt_entry(entry, entry_scope.key.as_ref())?);
+        }
+        Ok(merged)
+    }
+
+    /// Decrypt an entry previously exported from this vault.
+    ///
+    /// Works for archived copies as long as the scope hasn't been shredded
+    /// and the entry hasn't expired.
+    pub fn open(&self, scope: &str, entry: &VaultEntry) -> Result<HashMap<String, String>, AnonymaskError> {
+        if entry.is_expired_at(SystemTime::now()) {
+            return Err(AnonymaskError::VaultError(format!(
+                "entry '{}' has expired",
+                entry.id
+            )));
+        }
+        decrypt_entry(entry, self.scope(scope)?.key.as_ref())
+    }
+
+    /// Sealed entries of a scope, e.g. for archiving alongside anonymized data.
+    pub fn entries(&self, scope: &str) -> &[VaultEntry] {
+        self.scopes
+            .get(scope)
+            .map(|s| s.entries.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Destroy a scope's key and mappings.
+    ///
+    /// Any exported copies of the scope's entries become permanently
+    /// unreadable. Returns `false` if the scope didn't exist.
+    pub fn shred(&mut self, scope: &str) -> bool {
+        // The key is zeroed when the scope is dropped
+        self.scopes.remove(scope).is_some()
+    }
+
+    /// Remove entries whose TTL has elapsed.
+    ///
+    /// Scopes left without entries are shredded. Returns the number of
+    /// entries removed.
+    pub fn purge_expired(&mut self) -> usize {
+        let now = SystemTime::now();
+        let mut removed = 0;
+        for entry_scope in self.scopes.values_mut() {
+            let before = entry_scope.entries.len();
+            entry_scope.entries.retain(|entry| !entry.is_expired_at(now));
+            removed += before - entry_scope.entries.len();
+        }
+        self.scopes.retain(|_, s| !s.entries.is_empty());
+        removed
+    }
+
+    /// Remove every placeholder issued for an original value, e.g. to honor
+    /// a deletion request.
+    ///
+    /// Each entry of any scope mapping a placeholder to `original` (compared
+    /// exactly) is re-encrypted without it, including expired entries, and
+    /// removed if nothing else is left in it. The key of each affected scope
+    /// is replaced, so archived copies of its entries, which still hold the
+    /// value, can't be opened anymore; export its [`entries`](Self::entries)
+    /// again afterwards. Scopes left without entries are shredded.
+    ///
+    /// Nothing is changed if any affected entry fails to decrypt.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anonymask_core::vault::Vault;
+    /// use std::collections::HashMap;
+    ///
+    /// let mapping = HashMap::from([
+    ///     ("EMAIL_1".to_string(), "jo@x.io".to_string()),
+    ///     ("PHONE_2".to_string(), "555-0100".to_string()),
+    /// ]);
+    /// let mut vault = Vault::new();
+    /// let id = vault.store("tenant-a", &mapping, None).unwrap();
+    /// let archived = vault.entries("tenant-a")[0].clone();
+    ///
+    /// let report = vault.purge_value("jo@x.io").unwrap();
+    /// assert_eq!(report.tokens[0].placeholder, "EMAIL_1");
+    /// assert_eq!(vault.load("tenant-a", &id).unwrap().len(), 1);
+    /// assert!(vault.open("tenant-a", &archived).is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `AnonymaskError::VaultError` if an entry can't be decrypted
+    /// or re-encrypted.
+    pub fn purge_value(&mut self, original: &str) -> Result<PurgeReport, AnonymaskError> {
+        let mut report = PurgeReport::default();
+        let mut names: Vec<&String> = self.scopes.keys().collect();
+        names.sort();
+
+        // Re-encrypt affected scopes before changing any of them
+        let mut rekeyed = Vec::new();
+        for name in names {
+            let scope = &self.scopes[name];
+            let mut mappings = Vec::with_capacity(scope.entries.len());
+            let mut affected = false;
+            for entry in &scope.entries {
+                let mut mapping = decrypt_entry(entry, scope.key.as_ref())?;
+                let mut placeholders: Vec<String> = mapping
+                    .iter()
+                    .filter(|(_, value)| value.as_str() == original)
+                    .map(|(placeholder, _)| placeholder.clone())
+                    .collect();
+                placeholders.sort();
+                for placeholder in placeholders {
+                    mapping.remove(&placeholder);
+                    report.tokens.push(PurgedToken {
+                        scope: name.clone(),
+                        entry_id: entry.id.clone(),
+                        placeholder,
+                    });
+                    affected = true;
+                }
+                mappings.push(mapping);
+            }
+            if !affected {
+                continue;
+            }
+
+            let key = new_key()?;
+            let mut entries = Vec::with_capacity(scope.entries.len());
+            for (entry, mapping) in scope.entries.iter().zip(mappings) {
+                if mapping.is_empty() {
+                    report.removed_entries += 1;
+                    continue;
+                }
+                let aad = VaultEntry::associated_data(&entry.id, entry.created_at, entry.expires_at);
+                entries.push(VaultEntry {
+                    sealed: encrypt_mapping(&mapping, key.as_ref(), entry.sealed.threshold, &aad)?,
+                    ..entry.clone()
+                });
+            }
+            report.rekeyed_scopes.push(name.clone());
+            rekeyed.push((name.clone(), Scope { key, entries }));
+        }
+
+        for (name, scope) in rekeyed {
+            // The old key is zeroed when the replaced scope is dropped
+            if scope.entries.is_empty() {
+                self.scopes.remove(&name);
+            } else {
+                self.scopes.insert(name, scope);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Names of all live scopes.
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scopes.keys().map(String::as_str)
+    }
+
+    fn scope(&self, scope: &str) -> Result<&Scope, AnonymaskError> {
+        self.scopes.get(scope).ok_or_else(|| {
+            AnonymaskError::VaultError(format!("scope '{}' does not exist or was shredded", scope))
+        })
+    }
+}
+
+fn decrypt_entry(entry: &VaultEntry, key: &[u8]) -> Result<HashMap<String, String>, AnonymaskError> {
+    let aad = VaultEntry::associated_data(&entry.id, entry.created_at, entry.expires_at);
+    decrypt_mapping(&entry.sealed, key, &aad)
+}
+
+pub(crate) fn encrypt_mapping(
+    mapping: &HashMap<String, String>,
+    key: &[u8],
+    threshold: u8,
+    aad: &[u8],
+) -> Result<SealedMapping, AnonymaskError> {
+    let plaintext = serde_json::to_vec(mapping)
+        .map_err(|e| AnonymaskError::VaultError(format!("failed to encode mapping: {}", e)))?;
+
+    let mut nonce = [0u8; NONCE_LEN];
+    fill_random(&mut nonce)?;
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| AnonymaskError::VaultError("failed to encrypt mapping".to_string()))?;
+
+    Ok(SealedMapping {
+        threshold,
+        nonce: nonce.to_vec(),
+        ciphertext,
+    })
+}
+
+pub(crate) fn decrypt_mapping(
+    sealed: &SealedMapping,
+    key: &[u8],
+    aad: &[u8],
+) -> Result<HashMap<String, String>, AnonymaskError> {
+    if key.len() != KEY_LEN || sealed.nonce.len() != NONCE_LEN {
+        return Err(AnonymaskError::VaultError("malformed key or nonce".to_string()));
+    }
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(&sealed.nonce),
+            Payload {
+                msg: &sealed.ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| {
+            AnonymaskError::VaultError(
+                "failed to decrypt mapping: wrong key shares or corrupted data".to_string(),
+            )
+        })?;
+
+    serde_json::from_slice(&plaintext)
+        .map_err(|e| AnonymaskError::VaultError(format!("failed to decode mapping: {}", e)))
+}
+
+pub(crate) fn fill_random(buf: &mut [u8]) -> Result<(), AnonymaskError> {
+    getrandom::getrandom(buf)
+        .map_err(|e| AnonymaskError::VaultError(format!("random generator unavailable: {}", e)))
+}
+
+/// Split a secret into `count` shares over GF(256), `threshold` of which recover it.
+fn split_secret(secret: &[u8], threshold: u8, count: u8) -> Result<Vec<KeyShare>, AnonymaskError> {
+    let mut shares: Vec<KeyShare> = (1..=count)
+        .map(|index| KeyShare {
+            index,
+            threshold,
+            data: Vec::with_capacity(secret.len()),
+        })
+        .collect();
+
+    let mut coefficients = vec![0u8; threshold as usize];
+    for &byte in secret {
+        // Random polynomial of degree threshold - 1 with the secret byte as constant term
+        coefficients[0] = byte;
+        fill_random(&mut coefficients[1..])?;
+
+        for share in &mut shares {
+            // Horner evaluation at x = share.index
+            let y = coefficients
+                .iter()
+                .rev()
+                .fold(0u8, |acc, &c| gf_mul(acc, share.index) ^ c);
+            share.data.push(y);
+        }
+    }
+    coefficients.iter_mut().for_each(|c| *c = 0);
+
+    Ok(shares)
+}
+
+/// Recover a secret from shares using Lagrange interpolation at x = 0.
+fn combine_shares(shares: &[KeyShare], threshold: u8) -> Result<Vec<u8>, AnonymaskError> {
+    let mut distinct: Vec<&KeyShare> = Vec::new();
+    for share in shares {
+        if share.index == 0 || share.threshold != threshold {
+            return Err(AnonymaskError::VaultError(format!(
+                "key share {} does not belong to this mapping",
+                share.index
+            )));
+        }
+        if !distinct.iter().any(|s| s.index == share.index) {
+            distinct.push(share);
+        }
+    }
+
+    if distinct.len() < threshold as usize {
+        return Err(AnonymaskError::InsufficientKeyShares {
+            provided: distinct.len(),
+            required: threshold as usize,
+        });
+    }
+    distinct.truncate(threshold as usize);
+
+    let len = distinct[0].data.len();
+    if distinct.iter().any(|s| s.data.len() != len) {
+        return Err(AnonymaskError::VaultError(
+            "key shares have inconsistent lengths".to_string(),
+        ));
+    }
+
+    let mut secret = vec![0u8; len];
+    for (i, share) in distinct.iter().enumerate() {
+        // Lagrange basis polynomial for this share evaluated at 0
+        let mut basis = 1u8;
+        for (j, other) in distinct.iter().enumerate() {
+            if i != j {
+                basis = gf_mul(basis, gf_div(other.index, other.index ^ share.index));
+            }
+        }
+        for (byte, &y) in secret.iter_mut().zip(&share.data) {
+            *byte ^= gf_mul(y, basis);
+        }
+    }
+
+    Ok(secret)
+}
+
+/// Multiplication in GF(2^8) with the AES polynomial x^8 + x^4 + x^3 + x + 1.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+fn gf_inv(a: u8) -> u8 {
+    // a^254 == a^-1 in GF(2^8)
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exp = 254u8;
+    while exp != 0 {
+        if exp & 1 != 0 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+fn gf_div(a: u8, b: u8) -> u8 {
+    gf_mul(a, gf_inv(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_mapping() -> HashMap<String, String> {
+        let mut mapping = HashMap::new();
+        mapping.insert("EMAIL_1".to_string(), "john@email.com".to_string());
+        mapping.insert("PHONE_2".to_string(), "555-123-4567".to_string());
+        mapping
+    }
+
+    #[test]
+    fn test_any_threshold_subset_opens_mapping() {
+        let mapping = sample_mapping();
+        let (sealed, shares) = seal_mapping(&mapping, 2, 3).unwrap();
+
+        for (a, b) in [(0, 1), (0, 2), (1, 2)] {
+            let subset = [shares[a].clone(), shares[b].clone()];
+            assert_eq!(open_mapping(&sealed, &subset).unwrap(), mapping);
+        }
+    }
+
+    #[test]
+    fn test_single_share_is_rejected() {
+        let (sealed, shares) = seal_mapping(&sample_mapping(), 2, 2).unwrap();
+        let duplicated = [shares[0].clone(), shares[0].clone()];
+
+        assert!(matches!(
+            open_mapping(&sealed, &duplicated),
+            Err(AnonymaskError::InsufficientKeyShares {
+                provided: 1,
+                required: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_tampered_share_fails_to_decrypt() {
+        let (sealed, mut shares) = seal_mapping(&sample_mapping(), 2, 2).unwrap();
+        shares[1].data[0] ^= 0xff;
+
+        assert!(matches!(
+            open_mapping(&sealed, &shares),
+            Err(AnonymaskError::VaultError(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_scheme_is_rejected() {
+        assert!(seal_mapping(&sample_mapping(), 1, 3).is_err());
+        assert!(seal_mapping(&sample_mapping(), 3, 2).is_err());
+    }
+
+    #[test]
+    fn test_vault_shred_makes_archived_entries_unreadable() {
+        let mut vault = Vault::new();
+        let id = vault.store("session-1", &sample_mapping(), None).unwrap();
+        let archived = vault.entries("session-1")[0].clone();
+
+        assert_eq!(archived.id, id);
+        assert_eq!(vault.open("session-1", &archived).unwrap(), sample_mapping());
+
+        assert!(vault.shred("session-1"));
+        assert!(!vault.shred("session-1"));
+        assert!(vault.open("session-1", &archived).is_err());
+    }
+
+    #[test]
+    fn test_vault_expired_entries_are_rejected_and_purged() {
+        let mut vault = Vault::new();
+        let expired = vault
+            .store("tenant", &sample_mapping(), Some(Duration::ZERO))
+            .unwrap();
+        vault.store("tenant", &sample_mapping(), None).unwrap();
+
+        assert!(vault.load("tenant", &expired).is_err());
+        assert_eq!(vault.load_scope("tenant").unwrap(), sample_mapping());
+        assert_eq!(vault.purge_expired(), 1);
+        assert_eq!(vault.entries("tenant").len(), 1);
+    }
+
+    #[test]
+    fn test_vault_entry_metadata_is_authenticated() {
+        let mut vault = Vault::new();
+        vault
+            .store("tenant", &sample_mapping(), Some(Duration::from_secs(60)))
+            .unwrap();
+        let archived = vault.entries("tenant")[0].clone();
+
+        let mut extended = archived.clone();
+        extended.expires_at = None;
+        assert!(matches!(
+            vault.open("tenant", &extended),
+            Err(AnonymaskError::VaultError(_))
+        ));
+        let mut renamed = archived.clone();
+        renamed.id = "other".to_string();
+        assert!(vault.open("tenant", &renamed).is_err());
+        assert_eq!(vault.open("tenant", &archived).unwrap(), sample_mapping());
+    }
+
+    #[test]
+    fn test_vault_purge_value() {
+        let mut vault = Vault::new();
+        let first = vault.store("tenant", &sample_mapping(), None).unwrap();
+        let only = HashMap::from([("EMAIL_9".to_string(), "john@email.com".to_string())]);
+        vault.store("tenant", &only, Some(Duration::ZERO)).unwrap();
+        vault.store("session", &only, None).unwrap();
+        vault.store("other", &sample_mapping(), None).unwrap();
+        let exported = vault.entries("other")[0].clone();
+        vault.purge_value("555-123-4567").unwrap();
+        let archived = vault.entries("tenant")[0].clone();
+
+        let report = vault.purge_value("john@email.com").unwrap();
+        let tokens: Vec<_> = report
+            .tokens
+            .iter()
+            .map(|t| (t.scope.as_str(), t.placeholder.as_str()))
+            .collect();
+        assert_eq!(
+            tokens,
+            [("other", "EMAIL_1"), ("session", "EMAIL_9"), ("tenant", "EMAIL_1"), ("tenant", "EMAIL_9")]
+        );
+        assert_eq!(report.removed_entries, 4);
+        assert_eq!(report.rekeyed_scopes, ["other", "session", "tenant"]);
+
+        // Emptied scopes are shredded and archived copies no longer open
+        assert_eq!(vault.scopes().count(), 0);
+        assert!(vault.load("tenant", &first).is_err());
+        assert!(vault.open("tenant", &archived).is_err());
+        assert!(vault.open("other", &exported).is_err());
+        assert_eq!(vault.purge_value("john@email.com").unwrap(), PurgeReport::default());
+    }
+
+    #[test]
+    fn test_sealed_mapping_has_no_plaintext() {
+        let (sealed, _) = seal_mapping(&sample_mapping(), 2, 2).unwrap();
+        let serialized = serde_json::to_string(&sealed).unwrap();
+        assert!(!serialized.contains("john@email.com"));
+    }
+}
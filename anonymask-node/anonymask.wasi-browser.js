@@ -57,3 +57,4 @@ const {
 })
 export default __napiModule.exports
 export const Anonymizer = __napiModule.exports.Anonymizer
+export const AnonymizerStream = __napiModule.exports.AnonymizerStream
@@ -109,3 +109,4 @@ const { instance: __napiInstance, module: __wasiModule, napiModule: __napiModule
 })
 module.exports = __napiModule.exports
 module.exports.Anonymizer = __napiModule.exports.Anonymizer
+module.exports.AnonymizerStream = __napiModule.exports.AnonymizerStream
@@ -0,0 +1,153 @@
+/* auto-generated by NAPI-RS */
+/* eslint-disable */
+export interface AnonymizationResult {
+  anonymizedText: string
+  mapping: Record<string, string>
+  entities: Array<Entity>
+  /** Placeholder positions in `anonymized_text`, one per entity */
+  outputSpans: Array<OutputSpan>
+  /** Whether entities past `maxEntities` were left in the text */
+  truncated: boolean
+}
+
+export declare class Anonymizer {
+  /**
+   * Create a new anonymizer with optional configuration.
+   *
+   * Examples:
+   * ```js
+   * // Without config (uses defaults)
+   * const anonymizer = new Anonymizer(['email', 'phone']);
+   *
+   * // With config
+   * const config = {
+   *   caseSensitive: true,
+   *   wordBoundaryCheck: false,
+   *   placeholderFormat: 'short',
+   *   maxEntities: 100
+   * };
+   * const anonymizer = new Anonymizer(['email'], config);
+   * ```
+   */
+  constructor(entityTypes: Array<string>, config?: AnonymizerConfig | undefined | null)
+  anonymize(text: string): AnonymizationResult
+  /**
+   * Like `anonymize`, but runs on the libuv thread pool so large documents don't block the
+   * event loop.
+   *
+   * Aborting `signal` before the work starts rejects the promise.
+   *
+   * ```js
+   * const result = await anonymizer.anonymizeAsync(document);
+   * ```
+   */
+  anonymizeAsync(text: string, signal?: AbortSignal | undefined | null): Promise<AnonymizationResult>
+  anonymizeWithCustom(text: string, customEntities?: Record<string, Array<string>> | undefined | null): AnonymizationResult
+  /**
+   * Anonymize spans found by your own detector, skipping detection.
+   *
+   * Spans use byte offsets and must match `value`; overlapping or invalid spans throw.
+   */
+  anonymizeEntities(text: string, entities: Array<Entity>): AnonymizationResult
+  deanonymize(text: string, mapping: Record<string, string>): string
+  /** Restore many texts against one mapping, preparing the mapping only once. */
+  deanonymizeBatch(texts: Array<string>, mapping: Record<string, string>): Array<string>
+  /** Like `deanonymize`, but throws if the text contains a placeholder missing from the mapping. */
+  deanonymizeStrict(text: string, mapping: Record<string, string>): string
+  /**
+   * Start masking text that arrives in chunks, e.g. typed into a text area or read from a
+   * network body.
+   *
+   * `window` is the number of trailing bytes held back until the next chunk shows whether an
+   * entity continues (default 256).
+   *
+   * ```js
+   * const stream = anonymizer.chunkStream();
+   * for (const chunk of chunks) output.push(stream.push(chunk));
+   * output.push(stream.flush());
+   * ```
+   */
+  chunkStream(window?: number | undefined | null): AnonymizerStream
+}
+
+/**
+ * Configuration for anonymizer behavior.
+ *
+ * Provides fine-grained control over how PII is detected and replaced.
+ *
+ * Placeholder formats:
+ * - "standard": TYPE_UUID (e.g., "EMAIL_a1b2c3d4...")
+ * - "short": TYPE_INDEX, numbered per type (e.g., "EMAIL_1", "EMAIL_2", "PHONE_1")
+ * - Custom template string with {type}, {type_lower}, {uuid}, {counter} and {index_by_type}
+ *   placeholders, e.g. "<{type_lower}-{index_by_type:03}>"; write {{ and }} for literal braces
+ */
+export interface AnonymizerConfig {
+  /** Whether custom entity matching is case-sensitive (default: true) */
+  caseSensitive: boolean
+  /** Check word boundaries for custom entities (default: false) */
+  wordBoundaryCheck: boolean
+  /** Format for placeholders - "standard", "short", or custom template (default: "standard") */
+  placeholderFormat: string
+  /** Formats by entity type name, used instead of placeholderFormat, e.g. { ssn: "[REDACTED]" } (default: {}) */
+  placeholderFormats?: Record<string, string>
+  /** Maximum entities to detect, 0 for unlimited (default: 0) */
+  maxEntities: number
+  /** Regional pattern packs for phones, addresses and dates, e.g. ["us", "de"] (default: ["us"]) */
+  locales?: Array<string>
+  /** Discard detections scoring below this confidence, 0.0-1.0 (default: 0.5) */
+  minConfidence?: number
+  /** Optional library patterns to enable, e.g. ["aws_arn", "vin"] (default: []) */
+  namedPatterns?: Array<string>
+  /** Your own regular expressions by entity type name, e.g. { employee_id: "EMP-\\d{6}" } (default: {}) */
+  customPatterns?: Record<string, string>
+  /** Key name patterns whose values are PII, with their entity type name, e.g. { "(?i)dob": "date" } (default: {}) */
+  keyRules?: Record<string, string>
+  /** Known-safe values that are never anonymized, e.g. ["support@ourcompany.com"] (default: []) */
+  allowValues?: Array<string>
+  /** Regular expressions for known-safe values, matched against whole values (default: []) */
+  allowPatterns?: Array<string>
+  /** How overlapping detections are resolved: "earliest", "longest" or "priority:ssn,phone" (default: "earliest") */
+  overlapStrategy?: string
+  /** Which type wins when a value is detected both as a custom and a built-in type: "custom" or "builtin" (default: "custom") */
+  duplicatePreference?: string
+  /** Keyword weights by entity type; nearby keywords raise or lower confidence, e.g. { ssn: { order: -0.3 } } (default: {}) */
+  contextKeywords?: Record<string, Record<string, number>>
+  /** Bytes on either side of a match searched for context keywords (default: 32) */
+  contextWindow?: number
+  /** URL query parameters whose values are "credential" entities, e.g. ["token", "key"] (default: []) */
+  credentialQueryParams?: Array<string>
+  /** Shortest string the "secret" entropy detector considers (default: 20) */
+  secretMinLength?: number
+  /** Minimum Shannon entropy in bits per character for "secret" (default: 4.0) */
+  secretMinEntropy?: number
+  /** Also restore placeholders whose case, spaces or punctuation an LLM changed, e.g. "Email 1" (default: false) */
+  tolerantDeanonymization?: boolean
+  /** How complete US phone numbers must be: "loose" also matches partial numbers such as 555-123, "strict" only 10-digit ones (default: "loose") */
+  phoneMatching?: string
+}
+
+/** Chunk-based masking; created with `Anonymizer.chunkStream()`. */
+export declare class AnonymizerStream {
+  /** Add a chunk and return the masked text ready to emit, possibly empty. */
+  push(chunk: string): string
+  /** Return the masked text of everything held back, e.g. at the end of the input. */
+  flush(): string
+  /** Restore original values using the mapping accumulated so far. */
+  deanonymize(text: string): string
+  /** The accumulated placeholder -> original value mapping. */
+  get mapping(): Record<string, string>
+}
+
+export interface Entity {
+  entityType: string
+  value: string
+  start: number
+  end: number
+  confidence: number
+}
+
+export interface OutputSpan {
+  placeholder: string
+  start: number
+  end: number
+}
@@ -323,6 +323,7 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`);
 }
 
-const { Anonymizer } = nativeBinding;
+const { Anonymizer, AnonymizerStream } = nativeBinding;
 
 module.exports.Anonymizer = Anonymizer;
+module.exports.AnonymizerStream = AnonymizerStream;
@@ -1,9 +1,12 @@
+use napi::bindgen_prelude::{AbortSignal, AsyncTask};
+use napi::{Env, Task};
 use napi_derive::napi;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use anonymask_core::{
-    Anonymizer as CoreAnonymizer, AnonymizerConfig as CoreConfig,
-    EntityType, PlaceholderFormat as CorePlaceholderFormat,
+    AllowRule, AnonymizationStream, Anonymizer as CoreAnonymizer, AnonymizerConfig as CoreConfig, ContextRule,
+    CustomPattern, EntityType, KeyRule, Locale, PlaceholderFormat as CorePlaceholderFormat, PlaceholderOverride,
 };
 
 #[napi(object)]
@@ -13,6 +16,15 @@ pub struct Entity {
   pub value: String,
   pub start: u32,
   pub end: u32,
+  pub confidence: f64,
+}
+
+#[napi(object)]
+#[derive(Clone)]
+pub struct OutputSpan {
+  pub placeholder: String,
+  pub start: u32,
+  pub end: u32,
 }
 
 #[napi(object)]
@@ -20,6 +32,10 @@ pub struct AnonymizationResult {
   pub anonymized_text: String,
   pub mapping: HashMap<String, String>,
   pub entities: Vec<Entity>,
+  /// Placeholder positions in `anonymized_text`, one per entity
+  pub output_spans: Vec<OutputSpan>,
+  /// Whether entities past `maxEntities` were left in the text
+  pub truncated: bool,
 }
 
 /// Configuration for anonymizer behavior.
@@ -28,8 +44,9 @@ pub struct AnonymizationResult {
 ///
 /// Placeholder formats:
 /// - "standard": TYPE_UUID (e.g., "EMAIL_a1b2c3d4...")
-/// - "short": TYPE_COUNTER (e.g., "EMAIL_1", "EMAIL_2")
-/// - Custom template string with {type}, {uuid}, {counter} placeholders
+/// - "short": TYPE_INDEX, numbered per type (e.g., "EMAIL_1", "EMAIL_2", "PHONE_1")
+/// - Custom template string with {type}, {type_lower}, {uuid}, {counter} and {index_by_type}
+///   placeholders, e.g. "<{type_lower}-{index_by_type:03}>"; write {{ and }} for literal braces
 #[napi(object)]
 #[derive(Clone)]
 pub struct AnonymizerConfig {
@@ -39,8 +56,42 @@ pub struct AnonymizerConfig {
   pub word_boundary_check: bool,
   /// Format for placeholders - "standard", "short", or custom template (default: "standard")
   pub placeholder_format: String,
+  /// Formats by entity type name, used instead of placeholderFormat, e.g. { ssn: "[REDACTED]" } (default: {})
+  pub placeholder_formats: Option<HashMap<String, String>>,
   /// Maximum entities to detect, 0 for unlimited (default: 0)
   pub max_entities: u32,
+  /// Regional pattern packs for phones, addresses and dates, e.g. ["us", "de"] (default: ["us"])
+  pub locales: Option<Vec<String>>,
+  /// Discard detections scoring below this confidence, 0.0-1.0 (default: 0.5)
+  pub min_confidence: Option<f64>,
+  /// Optional library patterns to enable, e.g. ["aws_arn", "vin"] (default: [])
+  pub named_patterns: Option<Vec<String>>,
+  /// Your own regular expressions by entity type name, e.g. { employee_id: "EMP-\\d{6}" } (default: {})
+  pub custom_patterns: Option<HashMap<String, String>>,
+  /// Key name patterns whose values are PII, with their entity type name, e.g. { "(?i)dob": "date" } (default: {})
+  pub key_rules: Option<HashMap<String, String>>,
+  /// Known-safe values that are never anonymized, e.g. ["support@ourcompany.com"] (default: [])
+  pub allow_values: Option<Vec<String>>,
+  /// Regular expressions for known-safe values, matched against whole values (default: [])
+  pub allow_patterns: Option<Vec<String>>,
+  /// How overlapping detections are resolved: "earliest", "longest" or "priority:ssn,phone" (default: "earliest")
+  pub overlap_strategy: Option<String>,
+  /// Which type wins when a value is detected both as a custom and a built-in type: "custom" or "builtin" (default: "custom")
+  pub duplicate_preference: Option<String>,
+  /// Keyword weights by entity type; nearby keywords raise or lower confidence, e.g. { ssn: { order: -0.3 } } (default: {})
+  pub context_keywords: Option<HashMap<String, HashMap<String, f64>>>,
+  /// Bytes on either side of a match searched for context keywords (default: 32)
+  pub context_window: Option<u32>,
+  /// URL query parameters whose values are "credential" entities, e.g. ["token", "key"] (default: [])
+  pub credential_query_params: Option<Vec<String>>,
+  /// Shortest string the "secret" entropy detector considers (default: 20)
+  pub secret_min_length: Option<u32>,
+  /// Minimum Shannon entropy in bits per character for "secret" (default: 4.0)
+  pub secret_min_entropy: Option<f64>,
+  /// Also restore placeholders whose case, spaces or punctuation an LLM changed, e.g. "Email 1" (default: false)
+  pub tolerant_deanonymization: Option<bool>,
+  /// How complete US phone numbers must be: "loose" also matches partial numbers such as 555-123, "strict" only 10-digit ones (default: "loose")
+  pub phone_matching: Option<String>,
 }
 
 impl Default for AnonymizerConfig {
@@ -49,31 +100,209 @@ impl Default for AnonymizerConfig {
       case_sensitive: true,
       word_boundary_check: false,
       placeholder_format: "standard".to_string(),
+      placeholder_formats: None,
       max_entities: 0,
+      locales: None,
+      min_confidence: None,
+      named_patterns: None,
+      custom_patterns: None,
+      key_rules: None,
+      allow_values: None,
+      allow_patterns: None,
+      overlap_strategy: None,
+      duplicate_preference: None,
+      context_keywords: None,
+      context_window: None,
+      credential_query_params: None,
+      secret_min_length: None,
+      secret_min_entropy: None,
+      tolerant_deanonymization: None,
+      phone_matching: None,
     }
   }
 }
 
+fn placeholder_format(format: &str) -> CorePlaceholderFormat {
+  match format {
+    "standard" => CorePlaceholderFormat::Standard,
+    "short" => CorePlaceholderFormat::Short,
+    template => CorePlaceholderFormat::Custom(template.to_string()),
+  }
+}
+
 impl AnonymizerConfig {
-  fn to_core(&self) -> CoreConfig {
-    let placeholder_format = match self.placeholder_format.as_str() {
-      "standard" => CorePlaceholderFormat::Standard,
-      "short" => CorePlaceholderFormat::Short,
-      template => CorePlaceholderFormat::Custom(template.to_string()),
+  fn to_core(&self) -> napi::Result<CoreConfig> {
+    let mut placeholder_overrides = Vec::new();
+    for (entity_type, format) in self.placeholder_formats.iter().flatten() {
+      let entity_type =
+        EntityType::from_str(entity_type).map_err(|e| napi::Error::from_reason(e.to_string()))?;
+      placeholder_overrides.push(PlaceholderOverride::new(entity_type, placeholder_format(format)));
+    }
+    placeholder_overrides.sort_by_key(|o| o.entity_type.to_string());
+
+    let locales = match &self.locales {
+      Some(locales) => locales
+        .iter()
+        .map(|l| l.parse::<Locale>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?,
+      None => CoreConfig::default().locales,
+    };
+
+    let overlap_strategy = match &self.overlap_strategy {
+      Some(strategy) => strategy
+        .parse()
+        .map_err(|e: anonymask_core::AnonymaskError| napi::Error::from_reason(e.to_string()))?,
+      None => CoreConfig::default().overlap_strategy,
+    };
+
+    let duplicate_preference = match &self.duplicate_preference {
+      Some(preference) => preference
+        .parse()
+        .map_err(|e: anonymask_core::AnonymaskError| napi::Error::from_reason(e.to_string()))?,
+      None => CoreConfig::default().duplicate_preference,
+    };
+
+    let phone_matching = match &self.phone_matching {
+      Some(matching) => matching
+        .parse()
+        .map_err(|e: anonymask_core::AnonymaskError| napi::Error::from_reason(e.to_string()))?,
+      None => CoreConfig::default().phone_matching,
     };
 
-    CoreConfig {
+    let mut custom_patterns: Vec<CustomPattern> = self
+      .custom_patterns
+      .iter()
+      .flatten()
+      .map(|(name, pattern)| CustomPattern {
+        name: name.clone(),
+        pattern: pattern.clone(),
+      })
+      .collect();
+    custom_patterns.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut key_rules = Vec::new();
+    for (key, entity_type) in self.key_rules.iter().flatten() {
+      let entity_type =
+        EntityType::from_str(entity_type).map_err(|e| napi::Error::from_reason(e.to_string()))?;
+      key_rules.push(KeyRule::new(key.clone(), entity_type));
+    }
+    key_rules.sort_by(|a, b| a.key.cmp(&b.key));
+
+    let allow_list = self
+      .allow_values
+      .iter()
+      .flatten()
+      .map(AllowRule::value)
+      .chain(self.allow_patterns.iter().flatten().map(AllowRule::pattern))
+      .collect();
+
+    let mut context_rules = Vec::new();
+    for (entity_type, keywords) in self.context_keywords.iter().flatten() {
+      let entity_type =
+        EntityType::from_str(entity_type).map_err(|e| napi::Error::from_reason(e.to_string()))?;
+      for (keyword, weight) in keywords {
+        context_rules.push(ContextRule::new(
+          entity_type.clone(),
+          [keyword.clone()],
+          *weight as f32,
+        ));
+      }
+    }
+
+    let mut confidence = CoreConfig::default().confidence;
+    if let Some(min_confidence) = self.min_confidence {
+      confidence.min_confidence = min_confidence as f32;
+    }
+    if let Some(context_window) = self.context_window {
+      confidence.context_window = context_window as usize;
+    }
+
+    let mut secret_detection = CoreConfig::default().secret_detection;
+    if let Some(min_length) = self.secret_min_length {
+      secret_detection.min_length = min_length as usize;
+    }
+    if let Some(min_entropy) = self.secret_min_entropy {
+      secret_detection.min_entropy = min_entropy as f32;
+    }
+
+    Ok(CoreConfig {
       case_sensitive: self.case_sensitive,
       word_boundary_check: self.word_boundary_check,
-      placeholder_format,
+      placeholder_format: placeholder_format(&self.placeholder_format),
+      placeholder_overrides,
+      tolerant_deanonymization: self.tolerant_deanonymization.unwrap_or(false),
       max_entities: self.max_entities as usize,
-    }
+      locales,
+      named_patterns: self.named_patterns.clone().unwrap_or_default(),
+      custom_patterns,
+      key_rules,
+      allow_list,
+      overlap_strategy,
+      duplicate_preference,
+      context_rules,
+      credential_query_params: self.credential_query_params.clone().unwrap_or_default(),
+      phone_matching,
+      secret_detection,
+      confidence,
+      ..CoreConfig::default()
+    })
+  }
+}
+
+fn convert_result(result: anonymask_core::AnonymizationResult) -> AnonymizationResult {
+  AnonymizationResult {
+    anonymized_text: result.anonymized_text,
+    mapping: result.mapping,
+    entities: result
+      .entities
+      .into_iter()
+      .map(|e| Entity {
+        entity_type: e.entity_type.to_string(),
+        value: e.value,
+        start: e.start as u32,
+        end: e.end as u32,
+        confidence: e.confidence as f64,
+      })
+      .collect(),
+    output_spans: result
+      .output_spans
+      .into_iter()
+      .map(|span| OutputSpan {
+        placeholder: span.placeholder,
+        start: span.start as u32,
+        end: span.end as u32,
+      })
+      .collect(),
+    truncated: result.truncated,
+  }
+}
+
+/// `Anonymizer.anonymizeAsync()` on the libuv thread pool.
+pub struct AnonymizeTask {
+  inner: Arc<CoreAnonymizer>,
+  text: String,
+}
+
+impl Task for AnonymizeTask {
+  type Output = anonymask_core::AnonymizationResult;
+  type JsValue = AnonymizationResult;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    self
+      .inner
+      .anonymize(&self.text)
+      .map_err(|e| napi::Error::from_reason(e.to_string()))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(convert_result(output))
   }
 }
 
 #[napi]
 pub struct Anonymizer {
-  inner: CoreAnonymizer,
+  inner: Arc<CoreAnonymizer>,
 }
 
 #[napi]
@@ -98,19 +327,21 @@ impl Anonymizer {
   pub fn new(entity_types: Vec<String>, config: Option<AnonymizerConfig>) -> napi::Result<Self> {
     let entity_types: Result<Vec<EntityType>, _> = entity_types
       .into_iter()
-      .map(|s| EntityType::from_str(&s))
+      .map(|s| EntityType::from_str_strict(&s))
       .collect();
 
     let entity_types = entity_types.map_err(|e| napi::Error::from_reason(e.to_string()))?;
 
     let inner = if let Some(cfg) = config {
-      CoreAnonymizer::with_config(entity_types, cfg.to_core())
+      CoreAnonymizer::with_config(entity_types, cfg.to_core()?)
         .map_err(|e| napi::Error::from_reason(e.to_string()))?
     } else {
       CoreAnonymizer::new(entity_types).map_err(|e| napi::Error::from_reason(e.to_string()))?
     };
 
-    Ok(Self { inner })
+    Ok(Self {
+      inner: Arc::new(inner),
+    })
   }
 
   #[napi]
@@ -120,23 +351,24 @@ impl Anonymizer {
       .anonymize(&text)
       .map_err(|e| napi::Error::from_reason(e.to_string()))?;
 
-    Ok(AnonymizationResult {
-      anonymized_text: result.anonymized_text,
-      mapping: result.mapping,
-      entities: result
-        .entities
-        .into_iter()
-        .map(|e| Entity {
-          entity_type: match &e.entity_type {
-            EntityType::Custom(name) => name.clone(),
-            _ => format!("{:?}", e.entity_type).to_lowercase(),
-          },
-          value: e.value,
-          start: e.start as u32,
-          end: e.end as u32,
-        })
-        .collect(),
-    })
+    Ok(convert_result(result))
+  }
+
+  /// Like `anonymize`, but runs on the libuv thread pool so large documents don't block the
+  /// event loop.
+  ///
+  /// Aborting `signal` before the work starts rejects the promise.
+  ///
+  /// ```js
+  /// const result = await anonymizer.anonymizeAsync(document);
+  /// ```
+  #[napi(ts_return_type = "Promise<AnonymizationResult>")]
+  pub fn anonymize_async(&self, text: String, signal: Option<AbortSignal>) -> AsyncTask<AnonymizeTask> {
+    let task = AnonymizeTask {
+      inner: Arc::clone(&self.inner),
+      text,
+    };
+    AsyncTask::with_optional_signal(task, signal)
   }
 
   #[napi]
@@ -164,27 +396,128 @@ impl Anonymizer {
       .anonymize_with_custom(&text, custom_entities.as_ref())
       .map_err(|e| napi::Error::from_reason(e.to_string()))?;
 
-    Ok(AnonymizationResult {
-      anonymized_text: result.anonymized_text,
-      mapping: result.mapping,
-      entities: result
-        .entities
-        .into_iter()
-        .map(|e| Entity {
-          entity_type: match &e.entity_type {
-            EntityType::Custom(name) => name.clone(),
-            _ => format!("{:?}", e.entity_type).to_lowercase(),
-          },
+    Ok(convert_result(result))
+  }
+
+  /// Anonymize spans found by your own detector, skipping detection.
+  ///
+  /// Spans use byte offsets and must match `value`; overlapping or invalid spans throw.
+  #[napi]
+  pub fn anonymize_entities(
+    &self,
+    text: String,
+    entities: Vec<Entity>,
+  ) -> napi::Result<AnonymizationResult> {
+    let entities = entities
+      .into_iter()
+      .map(|e| {
+        Ok(anonymask_core::Entity {
+          entity_type: EntityType::from_str(&e.entity_type)
+            .map_err(|e| napi::Error::from_reason(e.to_string()))?,
           value: e.value,
-          start: e.start as u32,
-          end: e.end as u32,
+          start: e.start as usize,
+          end: e.end as usize,
+          confidence: e.confidence as f32,
         })
-        .collect(),
-    })
+      })
+      .collect::<napi::Result<Vec<_>>>()?;
+
+    let result = self
+      .inner
+      .anonymize_entities(&text, entities)
+      .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+    Ok(convert_result(result))
   }
 
   #[napi]
   pub fn deanonymize(&self, text: String, mapping: HashMap<String, String>) -> String {
     self.inner.deanonymize(&text, &mapping)
   }
+
+  /// Restore many texts against one mapping, preparing the mapping only once.
+  #[napi]
+  pub fn deanonymize_batch(
+    &self,
+    texts: Vec<String>,
+    mapping: HashMap<String, String>,
+  ) -> napi::Result<Vec<String>> {
+    let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+    self
+      .inner
+      .deanonymize_batch(&texts, &mapping)
+      .map_err(|e| napi::Error::from_reason(e.to_string()))
+  }
+
+  /// Like `deanonymize`, but throws if the text contains a placeholder missing from the mapping.
+  #[napi]
+  pub fn deanonymize_strict(
+    &self,
+    text: String,
+    mapping: HashMap<String, String>,
+  ) -> napi::Result<String> {
+    self
+      .inner
+      .deanonymize_strict(&text, &mapping)
+      .map_err(|e| napi::Error::from_reason(e.to_string()))
+  }
+
+  /// Start masking text that arrives in chunks, e.g. typed into a text area or read from a
+  /// network body.
+  ///
+  /// `window` is the number of trailing bytes held back until the next chunk shows whether an
+  /// entity continues (default 256).
+  ///
+  /// ```js
+  /// const stream = anonymizer.chunkStream();
+  /// for (const chunk of chunks) output.push(stream.push(chunk));
+  /// output.push(stream.flush());
+  /// ```
+  #[napi]
+  pub fn chunk_stream(&self, window: Option<u32>) -> AnonymizerStream {
+    let mut inner = AnonymizationStream::new(Arc::clone(&self.inner));
+    if let Some(window) = window {
+      inner = inner.with_window(window as usize);
+    }
+    AnonymizerStream { inner }
+  }
+}
+
+/// Chunk-based masking; created with `Anonymizer.chunkStream()`.
+#[napi]
+pub struct AnonymizerStream {
+  inner: AnonymizationStream<Arc<CoreAnonymizer>>,
+}
+
+#[napi]
+impl AnonymizerStream {
+  /// Add a chunk and return the masked text ready to emit, possibly empty.
+  #[napi]
+  pub fn push(&mut self, chunk: String) -> napi::Result<String> {
+    self
+      .inner
+      .push(&chunk)
+      .map_err(|e| napi::Error::from_reason(e.to_string()))
+  }
+
+  /// Return the masked text of everything held back, e.g. at the end of the input.
+  #[napi]
+  pub fn flush(&mut self) -> napi::Result<String> {
+    self
+      .inner
+      .flush()
+      .map_err(|e| napi::Error::from_reason(e.to_string()))
+  }
+
+  /// Restore original values using the mapping accumulated so far.
+  #[napi]
+  pub fn deanonymize(&self, text: String) -> String {
+    self.inner.deanonymize(&text)
+  }
+
+  /// The accumulated placeholder -> original value mapping.
+  #[napi(getter)]
+  pub fn mapping(&self) -> HashMap<String, String> {
+    self.inner.mapping()
+  }
 }
@@ -3,7 +3,17 @@
  * Integration tests for anonymask Node.js bindings.
  */
 
-const { Anonymizer } = require("../index.js");
+const { Readable } = require("stream");
+const generated = require("../index.js");
+const wrapper = require("../wrapper.js");
+const { Anonymizer } = wrapper;
+
+test("the entry point re-exports the generated bindings unchanged", () => {
+  for (const [name, value] of Object.entries(generated)) {
+    expect(wrapper[name]).toBe(value);
+  }
+  expect(Object.keys(wrapper).sort()).toEqual([...Object.keys(generated), "AnonymizeTransform"].sort());
+});
 
 describe("Anonymizer", () => {
   let anonymizer;
@@ -145,6 +155,46 @@ describe("Anonymizer", () => {
     expect(result.entities[1].entityType).toBe("company");
     expect(result.entities[1].value).toBe("Acme Corp");
   });
+
+  test("rejects misspelled entity types", () => {
+    expect(() => new Anonymizer(["emial"])).toThrow("did you mean 'email'");
+  });
+
+  test("masks text pushed in chunks", () => {
+    const stream = anonymizer.chunkStream(16);
+    const chunks = ["Write to jo", "hn@email.com or call ", "555-123-", "4567 today, thanks!"];
+
+    let masked = chunks.map((chunk) => stream.push(chunk)).join("") + stream.flush();
+
+    expect(masked).not.toContain("john@email.com");
+    expect(masked).not.toContain("555-123-4567");
+    expect(Object.keys(stream.mapping)).toHaveLength(2);
+    expect(stream.deanonymize(masked)).toBe(chunks.join(""));
+  });
+
+  test("masks text piped through a transform stream", async () => {
+    const text = "Write to jöhn@email.com or call 555-123-4567 today, thanks!";
+    const bytes = Buffer.from(text);
+    const chunks = [];
+    for (let i = 0; i < bytes.length; i += 5) chunks.push(bytes.subarray(i, i + 5));
+
+    const masking = anonymizer.stream({ window: 16 });
+    let masked = "";
+    for await (const chunk of Readable.from(chunks).pipe(masking)) masked += chunk;
+
+    expect(masked).not.toContain("555-123-4567");
+    expect(masking.deanonymize(masked)).toBe(text);
+    expect(anonymizer.transform).toBe(anonymizer.stream);
+  });
+
+  test("anonymizes off the event loop", async () => {
+    const text = "Contact john@email.com or 555-123-4567";
+    const result = await anonymizer.anonymizeAsync(text);
+
+    expect(result.anonymizedText).not.toContain("john@email.com");
+    expect(result.entities).toHaveLength(2);
+    expect(anonymizer.deanonymize(result.anonymizedText, result.mapping)).toBe(text);
+  });
 });
 
 describe("AnonymizerConfig (v2.0.0 features)", () => {
@@ -192,6 +242,34 @@ describe("AnonymizerConfig (v2.0.0 features)", () => {
     expect(result.entities).toHaveLength(1);
   });
 
+  test("uses placeholder formats by entity type", () => {
+    const config = {
+      caseSensitive: true,
+      wordBoundaryCheck: false,
+      placeholderFormat: "<{type}_{counter}>",
+      placeholderFormats: { ssn: "[REDACTED]" },
+      maxEntities: 0
+    };
+    const anonymizer = new Anonymizer(["email", "ssn"], config);
+    const result = anonymizer.anonymize("test@example.com, SSN 123-45-6789");
+
+    expect(result.anonymizedText).toBe("<EMAIL_1>, SSN [REDACTED]");
+  });
+
+  test("restores reformatted placeholders when tolerant", () => {
+    const config = {
+      caseSensitive: true,
+      wordBoundaryCheck: false,
+      placeholderFormat: "short",
+      maxEntities: 0,
+      tolerantDeanonymization: true
+    };
+    const anonymizer = new Anonymizer(["email"], config);
+    const result = anonymizer.anonymize("test@example.com");
+
+    expect(anonymizer.deanonymize("Wrote to Email 1", result.mapping)).toBe("Wrote to test@example.com");
+  });
+
   test("handles case sensitivity in custom entities", () => {
     const configSensitive = {
       caseSensitive: true,
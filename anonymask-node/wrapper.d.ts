@@ -0,0 +1,31 @@
+import type { Transform, TransformOptions } from "stream";
+
+export * from "./index";
+
+/** A Transform stream masking the text piped through it; created with `Anonymizer.stream()`. */
+export declare class AnonymizeTransform extends Transform {
+  /** Restore original values using the mapping accumulated so far. */
+  deanonymize(text: string): string
+  /** The accumulated placeholder -> original value mapping. */
+  get mapping(): Record<string, string>
+}
+
+export interface TransformStreamOptions extends TransformOptions {
+  /** Trailing bytes held back until the next chunk shows whether an entity continues (default 256) */
+  window?: number
+}
+
+declare module "./index" {
+  interface Anonymizer {
+    /**
+     * Start masking text piped through a Node stream, e.g. a log pipeline:
+     *
+     * ```js
+     * fs.createReadStream(file).pipe(anonymizer.stream()).pipe(out);
+     * ```
+     */
+    stream(options?: TransformStreamOptions): AnonymizeTransform
+    /** Alias of `stream()`. */
+    transform(options?: TransformStreamOptions): AnonymizeTransform
+  }
+}
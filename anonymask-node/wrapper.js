@@ -0,0 +1,70 @@
+/**
+ * Entry point of the package: the NAPI-RS generated bindings of `index.js`, which the build
+ * overwrites, plus helpers written in JavaScript.
+ */
+
+const { Transform } = require("stream");
+const { StringDecoder } = require("string_decoder");
+const binding = require("./index.js");
+
+/**
+ * A Transform stream masking the text piped through it; created with `Anonymizer.stream()`.
+ *
+ * Chunks are decoded as UTF-8 and passed to an `AnonymizerStream`, which holds back the
+ * trailing `window` bytes until the next chunk shows whether an entity continues.
+ */
+class AnonymizeTransform extends Transform {
+  constructor(anonymizer, options = {}) {
+    const { window, ...streamOptions } = options;
+    super({ ...streamOptions, decodeStrings: false });
+    this._inner = anonymizer.chunkStream(window);
+    this._decoder = new StringDecoder("utf8");
+  }
+
+  _transform(chunk, _encoding, callback) {
+    const text = typeof chunk === "string" ? chunk : this._decoder.write(chunk);
+    try {
+      const masked = this._inner.push(text);
+      if (masked) this.push(masked);
+      callback();
+    } catch (error) {
+      callback(error);
+    }
+  }
+
+  _flush(callback) {
+    try {
+      const masked = this._inner.push(this._decoder.end()) + this._inner.flush();
+      if (masked) this.push(masked);
+      callback();
+    } catch (error) {
+      callback(error);
+    }
+  }
+
+  /** Restore original values using the mapping accumulated so far. */
+  deanonymize(text) {
+    return this._inner.deanonymize(text);
+  }
+
+  /** The accumulated placeholder -> original value mapping. */
+  get mapping() {
+    return this._inner.mapping;
+  }
+}
+
+/**
+ * Start masking text piped through a Node stream, e.g. a log pipeline:
+ *
+ * ```js
+ * fs.createReadStream(file).pipe(anonymizer.stream()).pipe(out);
+ * ```
+ *
+ * `transform()` is an alias.
+ */
+binding.Anonymizer.prototype.stream = function stream(options) {
+  return new AnonymizeTransform(this, options);
+};
+binding.Anonymizer.prototype.transform = binding.Anonymizer.prototype.stream;
+
+module.exports = { ...binding, AnonymizeTransform };
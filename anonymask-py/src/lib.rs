@@ -1,3 +1,5 @@
+#![allow(clippy::useless_conversion)]
+
 use anonymask_core::*;
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
@@ -14,8 +16,9 @@ use anonymask_core::PlaceholderFormat as CorePlaceholderFormat;
 ///
 /// Placeholder formats:
 /// - "standard": TYPE_UUID (e.g., "EMAIL_a1b2c3d4...")
-/// - "short": TYPE_COUNTER (e.g., "EMAIL_1", "EMAIL_2")
-/// - Custom template string with {type}, {uuid}, {counter} placeholders
+/// - "short": TYPE_INDEX, numbered per type (e.g., "EMAIL_1", "EMAIL_2", "PHONE_1")
+/// - Custom template string with {type}, {type_lower}, {uuid}, {counter} and {index_by_type}
+///   placeholders, e.g. "<{type_lower}-{index_by_type:03}>"; write {{ and }} for literal braces
 #[pyclass(name = "AnonymizerConfig")]
 #[derive(Clone)]
 struct PyAnonymizerConfig {
@@ -27,6 +30,40 @@ struct PyAnonymizerConfig {
     pub max_entities: usize,
     #[pyo3(get, set)]
     pub placeholder_format: String,
+    #[pyo3(get, set)]
+    pub locales: Vec<String>,
+    #[pyo3(get, set)]
+    pub min_confidence: f32,
+    #[pyo3(get, set)]
+    pub named_patterns: Vec<String>,
+    #[pyo3(get, set)]
+    pub overlap_strategy: String,
+    #[pyo3(get, set)]
+    pub duplicate_preference: String,
+    #[pyo3(get, set)]
+    pub custom_patterns: std::collections::HashMap<String, String>,
+    #[pyo3(get, set)]
+    pub key_rules: std::collections::HashMap<String, String>,
+    #[pyo3(get, set)]
+    pub allow_values: Vec<String>,
+    #[pyo3(get, set)]
+    pub allow_patterns: Vec<String>,
+    #[pyo3(get, set)]
+    pub context_keywords: std::collections::HashMap<String, std::collections::HashMap<String, f32>>,
+    #[pyo3(get, set)]
+    pub context_window: usize,
+    #[pyo3(get, set)]
+    pub credential_query_params: Vec<String>,
+    #[pyo3(get, set)]
+    pub secret_min_length: usize,
+    #[pyo3(get, set)]
+    pub secret_min_entropy: f32,
+    #[pyo3(get, set)]
+    pub placeholder_formats: std::collections::HashMap<String, String>,
+    #[pyo3(get, set)]
+    pub tolerant_deanonymization: bool,
+    #[pyo3(get, set)]
+    pub phone_matching: String,
 }
 
 #[pymethods]
@@ -38,49 +75,191 @@ impl PyAnonymizerConfig {
     ///     word_boundary_check: Check word boundaries for custom entities (default: False)
     ///     placeholder_format: Format for placeholders - "standard", "short", or custom template (default: "standard")
     ///     max_entities: Maximum entities to detect, 0 for unlimited (default: 0)
+    ///     locales: Regional pattern packs for phones, addresses and dates (default: ["us"])
+    ///     min_confidence: Discard detections scoring below this, 0.0-1.0 (default: 0.5)
+    ///     named_patterns: Optional library patterns to enable, e.g. ["aws_arn", "vin"] (default: [])
+    ///     overlap_strategy: How overlapping detections are resolved - "earliest", "longest" or "priority:ssn,phone" (default: "earliest")
+    ///     duplicate_preference: Which type wins when a value is detected both as a custom and a built-in type - "custom" or "builtin" (default: "custom")
+    ///     custom_patterns: Your own regular expressions by entity type name, e.g. {"employee_id": r"EMP-\d{6}"} (default: {})
+    ///     key_rules: Key name patterns whose values are PII, with their entity type name, e.g. {"(?i)dob": "date"} (default: {})
+    ///     allow_values: Known-safe values that are never anonymized, e.g. ["support@ourcompany.com"] (default: [])
+    ///     allow_patterns: Regular expressions for known-safe values, matched against whole values (default: [])
+    ///     context_keywords: Keyword weights by entity type; nearby keywords raise or lower confidence, e.g. {"ssn": {"order": -0.3}} (default: {})
+    ///     context_window: Bytes on either side of a match searched for context keywords (default: 32)
+    ///     credential_query_params: URL query parameters whose values are "credential" entities, e.g. ["token", "key"] (default: [])
+    ///     secret_min_length: Shortest string the "secret" entropy detector considers (default: 20)
+    ///     secret_min_entropy: Minimum Shannon entropy in bits per character for "secret" (default: 4.0)
+    ///     placeholder_formats: Formats by entity type name, used instead of placeholder_format, e.g. {"ssn": "[REDACTED]"} (default: {})
+    ///     tolerant_deanonymization: Also restore placeholders whose case, spaces or punctuation an LLM changed, e.g. "Email 1" (default: False)
+    ///     phone_matching: How complete US phone numbers must be - "loose" also matches partial numbers such as 555-123, "strict" only 10-digit ones (default: "loose")
     ///
     /// Examples:
     ///     >>> config = AnonymizerConfig()
     ///     >>> config = AnonymizerConfig(placeholder_format="short")
     ///     >>> config = AnonymizerConfig(placeholder_format="[{type}:{counter}]")
     #[new]
-    #[pyo3(signature = (case_sensitive=true, word_boundary_check=false, placeholder_format="standard".to_string(), max_entities=0))]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (case_sensitive=true, word_boundary_check=false, placeholder_format="standard".to_string(), max_entities=0, locales=vec!["us".to_string()], min_confidence=0.5, named_patterns=vec![], overlap_strategy="earliest".to_string(), duplicate_preference="custom".to_string(), custom_patterns=std::collections::HashMap::new(), key_rules=std::collections::HashMap::new(), allow_values=vec![], allow_patterns=vec![], context_keywords=std::collections::HashMap::new(), context_window=32, credential_query_params=vec![], secret_min_length=20, secret_min_entropy=4.0, placeholder_formats=std::collections::HashMap::new(), tolerant_deanonymization=false, phone_matching="loose".to_string()))]
     fn new(
         case_sensitive: bool,
         word_boundary_check: bool,
         placeholder_format: String,
         max_entities: usize,
+        locales: Vec<String>,
+        min_confidence: f32,
+        named_patterns: Vec<String>,
+        overlap_strategy: String,
+        duplicate_preference: String,
+        custom_patterns: std::collections::HashMap<String, String>,
+        key_rules: std::collections::HashMap<String, String>,
+        allow_values: Vec<String>,
+        allow_patterns: Vec<String>,
+        context_keywords: std::collections::HashMap<String, std::collections::HashMap<String, f32>>,
+        context_window: usize,
+        credential_query_params: Vec<String>,
+        secret_min_length: usize,
+        secret_min_entropy: f32,
+        placeholder_formats: std::collections::HashMap<String, String>,
+        tolerant_deanonymization: bool,
+        phone_matching: String,
     ) -> Self {
         PyAnonymizerConfig {
             case_sensitive,
             word_boundary_check,
             placeholder_format,
             max_entities,
+            locales,
+            min_confidence,
+            named_patterns,
+            overlap_strategy,
+            duplicate_preference,
+            custom_patterns,
+            key_rules,
+            allow_values,
+            allow_patterns,
+            context_keywords,
+            context_window,
+            credential_query_params,
+            secret_min_length,
+            secret_min_entropy,
+            placeholder_formats,
+            tolerant_deanonymization,
+            phone_matching,
         }
     }
 
     fn __repr__(&self) -> String {
         format!(
-            "AnonymizerConfig(case_sensitive={}, word_boundary_check={}, placeholder_format='{}', max_entities={})",
-            self.case_sensitive, self.word_boundary_check, self.placeholder_format, self.max_entities
+            "AnonymizerConfig(case_sensitive={}, word_boundary_check={}, placeholder_format='{}', max_entities={}, locales={:?}, min_confidence={}, named_patterns={:?}, overlap_strategy='{}', duplicate_preference='{}', custom_patterns={:?}, key_rules={:?}, allow_values={:?}, allow_patterns={:?}, context_keywords={:?}, context_window={}, credential_query_params={:?}, secret_min_length={}, secret_min_entropy={}, placeholder_formats={:?}, tolerant_deanonymization={}, phone_matching='{}')",
+            self.case_sensitive, self.word_boundary_check, self.placeholder_format, self.max_entities, self.locales, self.min_confidence, self.named_patterns, self.overlap_strategy, self.duplicate_preference, self.custom_patterns, self.key_rules, self.allow_values, self.allow_patterns, self.context_keywords, self.context_window, self.credential_query_params, self.secret_min_length, self.secret_min_entropy, self.placeholder_formats, self.tolerant_deanonymization, self.phone_matching
         )
     }
 }
 
+fn placeholder_format(format: &str) -> CorePlaceholderFormat {
+    match format {
+        "standard" => CorePlaceholderFormat::Standard,
+        "short" => CorePlaceholderFormat::Short,
+        template => CorePlaceholderFormat::Custom(template.to_string()),
+    }
+}
+
 impl PyAnonymizerConfig {
-    fn to_core(&self) -> CoreConfig {
-        let placeholder_format = match self.placeholder_format.as_str() {
-            "standard" => CorePlaceholderFormat::Standard,
-            "short" => CorePlaceholderFormat::Short,
-            template => CorePlaceholderFormat::Custom(template.to_string()),
+    fn to_core(&self) -> PyResult<CoreConfig> {
+        let mut placeholder_overrides = Vec::new();
+        for (entity_type, format) in &self.placeholder_formats {
+            let entity_type =
+                EntityType::from_str(entity_type).map_err(|e| PyValueError::new_err(e.to_string()))?;
+            placeholder_overrides.push(PlaceholderOverride::new(entity_type, placeholder_format(format)));
+        }
+        placeholder_overrides.sort_by_key(|o| o.entity_type.to_string());
+
+        let locales = self
+            .locales
+            .iter()
+            .map(|l| l.parse::<Locale>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        let overlap_strategy = self
+            .overlap_strategy
+            .parse()
+            .map_err(|e: anonymask_core::AnonymaskError| PyValueError::new_err(e.to_string()))?;
+
+        let duplicate_preference = self
+            .duplicate_preference
+            .parse()
+            .map_err(|e: anonymask_core::AnonymaskError| PyValueError::new_err(e.to_string()))?;
+
+        let phone_matching = self
+            .phone_matching
+            .parse()
+            .map_err(|e: anonymask_core::AnonymaskError| PyValueError::new_err(e.to_string()))?;
+
+        let mut custom_patterns: Vec<CustomPattern> = self
+            .custom_patterns
+            .iter()
+            .map(|(name, pattern)| CustomPattern {
+                name: name.clone(),
+                pattern: pattern.clone(),
+            })
+            .collect();
+        custom_patterns.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut key_rules = Vec::new();
+        for (key, entity_type) in &self.key_rules {
+            let entity_type =
+                EntityType::from_str(entity_type).map_err(|e| PyValueError::new_err(e.to_string()))?;
+            key_rules.push(KeyRule::new(key.clone(), entity_type));
+        }
+        key_rules.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let allow_list = self
+            .allow_values
+            .iter()
+            .map(AllowRule::value)
+            .chain(self.allow_patterns.iter().map(AllowRule::pattern))
+            .collect();
+
+        let mut context_rules = Vec::new();
+        for (entity_type, keywords) in &self.context_keywords {
+            let entity_type =
+                EntityType::from_str(entity_type).map_err(|e| PyValueError::new_err(e.to_string()))?;
+            for (keyword, weight) in keywords {
+                context_rules.push(ContextRule::new(entity_type.clone(), [keyword.clone()], *weight));
+            }
+        }
+
+        let confidence = ConfidenceModel {
+            min_confidence: self.min_confidence,
+            context_window: self.context_window,
+            ..ConfidenceModel::default()
         };
 
-        CoreConfig {
+        Ok(CoreConfig {
             case_sensitive: self.case_sensitive,
             word_boundary_check: self.word_boundary_check,
-            placeholder_format,
+            placeholder_format: placeholder_format(&self.placeholder_format),
+            placeholder_overrides,
+            tolerant_deanonymization: self.tolerant_deanonymization,
             max_entities: self.max_entities,
-        }
+            locales,
+            named_patterns: self.named_patterns.clone(),
+            custom_patterns,
+            key_rules,
+            allow_list,
+            overlap_strategy,
+            duplicate_preference,
+            context_rules,
+            credential_query_params: self.credential_query_params.clone(),
+            phone_matching,
+            secret_detection: SecretDetection {
+                min_length: self.secret_min_length,
+                min_entropy: self.secret_min_entropy,
+            },
+            confidence,
+            ..CoreConfig::default()
+        })
     }
 }
 
@@ -96,12 +275,12 @@ impl Anonymizer {
     fn new(entity_types: Vec<String>, config: Option<PyAnonymizerConfig>) -> PyResult<Self> {
         let entity_types: Result<Vec<EntityType>, _> = entity_types
             .into_iter()
-            .map(|s| EntityType::from_str(&s))
+            .map(|s| EntityType::from_str_strict(&s))
             .collect();
         let entity_types = entity_types.map_err(|e| PyValueError::new_err(e.to_string()))?;
 
         let inner = if let Some(cfg) = config {
-            CoreAnonymizer::with_config(entity_types, cfg.to_core())
+            CoreAnonymizer::with_config(entity_types, cfg.to_core()?)
                 .map_err(|e| PyValueError::new_err(e.to_string()))?
         } else {
             CoreAnonymizer::new(entity_types).map_err(|e| PyValueError::new_err(e.to_string()))?
@@ -110,44 +289,32 @@ impl Anonymizer {
         Ok(Anonymizer { inner })
     }
 
-    fn anonymize(
-        &self,
-        text: &str,
-    ) -> PyResult<(
-        String,
-        std::collections::HashMap<String, String>,
-        Vec<PyEntity>,
-    )> {
-        let result = self
-            .inner
-            .anonymize(text)
+    fn anonymize(&self, py: Python<'_>, text: &str) -> PyResult<PyResultTuple> {
+        let result = py
+            .allow_threads(|| self.inner.anonymize(text))
             .map_err(|e| PyValueError::new_err(e.to_string()))?;
-        let entities: Vec<PyEntity> = result
-            .entities
-            .into_iter()
-            .map(|e| PyEntity {
-                entity_type: match &e.entity_type {
-                    EntityType::Custom(name) => name.clone(),
-                    _ => format!("{:?}", e.entity_type).to_lowercase(),
-                },
-                value: e.value,
-                start: e.start,
-                end: e.end,
-            })
-            .collect();
-        Ok((result.anonymized_text, result.mapping, entities))
+        Ok(to_tuple(result))
+    }
+
+    /// Anonymize a list of texts on Rust worker threads, returning one
+    /// `(anonymized_text, mapping, entities)` tuple per text, in order.
+    ///
+    /// Each text gets its own mapping, as with `anonymize`. Raises
+    /// ValueError if any text fails.
+    fn anonymize_batch(&self, py: Python<'_>, texts: Vec<String>) -> PyResult<Vec<PyResultTuple>> {
+        let results = py
+            .allow_threads(|| self.inner.anonymize_iter(texts).collect::<Result<Vec<_>, _>>())
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(results.into_iter().map(to_tuple).collect())
     }
 
     #[pyo3(signature = (text, custom_entities=None))]
     fn anonymize_with_custom(
         &self,
+        py: Python<'_>,
         text: &str,
         custom_entities: Option<std::collections::HashMap<String, Vec<String>>>,
-    ) -> PyResult<(
-        String,
-        std::collections::HashMap<String, String>,
-        Vec<PyEntity>,
-    )> {
+    ) -> PyResult<PyResultTuple> {
         // Convert string entity types to EntityType enum
         let custom_entities = match custom_entities {
             Some(map) => {
@@ -162,35 +329,94 @@ impl Anonymizer {
             None => None,
         };
 
-        let result = self
-            .inner
-            .anonymize_with_custom(text, custom_entities.as_ref())
+        let result = py
+            .allow_threads(|| self.inner.anonymize_with_custom(text, custom_entities.as_ref()))
             .map_err(|e| PyValueError::new_err(e.to_string()))?;
-        let entities: Vec<PyEntity> = result
-            .entities
+        Ok(to_tuple(result))
+    }
+
+    /// Anonymize spans found by an external detector, skipping detection.
+    ///
+    /// Each entity is an `(entity_type, start, end)` tuple of byte offsets.
+    fn anonymize_entities(
+        &self,
+        py: Python<'_>,
+        text: &str,
+        entities: Vec<(String, usize, usize)>,
+    ) -> PyResult<PyResultTuple> {
+        let entities = entities
             .into_iter()
-            .map(|e| PyEntity {
-                entity_type: match &e.entity_type {
-                    EntityType::Custom(name) => name.clone(),
-                    _ => format!("{:?}", e.entity_type).to_lowercase(),
-                },
-                value: e.value,
-                start: e.start,
-                end: e.end,
+            .map(|(entity_type, start, end)| {
+                Ok(anonymask_core::Entity {
+                    entity_type: EntityType::from_str(&entity_type)
+                        .map_err(|e| PyValueError::new_err(e.to_string()))?,
+                    value: text.get(start..end).unwrap_or_default().to_string(),
+                    start,
+                    end,
+                    confidence: 1.0,
+                })
             })
-            .collect();
-        Ok((result.anonymized_text, result.mapping, entities))
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let result = py
+            .allow_threads(|| self.inner.anonymize_entities(text, entities))
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(to_tuple(result))
     }
 
     fn deanonymize(
         &self,
+        py: Python<'_>,
         text: &str,
         mapping: std::collections::HashMap<String, String>,
     ) -> String {
-        self.inner.deanonymize(text, &mapping)
+        py.allow_threads(|| self.inner.deanonymize(text, &mapping))
+    }
+
+    /// Restore many texts against one mapping, preparing the mapping only
+    /// once.
+    fn deanonymize_batch(
+        &self,
+        py: Python<'_>,
+        texts: Vec<String>,
+        mapping: std::collections::HashMap<String, String>,
+    ) -> PyResult<Vec<String>> {
+        let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+        py.allow_threads(|| self.inner.deanonymize_batch(&texts, &mapping))
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Like `deanonymize`, but raises ValueError if the text contains a
+    /// placeholder missing from the mapping.
+    fn deanonymize_strict(
+        &self,
+        py: Python<'_>,
+        text: &str,
+        mapping: std::collections::HashMap<String, String>,
+    ) -> PyResult<String> {
+        py.allow_threads(|| self.inner.deanonymize_strict(text, &mapping))
+            .map_err(|e| PyValueError::new_err(e.to_string()))
     }
 }
 
+/// `(anonymized_text, mapping, entities)`, as returned by the anonymize methods.
+type PyResultTuple = (String, std::collections::HashMap<String, String>, Vec<PyEntity>);
+
+fn to_tuple(result: anonymask_core::AnonymizationResult) -> PyResultTuple {
+    let entities = result
+        .entities
+        .into_iter()
+        .map(|e| PyEntity {
+            entity_type: e.entity_type.to_string(),
+            value: e.value,
+            start: e.start,
+            end: e.end,
+            confidence: e.confidence,
+        })
+        .collect();
+    (result.anonymized_text, result.mapping, entities)
+}
+
 #[pyclass(name = "Entity")]
 #[derive(Clone)]
 struct PyEntity {
@@ -202,6 +428,8 @@ struct PyEntity {
     start: usize,
     #[pyo3(get)]
     end: usize,
+    #[pyo3(get)]
+    confidence: f32,
 }
 
 #[pymodule]
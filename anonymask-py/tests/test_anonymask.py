@@ -44,6 +44,17 @@ class TestAnonymizer:
 
         assert deanonymized == original
 
+    def test_anonymize_batch(self):
+        texts = ["Contact john@email.com", "No PII here", "Call 555-123-4567"]
+        results = self.anonymizer.anonymize_batch(texts)
+
+        assert len(results) == 3
+        assert "EMAIL_" in results[0][0]
+        assert results[1][0] == "No PII here"
+        assert "PHONE_" in results[2][0]
+        restored = [self.anonymizer.deanonymize(text, mapping) for text, mapping, _ in results]
+        assert restored == texts
+
     def test_empty_text(self):
         result = self.anonymizer.anonymize("")
         assert result[0] == ""
@@ -123,6 +134,10 @@ class TestAnonymizer:
         assert result[2][1].entity_type == "company"
         assert result[2][1].value == "Acme Corp"
 
+    def test_misspelled_entity_type(self):
+        with pytest.raises(ValueError, match="did you mean 'email'"):
+            Anonymizer(["emial"])
+
 
 class TestAnonymizerConfig:
     """Tests for v2.0.0 configuration features"""
@@ -159,6 +174,31 @@ class TestAnonymizerConfig:
         assert "[EMAIL:1]" in result[0]
         assert len(result[2]) == 1
 
+    def test_config_with_formats_by_type(self):
+        config = AnonymizerConfig(placeholder_format="<{type}_{counter}>", placeholder_formats={"ssn": "[REDACTED]"})
+        anonymizer = Anonymizer(["email", "ssn"], config)
+
+        result = anonymizer.anonymize("test@example.com, SSN 123-45-6789")
+        assert result[0] == "<EMAIL_1>, SSN [REDACTED]"
+
+    def test_tolerant_deanonymization(self):
+        config = AnonymizerConfig(placeholder_format="short", tolerant_deanonymization=True)
+        anonymizer = Anonymizer(["email"], config)
+
+        _, mapping, _ = anonymizer.anonymize("test@example.com")
+        assert anonymizer.deanonymize("Wrote to Email 1", mapping) == "Wrote to test@example.com"
+
+    def test_strict_phone_matching(self):
+        text = "Ticket 555-123, call 555-123-4567"
+        assert len(Anonymizer(["phone"]).anonymize(text)[2]) == 2
+
+        anonymizer = Anonymizer(["phone"], AnonymizerConfig(phone_matching="strict"))
+        _, _, entities = anonymizer.anonymize(text)
+        assert [entity.value for entity in entities] == ["555-123-4567"]
+
+        with pytest.raises(ValueError):
+            Anonymizer(["phone"], AnonymizerConfig(phone_matching="exact"))
+
     def test_config_case_sensitivity(self):
         """Test case sensitivity in custom entity matching"""
         # Case sensitive (default)